@@ -0,0 +1,443 @@
+use std::time::{Duration, SystemTime};
+
+use serde::Deserialize;
+
+use crate::constants::STATE_ON;
+use crate::data::{DeviceStatus, Filter};
+use crate::profile::DeviceProfile;
+
+/// Quantity an alarm rule watches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AlarmMetric {
+    RealFlow,
+    TargetFlow,
+    /// Real flow minus target flow, in m3/h.
+    FlowDeviation,
+    Speed,
+    /// Highest filter usage across the profile's filters, in percent of its limit.
+    FilterUsage,
+}
+
+impl AlarmMetric {
+    pub fn value(self, status: &DeviceStatus, profile: &DeviceProfile) -> Option<f64> {
+        match self {
+            Self::RealFlow => Some(f64::from(status.real_flow)),
+            Self::TargetFlow => Some(f64::from(status.target_flow)),
+            Self::FlowDeviation => {
+                Some(f64::from(status.real_flow) - f64::from(status.target_flow))
+            }
+            Self::Speed => Some(f64::from(status.speed_rpm)),
+            Self::FilterUsage => profile
+                .filters
+                .iter()
+                .filter(|filter| status.filter_limit(**filter) > 0)
+                .map(|filter| {
+                    f64::from(status.filter_total(*filter)) * 100.0
+                        / f64::from(status.filter_limit(*filter))
+                })
+                .reduce(f64::max),
+        }
+    }
+}
+
+/// One user-defined alarm, e.g. "flow deviation below -10 for 30 s".
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlarmRule {
+    pub name: String,
+    pub metric: AlarmMetric,
+    pub above: Option<f64>,
+    pub below: Option<f64>,
+    /// Seconds the condition must hold before the alarm is raised.
+    #[serde(default, rename = "for")]
+    pub hold_secs: u64,
+    /// Margin the value must recover by before the alarm clears.
+    #[serde(default)]
+    pub hysteresis: f64,
+}
+
+impl AlarmRule {
+    fn triggered(&self, value: f64) -> bool {
+        self.above.is_some_and(|limit| value > limit)
+            || self.below.is_some_and(|limit| value < limit)
+    }
+
+    fn cleared(&self, value: f64) -> bool {
+        self.above
+            .is_none_or(|limit| value <= limit - self.hysteresis)
+            && self
+                .below
+                .is_none_or(|limit| value >= limit + self.hysteresis)
+    }
+}
+
+/// How long `A` hides the alarms when the config file sets no `snooze_minutes`.
+pub const DEFAULT_SNOOZE: Duration = Duration::from_mins(15);
+
+#[derive(Debug, Default)]
+struct AlarmState {
+    pending_since: Option<SystemTime>,
+    active: bool,
+    /// Silenced until the alarm clears and is raised again.
+    acknowledged: bool,
+}
+
+/// Evaluates the configured alarm rules against each polled status.
+#[derive(Debug, Default)]
+pub struct AlarmMonitor {
+    rules: Vec<AlarmRule>,
+    states: Vec<AlarmState>,
+}
+
+impl AlarmMonitor {
+    pub fn new(rules: Vec<AlarmRule>) -> Self {
+        let states = rules.iter().map(|_| AlarmState::default()).collect();
+        Self { rules, states }
+    }
+
+    /// Replaces the rules, keeping the state of those that did not change.
+    pub fn set_rules(&mut self, rules: Vec<AlarmRule>) {
+        let mut old: Vec<(AlarmRule, AlarmState)> =
+            self.rules.drain(..).zip(self.states.drain(..)).collect();
+        *self = Self::new(rules);
+        for (rule, state) in self.rules.iter().zip(&mut self.states) {
+            if let Some(index) = old.iter().position(|(previous, _)| previous == rule) {
+                *state = old.swap_remove(index).1;
+            }
+        }
+    }
+
+    pub fn evaluate(&mut self, status: &DeviceStatus, profile: &DeviceProfile, now: SystemTime) {
+        for (rule, state) in self.rules.iter().zip(&mut self.states) {
+            let Some(value) = rule.metric.value(status, profile) else {
+                continue;
+            };
+            if state.active {
+                if rule.cleared(value) {
+                    *state = AlarmState::default();
+                }
+            } else if rule.triggered(value) {
+                let since = *state.pending_since.get_or_insert(now);
+                let held = now.duration_since(since).unwrap_or_default();
+                state.active = held >= Duration::from_secs(rule.hold_secs);
+            } else {
+                state.pending_since = None;
+            }
+        }
+    }
+
+    /// Names of the alarms currently raised and not acknowledged.
+    pub fn active(&self) -> impl Iterator<Item = &str> {
+        self.raised(false)
+    }
+
+    /// Names of the raised alarms that were acknowledged.
+    pub fn acknowledged(&self) -> impl Iterator<Item = &str> {
+        self.raised(true)
+    }
+
+    /// Acknowledges every raised alarm, returning the names of those not acknowledged before.
+    pub fn acknowledge(&mut self) -> Vec<&str> {
+        self.rules
+            .iter()
+            .zip(&mut self.states)
+            .filter(|(_, state)| state.active && !state.acknowledged)
+            .map(|(rule, state)| {
+                state.acknowledged = true;
+                rule.name.as_str()
+            })
+            .collect()
+    }
+
+    fn raised(&self, acknowledged: bool) -> impl Iterator<Item = &str> {
+        self.rules
+            .iter()
+            .zip(&self.states)
+            .filter(move |(_, state)| state.active && state.acknowledged == acknowledged)
+            .map(|(rule, _)| rule.name.as_str())
+    }
+}
+
+/// Settings for the built-in setpoint deviation alarm.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DeviationSettings {
+    pub enabled: bool,
+    /// Allowed gap between real and target flow, in m3/h.
+    pub tolerance: f64,
+    /// Seconds the gap must persist, long enough to ignore setpoint ramps.
+    #[serde(rename = "for")]
+    pub hold_secs: u64,
+}
+
+impl Default for DeviationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            tolerance: 10.0,
+            hold_secs: 30,
+        }
+    }
+}
+
+/// Flags real flow that persistently misses the target, e.g. clogged tubing or a failing motor.
+#[derive(Debug, Default)]
+pub struct DeviationDetector {
+    settings: DeviationSettings,
+    state: AlarmState,
+}
+
+impl DeviationDetector {
+    pub fn new(settings: DeviationSettings) -> Self {
+        Self {
+            settings,
+            state: AlarmState::default(),
+        }
+    }
+
+    pub fn set_settings(&mut self, settings: DeviationSettings) {
+        if settings != self.settings {
+            *self = Self::new(settings);
+        }
+    }
+
+    /// Updates the detector, returning `Some(active)` when the alarm is raised or cleared.
+    pub fn evaluate(&mut self, status: &DeviceStatus, now: SystemTime) -> Option<bool> {
+        let was_active = self.state.active;
+        let deviation = (f64::from(status.real_flow) - f64::from(status.target_flow)).abs();
+        if !self.settings.enabled || status.state != STATE_ON {
+            self.state = AlarmState::default();
+        } else if self.state.active {
+            // Clear at half the tolerance so a value hovering at the edge does not flap.
+            if deviation <= self.settings.tolerance / 2.0 {
+                self.state = AlarmState::default();
+            }
+        } else if deviation > self.settings.tolerance {
+            let since = *self.state.pending_since.get_or_insert(now);
+            let held = now.duration_since(since).unwrap_or_default();
+            self.state.active = held >= Duration::from_secs(self.settings.hold_secs);
+        } else {
+            self.state.pending_since = None;
+        }
+        (self.state.active != was_active).then_some(self.state.active)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.state.active
+    }
+}
+
+/// Filters at or past their device limit, shown as a dashboard banner until acknowledged.
+///
+/// The status flag bits are undocumented, so only the filter totals raise the banner.
+#[derive(Debug, Default)]
+pub struct FilterBanner {
+    over_limit: Vec<Filter>,
+    acknowledged: Vec<Filter>,
+}
+
+impl FilterBanner {
+    pub fn evaluate(&mut self, status: &DeviceStatus, filters: &[Filter]) {
+        self.over_limit = filters
+            .iter()
+            .copied()
+            .filter(|filter| {
+                let limit = status.filter_limit(*filter);
+                limit > 0 && status.filter_total(*filter) >= limit
+            })
+            .collect();
+        // A filter that drops back under its limit is raised again the next time it overruns.
+        let over_limit = &self.over_limit;
+        self.acknowledged
+            .retain(|filter| over_limit.contains(filter));
+    }
+
+    pub fn over_limit(&self) -> &[Filter] {
+        &self.over_limit
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.over_limit
+            .iter()
+            .any(|filter| !self.acknowledged.contains(filter))
+    }
+
+    /// Acknowledges the filters over their limit, returning those not acknowledged before.
+    pub fn acknowledge(&mut self) -> Vec<Filter> {
+        let fresh = self
+            .over_limit
+            .iter()
+            .copied()
+            .filter(|filter| !self.acknowledged.contains(filter))
+            .collect();
+        self.acknowledged.clone_from(&self.over_limit);
+        fresh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use super::{
+        AlarmMetric, AlarmMonitor, AlarmRule, DeviationDetector, DeviationSettings, FilterBanner,
+    };
+    use crate::constants::{STATE_OFF, STATE_ON, STATUS_POLL_REG_COUNT};
+    use crate::data::{DeviceStatus, Filter};
+    use crate::profile::QUICK_6101A2;
+
+    fn status(real_flow: u16) -> DeviceStatus {
+        let mut status = DeviceStatus::from_registers(vec![0; STATUS_POLL_REG_COUNT as usize])
+            .expect("status should parse");
+        status.state = STATE_ON;
+        status.target_flow = 70;
+        status.real_flow = real_flow;
+        status
+    }
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn deviation_must_hold_before_raising_and_recover_past_hysteresis() {
+        let mut monitor = AlarmMonitor::new(vec![AlarmRule {
+            name: String::from("Low flow"),
+            metric: AlarmMetric::FlowDeviation,
+            above: None,
+            below: Some(-10.0),
+            hold_secs: 30,
+            hysteresis: 2.0,
+        }]);
+
+        monitor.evaluate(&status(55), &QUICK_6101A2, at(0));
+        monitor.evaluate(&status(55), &QUICK_6101A2, at(20));
+        assert_eq!(monitor.active().count(), 0);
+        monitor.evaluate(&status(55), &QUICK_6101A2, at(30));
+        assert_eq!(monitor.active().collect::<Vec<_>>(), vec!["Low flow"]);
+
+        monitor.evaluate(&status(61), &QUICK_6101A2, at(31));
+        assert_eq!(monitor.active().count(), 1);
+        monitor.evaluate(&status(62), &QUICK_6101A2, at(32));
+        assert_eq!(monitor.active().count(), 0);
+    }
+
+    #[test]
+    fn acknowledged_alarm_stays_silent_until_raised_again() {
+        let mut monitor = AlarmMonitor::new(vec![AlarmRule {
+            name: String::from("Low flow"),
+            metric: AlarmMetric::RealFlow,
+            above: None,
+            below: Some(50.0),
+            hold_secs: 0,
+            hysteresis: 0.0,
+        }]);
+        monitor.evaluate(&status(40), &QUICK_6101A2, at(0));
+        assert_eq!(monitor.acknowledge(), ["Low flow"]);
+        assert!(monitor.acknowledge().is_empty());
+        monitor.evaluate(&status(40), &QUICK_6101A2, at(1));
+        assert_eq!(monitor.active().count(), 0);
+        assert_eq!(monitor.acknowledged().collect::<Vec<_>>(), ["Low flow"]);
+
+        monitor.evaluate(&status(60), &QUICK_6101A2, at(2));
+        monitor.evaluate(&status(40), &QUICK_6101A2, at(3));
+        assert_eq!(monitor.active().collect::<Vec<_>>(), ["Low flow"]);
+        assert_eq!(monitor.acknowledged().count(), 0);
+    }
+
+    #[test]
+    fn reloading_rules_keeps_unchanged_alarms_raised() {
+        let overflow = AlarmRule {
+            name: String::from("Overflow"),
+            metric: AlarmMetric::RealFlow,
+            above: Some(90.0),
+            below: None,
+            hold_secs: 0,
+            hysteresis: 0.0,
+        };
+        let mut monitor = AlarmMonitor::new(vec![overflow.clone()]);
+        monitor.evaluate(&status(95), &QUICK_6101A2, at(0));
+        assert_eq!(monitor.active().count(), 1);
+
+        let stricter = AlarmRule {
+            name: String::from("Stricter"),
+            above: Some(99.0),
+            ..overflow.clone()
+        };
+        monitor.set_rules(vec![stricter, overflow.clone()]);
+        assert_eq!(monitor.active().collect::<Vec<_>>(), vec!["Overflow"]);
+
+        monitor.set_rules(vec![AlarmRule {
+            above: Some(80.0),
+            ..overflow
+        }]);
+        assert_eq!(monitor.active().count(), 0);
+    }
+
+    #[test]
+    fn interrupted_condition_restarts_hold_timer() {
+        let mut monitor = AlarmMonitor::new(vec![AlarmRule {
+            name: String::from("Overflow"),
+            metric: AlarmMetric::RealFlow,
+            above: Some(90.0),
+            below: None,
+            hold_secs: 10,
+            hysteresis: 0.0,
+        }]);
+
+        monitor.evaluate(&status(95), &QUICK_6101A2, at(0));
+        monitor.evaluate(&status(80), &QUICK_6101A2, at(5));
+        monitor.evaluate(&status(95), &QUICK_6101A2, at(10));
+        assert_eq!(monitor.active().count(), 0);
+        monitor.evaluate(&status(95), &QUICK_6101A2, at(20));
+        assert_eq!(monitor.active().count(), 1);
+    }
+
+    #[test]
+    fn deviation_alarm_needs_persistent_gap_while_running() {
+        let mut detector = DeviationDetector::new(DeviationSettings::default());
+        assert_eq!(detector.evaluate(&status(50), at(0)), None);
+        assert_eq!(detector.evaluate(&status(50), at(30)), Some(true));
+        assert_eq!(detector.evaluate(&status(64), at(31)), None);
+        assert_eq!(detector.evaluate(&status(66), at(32)), Some(false));
+
+        let mut off = status(0);
+        off.state = STATE_OFF;
+        detector.evaluate(&off, at(40));
+        assert_eq!(detector.evaluate(&off, at(100)), None);
+        assert!(!detector.is_active());
+    }
+
+    #[test]
+    fn filter_banner_stays_acknowledged_until_the_overrun_clears() {
+        let filters = [Filter::Pre, Filter::Medium];
+        let mut worn = status(70);
+        worn.p_filter_limit = 100;
+        worn.p_filter_total = 100;
+        worn.m_filter_limit = 100;
+        let mut banner = FilterBanner::default();
+        banner.evaluate(&worn, &filters);
+        assert_eq!(banner.over_limit(), &[Filter::Pre]);
+        assert!(banner.is_visible());
+
+        assert_eq!(banner.acknowledge(), [Filter::Pre]);
+        banner.evaluate(&worn, &filters);
+        assert!(!banner.is_visible());
+
+        worn.m_filter_total = 120;
+        banner.evaluate(&worn, &filters);
+        assert!(banner.is_visible());
+
+        assert_eq!(
+            banner.acknowledge(),
+            [Filter::Medium],
+            "Pre was acknowledged before"
+        );
+        worn.p_filter_total = 0;
+        banner.evaluate(&worn, &filters);
+        worn.p_filter_total = 100;
+        banner.evaluate(&worn, &filters);
+        assert!(banner.is_visible());
+    }
+}
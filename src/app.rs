@@ -1,23 +1,333 @@
 use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
 
-use crate::data::DeviceStatus;
+use chrono::{DateTime, Local};
+use ratatui::layout::{Position, Rect};
+use serde::{Deserialize, Serialize};
+
+use crate::alarm::{AlarmMonitor, DEFAULT_SNOOZE, DeviationDetector, FilterBanner};
+use crate::clipboard;
+use crate::config::{ChartAxes, Config, FlowSteps, GaugeThresholds, Preset};
+use crate::config_editor::ConfigEditor;
+use crate::constants::{BEEPER_ON, POLL_INTERVALS_MS, REG_BEEPER, STATE_ON};
+use crate::data::{DeviceIdentity, DeviceStatus, Filter};
+use crate::dialog::{ErrorScreen, FilterResetDialog, ResetStage, Restart};
+use crate::event_log::{EventLog, EventSource, LogView, PENDING_WRITE_POLLS, Severity};
+use crate::filter_log::FilterLog;
+use crate::history::{
+    ChartWindow, DEFAULT_HISTORY_SAMPLES, Ema, History, RegisterTrail, Sample, SpeedScale,
+};
+use crate::i18n::Locale;
 use crate::interface::InterfaceMode;
+use crate::keymap::{KeyChord, Keymap};
+use crate::line_editor::LineEditor;
+use crate::macros::{Macro, MacroRecorder, MacroRun, free_key};
+use crate::profile::{DeviceProfile, QUICK_6101A2};
+use crate::ramp::RampTracker;
+use crate::register_editor::RegisterEditor;
+use crate::reminder::ReminderSchedule;
+use crate::repeat::TargetBatch;
+use crate::target_history::TargetHistory;
+use crate::theme::{ChartMarker, Theme, ThemeName};
+use crate::toast::Toasts;
+use crate::totalizer::Totalizer;
+use crate::transport::{LinkOutcome, PollStats, TransportCommand, TransportEvent};
+use crate::units::FlowUnit;
+use crate::wizard::LinkWizard;
+
+/// Register changes kept for the debug pane changelog.
+pub const REGISTER_CHANGELOG_LEN: usize = 6;
+
+/// Missed poll intervals after which the shown reading is marked stale, but never sooner than
+/// `STALE_MIN_AGE`, so a slow answer does not make it flicker.
+pub const STALE_POLLS: u32 = 3;
+pub const STALE_MIN_AGE: Duration = Duration::from_secs(2);
+
+/// A register value that differed from the previous poll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterChange {
+    pub timestamp: SystemTime,
+    pub register: u16,
+    pub old: u16,
+    pub new: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Tab {
+    #[default]
+    Dashboard,
+    Registers,
+    Config,
+    Filters,
+    Log,
+}
+
+/// A view with its own cursor or scroll position, taking the vi motions and Home/End.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pane {
+    Registers,
+    Filters,
+    Log,
+}
+
+impl Tab {
+    pub const ALL: [Self; 5] = [
+        Self::Dashboard,
+        Self::Registers,
+        Self::Config,
+        Self::Filters,
+        Self::Log,
+    ];
+
+    pub const fn title(self) -> &'static str {
+        match self {
+            Self::Dashboard => "Dashboard",
+            Self::Registers => "Registers",
+            Self::Config => "Config",
+            Self::Filters => "Filters",
+            Self::Log => "Log",
+        }
+    }
+
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self.index() + 1) % Self::ALL.len()]
+    }
+
+    pub fn previous(self) -> Self {
+        Self::ALL[(self.index() + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    /// Switches to `tab`, or back to the dashboard if it is already shown.
+    pub fn toggle(self, tab: Self) -> Self {
+        if self == tab { Self::Dashboard } else { tab }
+    }
+}
+
+/// A dashboard panel that can be hidden.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Panel {
+    Status,
+    Slider,
+    Flow,
+    Speed,
+    Filters,
+}
+
+/// Which dashboard panels are shown; hidden panels give their rows to the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Panels {
+    pub status: bool,
+    pub slider: bool,
+    pub flow: bool,
+    pub speed: bool,
+    pub filters: bool,
+}
+
+impl Default for Panels {
+    fn default() -> Self {
+        Self {
+            status: true,
+            slider: true,
+            flow: true,
+            speed: true,
+            filters: true,
+        }
+    }
+}
+
+impl Panels {
+    pub fn toggle(&mut self, panel: Panel) {
+        let shown = match panel {
+            Panel::Status => &mut self.status,
+            Panel::Slider => &mut self.slider,
+            Panel::Flow => &mut self.flow,
+            Panel::Speed => &mut self.speed,
+            Panel::Filters => &mut self.filters,
+        };
+        *shown = !*shown;
+    }
+}
+
+/// Column of `value` on a slider track `width` cells wide spanning `min..=max`.
+pub fn slider_column(value: u16, min: u16, max: u16, width: u16) -> u16 {
+    let steps = u32::from(width.saturating_sub(1));
+    let span = u32::from(max.saturating_sub(min)).max(1);
+    let offset = u32::from(value.clamp(min, max) - min);
+    u16::try_from((offset * steps + span / 2) / span).unwrap_or(u16::MAX)
+}
+
+/// At-a-glance health of one part of the extractor; `ok` is `None` while it is off or unknown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Indicator {
+    pub label: &'static str,
+    pub ok: Option<bool>,
+}
+
+/// Screen areas of the current frame that react to mouse clicks, set by `ui::update_layout`.
+#[derive(Debug, Default)]
+pub struct HitAreas {
+    pub target_flow: Rect,
+    /// Track of the target flow slider, spanning `flow_min..=flow_max`.
+    pub slider: Rect,
+    /// Plot rows of the flow chart, from 0 at the bottom to `flow_max` at the top.
+    pub flow_chart: Rect,
+    pub filters: Vec<(Filter, Rect)>,
+}
+
+impl HitAreas {
+    /// Target flow under a click on the slider track.
+    pub fn slider_value_at(&self, position: Position, min: u16, max: u16) -> Option<u16> {
+        if !self.slider.contains(position) {
+            return None;
+        }
+        let column = u32::from(position.x - self.slider.x);
+        let steps = u32::from(self.slider.width.saturating_sub(1)).max(1);
+        let span = u32::from(max - min);
+        let offset = (column * span + steps / 2) / steps;
+        Some(min + u16::try_from(offset).unwrap_or(u16::MAX).min(max - min))
+    }
+
+    /// Flow on the chart's value axis at the row of a click in the plot.
+    pub fn chart_value_at(&self, position: Position, max: u16) -> Option<u16> {
+        if !self.flow_chart.contains(position) {
+            return None;
+        }
+        let top = u32::from(self.flow_chart.height.saturating_sub(1)).max(1);
+        let above_bottom = top.saturating_sub(u32::from(position.y - self.flow_chart.y));
+        let value = (above_bottom * u32::from(max) + top / 2) / top;
+        Some(u16::try_from(value).unwrap_or(max).min(max))
+    }
+
+    /// Target flow for a press on the slider or the flow chart, within the model's range.
+    pub fn target_at(&self, position: Position, profile: &DeviceProfile) -> Option<u16> {
+        self.slider_value_at(position, profile.flow_min, profile.flow_max)
+            .or_else(|| self.chart_value_at(position, profile.flow_max))
+            .map(|value| profile.clamp_flow(value))
+    }
+
+    pub fn filter_at(&self, position: Position) -> Option<Filter> {
+        self.filters
+            .iter()
+            .find(|(_, area)| area.contains(position))
+            .map(|(filter, _)| *filter)
+    }
+}
 
 #[derive(Debug)]
 #[allow(clippy::struct_excessive_bools)]
 pub struct AppState {
     pub status: Option<DeviceStatus>,
     pub connected: bool,
-    pub flow_history: VecDeque<(f64, f64)>,
-    pub speed_history: VecDeque<(f64, f64)>,
+    pub poll_stats: PollStats,
+    pub poll_interval: Duration,
+    pub history: History,
+    /// Snapshot the charts show while paused; polling keeps filling `history`.
+    pub frozen_history: Option<History>,
+    /// Real-flow smoothing for the status line and chart, off when `None`.
+    pub smoothing: Option<Ema>,
+    pub speed_scale: SpeedScale,
+    pub alarms: AlarmMonitor,
+    pub filter_banner: FilterBanner,
+    pub deviation: DeviationDetector,
+    pub ramp: RampTracker,
+    pub totalizer: Totalizer,
+    pub filter_log: FilterLog,
+    pub reminders: ReminderSchedule,
     pub target_flow: u16,
-    pub tick: u32,
+    /// Target flow picked on the slider or chart, sent when the mouse button is released.
+    pub target_preview: Option<u16>,
+    /// Arrow key and wheel steps held back while a key is held down.
+    pub target_batch: TargetBatch,
+    /// Target flow presets, selected with keys 1-9; evenly spaced setpoints unless configured.
+    pub presets: Vec<Preset>,
+    pub keymap: Keymap,
+    pub macros: Vec<Macro>,
+    /// Macro being played back.
+    pub macro_run: Option<MacroRun>,
+    /// Accepted commands being recorded into a new macro.
+    pub macro_recorder: Option<MacroRecorder>,
+    /// Config file recorded macros are appended to.
+    pub config_path: Option<PathBuf>,
+    pub flow_step: FlowSteps,
+    pub gauge_thresholds: GaugeThresholds,
+    pub chart_axes: ChartAxes,
     pub should_quit: bool,
     pub interface: InterfaceMode,
     pub read_only: bool,
-    pub show_debug: bool,
+    pub tab: Tab,
     pub input_mode: bool,
-    pub input_buffer: String,
+    /// Target flow being typed in the popup, in the display unit.
+    pub target_input: LineEditor,
+    pub target_history: TargetHistory,
+    pub flow_unit: FlowUnit,
+    pub profile: &'static DeviceProfile,
+    pub identity: Option<DeviceIdentity>,
+    pub config_editor: Option<ConfigEditor>,
+    pub link_address: u8,
+    pub link_baud: u32,
+    pub link_wizard: Option<LinkWizard>,
+    pub filter_reset: Option<FilterResetDialog>,
+    /// Filter whose detail popup is open.
+    pub filter_detail: Option<Filter>,
+    /// Offers to restart the serial worker after it stopped.
+    pub error_screen: Option<ErrorScreen>,
+    /// Restart chosen on the error screen, carried out by the worker's owner.
+    pub restart: Option<Restart>,
+    /// Key binding overlay opened with `?`.
+    pub help_open: bool,
+    pub confirm_power_off: bool,
+    /// How long `A` hides the alarms for.
+    pub snooze: Duration,
+    /// Alarms and the filter banner are hidden until then.
+    pub snoozed_until: Option<SystemTime>,
+    /// Power-off confirmation shown instead of sending `SetPower(false)` at once.
+    pub power_off_prompt: bool,
+    pub register_editor: RegisterEditor,
+    /// A first `g` in the Registers or Log view, waiting for the second of `gg`.
+    pub pending_g: bool,
+    /// Filter selected in the Filters view, as an index into the profile's filters.
+    pub filter_cursor: usize,
+    /// Why the last write was refused, cleared on the next key press.
+    pub write_error: Option<String>,
+    /// Registers from the poll before the current one.
+    pub previous_registers: Vec<u16>,
+    /// Recent values per register, drawn as sparklines in the Registers view.
+    pub register_trail: RegisterTrail,
+    /// When the monitor started, for the session clock in the header.
+    pub session_start: SystemTime,
+    /// Poll at which the power state last changed.
+    pub last_state_change: Option<SystemTime>,
+    /// Registers shown on the dashboard, by ascending address.
+    pub pinned: Vec<u16>,
+    pub register_changes: VecDeque<RegisterChange>,
+    pub event_log: EventLog,
+    pub toasts: Toasts,
+    pub log_view: LogView,
+    pub hit_areas: HitAreas,
+    pub theme: Theme,
+    /// Built-in theme chosen with `--theme` or restored from the saved preferences; `None`
+    /// follows the config file.
+    pub theme_name: Option<ThemeName>,
+    pub locale: Locale,
+    /// Tell series and power state apart by marker and brightness instead of hue.
+    pub colorblind: bool,
+    /// Sparklines and a single status line instead of the full dashboard.
+    pub compact: bool,
+    pub panels: Panels,
+    /// Draw with plain ASCII only, for terminals without Unicode fonts.
+    pub ascii: bool,
+    pub marker: ChartMarker,
+    /// Plot fan speed on the flow chart against a right-hand axis instead of its own chart.
+    pub combined_chart: bool,
+    pub chart_window: ChartWindow,
 }
 
 impl AppState {
@@ -25,42 +335,856 @@ impl AppState {
         Self {
             status: None,
             connected: false,
-            flow_history: VecDeque::with_capacity(120),
-            speed_history: VecDeque::with_capacity(120),
+            poll_stats: PollStats::default(),
+            poll_interval: Duration::from_millis(500),
+            history: History::new(DEFAULT_HISTORY_SAMPLES),
+            frozen_history: None,
+            smoothing: None,
+            speed_scale: SpeedScale::default(),
+            alarms: AlarmMonitor::default(),
+            filter_banner: FilterBanner::default(),
+            deviation: DeviationDetector::default(),
+            ramp: RampTracker::default(),
+            totalizer: Totalizer::default(),
+            filter_log: FilterLog::default(),
+            reminders: ReminderSchedule::default(),
             target_flow: 0,
-            tick: 0,
+            target_preview: None,
+            target_batch: TargetBatch::default(),
+            presets: Preset::evenly_spaced(QUICK_6101A2.flow_min, QUICK_6101A2.flow_max),
+            keymap: Keymap::default(),
+            macros: Vec::new(),
+            macro_run: None,
+            macro_recorder: None,
+            config_path: None,
+            flow_step: FlowSteps::default(),
+            gauge_thresholds: GaugeThresholds::default(),
+            chart_axes: ChartAxes::default(),
             should_quit: false,
             interface,
             read_only,
-            show_debug: false,
+            tab: Tab::Dashboard,
             input_mode: false,
-            input_buffer: String::new(),
+            target_input: LineEditor::default(),
+            target_history: TargetHistory::default(),
+            flow_unit: FlowUnit::default(),
+            profile: &QUICK_6101A2,
+            identity: None,
+            config_editor: None,
+            link_address: interface.default_address(),
+            link_baud: interface.default_baud(),
+            link_wizard: None,
+            filter_reset: None,
+            filter_detail: None,
+            error_screen: None,
+            restart: None,
+            help_open: false,
+            confirm_power_off: false,
+            snooze: DEFAULT_SNOOZE,
+            snoozed_until: None,
+            power_off_prompt: false,
+            register_editor: RegisterEditor::default(),
+            pending_g: false,
+            filter_cursor: 0,
+            write_error: None,
+            previous_registers: Vec::new(),
+            register_trail: RegisterTrail::default(),
+            session_start: SystemTime::now(),
+            last_state_change: None,
+            pinned: Vec::new(),
+            register_changes: VecDeque::with_capacity(REGISTER_CHANGELOG_LEN),
+            event_log: EventLog::default(),
+            toasts: Toasts::default(),
+            log_view: LogView::default(),
+            hit_areas: HitAreas::default(),
+            theme: Theme::default(),
+            theme_name: None,
+            locale: Locale::default(),
+            colorblind: false,
+            compact: false,
+            panels: Panels::default(),
+            ascii: false,
+            marker: ChartMarker::default(),
+            combined_chart: false,
+            chart_window: ChartWindow::default(),
         }
     }
 
+    /// Applies the config file settings that can change while running; `theme` and `marker`
+    /// are the command line overrides.
+    pub fn apply_config(
+        &mut self,
+        config: &Config,
+        theme: Option<ThemeName>,
+        marker: Option<ChartMarker>,
+    ) {
+        self.theme_name = theme;
+        self.theme = config.theme.resolve(theme);
+        self.colorblind = config.theme.colorblind;
+        self.marker = marker.unwrap_or(config.theme.marker);
+        self.alarms.set_rules(config.alarms.clone());
+        self.deviation.set_settings(config.deviation);
+        self.presets = if config.presets.is_empty() {
+            Preset::evenly_spaced(self.profile.flow_min, self.profile.flow_max)
+        } else {
+            config.presets.clone()
+        };
+        self.keymap.clone_from(&config.keys);
+        self.macros.clone_from(&config.macros);
+        self.flow_step = config.flow_step;
+        self.confirm_power_off = config.confirm_power_off;
+        self.snooze = config
+            .snooze_minutes
+            .map_or(DEFAULT_SNOOZE, Duration::from_mins);
+        self.gauge_thresholds = config.filter_gauge;
+        self.chart_axes = config.chart;
+        self.speed_scale
+            .set_fixed(config.max_rpm.or(self.profile.speed_max));
+        self.reminders
+            .set_reminders(config.filter_reminders.clone());
+    }
+
     pub fn update_status(&mut self, status: DeviceStatus) {
+        self.update_status_at(status, SystemTime::now());
+    }
+
+    pub fn update_status_at(&mut self, status: DeviceStatus, timestamp: SystemTime) {
         self.target_flow = status.target_flow;
+        let mut sample = Sample::from_status(&status, timestamp);
+        if let Some(ema) = self.smoothing.as_mut() {
+            sample.smoothed_flow = ema.update(sample.real_flow);
+        }
+        self.history.push(sample);
+        self.speed_scale.observe(status.speed_rpm);
+        self.alarms.evaluate(&status, self.profile, timestamp);
+        self.filter_banner.evaluate(&status, self.profile.filters);
+        self.totalizer.add(timestamp, f64::from(status.real_flow));
+        if let Some(active) = self.deviation.evaluate(&status, timestamp) {
+            let message = if active {
+                "flow deviation: real flow is not reaching the target"
+            } else {
+                "flow deviation cleared"
+            };
+            self.event_log
+                .push(timestamp, EventSource::Monitor, message.to_string());
+        }
+        self.ramp.observe(&status, timestamp);
+        self.register_trail.push(&status.registers);
+        self.set_connected(true);
+        if self
+            .status
+            .as_ref()
+            .is_some_and(|previous| previous.state != status.state)
+        {
+            self.last_state_change = Some(timestamp);
+        }
+        if let Some(previous) = self.status.take() {
+            self.record_register_changes(&previous.registers, &status.registers, timestamp);
+            self.previous_registers = previous.registers;
+        }
         self.status = Some(status);
-        self.connected = true;
-        self.push_history();
     }
 
-    fn push_history(&mut self) {
-        let tick = f64::from(self.tick);
-        if let Some(status) = &self.status {
-            self.flow_history
-                .push_back((tick, f64::from(status.real_flow)));
-            self.flow_history
-                .push_back((tick, f64::from(status.target_flow)));
-            self.speed_history
-                .push_back((tick, f64::from(status.speed_rpm)));
+    /// Periodic work done on every UI tick, independent of polls arriving; `true` when it
+    /// changed something on screen.
+    pub fn tick(&mut self, now: SystemTime) -> bool {
+        let mut changed = self.toasts.expire(now);
+        if self.snoozed_until.is_some_and(|until| now >= until) {
+            self.snoozed_until = None;
+            self.event_log
+                .push(now, EventSource::Monitor, String::from("alarm snooze over"));
+            changed = true;
+        }
+        let today = DateTime::<Local>::from(now).date_naive();
+        for filter in self.reminders.evaluate(self.status.as_ref(), today) {
+            self.event_log.push(
+                now,
+                EventSource::Monitor,
+                format!("{} replacement due", filter.label()),
+            );
+            changed = true;
+        }
+        changed
+    }
+
+    /// Polls every `interval`, keeping writes pending for the same number of polls.
+    pub fn set_poll_interval(&mut self, interval: Duration) {
+        self.poll_interval = interval;
+        self.event_log
+            .set_pending_timeout(interval * PENDING_WRITE_POLLS);
+    }
+
+    /// Moves the poll interval to the next longer or shorter step of `POLL_INTERVALS_MS`;
+    /// `None` when there is none.
+    pub fn step_poll_interval(&mut self, longer: bool) -> Option<Duration> {
+        let current = u64::try_from(self.poll_interval.as_millis()).unwrap_or(u64::MAX);
+        let next = if longer {
+            POLL_INTERVALS_MS.iter().find(|ms| **ms > current)
+        } else {
+            POLL_INTERVALS_MS.iter().rev().find(|ms| **ms < current)
+        };
+        let interval = Duration::from_millis(*next?);
+        self.set_poll_interval(interval);
+        self.event_log.push(
+            SystemTime::now(),
+            EventSource::User,
+            format!("poll interval set to {} ms", interval.as_millis()),
+        );
+        Some(interval)
+    }
+
+    /// Age of the shown reading once polls have stopped being answered for a while.
+    pub fn stale_age(&self, now: SystemTime) -> Option<Duration> {
+        self.status.as_ref()?;
+        let age = self.poll_stats.since_success(now)?;
+        (age > (self.poll_interval * STALE_POLLS).max(STALE_MIN_AGE)).then_some(age)
+    }
+
+    /// Motor, filter, link and beeper health. The status flag bits are undocumented, so these
+    /// are derived from the decoded readings instead.
+    pub fn indicators(&self, now: SystemTime) -> Vec<Indicator> {
+        let status = self.status.as_ref();
+        let mut indicators = vec![Indicator {
+            label: "Motor",
+            ok: status
+                .filter(|status| status.state == STATE_ON)
+                .map(|status| status.speed_rpm > 0),
+        }];
+        indicators.extend(self.profile.filters.iter().map(|filter| {
+            Indicator {
+                label: &filter.label()[..1],
+                ok: status
+                    .filter(|status| status.filter_limit(*filter) > 0)
+                    .map(|status| status.filter_total(*filter) < status.filter_limit(*filter)),
+            }
+        }));
+        indicators.extend([
+            Indicator {
+                label: "Comms",
+                ok: Some(self.connected && self.stale_age(now).is_none()),
+            },
+            Indicator {
+                label: "Beeper",
+                ok: status
+                    .and_then(|status| status.register(REG_BEEPER))
+                    .and_then(|beeper| (beeper == BEEPER_ON).then_some(true)),
+            },
+        ]);
+        indicators
+    }
+
+    /// Pins `register` to the dashboard, or unpins it if it already is.
+    pub fn toggle_pin(&mut self, register: u16) {
+        match self.pinned.binary_search(&register) {
+            Ok(index) => {
+                self.pinned.remove(index);
+            }
+            Err(index) => self.pinned.insert(index, register),
+        }
+    }
+
+    /// The pane on screen, which takes the page keys.
+    pub const fn focused_pane(&self) -> Option<Pane> {
+        match self.tab {
+            Tab::Registers => Some(Pane::Registers),
+            Tab::Filters => Some(Pane::Filters),
+            Tab::Log => Some(Pane::Log),
+            Tab::Dashboard | Tab::Config => None,
+        }
+    }
+
+    /// A popup, prompt or editor is taking the keys.
+    pub const fn modal_open(&self) -> bool {
+        self.input_mode
+            || self.error_screen.is_some()
+            || self.help_open
+            || self.power_off_prompt
+            || self.config_editor.is_some()
+            || self.link_wizard.is_some()
+            || self.filter_reset.is_some()
+            || self.register_editor.editing
+            || self.register_editor.pending.is_some()
+            || self.register_editor.searching
+    }
+
+    /// A text field is open, so typed characters are text rather than hotkeys.
+    pub fn typing(&self) -> bool {
+        self.input_mode
+            || self.register_editor.editing
+            || self.register_editor.pending.is_some()
+            || self.register_editor.searching
+            || self
+                .error_screen
+                .as_ref()
+                .is_some_and(|screen| screen.port.is_some())
+            || self
+                .config_editor
+                .as_ref()
+                .is_some_and(|editor| editor.editing || editor.pending.is_some())
+            || self
+                .link_wizard
+                .as_ref()
+                .is_some_and(|wizard| wizard.step.typed())
+            || self
+                .filter_reset
+                .as_ref()
+                .is_some_and(|dialog| dialog.stage == ResetStage::Type)
+    }
+
+    /// Dashboard panels to lay out; the combined chart takes the speed chart's place.
+    pub fn shown_panels(&self) -> Panels {
+        Panels {
+            speed: self.panels.speed && !self.combined_chart,
+            ..self.panels
         }
-        self.tick = self.tick.wrapping_add(1);
-        while self.flow_history.len() > 240 {
-            self.flow_history.pop_front();
+    }
+
+    /// Freezes the charts on the current history, or resumes live scrolling.
+    pub fn toggle_pause(&mut self) {
+        self.frozen_history = match self.frozen_history {
+            Some(_) => None,
+            None => Some(self.history.clone()),
+        };
+    }
+
+    /// History the charts draw: the frozen snapshot while paused, otherwise live.
+    pub fn chart_history(&self) -> &History {
+        self.frozen_history.as_ref().unwrap_or(&self.history)
+    }
+
+    /// Whether a register differs from the previous poll.
+    pub fn register_changed(&self, register: usize, value: u16) -> bool {
+        self.previous_registers
+            .get(register)
+            .is_some_and(|previous| *previous != value)
+    }
+
+    fn record_register_changes(&mut self, old: &[u16], new: &[u16], timestamp: SystemTime) {
+        for (index, (old, new)) in old.iter().zip(new).enumerate() {
+            if old == new {
+                continue;
+            }
+            let Ok(register) = u16::try_from(index) else {
+                break;
+            };
+            self.event_log
+                .record_change(self.profile, register, *old, *new, timestamp);
+            if self.register_changes.len() == REGISTER_CHANGELOG_LEN {
+                self.register_changes.pop_front();
+            }
+            self.register_changes.push_back(RegisterChange {
+                timestamp,
+                register,
+                old: *old,
+                new: *new,
+            });
         }
-        while self.speed_history.len() > 120 {
-            self.speed_history.pop_front();
+    }
+
+    pub fn handle_applied_command(&mut self, command: &TransportCommand) {
+        if let Some(recorder) = self.macro_recorder.as_mut() {
+            recorder.record(command, SystemTime::now());
         }
+        let Some((register, value)) = command.register_write() else {
+            return;
+        };
+        let now = SystemTime::now();
+        self.event_log
+            .record_write(self.profile, register, value, now);
+        let message = match self.profile.register_info(register) {
+            Some(info) => self.locale.format(
+                "{} set to {}",
+                &[
+                    &info.name,
+                    &format!("{} {}", info.format_value(value), info.unit).trim_end(),
+                ],
+            ),
+            None => self
+                .locale
+                .format("{} set to {}", &[&format!("0x{register:04X}"), &value]),
+        };
+        self.toasts.push(now, Severity::Info, message);
+    }
+
+    /// Silences the raised alarms and the filter banner until they clear and are raised again,
+    /// logging what was acknowledged.
+    pub fn acknowledge_alarms(&mut self, now: SystemTime) {
+        let mut acknowledged: Vec<String> = self
+            .alarms
+            .acknowledge()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        acknowledged.extend(
+            self.filter_banner
+                .acknowledge()
+                .into_iter()
+                .map(|filter| format!("{} limit", filter.label())),
+        );
+        if !acknowledged.is_empty() {
+            self.event_log.push(
+                now,
+                EventSource::User,
+                format!("acknowledged {}", acknowledged.join(", ")),
+            );
+        }
+    }
+
+    /// Hides the alarms, reminders and filter banner for `snooze`, or brings them back when
+    /// they are already snoozed.
+    pub fn toggle_snooze(&mut self, now: SystemTime) {
+        let message = if self.snoozed_until.take().is_some() {
+            String::from("alarm snooze ended")
+        } else {
+            self.snoozed_until = Some(now + self.snooze);
+            format!("alarms snoozed for {} min", self.snooze.as_secs() / 60)
+        };
+        self.event_log.push(now, EventSource::User, message);
+    }
+
+    pub fn snoozed(&self) -> bool {
+        self.snoozed_until.is_some()
+    }
+
+    /// The filter limit banner is raised, not acknowledged and not snoozed.
+    pub fn filter_banner_shown(&self) -> bool {
+        self.filter_banner.is_visible() && !self.snoozed()
+    }
+
+    /// Plays the macro bound to the key, replacing one already playing; `false` when no macro
+    /// has the key.
+    pub fn start_macro(&mut self, chord: KeyChord, now: SystemTime) -> bool {
+        let Some(played) = self
+            .macros
+            .iter()
+            .find(|played| played.chord().is_ok_and(|bound| bound == chord))
+        else {
+            return false;
+        };
+        self.event_log.push(
+            now,
+            EventSource::User,
+            format!("macro {} started", played.name),
+        );
+        self.macro_run = Some(MacroRun::new(played));
+        true
+    }
+
+    /// Stops the macro being played, returning whether one was.
+    pub fn stop_macro(&mut self, now: SystemTime) -> bool {
+        let Some(run) = self.macro_run.take() else {
+            return false;
+        };
+        self.event_log.push(
+            now,
+            EventSource::User,
+            format!("macro {} stopped", run.name),
+        );
+        true
+    }
+
+    /// Commands of the playing macro due at `now`; the macro is dropped once it ran to the end.
+    pub fn macro_commands(&mut self, now: SystemTime) -> Vec<TransportCommand> {
+        let Some(run) = self.macro_run.as_mut() else {
+            return Vec::new();
+        };
+        let mut commands = Vec::new();
+        while let Some(step) = run.due(now) {
+            if let Some(command) = step.command(self.profile) {
+                if let TransportCommand::SetTargetFlow(flow) = command {
+                    self.target_flow = flow;
+                }
+                commands.push(command);
+            }
+        }
+        if run.finished() {
+            let message = format!("macro {} finished", run.name);
+            self.macro_run = None;
+            self.event_log.push(now, EventSource::User, message);
+        }
+        commands
+    }
+
+    /// Starts recording the accepted commands, or stops and binds the recording to the first
+    /// free function key, appending it to the config file when there is one.
+    pub fn toggle_recording(&mut self, now: SystemTime) {
+        let Some(recorder) = self.macro_recorder.take() else {
+            self.macro_recorder = Some(MacroRecorder::new(now));
+            let message = self.locale.text("Recording a macro").to_string();
+            self.toasts.push(now, Severity::Info, message);
+            return;
+        };
+        let Some(key) = free_key(&self.keymap, &self.macros) else {
+            let message = self
+                .locale
+                .text("No free key F6-F12 for the macro")
+                .to_string();
+            self.toasts.push(now, Severity::Warning, message);
+            return;
+        };
+        let name = DateTime::<Local>::from(now)
+            .format("recorded %Y-%m-%d %H:%M")
+            .to_string();
+        let Some(new_macro) = recorder.finish(name, key.clone()) else {
+            let message = self.locale.text("Nothing recorded").to_string();
+            self.toasts.push(now, Severity::Info, message);
+            return;
+        };
+        match self
+            .config_path
+            .as_deref()
+            .map(|path| new_macro.append_to(path))
+        {
+            Some(Ok(())) => self.event_log.push(
+                now,
+                EventSource::User,
+                format!("macro {} saved on {key}", new_macro.name),
+            ),
+            None => self.event_log.push(
+                now,
+                EventSource::User,
+                format!(
+                    "macro {} on {key} kept until exit; start with a config file to save it",
+                    new_macro.name
+                ),
+            ),
+            Some(Err(err)) => self.event_log.push_with_severity(
+                now,
+                EventSource::Monitor,
+                Severity::Warning,
+                format!("macro {} not saved: {err:#}", new_macro.name),
+            ),
+        }
+        let message = self.locale.format("Macro recorded on {}", &[&key]);
+        self.toasts.push(now, Severity::Info, message);
+        self.macros.push(new_macro);
+    }
+
+    /// Asks for a reconnect through the device's other interface.
+    pub fn switch_interface(&mut self, now: SystemTime) {
+        if let Some(interface) = self.interface.other() {
+            self.restart = Some(Restart::Interface(interface));
+        } else {
+            let message = self
+                .locale
+                .text("The simulation has no other interface")
+                .to_string();
+            self.toasts.push(now, Severity::Info, message);
+        }
+    }
+
+    /// Copies the last polled registers to the clipboard as a table.
+    pub fn copy_registers(&mut self, now: SystemTime) {
+        let Some(status) = &self.status else {
+            let message = self.locale.text("No register data yet").to_string();
+            self.toasts.push(now, Severity::Warning, message);
+            return;
+        };
+        let table = clipboard::register_table(self.profile, &status.registers);
+        let count = status.registers.len();
+        match clipboard::copy(&table) {
+            Ok(via) => {
+                self.event_log.push(
+                    now,
+                    EventSource::User,
+                    format!("copied {count} registers via {via}"),
+                );
+                let message = self.locale.format("Copied {} registers", &[&count]);
+                self.toasts.push(now, Severity::Info, message);
+            }
+            Err(err) => {
+                self.event_log.push_with_severity(
+                    now,
+                    EventSource::Monitor,
+                    Severity::Warning,
+                    format!("copy failed: {err:#}"),
+                );
+                let message = self.locale.text("Copy failed").to_string();
+                self.toasts.push(now, Severity::Warning, message);
+            }
+        }
+    }
+
+    pub fn handle_rejected_write(&mut self, reason: String) {
+        let now = SystemTime::now();
+        self.event_log
+            .push(now, EventSource::User, format!("write rejected: {reason}"));
+        self.toasts.push(
+            now,
+            Severity::Error,
+            self.locale.format("Write rejected: {}", &[&reason]),
+        );
+        self.write_error = Some(reason);
+    }
+
+    /// Tracks the link state, announcing when polls are answered again after an outage.
+    fn set_connected(&mut self, connected: bool) {
+        if connected && !self.connected && self.status.is_some() {
+            self.toasts.push(
+                SystemTime::now(),
+                Severity::Info,
+                self.locale.text("Reconnected").to_string(),
+            );
+        }
+        self.connected = connected;
+    }
+
+    pub fn handle_transport_event(&mut self, event: TransportEvent) {
+        match event {
+            TransportEvent::Status(status) => self.update_status(status),
+            TransportEvent::Connection(connected) => self.set_connected(connected),
+            TransportEvent::Poll(outcome) => self.poll_stats.record(outcome, SystemTime::now()),
+            TransportEvent::Fault(message) => self.handle_transport_fault(message, false),
+            TransportEvent::Link(outcome) => self.handle_link_outcome(&outcome),
+            TransportEvent::Rejected(reason) => self.handle_rejected_write(reason),
+            TransportEvent::Identity(identity) => self.identity = Some(identity),
+            TransportEvent::Applied(command) => self.handle_applied_command(&command),
+            TransportEvent::Error(err) => {
+                let message = format!("{:#}", err.wrap_err("serial connection failed"));
+                self.handle_transport_fault(message, true);
+            }
+        }
+    }
+
+    /// Logs a link error without ending the session; `fatal` marks a stopped worker and opens
+    /// the error screen.
+    pub fn handle_transport_fault(&mut self, message: String, fatal: bool) {
+        let severity = if fatal {
+            self.connected = false;
+            if self.error_screen.is_none() {
+                self.error_screen = Some(ErrorScreen::new(message.clone()));
+            }
+            Severity::Error
+        } else {
+            Severity::Warning
+        };
+        let now = SystemTime::now();
+        self.toasts.push(now, severity, message.clone());
+        self.event_log
+            .push_with_severity(now, EventSource::Transport, severity, message);
+    }
+
+    pub fn handle_link_outcome(&mut self, outcome: &LinkOutcome) {
+        if let LinkOutcome::Verified { address, baud }
+        | LinkOutcome::Unresponsive { address, baud } = outcome
+        {
+            self.link_address = *address;
+            self.link_baud = *baud;
+        }
+        let message = match outcome {
+            LinkOutcome::Verified { address, baud } => {
+                format!("link moved to address {address}, {baud} baud")
+            }
+            LinkOutcome::Rejected(reason) => format!("link change rejected: {reason}"),
+            LinkOutcome::Unresponsive { address, baud } => {
+                format!("no answer at address {address}, {baud} baud")
+            }
+            LinkOutcome::Indeterminate(reason) => {
+                format!("link change interrupted, device settings unknown: {reason}")
+            }
+        };
+        let severity = if matches!(outcome, LinkOutcome::Verified { .. }) {
+            Severity::Info
+        } else {
+            Severity::Warning
+        };
+        let now = SystemTime::now();
+        self.toasts.push(now, severity, message.clone());
+        self.event_log.push(now, EventSource::User, message);
+        if let Some(wizard) = self.link_wizard.as_mut() {
+            wizard.handle_outcome(outcome);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use ratatui::layout::{Position, Rect};
+
+    use crate::app::{AppState, HitAreas, slider_column};
+    use crate::constants::{REG_STATE, REG_TARGET_FLOW, STATE_ON, STATUS_POLL_REG_COUNT};
+    use crate::data::DeviceStatus;
+    use crate::history::Sample;
+    use crate::interface::InterfaceMode;
+    use crate::transport::{PollOutcome, TransportCommand, TransportEvent};
+
+    #[test]
+    fn applied_writes_and_reconnects_raise_toasts() {
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        app.handle_applied_command(&TransportCommand::SetTargetFlow(75));
+        let status = DeviceStatus::from_registers(vec![0; STATUS_POLL_REG_COUNT as usize])
+            .expect("status should parse");
+        app.update_status(status.clone());
+        app.handle_transport_event(TransportEvent::Connection(false));
+        app.update_status(status);
+        let messages: Vec<&str> = app
+            .toasts
+            .iter()
+            .map(|toast| toast.message.as_str())
+            .collect();
+        assert_eq!(messages, ["Target set to 75 m3/h", "Reconnected"]);
+    }
+
+    #[test]
+    fn power_state_changes_are_timestamped() {
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        let mut status = DeviceStatus::from_registers(vec![0; STATUS_POLL_REG_COUNT as usize])
+            .expect("status should parse");
+        app.update_status_at(status.clone(), UNIX_EPOCH);
+        app.update_status_at(status.clone(), UNIX_EPOCH + Duration::from_secs(1));
+        assert_eq!(app.last_state_change, None);
+        status.state = STATE_ON;
+        let switched = UNIX_EPOCH + Duration::from_secs(2);
+        app.update_status_at(status.clone(), switched);
+        app.update_status_at(status, UNIX_EPOCH + Duration::from_secs(3));
+        assert_eq!(app.last_state_change, Some(switched));
+    }
+
+    #[test]
+    fn indicators_follow_motor_filters_and_link() {
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        let oks = |app: &AppState| -> Vec<Option<bool>> {
+            app.indicators(SystemTime::now())
+                .iter()
+                .map(|indicator| indicator.ok)
+                .collect()
+        };
+        assert_eq!(oks(&app), [None, None, None, None, Some(false), None]);
+
+        let mut status = DeviceStatus::from_registers(vec![0; STATUS_POLL_REG_COUNT as usize])
+            .expect("status should parse");
+        status.state = STATE_ON;
+        status.speed_rpm = 1200;
+        status.p_filter_total = 50;
+        status.p_filter_limit = 50;
+        status.m_filter_limit = 100;
+        app.update_status(status);
+        assert_eq!(
+            oks(&app),
+            [Some(true), Some(false), Some(true), None, Some(true), None]
+        );
+    }
+
+    #[test]
+    fn poll_interval_steps_along_the_ladder() {
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        app.set_poll_interval(Duration::from_millis(300));
+        assert_eq!(
+            app.step_poll_interval(true),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            app.step_poll_interval(false),
+            Some(Duration::from_millis(250))
+        );
+        app.set_poll_interval(Duration::from_millis(100));
+        assert_eq!(app.step_poll_interval(false), None);
+        assert_eq!(app.poll_interval, Duration::from_millis(100));
+    }
+
+    #[test]
+    fn reading_goes_stale_after_missed_polls() {
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        let start = UNIX_EPOCH + Duration::from_secs(100);
+        app.poll_stats
+            .record(PollOutcome::Answered(Duration::from_millis(20)), start);
+        assert_eq!(app.stale_age(start + Duration::from_secs(10)), None);
+
+        let status = DeviceStatus::from_registers(vec![0; STATUS_POLL_REG_COUNT as usize])
+            .expect("status should parse");
+        app.update_status_at(status, start);
+        assert_eq!(app.stale_age(start + Duration::from_secs(2)), None);
+        assert_eq!(
+            app.stale_age(start + Duration::from_secs(3)),
+            Some(Duration::from_secs(3))
+        );
+        app.poll_interval = Duration::from_secs(2);
+        assert_eq!(app.stale_age(start + Duration::from_secs(5)), None);
+    }
+
+    #[test]
+    fn samples_carry_their_arrival_time() {
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        app.history.set_capacity(2);
+        for seconds in [10, 11, 15] {
+            let status = DeviceStatus::from_registers(vec![0; STATUS_POLL_REG_COUNT as usize])
+                .expect("status should parse");
+            app.update_status_at(status, UNIX_EPOCH + Duration::from_secs(seconds));
+        }
+
+        let times: Vec<f64> = app.history.iter().map(Sample::seconds).collect();
+        assert_eq!(times, vec![11.0, 15.0]);
+    }
+
+    #[test]
+    fn register_changes_are_tracked_between_polls() {
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        let mut registers = vec![0; STATUS_POLL_REG_COUNT as usize];
+        registers[usize::from(REG_TARGET_FLOW)] = 70;
+        let first = DeviceStatus::from_registers(registers.clone()).expect("status should parse");
+        app.update_status_at(first, UNIX_EPOCH);
+        registers[usize::from(REG_TARGET_FLOW)] = 75;
+        let second = DeviceStatus::from_registers(registers).expect("status should parse");
+        app.update_status_at(second, UNIX_EPOCH + Duration::from_secs(1));
+
+        assert!(app.register_changed(usize::from(REG_TARGET_FLOW), 75));
+        assert!(!app.register_changed(usize::from(REG_STATE), 0));
+        let change = app.register_changes.back().expect("change recorded");
+        assert_eq!(
+            (change.register, change.old, change.new),
+            (REG_TARGET_FLOW, 70, 75)
+        );
+    }
+
+    #[test]
+    fn paused_charts_keep_their_snapshot_while_polling_continues() {
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        let status = || {
+            DeviceStatus::from_registers(vec![0; STATUS_POLL_REG_COUNT as usize])
+                .expect("status should parse")
+        };
+        app.update_status_at(status(), UNIX_EPOCH);
+        app.toggle_pause();
+        app.update_status_at(status(), UNIX_EPOCH + Duration::from_secs(1));
+
+        assert_eq!(app.chart_history().iter().count(), 1);
+        assert_eq!(app.history.iter().count(), 2);
+        app.toggle_pause();
+        assert_eq!(app.chart_history().iter().count(), 2);
+    }
+
+    #[test]
+    fn slider_columns_and_clicks_map_onto_each_other() {
+        let areas = HitAreas {
+            slider: Rect::new(10, 5, 71, 1),
+            ..HitAreas::default()
+        };
+        assert_eq!(slider_column(30, 30, 100, 71), 0);
+        assert_eq!(slider_column(65, 30, 100, 71), 35);
+        assert_eq!(slider_column(100, 30, 100, 71), 70);
+        assert_eq!(
+            areas.slider_value_at(Position::new(10, 5), 30, 100),
+            Some(30)
+        );
+        assert_eq!(
+            areas.slider_value_at(Position::new(45, 5), 30, 100),
+            Some(65)
+        );
+        assert_eq!(
+            areas.slider_value_at(Position::new(80, 5), 30, 100),
+            Some(100)
+        );
+        assert_eq!(areas.slider_value_at(Position::new(45, 6), 30, 100), None);
+    }
+
+    #[test]
+    fn combined_chart_takes_the_speed_chart_place() {
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        assert!(app.shown_panels().speed);
+        app.combined_chart = true;
+        assert!(!app.shown_panels().speed);
+        assert!(app.panels.speed);
     }
 }
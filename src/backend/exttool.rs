@@ -9,9 +9,10 @@ use crate::constants::{
     REG_C_FILTER_LIMIT, REG_C_FILTER_TOTAL, REG_M_FILTER_LIMIT, REG_M_FILTER_TOTAL,
     REG_P_FILTER_LIMIT, REG_P_FILTER_TOTAL, REG_REAL_FLOW, REG_SPEED_RPM, REG_STATE,
     REG_STATUS_FLAGS, REG_TARGET_FLOW, REG_TUBE_DIAMETER, STATE_OFF, STATE_ON,
-    STATUS_POLL_REG_COUNT, TARGET_FLOW_MAX, TARGET_FLOW_MIN,
+    STATUS_POLL_REG_COUNT,
 };
 use crate::data::DeviceStatus;
+use crate::profile::DeviceProfile;
 use crate::rtu::{append_crc, read_exact_with_timeout, validate_crc};
 use crate::transport::TransportCommand;
 
@@ -41,10 +42,16 @@ pub(crate) struct ExtToolBackend {
     port: Box<dyn SerialPort>,
     address: u8,
     io_timeout: Duration,
+    profile: &'static DeviceProfile,
 }
 
 impl ExtToolBackend {
-    pub(crate) fn new(path: &str, baud: u32, address: u8) -> eyre::Result<Self> {
+    pub(crate) fn new(
+        path: &str,
+        baud: u32,
+        address: u8,
+        profile: &'static DeviceProfile,
+    ) -> eyre::Result<Self> {
         let io_timeout = Duration::from_millis(400);
         let port = serialport::new(path, baud)
             .timeout(io_timeout)
@@ -54,6 +61,7 @@ impl ExtToolBackend {
             port,
             address,
             io_timeout,
+            profile,
         })
     }
 
@@ -137,13 +145,56 @@ impl Backend for ExtToolBackend {
                 let state = if *on { STATE_ON } else { STATE_OFF };
                 self.write_single_register(IDX_STATE, state)
             }
-            TransportCommand::SetTargetFlow(flow) => self.write_single_register(
-                IDX_TARGET_FLOW,
-                (*flow).clamp(TARGET_FLOW_MIN, TARGET_FLOW_MAX),
-            ),
-            TransportCommand::Terminate => Ok(()),
+            TransportCommand::SetTargetFlow(flow) => {
+                self.write_single_register(IDX_TARGET_FLOW, self.profile.clamp_flow(*flow))
+            }
+            TransportCommand::SetBeeper(_) => {
+                Err(eyre::eyre!("beeper control is not supported over exttool"))
+            }
+            TransportCommand::SetMode(_) => {
+                Err(eyre::eyre!("mode changes are not supported over exttool"))
+            }
+            TransportCommand::ResetFilter(_) => {
+                Err(eyre::eyre!("filter resets are not supported over exttool"))
+            }
+            TransportCommand::WriteRegister { register, value } => {
+                let index = exttool_index_for_register(*register).ok_or_else(|| {
+                    eyre::eyre!("register 0x{register:04X} is not writable over exttool")
+                })?;
+                self.write_single_register(index, *value)
+            }
+            TransportCommand::Reconfigure { .. }
+            | TransportCommand::Reconnect { .. }
+            | TransportCommand::SetPollInterval(_)
+            | TransportCommand::PollNow
+            | TransportCommand::Terminate => Ok(()),
         }
     }
+
+    fn supports(&self, command: &TransportCommand) -> bool {
+        exttool_supports(command)
+    }
+}
+
+/// The exttool protocol only exposes the power state and target flow for writing.
+fn exttool_supports(command: &TransportCommand) -> bool {
+    match command {
+        TransportCommand::SetBeeper(_)
+        | TransportCommand::SetMode(_)
+        | TransportCommand::ResetFilter(_) => false,
+        TransportCommand::WriteRegister { register, .. } => {
+            exttool_index_for_register(*register).is_some()
+        }
+        _ => true,
+    }
+}
+
+fn exttool_index_for_register(register: u16) -> Option<u8> {
+    match register {
+        REG_STATE => Some(IDX_STATE),
+        REG_TARGET_FLOW => Some(IDX_TARGET_FLOW),
+        _ => None,
+    }
 }
 
 fn build_read_request(address: u8, start: u8, count: u8) -> eyre::Result<Vec<u8>> {
@@ -301,18 +352,19 @@ fn validate_write_range(start: u8, count: u8) -> eyre::Result<()> {
 #[cfg(test)]
 mod tests {
     use super::{
-        build_read_request, build_write_request, map_status_payload, parse_read_response,
-        parse_write_response, CMD_READ_STATUS, CMD_WRITE_COMMAND, IDX_C_FILTER_LIMIT,
-        IDX_C_FILTER_TOTAL, IDX_M_FILTER_LIMIT, IDX_M_FILTER_TOTAL, IDX_P_FILTER_LIMIT,
-        IDX_P_FILTER_TOTAL, IDX_REAL_FLOW, IDX_SPEED_RPM, IDX_STATE, IDX_TARGET_FLOW,
-        STATUS_BYTE_COUNT, STATUS_START,
+        CMD_READ_STATUS, CMD_WRITE_COMMAND, IDX_C_FILTER_LIMIT, IDX_C_FILTER_TOTAL,
+        IDX_M_FILTER_LIMIT, IDX_M_FILTER_TOTAL, IDX_P_FILTER_LIMIT, IDX_P_FILTER_TOTAL,
+        IDX_REAL_FLOW, IDX_SPEED_RPM, IDX_STATE, IDX_TARGET_FLOW, STATUS_BYTE_COUNT, STATUS_START,
+        build_read_request, build_write_request, exttool_supports, map_status_payload,
+        parse_read_response, parse_write_response,
     };
     use crate::constants::{
-        REG_C_FILTER_LIMIT, REG_C_FILTER_TOTAL, REG_M_FILTER_LIMIT, REG_M_FILTER_TOTAL,
-        REG_P_FILTER_LIMIT, REG_P_FILTER_TOTAL, REG_REAL_FLOW, REG_SPEED_RPM, REG_STATE,
-        REG_STATUS_FLAGS, REG_TARGET_FLOW, REG_TUBE_DIAMETER, STATUS_POLL_REG_COUNT,
+        REG_C_FILTER_LIMIT, REG_C_FILTER_TOTAL, REG_CALIBRATION_FACTOR, REG_M_FILTER_LIMIT,
+        REG_M_FILTER_TOTAL, REG_P_FILTER_LIMIT, REG_P_FILTER_TOTAL, REG_REAL_FLOW, REG_SPEED_RPM,
+        REG_STATE, REG_STATUS_FLAGS, REG_TARGET_FLOW, REG_TUBE_DIAMETER, STATUS_POLL_REG_COUNT,
     };
     use crate::rtu::append_crc;
+    use crate::transport::TransportCommand;
 
     #[test]
     fn builds_read_request_with_expected_shape() {
@@ -401,6 +453,20 @@ mod tests {
         assert_eq!(status.state, 1);
     }
 
+    #[test]
+    fn unsupported_commands_are_reported() {
+        assert!(exttool_supports(&TransportCommand::SetPower(true)));
+        assert!(exttool_supports(&TransportCommand::WriteRegister {
+            register: REG_TARGET_FLOW,
+            value: 60,
+        }));
+        assert!(!exttool_supports(&TransportCommand::SetBeeper(true)));
+        assert!(!exttool_supports(&TransportCommand::WriteRegister {
+            register: REG_CALIBRATION_FACTOR,
+            value: 100,
+        }));
+    }
+
     fn set_u16(payload: &mut [u8], idx: u8, value: u16) {
         let offset = usize::from(idx - STATUS_START) * 2;
         let [hi, lo] = value.to_be_bytes();
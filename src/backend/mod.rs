@@ -1,18 +1,43 @@
 use color_eyre::eyre;
 
-use crate::data::DeviceStatus;
+use crate::data::{DeviceIdentity, DeviceStatus};
 use crate::interface::InterfaceMode;
 use crate::transport::{TransportCommand, TransportConfig};
 
 mod exttool;
 mod remote;
 
+pub(crate) use remote::remote_write_for_command;
+
 #[cfg(debug_assertions)]
 mod sim;
 
 pub(crate) trait Backend {
     fn poll_status(&mut self) -> eyre::Result<DeviceStatus>;
     fn apply_command(&mut self, command: &TransportCommand) -> eyre::Result<()>;
+
+    /// Whether the interface can perform `command` at all, independent of link health.
+    fn supports(&self, _command: &TransportCommand) -> bool {
+        true
+    }
+
+    /// Reads model and firmware identification, if the interface exposes any.
+    fn identify(&mut self) -> eyre::Result<DeviceIdentity> {
+        Err(eyre::eyre!("identification not supported"))
+    }
+}
+
+/// Placeholder used while a backend is being replaced.
+pub(crate) struct DetachedBackend;
+
+impl Backend for DetachedBackend {
+    fn poll_status(&mut self) -> eyre::Result<DeviceStatus> {
+        Err(eyre::eyre!("backend detached"))
+    }
+
+    fn apply_command(&mut self, _command: &TransportCommand) -> eyre::Result<()> {
+        Err(eyre::eyre!("backend detached"))
+    }
 }
 
 pub(crate) fn build_backend(config: &TransportConfig) -> eyre::Result<Box<dyn Backend + Send>> {
@@ -30,13 +55,14 @@ pub(crate) fn build_backend(config: &TransportConfig) -> eyre::Result<Box<dyn Ba
                 .port
                 .as_ref()
                 .ok_or_else(|| eyre::eyre!("serial port required"))?;
-            let backend = exttool::ExtToolBackend::new(port, config.baud, config.address)?;
+            let backend =
+                exttool::ExtToolBackend::new(port, config.baud, config.address, config.profile)?;
             Ok(Box::new(backend))
         }
         InterfaceMode::Simulation => {
             #[cfg(debug_assertions)]
             {
-                Ok(Box::new(sim::SimBackend::new()))
+                Ok(Box::new(sim::SimBackend::new(config.profile)))
             }
             #[cfg(not(debug_assertions))]
             {
@@ -1,19 +1,26 @@
-use std::io::Write;
+use std::io::{Read, Write};
 use std::time::Duration;
 
 use color_eyre::eyre;
-use serialport::SerialPort;
+use serialport::{ClearBuffer, SerialPort};
 
 use crate::backend::Backend;
 use crate::constants::{
-    REG_STATE, REG_TARGET_FLOW, STATE_OFF, STATE_ON, STATUS_POLL_REG_COUNT, STATUS_POLL_REG_START,
+    BEEPER_OFF, BEEPER_ON, REG_BEEPER, REG_MODE, REG_STATE, REG_TARGET_FLOW, STATE_OFF, STATE_ON,
+    STATUS_POLL_REG_COUNT, STATUS_POLL_REG_START,
 };
-use crate::data::DeviceStatus;
+use crate::data::{DeviceIdentity, DeviceStatus};
 use crate::rtu::{append_crc, read_exact_with_timeout, validate_crc};
 use crate::transport::TransportCommand;
 
 const FUNC_READ_HOLDING_REGISTERS: u8 = 0x03;
 const FUNC_WRITE_SINGLE_REGISTER: u8 = 0x06;
+/// Standard Modbus "report server ID". The register map documents no identification
+/// registers, so this is a best effort: units without it answer with an exception and the
+/// header simply shows no identity.
+const FUNC_REPORT_SERVER_ID: u8 = 0x11;
+/// Exception replies are address, function, code and CRC.
+const EXCEPTION_FRAME_LEN: usize = 5;
 
 pub(crate) struct RemoteBackend {
     port: Box<dyn SerialPort>,
@@ -52,6 +59,27 @@ impl RemoteBackend {
         parse_write_single_response(&response, self.address, register, value)
     }
 
+    fn report_server_id(&mut self) -> eyre::Result<DeviceIdentity> {
+        let request = append_crc(&[self.address, FUNC_REPORT_SERVER_ID]);
+        self.send_request(&request)?;
+        let result = self
+            .read_response_header(FUNC_REPORT_SERVER_ID)
+            .and_then(|header| {
+                let byte_count = usize::from(header[2]);
+                let tail =
+                    read_exact_with_timeout(&mut *self.port, byte_count + 2, self.io_timeout)?;
+                let mut frame = header;
+                frame.extend_from_slice(&tail);
+                parse_report_server_id_response(&frame)
+            });
+        if result.is_err() {
+            // A unit that ignores or garbles the optional request must not leave bytes
+            // behind for the next poll.
+            self.port.clear(ClearBuffer::Input).ok();
+        }
+        result
+    }
+
     fn send_request(&mut self, request: &[u8]) -> eyre::Result<()> {
         self.port
             .write_all(request)
@@ -62,9 +90,17 @@ impl RemoteBackend {
         Ok(())
     }
 
+    fn read_response_header(&mut self, expected_func: u8) -> eyre::Result<Vec<u8>> {
+        read_response_header(
+            &mut *self.port,
+            self.address,
+            expected_func,
+            self.io_timeout,
+        )
+    }
+
     fn read_read_holding_response(&mut self) -> eyre::Result<Vec<u8>> {
-        let header = read_exact_with_timeout(&mut *self.port, 3, self.io_timeout)?;
-        validate_response_header(self.address, FUNC_READ_HOLDING_REGISTERS, &header)?;
+        let header = self.read_response_header(FUNC_READ_HOLDING_REGISTERS)?;
         let byte_count = usize::from(header[2]);
         let tail = read_exact_with_timeout(&mut *self.port, byte_count + 2, self.io_timeout)?;
         let mut frame = header;
@@ -73,8 +109,7 @@ impl RemoteBackend {
     }
 
     fn read_write_single_response(&mut self) -> eyre::Result<Vec<u8>> {
-        let header = read_exact_with_timeout(&mut *self.port, 3, self.io_timeout)?;
-        validate_response_header(self.address, FUNC_WRITE_SINGLE_REGISTER, &header)?;
+        let header = self.read_response_header(FUNC_WRITE_SINGLE_REGISTER)?;
         let tail = read_exact_with_timeout(&mut *self.port, 5, self.io_timeout)?;
         let mut frame = header;
         frame.extend_from_slice(&tail);
@@ -92,6 +127,10 @@ impl Backend for RemoteBackend {
             .ok_or_else(|| eyre::eyre!("unsupported command for remote backend"))?;
         self.write_single_register(register, value)
     }
+
+    fn identify(&mut self) -> eyre::Result<DeviceIdentity> {
+        self.report_server_id()
+    }
 }
 
 fn build_read_holding_request(address: u8, start: u16, quantity: u16) -> eyre::Result<Vec<u8>> {
@@ -187,12 +226,60 @@ fn parse_write_single_response(
     Ok(())
 }
 
+fn parse_report_server_id_response(frame: &[u8]) -> eyre::Result<DeviceIdentity> {
+    validate_crc(frame)?;
+    if frame.len() < 5 || usize::from(frame[2]) + 5 != frame.len() {
+        return Err(eyre::eyre!("report server id response length mismatch"));
+    }
+    DeviceIdentity::from_report(&frame[3..frame.len() - 2])
+        .ok_or_else(|| eyre::eyre!("empty report server id response"))
+}
+
+pub(crate) fn remote_write_for_command(command: &TransportCommand) -> Option<(u16, u16)> {
+    match command {
+        TransportCommand::SetPower(on) => {
+            let value = if *on { STATE_ON } else { STATE_OFF };
+            Some((REG_STATE, value))
+        }
+        TransportCommand::SetTargetFlow(flow) => Some((REG_TARGET_FLOW, *flow)),
+        TransportCommand::SetBeeper(on) => {
+            let value = if *on { BEEPER_ON } else { BEEPER_OFF };
+            Some((REG_BEEPER, value))
+        }
+        TransportCommand::SetMode(mode) => Some((REG_MODE, mode.register_value())),
+        TransportCommand::ResetFilter(filter) => Some((filter.total_register(), 0)),
+        TransportCommand::WriteRegister { register, value } => Some((*register, *value)),
+        TransportCommand::Reconfigure { .. }
+        | TransportCommand::Reconnect { .. }
+        | TransportCommand::SetPollInterval(_)
+        | TransportCommand::PollNow
+        | TransportCommand::Terminate => None,
+    }
+}
+
+/// Reads the first three bytes of a reply, consuming the whole frame if it is an exception.
+fn read_response_header(
+    reader: &mut dyn Read,
+    expected_addr: u8,
+    expected_func: u8,
+    timeout: Duration,
+) -> eyre::Result<Vec<u8>> {
+    let mut header = read_exact_with_timeout(reader, 3, timeout)?;
+    if header[1] == expected_func | 0x80 {
+        let crc = read_exact_with_timeout(reader, EXCEPTION_FRAME_LEN - header.len(), timeout)?;
+        header.extend_from_slice(&crc);
+        validate_crc(&header)?;
+    }
+    validate_response_header(expected_addr, expected_func, &header)?;
+    Ok(header)
+}
+
 fn validate_response_header(
     expected_addr: u8,
     expected_func: u8,
     header: &[u8],
 ) -> eyre::Result<()> {
-    if header.len() != 3 {
+    if header.len() != 3 && header.len() != EXCEPTION_FRAME_LEN {
         return Err(eyre::eyre!("response header length mismatch"));
     }
 
@@ -220,32 +307,27 @@ fn validate_response_header(
     Ok(())
 }
 
-fn remote_write_for_command(command: &TransportCommand) -> Option<(u16, u16)> {
-    match command {
-        TransportCommand::SetPower(on) => {
-            let value = if *on { STATE_ON } else { STATE_OFF };
-            Some((REG_STATE, value))
-        }
-        TransportCommand::SetTargetFlow(flow) => Some((REG_TARGET_FLOW, *flow)),
-        TransportCommand::Terminate => None,
-    }
-}
-
 #[cfg(test)]
 mod tests {
+    use std::io::Cursor;
+    use std::time::Duration;
+
     use super::{
+        FUNC_READ_HOLDING_REGISTERS, FUNC_REPORT_SERVER_ID, FUNC_WRITE_SINGLE_REGISTER,
         build_read_holding_request, build_write_single_request, parse_read_holding_response,
-        parse_write_single_response, remote_write_for_command, FUNC_READ_HOLDING_REGISTERS,
-        FUNC_WRITE_SINGLE_REGISTER,
+        parse_report_server_id_response, parse_write_single_response, read_response_header,
+        remote_write_for_command,
     };
     use crate::constants::{
-        REG_STATE, REG_TARGET_FLOW, STATE_OFF, STATE_ON, STATUS_POLL_REG_COUNT,
-        STATUS_POLL_REG_START,
+        BEEPER_OFF, REG_BEEPER, REG_CALIBRATION_FACTOR, REG_M_FILTER_TOTAL, REG_STATE,
+        REG_TARGET_FLOW, STATE_OFF, STATE_ON, STATUS_POLL_REG_COUNT, STATUS_POLL_REG_START,
     };
+    use crate::data::Filter;
     use crate::rtu::append_crc;
     use crate::transport::TransportCommand;
 
     #[test]
+    #[allow(clippy::cast_possible_truncation)]
     fn builds_standard_read_holding_request_frame() {
         let frame = build_read_holding_request(0x02, STATUS_POLL_REG_START, STATUS_POLL_REG_COUNT)
             .expect("frame should build");
@@ -279,6 +361,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::cast_possible_truncation)]
     fn builds_and_parses_write_single_register_frames() {
         let request = build_write_single_request(0x02, REG_TARGET_FLOW, 65);
         assert_eq!(request[0], 0x02);
@@ -296,6 +379,31 @@ mod tests {
             .expect("write response should parse");
     }
 
+    #[test]
+    fn exception_reply_is_consumed_with_its_crc() {
+        let mut input = append_crc(&[0x02, FUNC_REPORT_SERVER_ID | 0x80, 0x01]);
+        input.extend_from_slice(&[0x02, FUNC_READ_HOLDING_REGISTERS]);
+        let mut reader = Cursor::new(input);
+
+        let err = read_response_header(
+            &mut reader,
+            0x02,
+            FUNC_REPORT_SERVER_ID,
+            Duration::from_millis(50),
+        )
+        .expect_err("exception should be reported");
+        assert!(err.to_string().contains("code 0x01"));
+        assert_eq!(reader.position(), 5);
+    }
+
+    #[test]
+    fn parses_report_server_id_response() {
+        let frame = append_crc(&[0x02, FUNC_REPORT_SERVER_ID, 0x04, 0x02, 0xFF, b'V', b'2']);
+        let identity = parse_report_server_id_response(&frame).expect("identity should parse");
+        assert_eq!(identity.server_id, 2);
+        assert_eq!(identity.revision, "V2");
+    }
+
     #[test]
     fn maps_power_command_to_state_register() {
         assert_eq!(
@@ -316,6 +424,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn maps_beeper_command_to_beeper_register() {
+        assert_eq!(
+            remote_write_for_command(&TransportCommand::SetBeeper(false)),
+            Some((REG_BEEPER, BEEPER_OFF))
+        );
+    }
+
+    #[test]
+    fn maps_filter_reset_to_zeroing_the_total() {
+        assert_eq!(
+            remote_write_for_command(&TransportCommand::ResetFilter(Filter::Medium)),
+            Some((REG_M_FILTER_TOTAL, 0))
+        );
+    }
+
+    #[test]
+    fn maps_register_write_command_directly() {
+        assert_eq!(
+            remote_write_for_command(&TransportCommand::WriteRegister {
+                register: REG_CALIBRATION_FACTOR,
+                value: 105,
+            }),
+            Some((REG_CALIBRATION_FACTOR, 105))
+        );
+    }
+
     #[test]
     fn terminate_has_no_register_mapping() {
         assert_eq!(remote_write_for_command(&TransportCommand::Terminate), None);
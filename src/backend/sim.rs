@@ -1,18 +1,22 @@
 use color_eyre::eyre;
 
 use crate::backend::Backend;
-use crate::data::DeviceStatus;
+use crate::constants::{BEEPER_OFF, BEEPER_ON, REG_BEEPER, REG_MODE};
+use crate::data::{DeviceIdentity, DeviceStatus};
+use crate::profile::DeviceProfile;
 use crate::sim::SimState;
 use crate::transport::TransportCommand;
 
 pub(crate) struct SimBackend {
     sim: SimState,
+    profile: &'static DeviceProfile,
 }
 
 impl SimBackend {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(profile: &'static DeviceProfile) -> Self {
         Self {
-            sim: SimState::new(),
+            sim: SimState::new(profile),
+            profile,
         }
     }
 }
@@ -26,8 +30,32 @@ impl Backend for SimBackend {
         match command {
             TransportCommand::SetPower(on) => self.sim.set_power(*on),
             TransportCommand::SetTargetFlow(flow) => self.sim.set_target_flow(*flow),
-            TransportCommand::Terminate => {}
+            TransportCommand::SetBeeper(on) => {
+                let value = if *on { BEEPER_ON } else { BEEPER_OFF };
+                self.sim.write_register(REG_BEEPER, value);
+            }
+            TransportCommand::SetMode(mode) => {
+                self.sim.write_register(REG_MODE, mode.register_value());
+            }
+            TransportCommand::ResetFilter(filter) => {
+                self.sim.write_register(filter.total_register(), 0);
+            }
+            TransportCommand::WriteRegister { register, value } => {
+                self.sim.write_register(*register, *value);
+            }
+            TransportCommand::Reconfigure { .. }
+            | TransportCommand::Reconnect { .. }
+            | TransportCommand::SetPollInterval(_)
+            | TransportCommand::PollNow
+            | TransportCommand::Terminate => {}
         }
         Ok(())
     }
+
+    fn identify(&mut self) -> eyre::Result<DeviceIdentity> {
+        Ok(DeviceIdentity {
+            server_id: 0,
+            revision: format!("SIM-{}", self.profile.name),
+        })
+    }
 }
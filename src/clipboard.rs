@@ -0,0 +1,92 @@
+use std::env;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use color_eyre::eyre::{self, WrapErr};
+use crossterm::clipboard::CopyToClipboard;
+use crossterm::execute;
+
+use crate::profile::DeviceProfile;
+
+/// Clipboard tools tried in turn on a local session, with their arguments.
+const CLIPBOARD_COMMANDS: &[(&str, &[&str])] = &[
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("clip.exe", &[]),
+];
+
+/// Registers as a plain text table of address, name, hex and decimal, for pasting into mail.
+pub fn register_table(profile: &DeviceProfile, registers: &[u16]) -> String {
+    let header = format!("{:<8}{:<14}{:<8}{}\n", "Address", "Name", "Hex", "Decimal");
+    let rows = registers.iter().enumerate().map(|(index, value)| {
+        let name = u16::try_from(index)
+            .ok()
+            .and_then(|address| profile.register_info(address))
+            .map_or("-", |info| info.name);
+        format!(
+            "{:<8}{name:<14}{:<8}{value}\n",
+            format!("0x{index:04X}"),
+            format!("0x{value:04X}")
+        )
+    });
+    std::iter::once(header).chain(rows).collect()
+}
+
+/// Puts `text` on the clipboard, returning how: a local clipboard tool, or the terminal
+/// through OSC 52 over SSH or when no tool is installed.
+pub fn copy(text: &str) -> eyre::Result<&'static str> {
+    let remote = env::var_os("SSH_TTY").is_some() || env::var_os("SSH_CONNECTION").is_some();
+    if !remote {
+        for (program, args) in CLIPBOARD_COMMANDS {
+            if pipe_to(program, args, text).is_ok() {
+                return Ok(program);
+            }
+        }
+    }
+    execute!(io::stdout(), CopyToClipboard::to_clipboard_from(text))
+        .wrap_err("write OSC 52 sequence")?;
+    Ok("OSC 52")
+}
+
+fn pipe_to(program: &str, args: &[&str], text: &str) -> eyre::Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .wrap_err_with(|| format!("start {program}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(text.as_bytes())
+            .wrap_err_with(|| format!("write to {program}"))?;
+    }
+    let status = child
+        .wait()
+        .wrap_err_with(|| format!("wait for {program}"))?;
+    eyre::ensure!(status.success(), "{program} exited with {status}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::register_table;
+    use crate::profile::QUICK_6101A2;
+
+    #[test]
+    fn table_lists_address_name_hex_and_decimal() {
+        let table = register_table(&QUICK_6101A2, &[1, 75, 0xABCD]);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "Address Name          Hex     Decimal",
+                "0x0000  State         0x0001  1",
+                "0x0001  Target        0x004B  75",
+                "0x0002  Status        0xABCD  43981",
+            ]
+        );
+    }
+}
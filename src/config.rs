@@ -0,0 +1,595 @@
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use color_eyre::eyre::{self, WrapErr};
+use serde::Deserialize;
+
+use crate::alarm::{AlarmRule, DeviationSettings};
+use crate::export::{HttpConfig, InfluxConfig, MqttConfig};
+use crate::interface::InterfaceMode;
+use crate::keymap::Keymap;
+use crate::macros::Macro;
+use crate::reminder::FilterReminder;
+use crate::theme::ThemeConfig;
+
+/// Presets beyond this have no number key.
+pub const MAX_PRESETS: usize = 9;
+
+/// Settings loaded from the `--config` TOML file.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Serial port of the device; `--port` wins over it, like the other connection keys.
+    pub port: Option<String>,
+    pub baud: Option<u32>,
+    pub address: Option<u8>,
+    pub interface: Option<InterfaceMode>,
+    /// Poll interval in milliseconds.
+    pub poll_interval: Option<u64>,
+    #[serde(default, rename = "alarm")]
+    pub alarms: Vec<AlarmRule>,
+    #[serde(default, rename = "preset")]
+    pub presets: Vec<Preset>,
+    #[serde(default, rename = "macro")]
+    pub macros: Vec<Macro>,
+    /// Actions moved to other keys; conflicting keys are refused when the file is loaded.
+    #[serde(default)]
+    pub keys: Keymap,
+    #[serde(default)]
+    pub deviation: DeviationSettings,
+    #[serde(default, rename = "filter_reminder")]
+    pub filter_reminders: Vec<FilterReminder>,
+    /// File keeping the extracted air volume across sessions.
+    pub totalizer_file: Option<PathBuf>,
+    /// File the filter totals are logged to for the Filters view, kept across sessions.
+    pub filter_history_file: Option<PathBuf>,
+    /// Full scale of the fan speed gauge and chart, in RPM.
+    pub max_rpm: Option<u16>,
+    /// Minutes `A` hides the alarms for; 15 when unset.
+    pub snooze_minutes: Option<u64>,
+    /// Ask before Space switches the extractor off; switching on stays instant.
+    #[serde(default)]
+    pub confirm_power_off: bool,
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    #[serde(default)]
+    pub flow_step: FlowSteps,
+    #[serde(default)]
+    pub filter_gauge: GaugeThresholds,
+    #[serde(default)]
+    pub chart: ChartAxes,
+    /// Further devices polled alongside the one given on the command line.
+    #[serde(default, rename = "device")]
+    pub devices: Vec<DeviceConfig>,
+    /// Broker that status samples and alarm transitions are published to.
+    pub mqtt: Option<MqttConfig>,
+    /// Where samples are written as Influx line protocol.
+    pub influx: Option<InfluxConfig>,
+    /// REST API for showing and controlling the devices from other machines.
+    pub http: Option<HttpConfig>,
+    /// Unix socket taking newline-delimited JSON commands from other local processes.
+    pub control_socket: Option<PathBuf>,
+    /// Loopback TCP address taking the same requests as the control socket.
+    pub control_listen: Option<SocketAddr>,
+    /// CSV file every answered poll is appended to.
+    pub log_csv: Option<PathBuf>,
+    /// Whether `log_csv` rows end with every polled register.
+    #[serde(default)]
+    pub log_csv_registers: bool,
+}
+
+/// A device shown beside the others on the overview; unset fields follow the interface defaults.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeviceConfig {
+    pub name: String,
+    pub port: Option<String>,
+    pub address: Option<u8>,
+    pub baud: Option<u32>,
+    #[serde(default)]
+    pub interface: InterfaceMode,
+}
+
+/// Target flow change, in m3/h, of the Shift and Ctrl arrow steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FlowSteps {
+    pub medium: u16,
+    pub large: u16,
+}
+
+impl Default for FlowSteps {
+    fn default() -> Self {
+        Self {
+            medium: 5,
+            large: 10,
+        }
+    }
+}
+
+/// Filter usage percentages at which a gauge turns to the warning and error colours.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GaugeThresholds {
+    pub warn: f64,
+    pub critical: f64,
+}
+
+impl Default for GaugeThresholds {
+    fn default() -> Self {
+        Self {
+            warn: 75.0,
+            critical: 90.0,
+        }
+    }
+}
+
+/// Value axis labelling of the flow and speed charts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChartAxes {
+    /// Labels between 0 and full scale, evenly spaced; fewer are drawn on short charts.
+    pub y_ticks: u8,
+    /// Faint horizontal lines at the intermediate labels.
+    pub gridlines: bool,
+}
+
+impl Default for ChartAxes {
+    fn default() -> Self {
+        Self {
+            y_ticks: 3,
+            gridlines: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GaugeLevel {
+    Normal,
+    Warn,
+    Critical,
+}
+
+impl GaugeThresholds {
+    pub fn level(&self, percent: f64) -> GaugeLevel {
+        if percent >= self.critical {
+            GaugeLevel::Critical
+        } else if percent >= self.warn {
+            GaugeLevel::Warn
+        } else {
+            GaugeLevel::Normal
+        }
+    }
+}
+
+/// A named target flow bound to a number key, in file order.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Preset {
+    pub name: String,
+    /// Target flow in m3/h.
+    pub flow: u16,
+}
+
+impl Preset {
+    /// Nine setpoints spread evenly from `min` to `max`, for the number keys when the config
+    /// file lists no presets.
+    pub fn evenly_spaced(min: u16, max: u16) -> Vec<Self> {
+        let last = u32::try_from(MAX_PRESETS).unwrap_or(u32::MAX) - 1;
+        (0..=last)
+            .map(|step| {
+                let offset = u32::from(max.saturating_sub(min)) * step / last;
+                let name = match step {
+                    0 => String::from("min"),
+                    step if step == last => String::from("max"),
+                    step => format!("{}%", (step * 100 + last / 2) / last),
+                };
+                Self {
+                    name,
+                    flow: min + u16::try_from(offset).unwrap_or(u16::MAX),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Config {
+    /// `config.toml` in the user's config directory, read when `--config` is not given.
+    pub fn default_path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("config.toml"))
+    }
+
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let text =
+            fs::read_to_string(path).wrap_err_with(|| format!("read config {}", path.display()))?;
+        Self::parse(&text).wrap_err_with(|| format!("parse config {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> eyre::Result<Self> {
+        let config: Self = toml::from_str(text)?;
+        for alarm in &config.alarms {
+            if alarm.above.is_none() && alarm.below.is_none() {
+                return Err(eyre::eyre!(
+                    "alarm '{}' needs an `above` or `below` threshold",
+                    alarm.name
+                ));
+            }
+            if alarm.hysteresis < 0.0 {
+                return Err(eyre::eyre!(
+                    "alarm '{}' hysteresis must not be negative",
+                    alarm.name
+                ));
+            }
+        }
+        let gauge = config.filter_gauge;
+        if !(0.0..=gauge.critical).contains(&gauge.warn) {
+            return Err(eyre::eyre!(
+                "filter_gauge warn must be between 0 and critical ({})",
+                gauge.critical
+            ));
+        }
+        if config.flow_step.medium == 0 || config.flow_step.large == 0 {
+            return Err(eyre::eyre!("flow_step steps must be at least 1 m3/h"));
+        }
+        if config.poll_interval == Some(0) {
+            return Err(eyre::eyre!("poll_interval must be at least 1 ms"));
+        }
+        if config.snooze_minutes == Some(0) {
+            return Err(eyre::eyre!("snooze_minutes must be at least 1"));
+        }
+        if config.presets.len() > MAX_PRESETS {
+            return Err(eyre::eyre!(
+                "at most {MAX_PRESETS} presets can be bound to number keys"
+            ));
+        }
+        check_macros(&config)?;
+        if let Some(mqtt) = &config.mqtt {
+            if mqtt.broker.is_empty() {
+                return Err(eyre::eyre!("mqtt needs a `broker`"));
+            }
+            if mqtt.qos().is_none() {
+                return Err(eyre::eyre!("mqtt qos must be 0, 1 or 2"));
+            }
+        }
+        if let Some(influx) = &config.influx {
+            check_influx(influx)?;
+        }
+        if config
+            .http
+            .as_ref()
+            .is_some_and(|http| http.token.is_empty())
+        {
+            return Err(eyre::eyre!("http needs a `token`"));
+        }
+        for device in &config.devices {
+            match device.interface {
+                InterfaceMode::Simulation if cfg!(not(debug_assertions)) => {
+                    return Err(eyre::eyre!(
+                        "device '{}': simulation interface is only available in debug builds",
+                        device.name
+                    ));
+                }
+                InterfaceMode::Simulation => {}
+                _ if device.port.is_none() => {
+                    return Err(eyre::eyre!("device '{}' needs a `port`", device.name));
+                }
+                _ => {}
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// Refuses an `[influx]` table without exactly one target or with an endpoint that is not HTTP.
+fn check_influx(influx: &InfluxConfig) -> eyre::Result<()> {
+    if influx.targets() != 1 {
+        return Err(eyre::eyre!(
+            "influx needs exactly one of `file`, `udp` or `url`"
+        ));
+    }
+    if influx.measurement.is_empty() {
+        return Err(eyre::eyre!("influx needs a `measurement`"));
+    }
+    if let Some(url) = &influx.url
+        && !url.starts_with("http://")
+        && !url.starts_with("https://")
+    {
+        return Err(eyre::eyre!(
+            "influx url '{url}' must start with http:// or https://"
+        ));
+    }
+    Ok(())
+}
+
+/// Refuses a macro without steps or on a key an action, a preset, a view or another macro has.
+fn check_macros(config: &Config) -> eyre::Result<()> {
+    for (index, played) in config.macros.iter().enumerate() {
+        let name = &played.name;
+        if played.steps.is_empty() {
+            return Err(eyre::eyre!("macro '{name}' has no steps"));
+        }
+        let chord = played.chord().wrap_err_with(|| format!("macro '{name}'"))?;
+        if chord.reserved() {
+            return Err(eyre::eyre!(
+                "macro '{name}': `{}` is reserved for presets and views",
+                played.key
+            ));
+        }
+        if let Some(action) = config.keys.bound(chord) {
+            return Err(eyre::eyre!(
+                "macro '{name}': `{}` is bound to {}",
+                played.key,
+                action.name()
+            ));
+        }
+        if let Some(other) = config.macros[..index]
+            .iter()
+            .find(|other| other.chord().is_ok_and(|bound| bound == chord))
+        {
+            return Err(eyre::eyre!(
+                "`{}` is bound to both macro '{}' and macro '{name}'",
+                played.key,
+                other.name
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Per-user directory of the monitor's files: `$XDG_CONFIG_HOME/qu6101a2-mon`, falling back to
+/// `~/.config/qu6101a2-mon` (`%APPDATA%` on Windows).
+pub fn config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("qu6101a2-mon"))
+}
+
+/// Notices edits to the config file by its modification time and size.
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    path: PathBuf,
+    stamp: Option<(SystemTime, u64)>,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        let stamp = file_stamp(&path);
+        Self { path, stamp }
+    }
+
+    /// The reloaded config, or why it could not be loaded, when the file changed since the last
+    /// call.
+    pub fn poll(&mut self) -> Option<eyre::Result<Config>> {
+        let stamp = file_stamp(&self.path);
+        if stamp == self.stamp {
+            return None;
+        }
+        self.stamp = stamp;
+        Some(Config::load(&self.path))
+    }
+}
+
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{Config, ConfigWatcher, GaugeLevel, Preset};
+    use crate::alarm::AlarmMetric;
+    use crate::data::Filter;
+    use crate::export::MqttConfig;
+    use crate::interface::InterfaceMode;
+
+    #[test]
+    fn parses_alarm_rules() {
+        let config = Config::parse(
+            r#"
+            [[alarm]]
+            name = "Low flow"
+            metric = "flow-deviation"
+            below = -10
+            for = 30
+            hysteresis = 2
+
+            [[alarm]]
+            name = "Filter worn"
+            metric = "filter-usage"
+            above = 90
+            "#,
+        )
+        .expect("config should parse");
+        assert_eq!(config.alarms.len(), 2);
+        assert_eq!(config.alarms[0].metric, AlarmMetric::FlowDeviation);
+        assert_eq!(config.alarms[0].hold_secs, 30);
+        assert_eq!(config.alarms[1].hold_secs, 0);
+    }
+
+    #[test]
+    fn presets_keep_file_order() {
+        let config = Config::parse(
+            r#"
+            [[preset]]
+            name = "soldering"
+            flow = 70
+
+            [[preset]]
+            name = "standby"
+            flow = 35
+            "#,
+        )
+        .expect("config should parse");
+        let names: Vec<&str> = config.presets.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["soldering", "standby"]);
+    }
+
+    #[test]
+    fn spaced_presets_run_from_min_to_max() {
+        let presets = Preset::evenly_spaced(20, 100);
+        let flows: Vec<u16> = presets.iter().map(|preset| preset.flow).collect();
+        assert_eq!(flows, [20, 30, 40, 50, 60, 70, 80, 90, 100]);
+        assert_eq!(presets[0].name, "min");
+        assert_eq!(presets[4].name, "50%");
+        assert_eq!(presets[8].name, "max");
+    }
+
+    #[test]
+    fn parses_filter_reminders() {
+        let config = Config::parse(
+            r#"
+            [[filter_reminder]]
+            filter = "chemical"
+            due = "2026-12-01"
+            after_km3 = 150
+            "#,
+        )
+        .expect("config should parse");
+        assert_eq!(config.filter_reminders[0].filter, Filter::Chemical);
+        assert_eq!(config.filter_reminders[0].after_km3, Some(150));
+    }
+
+    #[test]
+    fn alarm_without_threshold_is_rejected() {
+        let err = Config::parse(
+            r#"
+            [[alarm]]
+            name = "Nothing"
+            metric = "speed"
+            "#,
+        )
+        .expect_err("threshold should be required");
+        assert!(err.to_string().contains("Nothing"));
+    }
+
+    #[test]
+    fn negative_hysteresis_is_rejected() {
+        let err = Config::parse(
+            r#"
+            [[alarm]]
+            name = "Inverted"
+            metric = "speed"
+            above = 80
+            hysteresis = -5
+            "#,
+        )
+        .expect_err("negative hysteresis should be rejected");
+        assert!(err.to_string().contains("hysteresis"));
+    }
+
+    #[test]
+    fn macro_keys_must_be_free() {
+        let macro_on = |key: &str| {
+            format!("[[macro]]\nname = \"warm-up\"\nkey = \"{key}\"\nsteps = [{{ power = true }}]")
+        };
+        assert!(Config::parse(&macro_on("f6")).is_ok());
+        for (key, reason) in [
+            ("q", "bound to quit"),
+            ("f2", "reserved"),
+            ("hyper+x", "unknown modifier"),
+        ] {
+            let err = Config::parse(&macro_on(key)).expect_err("key should be refused");
+            assert!(format!("{err:#}").contains(reason), "{key}: {err:#}");
+        }
+        let twice = format!("{}\n{}", macro_on("f6"), macro_on("F6"));
+        let err = Config::parse(&twice).expect_err("a key should run one macro");
+        assert!(err.to_string().contains("bound to both"));
+    }
+
+    #[test]
+    fn devices_need_a_port_unless_simulated() {
+        let config = Config::parse(
+            r#"
+            [[device]]
+            name = "bench"
+            port = "/dev/ttyUSB1"
+            interface = "exttool"
+            "#,
+        )
+        .expect("config should parse");
+        assert_eq!(config.devices[0].interface, InterfaceMode::Exttool);
+        assert_eq!(config.devices[0].address, None);
+
+        let err =
+            Config::parse("[[device]]\nname = \"loose\"").expect_err("a port should be required");
+        assert!(err.to_string().contains("loose"));
+    }
+
+    #[test]
+    fn mqtt_needs_a_broker_and_a_valid_qos() {
+        let config =
+            Config::parse("[mqtt]\nbroker = \"pi.local\"\nqos = 1").expect("config should parse");
+        assert_eq!(
+            config.mqtt,
+            Some(MqttConfig {
+                broker: String::from("pi.local"),
+                qos: 1,
+                ..MqttConfig::default()
+            })
+        );
+        assert!(Config::parse("[mqtt]\nport = 1883").is_err());
+        assert!(Config::parse("[mqtt]\nbroker = \"pi.local\"\nqos = 3").is_err());
+    }
+
+    #[test]
+    fn influx_takes_exactly_one_target_and_http_a_token() {
+        let config = Config::parse("[influx]\nudp = \"influx:8089\"\ntags = { site = \"hall\" }")
+            .expect("config should parse");
+        let influx = config.influx.expect("influx table");
+        assert_eq!(influx.udp.as_deref(), Some("influx:8089"));
+        assert_eq!(influx.measurement, "qu6101a2");
+        assert_eq!(influx.tags["site"], "hall");
+
+        assert!(Config::parse("[influx]\nmeasurement = \"fans\"").is_err());
+        assert!(Config::parse("[http]\nlisten = \"0.0.0.0:8080\"").is_err());
+        assert!(Config::parse("[influx]\nudp = \"influx:8089\"\nfile = \"fans.lp\"").is_err());
+        assert!(Config::parse("[influx]\nurl = \"influx:8086/write\"").is_err());
+    }
+
+    #[test]
+    fn filter_gauge_thresholds_pick_the_level() {
+        let config = Config::parse(
+            "
+            [filter_gauge]
+            warn = 60
+            ",
+        )
+        .expect("config should parse");
+        let gauge = config.filter_gauge;
+        assert_eq!(gauge.level(59.0), GaugeLevel::Normal);
+        assert_eq!(gauge.level(60.0), GaugeLevel::Warn);
+        assert_eq!(gauge.level(95.0), GaugeLevel::Critical);
+
+        let err = Config::parse("[filter_gauge]\nwarn = 95\ncritical = 80")
+            .expect_err("warn above critical should fail");
+        assert!(err.to_string().contains("warn"));
+    }
+
+    #[test]
+    fn watcher_reloads_only_after_the_file_changes() {
+        let path = std::env::temp_dir().join(format!("config-watch-{}.toml", std::process::id()));
+        fs::write(&path, "max_rpm = 3000\n").expect("config should be written");
+        let mut watcher = ConfigWatcher::new(path.clone());
+        assert!(watcher.poll().is_none());
+
+        fs::write(&path, "max_rpm = 3200\nconfirm_power_off = true\n")
+            .expect("config should be written");
+        let config = watcher
+            .poll()
+            .expect("change should be noticed")
+            .expect("config should load");
+        assert_eq!(config.max_rpm, Some(3200));
+        assert!(watcher.poll().is_none());
+
+        fs::write(&path, "max_rpm = \"fast\"\n").expect("config should be written");
+        assert!(watcher.poll().expect("change should be noticed").is_err());
+        fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,201 @@
+use crate::constants::{
+    REG_BAUD_RATE, REG_BAUD_RATE_LO, REG_CALIBRATION_FACTOR, REG_COMM_ADDRESS, REG_THRESHOLD_A,
+    REG_THRESHOLD_B,
+};
+use crate::profile::DeviceProfile;
+use crate::register_editor::parse_input;
+
+/// Configuration registers editable from the config screen, in display order.
+pub const CONFIG_REGISTERS: &[u16] = &[REG_CALIBRATION_FACTOR, REG_THRESHOLD_A, REG_THRESHOLD_B];
+
+/// Registers a wrong write to would skew readings or cut the link, so the value has to be typed
+/// twice.
+pub const GUARDED_REGISTERS: &[u16] = &[
+    REG_CALIBRATION_FACTOR,
+    REG_THRESHOLD_A,
+    REG_THRESHOLD_B,
+    REG_COMM_ADDRESS,
+    REG_BAUD_RATE_LO,
+    REG_BAUD_RATE,
+];
+
+pub fn guarded(register: u16) -> bool {
+    GUARDED_REGISTERS.contains(&register)
+}
+
+/// A validated write held until its value is typed a second time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingWrite {
+    pub register: u16,
+    pub value: u16,
+    pub retyped: String,
+}
+
+impl PendingWrite {
+    pub const fn new(register: u16, value: u16) -> Self {
+        Self {
+            register,
+            value,
+            retyped: String::new(),
+        }
+    }
+
+    /// The write once the retyped value matches the first; on a mismatch the retyped text is
+    /// cleared for another try and the error is returned.
+    pub fn confirm(&mut self, profile: &DeviceProfile) -> Result<(u16, u16), String> {
+        let info = profile
+            .register_info(self.register)
+            .ok_or_else(|| format!("unknown register 0x{:04X}", self.register))?;
+        if parse_input(&info, &self.retyped) == Some(self.value) {
+            return Ok((self.register, self.value));
+        }
+        self.retyped.clear();
+        Err(format!("{}: the values differ, type it again", info.name))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ConfigEditor {
+    pub selected: usize,
+    pub editing: bool,
+    pub buffer: String,
+    pub pending: Option<PendingWrite>,
+    pub error: Option<String>,
+}
+
+impl ConfigEditor {
+    pub fn selected_register(&self) -> u16 {
+        CONFIG_REGISTERS[self.selected.min(CONFIG_REGISTERS.len() - 1)]
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % CONFIG_REGISTERS.len();
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self
+            .selected
+            .checked_sub(1)
+            .unwrap_or(CONFIG_REGISTERS.len() - 1);
+    }
+
+    /// Starts editing the selected register, pre-filled with the last read value.
+    pub fn begin_edit(&mut self, current: Option<u16>, profile: &DeviceProfile) {
+        self.editing = true;
+        self.error = None;
+        let info = profile.register_info(self.selected_register());
+        self.buffer = current
+            .zip(info)
+            .map(|(value, info)| info.format_value(value))
+            .unwrap_or_default();
+    }
+
+    pub fn cancel(&mut self) {
+        self.editing = false;
+        self.pending = None;
+        self.buffer.clear();
+    }
+
+    /// Validates the typed value against the register metadata and stages it for confirmation.
+    pub fn stage(&mut self, profile: &DeviceProfile) {
+        let register = self.selected_register();
+        let Some(info) = profile.register_info(register) else {
+            self.error = Some(format!("unknown register 0x{register:04X}"));
+            return;
+        };
+        let Some(value) = info.parse_value(&self.buffer) else {
+            self.error = Some(format!("{}: not a valid value", info.name));
+            return;
+        };
+        if !info.writable || !info.accepts(value) {
+            self.error = Some(format!(
+                "{}: value must be within {}..={}",
+                info.name,
+                info.format_value(info.min),
+                info.format_value(info.max)
+            ));
+            return;
+        }
+        self.error = None;
+        self.editing = false;
+        self.pending = Some(PendingWrite::new(register, value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfigEditor, PendingWrite};
+    use crate::constants::{REG_CALIBRATION_FACTOR, REG_THRESHOLD_A};
+    use crate::profile::QUICK_6101A2;
+
+    #[test]
+    fn stage_accepts_valid_value() {
+        let mut editor = ConfigEditor::default();
+        editor.begin_edit(Some(100), &QUICK_6101A2);
+        assert_eq!(editor.buffer, "1.00");
+        editor.buffer = String::from("1.05");
+        editor.stage(&QUICK_6101A2);
+        assert_eq!(
+            editor.pending,
+            Some(PendingWrite::new(REG_CALIBRATION_FACTOR, 105))
+        );
+        assert!(editor.error.is_none());
+    }
+
+    #[test]
+    fn write_waits_for_the_same_value_typed_again() {
+        let mut pending = PendingWrite::new(REG_CALIBRATION_FACTOR, 105);
+        pending.retyped = String::from("1.50");
+        assert_eq!(
+            pending.confirm(&QUICK_6101A2),
+            Err(String::from("Cal-Factor: the values differ, type it again"))
+        );
+        assert!(pending.retyped.is_empty());
+        pending.retyped = String::from("0x69");
+        assert_eq!(
+            pending.confirm(&QUICK_6101A2),
+            Ok((REG_CALIBRATION_FACTOR, 105))
+        );
+    }
+
+    #[test]
+    fn thresholds_are_bounds_checked() {
+        let mut editor = ConfigEditor::default();
+        editor.select_next();
+        assert_eq!(editor.selected_register(), REG_THRESHOLD_A);
+        editor.begin_edit(Some(0), &QUICK_6101A2);
+        editor.buffer = String::from("150");
+        editor.stage(&QUICK_6101A2);
+        assert!(editor.pending.is_none());
+        assert!(editor.error.is_some());
+
+        editor.buffer = String::from("40");
+        editor.stage(&QUICK_6101A2);
+        assert_eq!(editor.pending, Some(PendingWrite::new(REG_THRESHOLD_A, 40)));
+    }
+
+    #[test]
+    fn calibration_factor_is_bounds_checked() {
+        let mut editor = ConfigEditor::default();
+        editor.begin_edit(Some(100), &QUICK_6101A2);
+        for value in ["0.00", "0.49", "2.01"] {
+            editor.buffer = String::from(value);
+            editor.stage(&QUICK_6101A2);
+            assert!(editor.pending.is_none(), "{value} should be rejected");
+            assert_eq!(
+                editor.error.as_deref(),
+                Some("Cal-Factor: value must be within 0.50..=2.00")
+            );
+        }
+    }
+
+    #[test]
+    fn stage_rejects_garbage() {
+        let mut editor = ConfigEditor::default();
+        editor.begin_edit(None, &QUICK_6101A2);
+        editor.buffer = String::from("1.2.3");
+        editor.stage(&QUICK_6101A2);
+        assert!(editor.pending.is_none());
+        assert!(editor.error.is_some());
+    }
+}
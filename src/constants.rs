@@ -23,6 +23,18 @@ pub const REG_REAL_FLOW: u16 = 0x0014;
 pub const STATE_OFF: u16 = 0;
 pub const STATE_ON: u16 = 1;
 
+/// Unconfirmed operating mode encoding, see the `REG_MODE` catalogue entry.
+pub const MODE_MANUAL: u16 = 0;
+pub const MODE_AUTO: u16 = 1;
+
+pub const BEEPER_OFF: u16 = 0;
+pub const BEEPER_ON: u16 = 1;
+
+pub const BAUD_RATES: &[u32] = &[9_600, 19_200, 38_400, 57_600, 115_200];
+
+/// Poll intervals in milliseconds stepped through with `+`/`-`.
+pub const POLL_INTERVALS_MS: &[u64] = &[100, 200, 250, 500, 1_000, 2_000, 5_000, 10_000];
+
 pub const TARGET_FLOW_MIN: u16 = 30;
 pub const TARGET_FLOW_MAX: u16 = 100;
 
@@ -1,12 +1,17 @@
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
 use crate::constants::{
-    REG_BAUD_RATE, REG_BAUD_RATE_LO, REG_BEEPER, REG_CALIBRATION_FACTOR, REG_COMM_ADDRESS,
-    REG_C_FILTER_LIMIT, REG_C_FILTER_TOTAL, REG_FLAGS, REG_MODE, REG_M_FILTER_LIMIT,
-    REG_M_FILTER_TOTAL, REG_P_FILTER_LIMIT, REG_P_FILTER_TOTAL, REG_REAL_FLOW, REG_SPEED_RPM,
-    REG_STATE, REG_STATUS_FLAGS, REG_TARGET_FLOW, REG_THRESHOLD_A, REG_THRESHOLD_B,
-    REG_TUBE_DIAMETER, STATUS_POLL_REG_COUNT,
+    MODE_AUTO, MODE_MANUAL, REG_BAUD_RATE, REG_BAUD_RATE_LO, REG_BEEPER, REG_C_FILTER_LIMIT,
+    REG_C_FILTER_TOTAL, REG_CALIBRATION_FACTOR, REG_COMM_ADDRESS, REG_FLAGS, REG_M_FILTER_LIMIT,
+    REG_M_FILTER_TOTAL, REG_MODE, REG_P_FILTER_LIMIT, REG_P_FILTER_TOTAL, REG_REAL_FLOW,
+    REG_SPEED_RPM, REG_STATE, REG_STATUS_FLAGS, REG_TARGET_FLOW, REG_THRESHOLD_A, REG_THRESHOLD_B,
+    REG_TUBE_DIAMETER, STATE_OFF, STATE_ON, STATUS_POLL_REG_COUNT, TARGET_FLOW_MAX,
+    TARGET_FLOW_MIN,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DeviceStatus {
     pub state: u16,
     pub target_flow: u16,
@@ -41,42 +46,430 @@ impl DeviceStatus {
             registers,
         })
     }
+
+    pub fn register(&self, address: u16) -> Option<u16> {
+        self.registers.get(usize::from(address)).copied()
+    }
+
+    pub fn mode(&self) -> Option<OperatingMode> {
+        self.register(REG_MODE)
+            .and_then(OperatingMode::from_register)
+    }
+
+    pub fn filter_total(&self, filter: Filter) -> u16 {
+        match filter {
+            Filter::Pre => self.p_filter_total,
+            Filter::Medium => self.m_filter_total,
+            Filter::Chemical => self.c_filter_total,
+        }
+    }
+
+    pub fn filter_limit(&self, filter: Filter) -> u16 {
+        match filter {
+            Filter::Pre => self.p_filter_limit,
+            Filter::Medium => self.m_filter_limit,
+            Filter::Chemical => self.c_filter_limit,
+        }
+    }
 }
 
-pub fn register_name(index: u16) -> Option<&'static str> {
-    match index {
-        REG_STATE => Some("State"),
-        REG_TARGET_FLOW => Some("Target"),
-        REG_STATUS_FLAGS => Some("Status"),
-        REG_P_FILTER_TOTAL => Some("P-Total"),
-        REG_M_FILTER_TOTAL => Some("M-Total"),
-        REG_C_FILTER_TOTAL => Some("C-Total"),
-        REG_P_FILTER_LIMIT => Some("P-Limit"),
-        REG_M_FILTER_LIMIT => Some("M-Limit"),
-        REG_C_FILTER_LIMIT => Some("C-Limit"),
-        REG_FLAGS => Some("Flags"),
-        REG_COMM_ADDRESS => Some("Address"),
-        REG_BAUD_RATE_LO => Some("Baud-Lo"),
-        REG_BAUD_RATE => Some("Baud-Hi"),
-        REG_BEEPER => Some("Beeper"),
-        REG_SPEED_RPM => Some("Speed"),
-        REG_TUBE_DIAMETER => Some("Tube"),
-        REG_THRESHOLD_A => Some("Thresh-A"),
-        REG_THRESHOLD_B => Some("Thresh-B"),
-        REG_MODE => Some("Mode"),
-        REG_CALIBRATION_FACTOR => Some("Cal-Factor"),
-        REG_REAL_FLOW => Some("Flow"),
-        _ => None,
+/// Identification reported by the device, read after connecting and reconnecting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceIdentity {
+    pub server_id: u8,
+    /// Model and firmware text from the additional identification bytes.
+    pub revision: String,
+}
+
+impl DeviceIdentity {
+    /// Decodes a Modbus "report server ID" payload: id, run indicator, then vendor bytes.
+    pub fn from_report(payload: &[u8]) -> Option<Self> {
+        let (&server_id, rest) = payload.split_first()?;
+        let extra = rest.get(1..).unwrap_or_default();
+        let text: String = extra
+            .iter()
+            .map(|byte| char::from(*byte))
+            .filter(|ch| ch.is_ascii_graphic() || *ch == ' ')
+            .collect();
+        let revision = if text.trim().is_empty() {
+            extra.iter().fold(String::new(), |mut hex, byte| {
+                let _ = write!(hex, "{byte:02X}");
+                hex
+            })
+        } else {
+            text.trim().to_string()
+        };
+        Some(Self {
+            server_id,
+            revision,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperatingMode {
+    Manual,
+    Auto,
+}
+
+impl OperatingMode {
+    pub const fn from_register(value: u16) -> Option<Self> {
+        match value {
+            MODE_MANUAL => Some(Self::Manual),
+            MODE_AUTO => Some(Self::Auto),
+            _ => None,
+        }
+    }
+
+    pub const fn register_value(self) -> u16 {
+        match self {
+            Self::Manual => MODE_MANUAL,
+            Self::Auto => MODE_AUTO,
+        }
+    }
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Manual => "MANUAL",
+            Self::Auto => "AUTO",
+        }
+    }
+
+    pub const fn toggled(self) -> Self {
+        match self {
+            Self::Manual => Self::Auto,
+            Self::Auto => Self::Manual,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Filter {
+    Pre,
+    Medium,
+    Chemical,
+}
+
+impl Filter {
+    pub const ALL: [Self; 3] = [Self::Pre, Self::Medium, Self::Chemical];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Pre => "P-Filter",
+            Self::Medium => "M-Filter",
+            Self::Chemical => "C-Filter",
+        }
+    }
+
+    pub const fn total_register(self) -> u16 {
+        match self {
+            Self::Pre => REG_P_FILTER_TOTAL,
+            Self::Medium => REG_M_FILTER_TOTAL,
+            Self::Chemical => REG_C_FILTER_TOTAL,
+        }
+    }
+}
+
+/// How the raw register word is interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterKind {
+    Unsigned,
+    /// Two's complement; `min`/`max` hold the raw words of the signed bounds.
+    Signed,
+    Bool,
+    /// Raw values with display labels.
+    Enum(&'static [(u16, &'static str)]),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegisterInfo {
+    pub address: u16,
+    pub name: &'static str,
+    pub unit: &'static str,
+    pub kind: RegisterKind,
+    pub scale: f64,
+    pub min: u16,
+    pub max: u16,
+    pub writable: bool,
+    pub description: &'static str,
+}
+
+impl RegisterInfo {
+    const fn read_only(
+        address: u16,
+        name: &'static str,
+        unit: &'static str,
+        description: &'static str,
+    ) -> Self {
+        Self {
+            address,
+            name,
+            unit,
+            kind: RegisterKind::Unsigned,
+            scale: 1.0,
+            min: 0,
+            max: u16::MAX,
+            writable: false,
+            description,
+        }
+    }
+
+    const fn writable(
+        address: u16,
+        name: &'static str,
+        unit: &'static str,
+        min: u16,
+        max: u16,
+        description: &'static str,
+    ) -> Self {
+        Self {
+            address,
+            name,
+            unit,
+            kind: RegisterKind::Unsigned,
+            scale: 1.0,
+            min,
+            max,
+            writable: true,
+            description,
+        }
+    }
+
+    /// Marks the register as fixed-point: engineering value = raw * scale.
+    const fn scaled(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    const fn typed(mut self, kind: RegisterKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Decimal places needed to show a value at this register's resolution.
+    pub fn decimals(&self) -> usize {
+        let mut step = self.scale;
+        let mut decimals = 0;
+        while (step - step.round()).abs() > 1e-9 && decimals < 6 {
+            step *= 10.0;
+            decimals += 1;
+        }
+        decimals
+    }
+
+    pub fn format_value(&self, raw: u16) -> String {
+        match self.kind {
+            RegisterKind::Unsigned => {
+                format!("{:.*}", self.decimals(), f64::from(raw) * self.scale)
+            }
+            RegisterKind::Signed => format!(
+                "{:.*}",
+                self.decimals(),
+                f64::from(raw.cast_signed()) * self.scale
+            ),
+            RegisterKind::Bool => String::from(if raw == 0 { "OFF" } else { "ON" }),
+            RegisterKind::Enum(labels) => labels
+                .iter()
+                .find(|(value, _)| *value == raw)
+                .map_or_else(|| raw.to_string(), |(_, label)| (*label).to_string()),
+        }
+    }
+
+    /// Parses an engineering value (or label) into the raw register word.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn parse_value(&self, input: &str) -> Option<u16> {
+        let input = input.trim();
+        match self.kind {
+            RegisterKind::Unsigned => {
+                let raw = (input.parse::<f64>().ok()? / self.scale).round();
+                (0.0..=f64::from(u16::MAX))
+                    .contains(&raw)
+                    .then_some(raw as u16)
+            }
+            RegisterKind::Signed => {
+                let raw = (input.parse::<f64>().ok()? / self.scale).round();
+                (f64::from(i16::MIN)..=f64::from(i16::MAX))
+                    .contains(&raw)
+                    .then_some((raw as i16).cast_unsigned())
+            }
+            RegisterKind::Bool => match input.to_ascii_lowercase().as_str() {
+                "1" | "on" | "true" => Some(1),
+                "0" | "off" | "false" => Some(0),
+                _ => None,
+            },
+            RegisterKind::Enum(labels) => labels
+                .iter()
+                .find(|(_, label)| label.eq_ignore_ascii_case(input))
+                .map(|(value, _)| *value)
+                .or_else(|| input.parse().ok()),
+        }
+    }
+
+    /// Whether a raw word lies within the register bounds, compared in its own type.
+    pub fn accepts(&self, raw: u16) -> bool {
+        match self.kind {
+            RegisterKind::Signed => {
+                (self.min.cast_signed()..=self.max.cast_signed()).contains(&raw.cast_signed())
+            }
+            RegisterKind::Unsigned | RegisterKind::Bool | RegisterKind::Enum(_) => {
+                (self.min..=self.max).contains(&raw)
+            }
+        }
     }
 }
 
+pub const REGISTERS: &[RegisterInfo] = &[
+    RegisterInfo::writable(
+        REG_STATE,
+        "State",
+        "",
+        STATE_OFF,
+        STATE_ON,
+        "Power state (0 = off, 1 = on)",
+    )
+    .typed(RegisterKind::Bool),
+    RegisterInfo::writable(
+        REG_TARGET_FLOW,
+        "Target",
+        "m3/h",
+        TARGET_FLOW_MIN,
+        TARGET_FLOW_MAX,
+        "Target airflow setpoint",
+    ),
+    RegisterInfo::read_only(REG_STATUS_FLAGS, "Status", "", "Device status flag bits"),
+    RegisterInfo::writable(
+        REG_P_FILTER_TOTAL,
+        "P-Total",
+        "km3",
+        0,
+        u16::MAX,
+        "Pre-filter accumulated volume",
+    ),
+    RegisterInfo::writable(
+        REG_M_FILTER_TOTAL,
+        "M-Total",
+        "km3",
+        0,
+        u16::MAX,
+        "Medium filter accumulated volume",
+    ),
+    RegisterInfo::writable(
+        REG_C_FILTER_TOTAL,
+        "C-Total",
+        "km3",
+        0,
+        u16::MAX,
+        "Chemical filter accumulated volume",
+    ),
+    RegisterInfo::read_only(
+        REG_P_FILTER_LIMIT,
+        "P-Limit",
+        "km3",
+        "Pre-filter replacement limit",
+    ),
+    RegisterInfo::read_only(
+        REG_M_FILTER_LIMIT,
+        "M-Limit",
+        "km3",
+        "Medium filter replacement limit",
+    ),
+    RegisterInfo::read_only(
+        REG_C_FILTER_LIMIT,
+        "C-Limit",
+        "km3",
+        "Chemical filter replacement limit",
+    ),
+    RegisterInfo::read_only(REG_FLAGS, "Flags", "", "Configuration flag bits"),
+    RegisterInfo::writable(
+        REG_COMM_ADDRESS,
+        "Address",
+        "",
+        1,
+        247,
+        "Modbus device address",
+    ),
+    RegisterInfo::writable(
+        REG_BAUD_RATE_LO,
+        "Baud-Lo",
+        "",
+        0,
+        u16::MAX,
+        "Baud rate, low word",
+    ),
+    RegisterInfo::writable(
+        REG_BAUD_RATE,
+        "Baud-Hi",
+        "",
+        0,
+        u16::MAX,
+        "Baud rate, high word",
+    ),
+    RegisterInfo::writable(
+        REG_BEEPER,
+        "Beeper",
+        "",
+        0,
+        1,
+        "Alarm buzzer (0 = off, 1 = on)",
+    )
+    .typed(RegisterKind::Bool),
+    RegisterInfo::read_only(REG_SPEED_RPM, "Speed", "rpm", "Fan speed"),
+    RegisterInfo::read_only(REG_TUBE_DIAMETER, "Tube", "mm", "Configured tube diameter"),
+    // No register map documents a fixed-point scale for the thresholds, so they stay raw
+    // signed words rather than guessing one; add `.scaled()` here once it is confirmed.
+    RegisterInfo::writable(
+        REG_THRESHOLD_A,
+        "Thresh-A",
+        "",
+        TARGET_FLOW_MAX.wrapping_neg(),
+        TARGET_FLOW_MAX,
+        "Device threshold A",
+    )
+    .typed(RegisterKind::Signed),
+    RegisterInfo::writable(
+        REG_THRESHOLD_B,
+        "Thresh-B",
+        "",
+        TARGET_FLOW_MAX.wrapping_neg(),
+        TARGET_FLOW_MAX,
+        "Device threshold B",
+    )
+    .typed(RegisterKind::Signed),
+    // The 0/1 encoding is not confirmed by a register map, so the mode is shown but not written.
+    RegisterInfo::read_only(
+        REG_MODE,
+        "Mode",
+        "",
+        "Operating mode (0 = manual, 1 = auto)",
+    )
+    .typed(RegisterKind::Enum(&[
+        (MODE_MANUAL, "Manual"),
+        (MODE_AUTO, "Auto"),
+    ])),
+    // The 0.01 scale is an assumption (raw 100 read as a factor of 1.00), not a documented value.
+    RegisterInfo::writable(
+        REG_CALIBRATION_FACTOR,
+        "Cal-Factor",
+        "",
+        50,
+        200,
+        "Flow sensor calibration factor",
+    )
+    .scaled(0.01),
+    RegisterInfo::read_only(REG_REAL_FLOW, "Flow", "m3/h", "Measured airflow"),
+];
+
 #[cfg(test)]
 mod tests {
     use crate::constants::{
-        REG_C_FILTER_LIMIT, REG_C_FILTER_TOTAL, REG_M_FILTER_LIMIT, REG_M_FILTER_TOTAL,
-        REG_P_FILTER_LIMIT, REG_P_FILTER_TOTAL, STATUS_POLL_REG_COUNT,
+        REG_C_FILTER_LIMIT, REG_C_FILTER_TOTAL, REG_CALIBRATION_FACTOR, REG_M_FILTER_LIMIT,
+        REG_M_FILTER_TOTAL, REG_MODE, REG_P_FILTER_LIMIT, REG_P_FILTER_TOTAL, REG_STATE,
+        REG_THRESHOLD_A, STATUS_POLL_REG_COUNT,
     };
-    use crate::data::DeviceStatus;
+    use crate::data::{DeviceIdentity, DeviceStatus, OperatingMode, REGISTERS};
+    use crate::profile::QUICK_6101A2;
 
     #[test]
     fn parses_filter_totals_and_limits() {
@@ -96,4 +489,97 @@ mod tests {
         assert_eq!(status.m_filter_limit, 222);
         assert_eq!(status.c_filter_limit, 333);
     }
+
+    #[test]
+    fn register_catalog_has_unique_addresses() {
+        for (index, info) in REGISTERS.iter().enumerate() {
+            assert!(
+                REGISTERS[index + 1..]
+                    .iter()
+                    .all(|other| other.address != info.address),
+                "duplicate entry for 0x{:04X}",
+                info.address
+            );
+            assert!(info.accepts(info.min) && info.accepts(info.max));
+        }
+    }
+
+    #[test]
+    fn catalog_describes_target_and_status() {
+        let status = QUICK_6101A2
+            .register_info(0x0002)
+            .expect("status flags are catalogued");
+        assert!(!status.writable);
+        let target = QUICK_6101A2
+            .register_info(0x0001)
+            .expect("target flow is catalogued");
+        assert!(target.writable);
+        assert_eq!((target.min, target.max), (30, 100));
+        assert!(QUICK_6101A2.register_info(0x0017).is_none());
+    }
+
+    #[test]
+    fn decodes_operating_mode_register() {
+        let mut registers = vec![0u16; STATUS_POLL_REG_COUNT as usize];
+        registers[REG_MODE as usize] = 1;
+        let status = DeviceStatus::from_registers(registers).expect("status should parse");
+        assert_eq!(status.mode(), Some(OperatingMode::Auto));
+        assert_eq!(OperatingMode::from_register(7), None);
+    }
+
+    #[test]
+    fn scaled_registers_convert_engineering_values() {
+        let info = QUICK_6101A2
+            .register_info(REG_CALIBRATION_FACTOR)
+            .expect("calibration factor is catalogued");
+        assert_eq!(info.decimals(), 2);
+        assert_eq!(info.format_value(125), "1.25");
+        assert_eq!(info.parse_value("1.25"), Some(125));
+        assert_eq!(info.parse_value("-1"), None);
+        assert_eq!(info.parse_value("1000"), None);
+    }
+
+    #[test]
+    fn identity_prefers_printable_revision_text() {
+        let identity =
+            DeviceIdentity::from_report(&[0x02, 0xFF, b'V', b'1', b'.', b'3']).expect("identity");
+        assert_eq!(identity.server_id, 2);
+        assert_eq!(identity.revision, "V1.3");
+        let binary = DeviceIdentity::from_report(&[0x02, 0xFF, 0x01, 0x07]).expect("identity");
+        assert_eq!(binary.revision, "0107");
+        assert_eq!(DeviceIdentity::from_report(&[]), None);
+    }
+
+    #[test]
+    fn typed_registers_render_and_parse_in_their_domain() {
+        let threshold = QUICK_6101A2
+            .register_info(REG_THRESHOLD_A)
+            .expect("threshold is catalogued");
+        assert_eq!(threshold.format_value(u16::MAX), "-1");
+        assert_eq!(threshold.parse_value("-10"), Some((-10i16).cast_unsigned()));
+        assert!(threshold.accepts((-10i16).cast_unsigned()));
+        assert!(!threshold.accepts((-150i16).cast_unsigned()));
+
+        let mode = QUICK_6101A2
+            .register_info(REG_MODE)
+            .expect("mode is catalogued");
+        assert_eq!(mode.format_value(1), "Auto");
+        assert_eq!(mode.parse_value("manual"), Some(0));
+        let state = QUICK_6101A2
+            .register_info(REG_STATE)
+            .expect("state is catalogued");
+        assert_eq!(state.parse_value("on"), Some(1));
+    }
+
+    #[test]
+    fn status_roundtrips_through_json() {
+        let mut registers = vec![0u16; STATUS_POLL_REG_COUNT as usize];
+        registers[REG_P_FILTER_TOTAL as usize] = 11;
+        let status = DeviceStatus::from_registers(registers).expect("status should parse");
+
+        let json = serde_json::to_string(&status).expect("status should serialize");
+        assert!(json.contains("\"p_filter_total\":11"));
+        let parsed: DeviceStatus = serde_json::from_str(&json).expect("status should parse");
+        assert_eq!(parsed, status);
+    }
 }
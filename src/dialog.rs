@@ -0,0 +1,173 @@
+use crate::data::Filter;
+use crate::interface::InterfaceMode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetStage {
+    Select,
+    Confirm,
+    ConfirmAgain,
+    /// Waiting for `RESET_WORD` to be typed.
+    Type,
+}
+
+/// Word typed to confirm a reset started with `R`.
+pub const RESET_WORD: &str = "RESET";
+
+/// Filter counter reset, confirmed twice, or by typing `RESET_WORD`, before anything is written.
+#[derive(Debug)]
+pub struct FilterResetDialog {
+    pub filters: &'static [Filter],
+    pub selected: usize,
+    pub stage: ResetStage,
+    /// Typed so far at `ResetStage::Type`.
+    pub typed: String,
+}
+
+impl FilterResetDialog {
+    pub fn new(filters: &'static [Filter]) -> Self {
+        Self {
+            filters,
+            selected: 0,
+            stage: ResetStage::Select,
+            typed: String::new(),
+        }
+    }
+
+    /// Asks for `RESET_WORD` before resetting `filter`.
+    pub fn typed(filters: &'static [Filter], filter: Filter) -> Self {
+        Self {
+            selected: filters
+                .iter()
+                .position(|candidate| *candidate == filter)
+                .unwrap_or_default(),
+            stage: ResetStage::Type,
+            ..Self::new(filters)
+        }
+    }
+
+    pub fn filter(&self) -> Filter {
+        self.filters[self.selected.min(self.filters.len() - 1)]
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.filters.len();
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self
+            .selected
+            .checked_sub(1)
+            .unwrap_or(self.filters.len() - 1);
+    }
+
+    /// Moves to the next confirmation stage, returning the filter once both are accepted.
+    pub fn accept(&mut self) -> Option<Filter> {
+        match self.stage {
+            ResetStage::Select => self.stage = ResetStage::Confirm,
+            ResetStage::Confirm => self.stage = ResetStage::ConfirmAgain,
+            ResetStage::ConfirmAgain => return Some(self.filter()),
+            ResetStage::Type => return (self.typed == RESET_WORD).then(|| self.filter()),
+        }
+        None
+    }
+}
+
+/// Ways out of the error screen, in button order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorChoice {
+    Retry,
+    ChangePort,
+    Quit,
+}
+
+impl ErrorChoice {
+    pub const ALL: [Self; 3] = [Self::Retry, Self::ChangePort, Self::Quit];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Retry => "Retry",
+            Self::ChangePort => "Change port",
+            Self::Quit => "Quit",
+        }
+    }
+}
+
+/// How to start the serial worker again, after it stopped or to change the interface.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Restart {
+    Retry,
+    Port(String),
+    /// Reconnect through this interface with its default address and baud rate.
+    Interface(InterfaceMode),
+}
+
+/// Shown when the serial worker stopped, so a brief adapter hiccup does not end the session.
+#[derive(Debug)]
+pub struct ErrorScreen {
+    pub message: String,
+    pub selected: usize,
+    /// Path being typed after choosing Change port.
+    pub port: Option<String>,
+}
+
+impl ErrorScreen {
+    pub const fn new(message: String) -> Self {
+        Self {
+            message,
+            selected: 0,
+            port: None,
+        }
+    }
+
+    pub const fn choice(&self) -> ErrorChoice {
+        ErrorChoice::ALL[self.selected]
+    }
+
+    pub const fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % ErrorChoice::ALL.len();
+    }
+
+    pub const fn select_previous(&mut self) {
+        self.selected = (self.selected + ErrorChoice::ALL.len() - 1) % ErrorChoice::ALL.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ErrorChoice, ErrorScreen, FilterResetDialog, ResetStage};
+    use crate::data::Filter;
+
+    #[test]
+    fn reset_needs_two_confirmations() {
+        let mut dialog = FilterResetDialog::new(&[Filter::Pre, Filter::Medium, Filter::Chemical]);
+        dialog.select_previous();
+        assert_eq!(dialog.filter(), Filter::Chemical);
+        assert_eq!(dialog.accept(), None);
+        assert_eq!(dialog.stage, ResetStage::Confirm);
+        assert_eq!(dialog.accept(), None);
+        assert_eq!(dialog.accept(), Some(Filter::Chemical));
+    }
+
+    #[test]
+    fn typed_reset_needs_the_exact_word() {
+        let mut dialog = FilterResetDialog::typed(
+            &[Filter::Pre, Filter::Medium, Filter::Chemical],
+            Filter::Medium,
+        );
+        dialog.typed = String::from("reset");
+        assert_eq!(dialog.accept(), None);
+        dialog.typed = String::from("RESET");
+        assert_eq!(dialog.accept(), Some(Filter::Medium));
+    }
+
+    #[test]
+    fn error_screen_choices_wrap_around() {
+        let mut screen = ErrorScreen::new(String::from("port vanished"));
+        assert_eq!(screen.choice(), ErrorChoice::Retry);
+        screen.select_previous();
+        assert_eq!(screen.choice(), ErrorChoice::Quit);
+        screen.select_next();
+        screen.select_next();
+        assert_eq!(screen.choice(), ErrorChoice::ChangePort);
+    }
+}
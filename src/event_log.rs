@@ -0,0 +1,259 @@
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Local};
+
+use crate::profile::DeviceProfile;
+
+pub const EVENT_LOG_CAPACITY: usize = 200;
+
+/// Polls a write may take to show up before a matching change is no longer attributed to it.
+pub const PENDING_WRITE_POLLS: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventSource {
+    /// Caused by a command sent from this monitor.
+    User,
+    /// Observed on the device without a matching command, e.g. from the front panel.
+    Device,
+    /// Raised by the monitor itself, such as schedules and alarms.
+    Monitor,
+    /// Serial link faults such as timeouts, CRC errors and failed writes.
+    Transport,
+}
+
+impl EventSource {
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Device => "device",
+            Self::Monitor => "monitor",
+            Self::Transport => "link",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Severity {
+    #[default]
+    Info,
+    Warning,
+    /// The link stopped working and will not recover by itself.
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub timestamp: SystemTime,
+    pub source: EventSource,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl LogEntry {
+    /// Wall-clock time of the entry as `HH:MM:SS`.
+    pub fn clock(&self) -> String {
+        DateTime::<Local>::from(self.timestamp)
+            .format("%H:%M:%S")
+            .to_string()
+    }
+}
+
+/// Ring buffer of register changes and commands, newest last.
+#[derive(Debug)]
+pub struct EventLog {
+    entries: VecDeque<LogEntry>,
+    /// Register writes applied by the monitor that have not shown up in a poll yet,
+    /// with the time after which they are dropped.
+    pending_writes: Vec<(u16, u16, SystemTime)>,
+    pending_timeout: Duration,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self {
+            entries: VecDeque::with_capacity(EVENT_LOG_CAPACITY),
+            pending_writes: Vec::new(),
+            pending_timeout: Duration::from_millis(500) * PENDING_WRITE_POLLS,
+        }
+    }
+}
+
+impl EventLog {
+    pub fn push(&mut self, timestamp: SystemTime, source: EventSource, message: String) {
+        self.push_with_severity(timestamp, source, Severity::Info, message);
+    }
+
+    pub fn push_with_severity(
+        &mut self,
+        timestamp: SystemTime,
+        source: EventSource,
+        severity: Severity,
+        message: String,
+    ) {
+        if self.entries.len() == EVENT_LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LogEntry {
+            timestamp,
+            source,
+            severity,
+            message,
+        });
+    }
+
+    /// Sets how long an applied write waits for its change, usually a few poll intervals.
+    pub fn set_pending_timeout(&mut self, timeout: Duration) {
+        self.pending_timeout = timeout;
+    }
+
+    /// Logs a write sent by the monitor and remembers it so the change it causes is
+    /// attributed to the user.
+    pub fn record_write(
+        &mut self,
+        profile: &DeviceProfile,
+        register: u16,
+        value: u16,
+        timestamp: SystemTime,
+    ) {
+        let message = match profile.register_info(register) {
+            Some(info) => format!(
+                "sent {} {}",
+                info.name.to_lowercase(),
+                info.format_value(value)
+            ),
+            None => format!("sent 0x{register:04X} = {value}"),
+        };
+        self.push(timestamp, EventSource::User, message);
+        self.pending_writes
+            .retain(|(pending, _, _)| *pending != register);
+        self.pending_writes
+            .push((register, value, timestamp + self.pending_timeout));
+    }
+
+    /// Logs a register change seen between two polls.
+    pub fn record_change(
+        &mut self,
+        profile: &DeviceProfile,
+        register: u16,
+        old: u16,
+        new: u16,
+        timestamp: SystemTime,
+    ) {
+        self.pending_writes
+            .retain(|(_, _, deadline)| *deadline >= timestamp);
+        let source = if let Some(index) = self
+            .pending_writes
+            .iter()
+            .position(|(pending, value, _)| (*pending, *value) == (register, new))
+        {
+            self.pending_writes.swap_remove(index);
+            EventSource::User
+        } else {
+            EventSource::Device
+        };
+        let Some(info) = profile.register_info(register) else {
+            return;
+        };
+        let message = format!(
+            "{} {}→{}",
+            info.name.to_lowercase(),
+            info.format_value(old),
+            info.format_value(new)
+        );
+        self.push(timestamp, source, message);
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &LogEntry> + ExactSizeIterator {
+        self.entries.iter()
+    }
+}
+
+/// Scroll state of the Log tab, counted in entries back from the newest.
+#[derive(Debug, Default)]
+pub struct LogView {
+    /// Newest entries hidden below the pane; 0 follows new entries.
+    pub scroll_back: usize,
+    /// Pane height in entries, set by `fit` before each draw.
+    pub viewport: usize,
+}
+
+impl LogView {
+    /// Takes the pane height from `ui::update_layout`, keeping the pane full after it grows.
+    pub fn fit(&mut self, viewport: usize, entries: usize) {
+        self.viewport = viewport;
+        self.scroll_back = self.scroll_back.min(entries.saturating_sub(viewport));
+    }
+
+    /// Scrolls towards older entries for positive `lines`, keeping the pane full.
+    pub fn scroll_by(&mut self, lines: isize, entries: usize) {
+        let max = entries.saturating_sub(self.viewport);
+        self.scroll_back = self.scroll_back.saturating_add_signed(lines).min(max);
+    }
+
+    pub fn page(&self) -> isize {
+        isize::try_from(self.viewport.max(1)).unwrap_or(isize::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{EventLog, EventSource, LogView};
+    use crate::constants::{REG_STATE, REG_TARGET_FLOW, STATE_OFF, STATE_ON};
+    use crate::profile::QUICK_6101A2;
+
+    #[test]
+    fn changes_matching_applied_writes_are_attributed_to_user() {
+        let mut log = EventLog::default();
+        log.record_write(&QUICK_6101A2, REG_TARGET_FLOW, 75, UNIX_EPOCH);
+        log.record_change(&QUICK_6101A2, REG_TARGET_FLOW, 70, 75, UNIX_EPOCH);
+        log.record_change(&QUICK_6101A2, REG_STATE, STATE_ON, STATE_OFF, UNIX_EPOCH);
+
+        let entries: Vec<(EventSource, &str)> = log
+            .iter()
+            .map(|entry| (entry.source, entry.message.as_str()))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (EventSource::User, "sent target 75"),
+                (EventSource::User, "target 70→75"),
+                (EventSource::Device, "state ON→OFF"),
+            ]
+        );
+    }
+
+    #[test]
+    fn pending_writes_expire_after_the_timeout() {
+        let mut log = EventLog::default();
+        log.set_pending_timeout(Duration::from_secs(3));
+        log.record_write(&QUICK_6101A2, REG_TARGET_FLOW, 75, UNIX_EPOCH);
+        let late = UNIX_EPOCH + Duration::from_secs(10);
+        log.record_change(&QUICK_6101A2, REG_TARGET_FLOW, 70, 75, late);
+
+        let last = log.iter().last().expect("change should be logged");
+        assert_eq!(last.source, EventSource::Device);
+    }
+
+    #[test]
+    fn log_view_scrolls_back_within_the_entries() {
+        let mut view = LogView {
+            viewport: 5,
+            ..LogView::default()
+        };
+        view.scroll_by(100, 12);
+        assert_eq!(view.scroll_back, 7);
+        view.scroll_by(-view.page(), 12);
+        assert_eq!(view.scroll_back, 2);
+        view.scroll_by(-10, 12);
+        assert_eq!(view.scroll_back, 0);
+        view.scroll_by(3, 4);
+        assert_eq!(view.scroll_back, 0);
+
+        view.scroll_by(100, 12);
+        view.fit(10, 12);
+        assert_eq!(view.scroll_back, 2);
+    }
+}
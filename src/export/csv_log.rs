@@ -0,0 +1,230 @@
+use std::fmt::{self, Write as _};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::SystemTime;
+
+use color_eyre::eyre::{self, WrapErr};
+
+use super::{Exporter, Incoming};
+use crate::app::AppState;
+use crate::constants::STATUS_POLL_REG_COUNT;
+use crate::headless::{CSV_HEADER, cells, csv_row};
+use crate::transport::PollOutcome;
+
+/// Appends a `watch --format csv` row per answered poll to a file, optionally followed by every
+/// polled register, and syncs each batch to disk so a power cut loses at most the last rows.
+pub struct CsvLog {
+    path: PathBuf,
+    registers: bool,
+    /// Taken on drop to stop the writer, which is then joined so the last rows reach the disk.
+    rows: Option<Sender<String>>,
+    writer: Option<JoinHandle<()>>,
+    faults: Receiver<String>,
+}
+
+impl fmt::Debug for CsvLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CsvLog")
+            .field("path", &self.path)
+            .field("registers", &self.registers)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CsvLog {
+    /// Opens `path` for appending, writing the header to a new file and refusing one whose
+    /// header has other columns, and starts the writer thread.
+    pub fn open(path: &Path, registers: bool) -> eyre::Result<Self> {
+        let created = !path.exists();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)
+            .wrap_err_with(|| format!("open CSV log {}", path.display()))?;
+        prepare(&mut file, &header(registers))
+            .wrap_err_with(|| format!("CSV log {}", path.display()))?;
+        if created {
+            // The new file's directory entry must reach the disk too, or a power cut loses it.
+            let directory = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+                .unwrap_or(Path::new("."));
+            File::open(directory)
+                .and_then(|directory| directory.sync_all())
+                .wrap_err_with(|| format!("sync {}", directory.display()))?;
+        }
+
+        let (rows, queued) = mpsc::channel::<String>();
+        let (fault_tx, faults) = mpsc::channel();
+        let writer = thread::spawn(move || {
+            let mut last_fault = None;
+            while let Ok(first) = queued.recv() {
+                let mut batch = first;
+                while let Ok(row) = queued.try_recv() {
+                    batch.push_str(&row);
+                }
+                let written = file
+                    .write_all(batch.as_bytes())
+                    .and_then(|()| file.sync_data());
+                match written {
+                    Ok(()) => last_fault = None,
+                    Err(err) => {
+                        let fault = format!("CSV log: {err}");
+                        if last_fault.as_ref() != Some(&fault) {
+                            last_fault = Some(fault.clone());
+                            if fault_tx.send(fault).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+        Ok(Self {
+            path: path.to_path_buf(),
+            registers,
+            rows: Some(rows),
+            writer: Some(writer),
+            faults,
+        })
+    }
+
+    fn row(
+        &self,
+        device: &str,
+        app: &AppState,
+        outcome: PollOutcome,
+        now: SystemTime,
+    ) -> Option<String> {
+        let mut cells = cells(device, app, outcome, now)?.to_vec();
+        if self.registers
+            && let Some(status) = &app.status
+        {
+            cells.extend(status.registers.iter().map(ToString::to_string));
+        }
+        Some(csv_row(&cells) + "\n")
+    }
+}
+
+impl Drop for CsvLog {
+    fn drop(&mut self) {
+        drop(self.rows.take());
+        if let Some(writer) = self.writer.take() {
+            writer.join().ok();
+        }
+    }
+}
+
+impl Exporter for CsvLog {
+    fn poll(&mut self, device: &str, app: &AppState, outcome: PollOutcome, now: SystemTime) {
+        if let (Some(row), Some(rows)) = (self.row(device, app, outcome, now), &self.rows) {
+            rows.send(row).ok();
+        }
+    }
+
+    fn incoming(&mut self) -> Vec<Incoming> {
+        self.faults.try_iter().map(Incoming::Fault).collect()
+    }
+}
+
+/// The header row, with a column per polled register when they are logged.
+fn header(registers: bool) -> String {
+    let mut header = String::from(CSV_HEADER);
+    if registers {
+        for register in 0..STATUS_POLL_REG_COUNT {
+            let _ = write!(header, ",reg_0x{register:04X}");
+        }
+    }
+    header
+}
+
+/// Starts an empty file with `header`, checks an existing one has it, and ends a row cut short
+/// by a crash so the next one starts on its own line.
+fn prepare(file: &mut File, header: &str) -> eyre::Result<()> {
+    let mut first = String::new();
+    BufReader::new(&*file).read_line(&mut first)?;
+    if first.is_empty() {
+        writeln!(file, "{header}")?;
+        file.sync_all()?;
+        return Ok(());
+    }
+    eyre::ensure!(
+        first.trim_end() == header,
+        "the file has other columns; log to a new file"
+    );
+    file.seek(SeekFrom::End(-1))?;
+    let mut last = [0];
+    file.read_exact(&mut last)?;
+    if last != *b"\n" {
+        writeln!(file)?;
+        file.sync_data()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{CsvLog, header};
+    use crate::app::AppState;
+    use crate::constants::STATUS_POLL_REG_COUNT;
+    use crate::data::DeviceStatus;
+    use crate::export::Exporter;
+    use crate::interface::InterfaceMode;
+    use crate::transport::PollOutcome;
+
+    #[test]
+    fn rows_carry_the_registers_when_asked_and_files_keep_their_columns() {
+        let path = std::env::temp_dir().join(format!("qu6101a2-log-{}.csv", std::process::id()));
+        fs::remove_file(&path).ok();
+        let mut log = CsvLog::open(&path, true).expect("new log opens");
+        assert_eq!(
+            fs::read_to_string(&path).expect("log reads"),
+            header(true) + "\n"
+        );
+
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        let mut registers = vec![0; STATUS_POLL_REG_COUNT as usize];
+        registers[1] = 65;
+        app.status = Some(DeviceStatus::from_registers(registers).expect("status should parse"));
+        let answered = PollOutcome::Answered(Duration::from_millis(5));
+        let row = log
+            .row("lab", &app, answered, UNIX_EPOCH)
+            .expect("answered polls have a row");
+        assert_eq!(
+            row.split(',').count(),
+            header(true).split(',').count(),
+            "{row}"
+        );
+        assert!(
+            row.ends_with(",0,65,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0\n"),
+            "{row}"
+        );
+        assert!(
+            log.row("lab", &app, PollOutcome::Timeout, UNIX_EPOCH)
+                .is_none()
+        );
+        log.poll("lab", &app, answered, UNIX_EPOCH);
+        drop(log);
+        assert_eq!(
+            fs::read_to_string(&path).expect("log reads"),
+            header(true) + "\n" + &row
+        );
+
+        assert!(CsvLog::open(&path, false).is_err());
+        fs::write(&path, header(false) + "\n1970-01-01,lab,on,8").expect("torn log writes");
+        drop(CsvLog::open(&path, false).expect("matching log opens"));
+        assert!(
+            fs::read_to_string(&path)
+                .expect("log reads")
+                .ends_with(",on,8\n")
+        );
+        fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,759 @@
+use std::fmt::{self, Write as _};
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TryRecvError};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use color_eyre::eyre;
+use serde::Deserialize;
+use tungstenite::handshake::derive_accept_key;
+use tungstenite::protocol::Role;
+use tungstenite::protocol::frame::CloseFrame;
+use tungstenite::protocol::frame::coding::CloseCode;
+use tungstenite::{Message, WebSocket};
+
+use super::{AlarmTransitions, Exporter, Incoming, Snapshot, Snapshots, find, lock, record};
+use crate::app::AppState;
+use crate::headless::{PollReport, timestamp};
+use crate::transport::{PollOutcome, TransportCommand};
+
+/// Longest request body read, far above any command.
+const MAX_BODY: u64 = 4096;
+/// Longest request line and headers read, far above what browsers send.
+const MAX_HEAD: u64 = 16 * 1024;
+/// How long a client may take over its request, or over reading a reply, before it is dropped.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// How often a `/stream` socket stops waiting on the client to send what was queued.
+const STREAM_TICK: Duration = Duration::from_millis(50);
+/// Messages a `/stream` client may fall behind by before it is dropped.
+const STREAM_BACKLOG: usize = 256;
+
+/// The `[http]` table: the REST API for an intranet page to show and control the devices.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    /// `address:port` to listen on; `--http-listen` overrides it.
+    pub listen: Option<String>,
+    /// Expected as `Authorization: Bearer <token>` on every request.
+    pub token: String,
+    /// Origin of a page on another host allowed to call the API, such as
+    /// `http://intranet.local` or `*`; without it browsers only allow same-origin pages.
+    pub allow_origin: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FlowBody {
+    flow: u16,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PowerBody {
+    power: bool,
+}
+
+/// The answer to one request, and the write it asked for.
+#[derive(Debug, PartialEq)]
+struct Reply {
+    status: u16,
+    body: serde_json::Value,
+    command: Option<Incoming>,
+}
+
+impl Reply {
+    fn ok(body: serde_json::Value) -> Self {
+        Self {
+            status: 200,
+            body,
+            command: None,
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: serde_json::json!({ "error": message.into() }),
+            command: None,
+        }
+    }
+}
+
+/// Outgoing messages of each open `/stream` WebSocket; one that closed, or whose client fell
+/// [`STREAM_BACKLOG`] messages behind, is dropped on the next poll.
+type Streams = Arc<Mutex<Vec<SyncSender<String>>>>;
+
+/// Serves `GET /status`, `GET /history`, `POST /target-flow` and `POST /power` from the
+/// latest polls, and pushes polls and alarm transitions to `/stream` WebSocket clients;
+/// writes go to the devices like keyboard ones.
+pub struct HttpApi {
+    devices: Snapshots,
+    streams: Streams,
+    alarms: AlarmTransitions,
+    commands: Receiver<Incoming>,
+    address: String,
+}
+
+impl fmt::Debug for HttpApi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpApi")
+            .field("address", &self.address)
+            .finish_non_exhaustive()
+    }
+}
+
+impl HttpApi {
+    /// Binds `listen` and answers requests from a background thread.
+    pub fn serve(config: &HttpConfig, devices: &[String], read_only: bool) -> eyre::Result<Self> {
+        let listen = config.listen.as_deref().unwrap_or_default();
+        let listener = TcpListener::bind(listen)
+            .map_err(|err| eyre::eyre!("cannot listen for HTTP on {listen}: {err}"))?;
+        let shared = super::snapshots(devices);
+        let streams = Streams::default();
+        let (command_tx, commands) = mpsc::channel();
+        let api = Api {
+            devices: Arc::clone(&shared),
+            streams: Arc::clone(&streams),
+            token: config.token.clone(),
+            allow_origin: config.allow_origin.clone(),
+            read_only,
+        };
+        let api = Arc::new(api);
+        // Each client gets its own thread, so one that stalls mid-request holds up no one.
+        thread::spawn(move || {
+            for client in listener.incoming().flatten() {
+                let api = Arc::clone(&api);
+                let commands = command_tx.clone();
+                thread::spawn(move || api.serve(client, &commands));
+            }
+        });
+        Ok(Self {
+            devices: shared,
+            streams,
+            alarms: AlarmTransitions::default(),
+            commands,
+            address: listen.to_string(),
+        })
+    }
+}
+
+impl Exporter for HttpApi {
+    fn poll(&mut self, device: &str, app: &AppState, outcome: PollOutcome, now: SystemTime) {
+        let report = serde_json::to_value(PollReport::new(device, app, outcome, now)).ok();
+        let mut messages: Vec<String> = report.iter().map(status_message).collect();
+        for (alarm, raised) in self.alarms.update(device, app) {
+            let message = serde_json::json!({
+                "type": "alarm",
+                "device": device,
+                "time": timestamp(now),
+                "alarm": alarm,
+                "raised": raised,
+            });
+            messages.push(message.to_string());
+        }
+        self.streams
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|stream| {
+                messages
+                    .iter()
+                    .all(|message| stream.try_send(message.clone()).is_ok())
+            });
+        record(&self.devices, device, app, report);
+    }
+
+    fn incoming(&mut self) -> Vec<Incoming> {
+        self.commands.try_iter().collect()
+    }
+}
+
+/// A request line and its headers; the body is left unread until the token checks out.
+#[derive(Debug, Default)]
+struct Head {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+}
+
+impl Head {
+    /// Reads a request head, `None` when the client hung up, went quiet or did not speak
+    /// HTTP/1.
+    fn read(reader: &mut impl BufRead) -> Option<Self> {
+        let mut reader = reader.take(MAX_HEAD);
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let mut parts = line.split_whitespace();
+        let (method, url, version) = (parts.next()?, parts.next()?, parts.next()?);
+        if !version.starts_with("HTTP/1.") || parts.next().is_some() {
+            return None;
+        }
+        let mut head = Self {
+            method: method.to_string(),
+            url: url.to_string(),
+            headers: Vec::new(),
+        };
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).ok()? == 0 {
+                return None;
+            }
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.is_empty() {
+                return Some(head);
+            }
+            let (name, value) = line.split_once(':')?;
+            head.headers
+                .push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Reads the body announced by `Content-Length`, as chunked ones are never needed.
+    fn body(&self, reader: &mut impl Read) -> Result<String, Reply> {
+        let length = match self.header("Content-Length") {
+            None if self.header("Transfer-Encoding").is_some() => {
+                return Err(Reply::error(411, "send the body with a Content-Length"));
+            }
+            None => 0,
+            Some(length) => length
+                .parse::<u64>()
+                .map_err(|_| Reply::error(400, "Content-Length is not a number"))?,
+        };
+        if length > MAX_BODY {
+            return Err(Reply::error(413, "body too large"));
+        }
+        let mut body = String::new();
+        match reader.take(length).read_to_string(&mut body) {
+            Ok(read) if read as u64 == length => Ok(body),
+            Ok(_) => Err(Reply::error(400, "body cut short")),
+            Err(_) => Err(Reply::error(400, "body is not UTF-8 text")),
+        }
+    }
+}
+
+/// The server thread's side: the snapshots and what requests must present.
+struct Api {
+    devices: Snapshots,
+    streams: Streams,
+    token: String,
+    allow_origin: Option<String>,
+    read_only: bool,
+}
+
+impl Api {
+    /// Answers one request on `client` and closes the connection, or keeps it open as a
+    /// `/stream` WebSocket.
+    fn serve(&self, client: TcpStream, commands: &Sender<Incoming>) {
+        client.set_read_timeout(Some(REQUEST_TIMEOUT)).ok();
+        client.set_write_timeout(Some(REQUEST_TIMEOUT)).ok();
+        let Ok(mut writer) = client.try_clone() else {
+            return;
+        };
+        let mut reader = BufReader::new(client);
+        let Some(head) = Head::read(&mut reader) else {
+            self.respond(&mut writer, &Reply::error(400, "not an HTTP/1 request"))
+                .ok();
+            return;
+        };
+        if head.method == "GET"
+            && head.url.split('?').next() == Some("/stream")
+            && self.authorized(&head.url, head.header("Authorization"))
+            && let Some(key) = head.header("Sec-WebSocket-Key")
+        {
+            self.open_stream(reader, key);
+            return;
+        }
+        // A client that went away is no reason to stop serving the others.
+        self.answer(&head, &mut reader, &mut writer, commands).ok();
+    }
+
+    /// Replies to the request `head` starts, checking the token before any body is read.
+    fn answer(
+        &self,
+        head: &Head,
+        reader: &mut impl Read,
+        writer: &mut impl Write,
+        commands: &Sender<Incoming>,
+    ) -> io::Result<()> {
+        let mut reply = if head.method == "OPTIONS" {
+            // Browsers send preflights without credentials, so they cannot need the token.
+            Reply {
+                status: 204,
+                body: serde_json::Value::Null,
+                command: None,
+            }
+        } else if !self.authorized(&head.url, head.header("Authorization")) {
+            Reply::error(401, "missing or wrong bearer token")
+        } else {
+            if head
+                .header("Expect")
+                .is_some_and(|expect| expect.eq_ignore_ascii_case("100-continue"))
+            {
+                writer.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+            }
+            match head.body(reader) {
+                Ok(body) => self.route(&head.method, &head.url, &body),
+                Err(reply) => reply,
+            }
+        };
+        if let Some(command) = reply.command.take() {
+            // Only fails once the dashboard is gone and the process is exiting.
+            commands.send(command).ok();
+        }
+        self.respond(writer, &reply)
+    }
+
+    /// Writes `reply` with the headers from [`Api::headers`], closing the connection after it.
+    fn respond(&self, writer: &mut impl Write, reply: &Reply) -> io::Result<()> {
+        let body = if reply.body.is_null() {
+            String::new()
+        } else {
+            reply.body.to_string()
+        };
+        let mut response = format!(
+            "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            reply.status,
+            reason(reply.status),
+            body.len()
+        );
+        for (name, value) in self.headers(!reply.body.is_null()) {
+            let _ = write!(response, "{name}: {value}\r\n");
+        }
+        response.push_str("\r\n");
+        response.push_str(&body);
+        writer.write_all(response.as_bytes())?;
+        writer.flush()
+    }
+
+    /// The JSON content type for replies with a body, and the CORS headers when
+    /// `allow_origin` is set.
+    fn headers(&self, json: bool) -> Vec<(&'static str, String)> {
+        let mut headers = Vec::new();
+        if json {
+            headers.push(("Content-Type", String::from("application/json")));
+        }
+        if let Some(origin) = &self.allow_origin {
+            headers.push(("Access-Control-Allow-Origin", origin.clone()));
+            headers.push((
+                "Access-Control-Allow-Headers",
+                String::from("Authorization, Content-Type"),
+            ));
+            headers.push((
+                "Access-Control-Allow-Methods",
+                String::from("GET, POST, OPTIONS"),
+            ));
+        }
+        headers
+    }
+
+    /// A bearer token in the header, or for `/stream` in `?token=` too, as browsers cannot set
+    /// headers on a WebSocket.
+    fn authorized(&self, url: &str, authorization: Option<&str>) -> bool {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        authorization
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|token| same_secret(token, &self.token))
+            || path == "/stream"
+                && query
+                    .split('&')
+                    .filter_map(|pair| pair.strip_prefix("token="))
+                    .any(|token| same_secret(&percent_decode(token), &self.token))
+    }
+
+    /// Completes the WebSocket handshake and feeds the socket until it closes, starting with
+    /// the latest poll of each device.
+    fn open_stream(&self, reader: BufReader<TcpStream>, key: &str) {
+        let read_ahead = reader.buffer().to_vec();
+        let mut client = reader.into_inner();
+        let response = format!(
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Accept: {}\r\n\r\n",
+            derive_accept_key(key.as_bytes())
+        );
+        if client.write_all(response.as_bytes()).is_err()
+            || client.set_read_timeout(Some(STREAM_TICK)).is_err()
+        {
+            return;
+        }
+        let mut socket = WebSocket::from_partially_read(client, read_ahead, Role::Server, None);
+        let (sender, messages) = mpsc::sync_channel(STREAM_BACKLOG);
+        {
+            let mut streams = self.streams.lock().unwrap_or_else(PoisonError::into_inner);
+            for snapshot in self
+                .devices
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .iter()
+            {
+                if let Some(report) = &snapshot.report {
+                    sender.try_send(status_message(report)).ok();
+                }
+            }
+            streams.push(sender);
+        }
+        feed(&mut socket, &messages);
+        // Forgotten before hanging up, so the next poll after a client leaves drops its sender.
+        drop(messages);
+    }
+
+    fn route(&self, method: &str, url: &str, body: &str) -> Reply {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let devices = lock(&self.devices);
+        let selected = match select(&devices, query) {
+            Ok(selected) => selected,
+            Err(reply) => return reply,
+        };
+        match (method, path) {
+            ("GET", "/status") => Reply::ok(match selected {
+                Some(snapshot) => snapshot.report(),
+                None => devices.iter().map(Snapshot::report).collect(),
+            }),
+            ("GET", "/history") => match selected.or_else(|| only(&devices)) {
+                Some(snapshot) => Reply::ok(serde_json::json!(snapshot.history())),
+                None => Reply::error(400, "name a device with ?device="),
+            },
+            ("POST", "/target-flow" | "/power") if self.read_only => {
+                Reply::error(403, "writes are disabled by --read-only")
+            }
+            ("POST", "/target-flow" | "/power") => {
+                let Some(snapshot) = selected.or_else(|| only(&devices)) else {
+                    return Reply::error(400, "name a device with ?device=");
+                };
+                let command = if path == "/power" {
+                    serde_json::from_str(body)
+                        .map(|body: PowerBody| TransportCommand::SetPower(body.power))
+                } else {
+                    serde_json::from_str(body)
+                        .map(|body: FlowBody| TransportCommand::SetTargetFlow(body.flow))
+                };
+                match command {
+                    Ok(command) => Reply {
+                        status: 202,
+                        body: serde_json::json!({ "device": snapshot.name, "queued": true }),
+                        command: Some(Incoming::Command {
+                            device: snapshot.name.clone(),
+                            command,
+                            source: "HTTP",
+                        }),
+                    },
+                    Err(err) => Reply::error(400, format!("body not understood: {err}")),
+                }
+            }
+            ("GET", "/stream") => Reply::error(400, "/stream needs a WebSocket upgrade"),
+            (_, "/status" | "/history" | "/target-flow" | "/power" | "/stream") => {
+                Reply::error(405, "method not allowed")
+            }
+            _ => Reply::error(404, "no such endpoint"),
+        }
+    }
+}
+
+/// Sends `messages` down `socket` while reading what the client sends between them, so pings
+/// get their pong and a close its reply; ends when either side closes, or once the client fell
+/// behind and its sender was dropped.
+fn feed(socket: &mut WebSocket<TcpStream>, messages: &Receiver<String>) {
+    loop {
+        match socket.read() {
+            // tungstenite queues the pong or close reply and writes it on the next call.
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(err))
+                if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(_) => return,
+        }
+        loop {
+            match messages.try_recv() {
+                Ok(message) => {
+                    if socket.send(Message::text(message)).is_err() {
+                        return;
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    let behind = CloseFrame {
+                        code: CloseCode::Again,
+                        reason: "fell behind".into(),
+                    };
+                    socket.close(Some(behind)).ok();
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// The reason phrase for the statuses the API answers with.
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        411 => "Length Required",
+        413 => "Content Too Large",
+        _ => "",
+    }
+}
+
+/// Compares a presented token with the expected one in time that does not depend on where
+/// they first differ, so the token cannot be guessed byte by byte.
+fn same_secret(given: &str, expected: &str) -> bool {
+    let (given, expected) = (given.as_bytes(), expected.as_bytes());
+    given.len() == expected.len()
+        && given
+            .iter()
+            .zip(expected)
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+/// The device named by `?device=`, by its name or topic level; `None` when not given.
+fn select<'a>(devices: &'a [Snapshot], query: &str) -> Result<Option<&'a Snapshot>, Reply> {
+    let Some(wanted) = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("device="))
+        .map(percent_decode)
+    else {
+        return Ok(None);
+    };
+    find(devices, &wanted)
+        .map(Some)
+        .ok_or_else(|| Reply::error(404, format!("no device {wanted}")))
+}
+
+/// The device when there is only one, so single-device setups can leave `?device=` out.
+fn only(devices: &[Snapshot]) -> Option<&Snapshot> {
+    match devices {
+        [snapshot] => Some(snapshot),
+        _ => None,
+    }
+}
+
+/// A poll report as a `/stream` message, told apart from alarms by `"type": "status"`.
+fn status_message(report: &serde_json::Value) -> String {
+    let mut message = report.clone();
+    if let Some(fields) = message.as_object_mut() {
+        fields.insert(String::from("type"), serde_json::json!("status"));
+    }
+    message.to_string()
+}
+
+/// Decodes `%xx` escapes and `+` in a query value; malformed escapes are kept as they are.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| text.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], escaped) {
+            (_, Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (b'+', None) => {
+                decoded.push(b' ');
+                index += 1;
+            }
+            (byte, None) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::mpsc;
+    use std::time::UNIX_EPOCH;
+
+    use tungstenite::{Message, WebSocket};
+
+    use super::{Api, Head, HttpApi, HttpConfig, Streams, percent_decode, status_message};
+    use crate::app::AppState;
+    use crate::export::snapshots;
+    use crate::export::{Exporter, Incoming};
+    use crate::interface::InterfaceMode;
+    use crate::transport::{PollOutcome, TransportCommand};
+
+    fn api(names: &[&str], read_only: bool) -> Api {
+        let names: Vec<String> = names.iter().map(ToString::to_string).collect();
+        Api {
+            devices: snapshots(&names),
+            streams: Streams::default(),
+            token: String::from("secret"),
+            allow_origin: Some(String::from("http://intranet.local")),
+            read_only,
+        }
+    }
+
+    #[test]
+    fn routes_answer_reads_and_queue_writes_for_the_named_device() {
+        let api = api(&["/dev/ttyUSB0", "hall 2"], false);
+        let status = api.route("GET", "/status", "");
+        assert_eq!(status.status, 200);
+        assert_eq!(status.body[1]["device"], "hall 2");
+
+        let flow = api.route("POST", "/target-flow?device=dev_ttyUSB0", r#"{"flow": 70}"#);
+        assert_eq!(flow.status, 202);
+        assert_eq!(
+            flow.command,
+            Some(Incoming::Command {
+                device: String::from("/dev/ttyUSB0"),
+                command: TransportCommand::SetTargetFlow(70),
+                source: "HTTP",
+            })
+        );
+        let power = api.route("POST", "/power?device=hall%202", r#"{"power": false}"#);
+        assert_eq!(power.status, 202);
+
+        assert_eq!(
+            api.route("POST", "/power", r#"{"power": false}"#).status,
+            400
+        );
+        assert_eq!(api.route("POST", "/power?device=hall+2", "on").status, 400);
+        assert_eq!(api.route("GET", "/history?device=lab", "").status, 404);
+        assert_eq!(api.route("GET", "/target-flow", "").status, 405);
+        assert_eq!(api.route("GET", "/", "").status, 404);
+    }
+
+    #[test]
+    fn read_only_refuses_writes_and_one_device_needs_no_name() {
+        let api = api(&["lab"], true);
+        assert_eq!(api.route("GET", "/history", "").status, 200);
+        let refused = api.route("POST", "/target-flow", r#"{"flow": 70}"#);
+        assert_eq!(refused.status, 403);
+        assert_eq!(refused.command, None);
+        assert_eq!(percent_decode("%2Fdev%2FttyUSB0"), "/dev/ttyUSB0");
+        assert_eq!(percent_decode("100%"), "100%");
+    }
+
+    #[test]
+    fn writes_need_the_token_before_their_body_counts() {
+        let api = api(&["lab"], false);
+        let (commands, queued) = mpsc::channel();
+        let power = |authorization: &str| {
+            let request =
+                format!("POST /power HTTP/1.1\r\nContent-Length: 16\r\n{authorization}\r\n");
+            Head::read(&mut request.as_bytes()).expect("head parses")
+        };
+        let mut reply = Vec::new();
+        api.answer(
+            &power(""),
+            &mut &br#"{"power": false}"#[..],
+            &mut reply,
+            &commands,
+        )
+        .expect("reply written");
+        assert!(reply.starts_with(b"HTTP/1.1 401 Unauthorized\r\n"));
+        assert!(queued.try_recv().is_err());
+
+        reply.clear();
+        api.answer(
+            &power("authorization: Bearer secret\r\n"),
+            &mut &br#"{"power": false}"#[..],
+            &mut reply,
+            &commands,
+        )
+        .expect("reply written");
+        assert!(reply.starts_with(b"HTTP/1.1 202 Accepted\r\n"));
+        assert!(matches!(
+            queued.try_recv(),
+            Ok(Incoming::Command {
+                command: TransportCommand::SetPower(false),
+                ..
+            })
+        ));
+
+        assert!(Head::read(&mut &b"{\"power\": false}\n"[..]).is_none());
+        assert!(Head::read(&mut &b"GET /status HTTP/1.1\r\nHost: lab\r\n"[..]).is_none());
+        let cut = Head::read(&mut &b"POST /power HTTP/1.1\r\nContent-Length: 16\r\n\r\n{"[..])
+            .expect("head parses");
+        assert_eq!(
+            cut.body(&mut &b"{"[..]).map_err(|reply| reply.status),
+            Err(400)
+        );
+    }
+
+    #[test]
+    fn stream_clients_get_polls_and_their_pings_answered() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("free port");
+        let address = listener.local_addr().expect("bound address");
+        drop(listener);
+        let config = HttpConfig {
+            listen: Some(address.to_string()),
+            token: String::from("secret"),
+            allow_origin: None,
+        };
+        let mut http = HttpApi::serve(&config, &[String::from("lab")], false).expect("listens");
+
+        let connection = TcpStream::connect(address).expect("client connects");
+        let url = format!("ws://{address}/stream?token=secret");
+        let (mut socket, _): (WebSocket<TcpStream>, _) =
+            tungstenite::client(url, connection).expect("handshake completes");
+        socket.send(Message::Ping("hi".into())).expect("ping sent");
+        assert_eq!(
+            socket.read().expect("pong read"),
+            Message::Pong("hi".into())
+        );
+
+        let app = AppState::new(InterfaceMode::Remote, false);
+        http.poll("lab", &app, PollOutcome::Timeout, UNIX_EPOCH);
+        let Message::Text(poll) = socket.read().expect("poll read") else {
+            panic!("polls are text");
+        };
+        assert!(poll.contains(r#""type":"status""#), "{poll}");
+
+        socket.close(None).expect("close sent");
+        while socket.read().is_ok() {}
+        assert_eq!(http.streams.lock().expect("streams lock").len(), 1);
+        http.poll("lab", &app, PollOutcome::Timeout, UNIX_EPOCH);
+        assert!(http.streams.lock().expect("streams lock").is_empty());
+
+        let mut plain = TcpStream::connect(address).expect("client connects");
+        plain.write_all(b"{\"power\": false}\n").expect("line sent");
+        let mut refused = String::new();
+        plain.read_to_string(&mut refused).expect("reply read");
+        assert!(refused.starts_with("HTTP/1.1 400 "), "{refused}");
+    }
+
+    #[test]
+    fn only_the_stream_takes_its_token_from_the_query() {
+        let api = api(&["lab"], false);
+        assert!(api.authorized("/status", Some("Bearer secret")));
+        assert!(!api.authorized("/status", Some("secret")));
+        assert!(!api.authorized("/status?token=secret", None));
+        assert!(api.authorized("/stream?token=secret", None));
+        assert!(!api.authorized("/stream?token=guess", None));
+        assert!(!api.authorized("/status", Some("Bearer secreT")));
+        assert!(!api.authorized("/status", Some("Bearer secret2")));
+        assert_eq!(api.route("GET", "/stream", "").status, 400);
+
+        let headers = api.headers(false);
+        assert!(headers.contains(&(
+            "Access-Control-Allow-Origin",
+            String::from("http://intranet.local")
+        )));
+        assert!(!headers.iter().any(|(name, _)| *name == "Content-Type"));
+
+        let message = status_message(&serde_json::json!({ "device": "lab" }));
+        assert_eq!(message, r#"{"device":"lab","type":"status"}"#);
+    }
+}
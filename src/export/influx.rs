@@ -0,0 +1,297 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Write as _};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::net::UdpSocket;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+
+use super::{Exporter, Incoming, raised_alarms};
+use crate::app::AppState;
+use crate::transport::PollOutcome;
+
+/// Longest an HTTP write may take before it counts as failed.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The `[influx]` table: where samples go as Influx line protocol, with one of `file`, `udp`
+/// or `url` set.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InfluxConfig {
+    /// File the lines are appended to.
+    pub file: Option<PathBuf>,
+    /// `host:port` of an Influx UDP listener.
+    pub udp: Option<String>,
+    /// Write endpoint, e.g. `http://influx:8086/api/v2/write?org=shop&bucket=fans`.
+    pub url: Option<String>,
+    /// Sent as `Authorization: Token <token>` with HTTP writes.
+    pub token: Option<String>,
+    pub measurement: String,
+    /// Tags added to every line beside `device`.
+    pub tags: BTreeMap<String, String>,
+}
+
+impl Default for InfluxConfig {
+    fn default() -> Self {
+        Self {
+            file: None,
+            udp: None,
+            url: None,
+            token: None,
+            measurement: String::from("qu6101a2"),
+            tags: BTreeMap::new(),
+        }
+    }
+}
+
+impl InfluxConfig {
+    /// How many of `file`, `udp` and `url` are set; exactly one is valid.
+    pub fn targets(&self) -> usize {
+        usize::from(self.file.is_some())
+            + usize::from(self.udp.is_some())
+            + usize::from(self.url.is_some())
+    }
+}
+
+/// Where the writer thread sends its batches.
+enum Target {
+    File(File),
+    Udp(UdpSocket),
+    Http {
+        agent: ureq::Agent,
+        url: String,
+        token: Option<String>,
+    },
+}
+
+impl Target {
+    fn open(config: &InfluxConfig) -> io::Result<Self> {
+        if let Some(path) = &config.file {
+            return Ok(Self::File(
+                OpenOptions::new().create(true).append(true).open(path)?,
+            ));
+        }
+        if let Some(address) = &config.udp {
+            let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+            socket.connect(address)?;
+            return Ok(Self::Udp(socket));
+        }
+        let agent = ureq::Agent::new_with_config(
+            ureq::config::Config::builder()
+                .timeout_global(Some(HTTP_TIMEOUT))
+                .build(),
+        );
+        Ok(Self::Http {
+            agent,
+            url: config.url.clone().unwrap_or_default(),
+            token: config.token.clone(),
+        })
+    }
+
+    fn write(&mut self, batch: &str) -> Result<(), String> {
+        match self {
+            Self::File(file) => file
+                .write_all(batch.as_bytes())
+                .map_err(|err| err.to_string()),
+            // A datagram per line keeps each under the listener's packet size.
+            Self::Udp(socket) => batch.lines().try_for_each(|line| {
+                socket
+                    .send(line.as_bytes())
+                    .map(drop)
+                    .map_err(|err| err.to_string())
+            }),
+            Self::Http { agent, url, token } => {
+                let mut request = agent
+                    .post(url.as_str())
+                    .content_type("text/plain; charset=utf-8");
+                if let Some(token) = token {
+                    request = request.header("Authorization", format!("Token {token}"));
+                }
+                request.send(batch).map(drop).map_err(|err| err.to_string())
+            }
+        }
+    }
+}
+
+/// Writes a line per poll and device in Influx line protocol from a background thread, so a
+/// slow endpoint never holds up polling.
+pub struct InfluxExporter {
+    measurement: String,
+    /// `,key=value` pairs from the config, escaped once.
+    tags: String,
+    lines: Sender<String>,
+    faults: Receiver<String>,
+}
+
+impl fmt::Debug for InfluxExporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InfluxExporter")
+            .field("measurement", &self.measurement)
+            .field("tags", &self.tags)
+            .finish_non_exhaustive()
+    }
+}
+
+impl InfluxExporter {
+    /// Starts the writer thread; a target that cannot be opened or written is logged, not fatal.
+    pub fn start(config: &InfluxConfig) -> Self {
+        let (lines, queued) = mpsc::channel::<String>();
+        let (fault_tx, faults) = mpsc::channel();
+        let config_for_thread = config.clone();
+        thread::spawn(move || {
+            let mut target = None;
+            let mut last_fault = None;
+            while let Ok(first) = queued.recv() {
+                let mut batch = first;
+                while let Ok(line) = queued.try_recv() {
+                    batch.push_str(&line);
+                }
+                let written = match &mut target {
+                    Some(target) => Ok(target),
+                    None => Target::open(&config_for_thread)
+                        .map(|opened| target.insert(opened))
+                        .map_err(|err| err.to_string()),
+                }
+                .and_then(|target| target.write(&batch));
+                match written {
+                    Ok(()) => last_fault = None,
+                    Err(err) => {
+                        let fault = format!("Influx output: {err}");
+                        if last_fault.as_ref() != Some(&fault) {
+                            last_fault = Some(fault.clone());
+                            if fault_tx.send(fault).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+        let mut tags = String::new();
+        for (key, value) in &config.tags {
+            let _ = write!(tags, ",{}={}", escape_tag(key), escape_tag(value));
+        }
+        Self {
+            measurement: escape_measurement(&config.measurement),
+            tags,
+            lines,
+            faults,
+        }
+    }
+
+    fn line(&self, device: &str, app: &AppState, outcome: PollOutcome, now: SystemTime) -> String {
+        let mut fields = vec![
+            format!("poll={}", string_field(outcome.name())),
+            format!("connected={}", app.connected),
+        ];
+        if let PollOutcome::Answered(latency) = outcome {
+            fields.push(format!("latency_ms={}i", latency.as_millis()));
+            if let Some(status) = &app.status {
+                for (name, value) in [
+                    ("state", status.state),
+                    ("target_flow", status.target_flow),
+                    ("real_flow", status.real_flow),
+                    ("speed_rpm", status.speed_rpm),
+                    ("p_filter_total", status.p_filter_total),
+                    ("m_filter_total", status.m_filter_total),
+                    ("c_filter_total", status.c_filter_total),
+                ] {
+                    fields.push(format!("{name}={value}i"));
+                }
+            }
+        }
+        fields.push(format!("alarms={}i", raised_alarms(app).len()));
+        fields.push(format!("deviation={}", app.deviation.is_active()));
+        let nanos = now
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_nanos());
+        format!(
+            "{},device={}{} {} {nanos}\n",
+            self.measurement,
+            escape_tag(device),
+            self.tags,
+            fields.join(",")
+        )
+    }
+}
+
+impl Exporter for InfluxExporter {
+    fn poll(&mut self, device: &str, app: &AppState, outcome: PollOutcome, now: SystemTime) {
+        let line = self.line(device, app, outcome, now);
+        self.lines.send(line).ok();
+    }
+
+    fn incoming(&mut self) -> Vec<Incoming> {
+        self.faults.try_iter().map(Incoming::Fault).collect()
+    }
+}
+
+fn escape_measurement(name: &str) -> String {
+    name.replace(',', "\\,").replace(' ', "\\ ")
+}
+
+fn escape_tag(text: &str) -> String {
+    escape_measurement(text).replace('=', "\\=")
+}
+
+fn string_field(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use super::{InfluxConfig, InfluxExporter};
+    use crate::app::AppState;
+    use crate::constants::STATUS_POLL_REG_COUNT;
+    use crate::data::DeviceStatus;
+    use crate::interface::InterfaceMode;
+    use crate::transport::PollOutcome;
+
+    #[test]
+    fn polls_become_escaped_lines_with_the_configured_tags() {
+        let config = InfluxConfig {
+            file: Some(std::env::temp_dir().join("qu6101a2-influx-test.lp")),
+            measurement: String::from("fan unit"),
+            tags: [(String::from("site"), String::from("hall 2"))].into(),
+            ..InfluxConfig::default()
+        };
+        let exporter = InfluxExporter::start(&config);
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        let mut status = DeviceStatus::from_registers(vec![0; STATUS_POLL_REG_COUNT as usize])
+            .expect("status should parse");
+        status.state = 1;
+        status.target_flow = 75;
+        status.real_flow = 74;
+        status.speed_rpm = 1450;
+        app.status = Some(status);
+        app.connected = true;
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let line = exporter.line(
+            "/dev/ttyUSB0",
+            &app,
+            PollOutcome::Answered(Duration::from_millis(12)),
+            now,
+        );
+        assert_eq!(
+            line,
+            "fan\\ unit,device=/dev/ttyUSB0,site=hall\\ 2 poll=\"answered\",connected=true,\
+             latency_ms=12i,state=1i,target_flow=75i,real_flow=74i,speed_rpm=1450i,\
+             p_filter_total=0i,m_filter_total=0i,c_filter_total=0i,alarms=0i,deviation=false \
+             1700000000000000000\n"
+        );
+
+        app.connected = false;
+        let line = exporter.line("a=b", &app, PollOutcome::Timeout, SystemTime::UNIX_EPOCH);
+        assert_eq!(
+            line,
+            "fan\\ unit,device=a\\=b,site=hall\\ 2 poll=\"timeout\",connected=false,alarms=0i,\
+             deviation=false 0\n"
+        );
+    }
+}
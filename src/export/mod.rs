@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::SystemTime;
+
+use serde::Serialize;
+
+use crate::app::AppState;
+use crate::data::Filter;
+use crate::headless::timestamp;
+use crate::history::Sample;
+use crate::transport::{PollOutcome, TransportCommand};
+
+mod csv_log;
+mod http;
+mod influx;
+mod mqtt;
+mod rpc;
+#[cfg(unix)]
+mod socket;
+
+pub use csv_log::CsvLog;
+pub use http::{HttpApi, HttpConfig};
+pub use influx::{InfluxConfig, InfluxExporter};
+pub use mqtt::{MqttConfig, MqttExporter};
+pub use rpc::DeviceInfo;
+#[cfg(unix)]
+pub use socket::ControlSocket;
+
+/// What an exporter heard back since it was last asked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Incoming {
+    /// A write requested for the named device; `source` names the exporter in the event log.
+    Command {
+        device: String,
+        command: TransportCommand,
+        source: &'static str,
+    },
+    /// A failure worth a warning in the event log, such as an unreachable broker.
+    Fault(String),
+}
+
+/// A sink fed every finished poll of every device, alongside the dashboard or headless output.
+pub trait Exporter: fmt::Debug {
+    fn poll(&mut self, device: &str, app: &AppState, outcome: PollOutcome, now: SystemTime);
+
+    /// Writes requested and faults met since the last call.
+    fn incoming(&mut self) -> Vec<Incoming> {
+        Vec::new()
+    }
+}
+
+/// What server threads know of a device: the last poll and the retained history.
+#[derive(Debug)]
+pub struct Snapshot {
+    pub name: String,
+    /// The `--headless` object of the last poll; `None` before the first.
+    pub report: Option<serde_json::Value>,
+    pub history: Vec<Sample>,
+}
+
+/// Snapshots of every device, updated by an exporter and read by its server threads.
+pub type Snapshots = Arc<Mutex<Vec<Snapshot>>>;
+
+impl Snapshot {
+    /// The last poll report, or one with only the device and a `null` poll before the first.
+    pub fn report(&self) -> serde_json::Value {
+        self.report.clone().unwrap_or_else(
+            || serde_json::json!({ "device": self.name, "poll": serde_json::Value::Null }),
+        )
+    }
+
+    pub fn history(&self) -> Vec<HistoryPoint> {
+        self.history.iter().map(HistoryPoint::from).collect()
+    }
+}
+
+/// A snapshot per device, empty until their first poll.
+pub fn snapshots(devices: &[String]) -> Snapshots {
+    Arc::new(Mutex::new(
+        devices
+            .iter()
+            .map(|name| Snapshot {
+                name: name.clone(),
+                report: None,
+                history: Vec::new(),
+            })
+            .collect(),
+    ))
+}
+
+/// The snapshots, also when a server thread panicked while holding them.
+pub fn lock(snapshots: &Snapshots) -> MutexGuard<'_, Vec<Snapshot>> {
+    snapshots.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Stores a finished poll of `device` and its history.
+pub fn record(
+    snapshots: &Snapshots,
+    device: &str,
+    app: &AppState,
+    report: Option<serde_json::Value>,
+) {
+    if let Some(snapshot) = lock(snapshots)
+        .iter_mut()
+        .find(|snapshot| snapshot.name == device)
+    {
+        snapshot.report = report;
+        snapshot.history = app.history.iter().copied().collect();
+    }
+}
+
+/// The device called `wanted`, by its name or topic level.
+pub fn find<'a>(snapshots: &'a [Snapshot], wanted: &str) -> Option<&'a Snapshot> {
+    snapshots
+        .iter()
+        .find(|snapshot| snapshot.name == wanted || topic_level(&snapshot.name) == wanted)
+}
+
+/// A retained sample as the history queries list it.
+#[derive(Debug, Serialize)]
+pub struct HistoryPoint {
+    time: String,
+    real_flow: f64,
+    target_flow: f64,
+    speed_rpm: f64,
+    p_filter_total: f64,
+    m_filter_total: f64,
+    c_filter_total: f64,
+}
+
+impl From<&Sample> for HistoryPoint {
+    fn from(sample: &Sample) -> Self {
+        Self {
+            time: timestamp(sample.timestamp),
+            real_flow: sample.real_flow,
+            target_flow: sample.target_flow,
+            speed_rpm: sample.speed_rpm,
+            p_filter_total: sample.filter_total(Filter::Pre),
+            m_filter_total: sample.filter_total(Filter::Medium),
+            c_filter_total: sample.filter_total(Filter::Chemical),
+        }
+    }
+}
+
+/// The alarms raised on each device when last seen, so exporters report each raise and clear
+/// once.
+#[derive(Debug, Default)]
+pub struct AlarmTransitions {
+    raised: HashMap<String, Vec<String>>,
+}
+
+impl AlarmTransitions {
+    /// Alarms raised (`true`) and cleared (`false`) on `device` since the last call.
+    pub fn update(&mut self, device: &str, app: &AppState) -> Vec<(String, bool)> {
+        let now = raised_alarms(app);
+        let before = self.raised.insert(device.to_string(), now.clone());
+        transitions(&before.unwrap_or_default(), &now)
+    }
+}
+
+/// Names of the raised `[[alarm]]` rules, the flow deviation and the filters at their limit.
+pub fn raised_alarms(app: &AppState) -> Vec<String> {
+    let mut raised: Vec<String> = app
+        .alarms
+        .active()
+        .chain(app.alarms.acknowledged())
+        .map(String::from)
+        .collect();
+    if app.deviation.is_active() {
+        raised.push(String::from("flow deviation"));
+    }
+    raised.extend(
+        app.filter_banner
+            .over_limit()
+            .iter()
+            .map(|filter| format!("{} at limit", filter.label())),
+    );
+    raised
+}
+
+fn transitions(before: &[String], now: &[String]) -> Vec<(String, bool)> {
+    let cleared = before
+        .iter()
+        .filter(|name| !now.contains(name))
+        .map(|name| (name.clone(), false));
+    let raised = now
+        .iter()
+        .filter(|name| !before.contains(name))
+        .map(|name| (name.clone(), true));
+    cleared.chain(raised).collect()
+}
+
+/// A device name as one topic or path level: separators and wildcards become `_`, so
+/// `/dev/ttyUSB0` is `dev_ttyUSB0`.
+pub fn topic_level(name: &str) -> String {
+    let level: String = name
+        .chars()
+        .map(|ch| {
+            if matches!(ch, '/' | '+' | '#') {
+                '_'
+            } else {
+                ch
+            }
+        })
+        .collect();
+    level.trim_matches('_').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{topic_level, transitions};
+
+    #[test]
+    fn transitions_report_raises_and_clears_once() {
+        let names =
+            |names: &[&str]| -> Vec<String> { names.iter().map(ToString::to_string).collect() };
+        assert_eq!(
+            transitions(
+                &names(&["low flow"]),
+                &names(&["low flow", "flow deviation"])
+            ),
+            [(String::from("flow deviation"), true)]
+        );
+        assert_eq!(
+            transitions(
+                &names(&["low flow", "flow deviation"]),
+                &names(&["flow deviation"])
+            ),
+            [(String::from("low flow"), false)]
+        );
+        assert!(transitions(&names(&["low flow"]), &names(&["low flow"])).is_empty());
+        assert_eq!(topic_level("/dev/ttyUSB0"), "dev_ttyUSB0");
+        assert_eq!(topic_level("hall #2"), "hall _2");
+    }
+}
@@ -0,0 +1,311 @@
+use std::fmt;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use rumqttc::{Client, Event, MqttOptions, Packet, QoS};
+use serde::{Deserialize, Serialize};
+
+use super::{AlarmTransitions, Exporter, Incoming, topic_level};
+use crate::app::AppState;
+use crate::headless::{PollReport, timestamp};
+use crate::macros::MacroStep;
+use crate::transport::{PollOutcome, TransportCommand};
+
+/// Wait before the next connection attempt after the broker failed.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// The `[mqtt]` table: the broker that receives status samples and alarm transitions and
+/// sends commands.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MqttConfig {
+    /// Host name or address of the broker.
+    pub broker: String,
+    pub port: u16,
+    pub client_id: String,
+    /// First level of every topic: `<prefix>/<device>/status`, `/alarm` and `/set`.
+    pub topic_prefix: String,
+    /// Delivery guarantee of published samples and the command subscription: 0, 1 or 2.
+    pub qos: u8,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            broker: String::new(),
+            port: 1883,
+            client_id: String::from("qu6101a2-mon"),
+            topic_prefix: String::from("qu6101a2-mon"),
+            qos: 0,
+            username: None,
+            password: None,
+        }
+    }
+}
+
+impl MqttConfig {
+    pub fn qos(&self) -> Option<QoS> {
+        match self.qos {
+            0 => Some(QoS::AtMostOnce),
+            1 => Some(QoS::AtLeastOnce),
+            2 => Some(QoS::ExactlyOnce),
+            _ => None,
+        }
+    }
+}
+
+/// Heard by the connection thread.
+enum Heard {
+    Publish { topic: String, payload: Vec<u8> },
+    Fault(String),
+}
+
+/// A raised or cleared alarm, published to `<prefix>/<device>/alarm`.
+#[derive(Debug, Serialize)]
+struct AlarmMessage<'a> {
+    time: String,
+    alarm: &'a str,
+    raised: bool,
+}
+
+/// Publishes every poll and alarm transition, and takes `{ flow = .. }` style commands from
+/// `<prefix>/<device>/set` unless read-only.
+pub struct MqttExporter {
+    client: Client,
+    prefix: String,
+    qos: QoS,
+    /// Device names with their topic level.
+    devices: Vec<(String, String)>,
+    alarms: AlarmTransitions,
+    heard: Receiver<Heard>,
+    /// A publish failure waiting for the event log.
+    fault: Option<String>,
+    /// Set once a publish failure was reported, cleared when a publish works again.
+    fault_reported: bool,
+}
+
+impl fmt::Debug for MqttExporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MqttExporter")
+            .field("prefix", &self.prefix)
+            .field("devices", &self.devices)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MqttExporter {
+    /// Starts connecting in the background; a broker that is down is retried and logged, not
+    /// fatal.
+    pub fn connect(config: &MqttConfig, devices: &[String], read_only: bool) -> Self {
+        let qos = config.qos().unwrap_or(QoS::AtMostOnce);
+        let mut options = MqttOptions::new(&config.client_id, &config.broker, config.port);
+        options.set_keep_alive(Duration::from_secs(30));
+        if let Some(username) = &config.username {
+            options.set_credentials(username, config.password.clone().unwrap_or_default());
+        }
+        let (client, mut connection) = Client::new(options, 64);
+        let (heard_tx, heard) = mpsc::channel();
+        let subscriber = (!read_only).then(|| client.clone());
+        let commands = format!("{}/+/set", config.topic_prefix);
+        let broker = format!("{}:{}", config.broker, config.port);
+        thread::spawn(move || {
+            let mut last_fault = None;
+            for notification in connection.iter() {
+                let heard = match notification {
+                    // Subscriptions are made again on every connect, as the session is clean.
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        last_fault = None;
+                        if let Some(client) = &subscriber {
+                            client.try_subscribe(commands.as_str(), qos).ok();
+                        }
+                        continue;
+                    }
+                    Ok(Event::Incoming(Packet::Publish(publish))) => Heard::Publish {
+                        topic: publish.topic,
+                        payload: publish.payload.to_vec(),
+                    },
+                    Ok(_) => continue,
+                    Err(err) => {
+                        let fault = format!("MQTT broker {broker}: {err}");
+                        thread::sleep(RECONNECT_DELAY);
+                        if last_fault.as_ref() == Some(&fault) {
+                            continue;
+                        }
+                        last_fault = Some(fault.clone());
+                        Heard::Fault(fault)
+                    }
+                };
+                if heard_tx.send(heard).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            client,
+            prefix: config.topic_prefix.clone(),
+            qos,
+            devices: devices
+                .iter()
+                .map(|name| (name.clone(), topic_level(name)))
+                .collect(),
+            alarms: AlarmTransitions::default(),
+            heard,
+            fault: None,
+            fault_reported: false,
+        }
+    }
+
+    fn publish(&mut self, topic: String, payload: Vec<u8>) {
+        let published = self.client.try_publish(topic, self.qos, false, payload);
+        self.published(published.map_err(|err| err.to_string()));
+    }
+
+    /// Queues the first failure of an outage for the event log.
+    fn published(&mut self, result: Result<(), String>) {
+        match result {
+            Ok(()) => self.fault_reported = false,
+            Err(_) if self.fault_reported => {}
+            Err(err) => {
+                self.fault = Some(format!("MQTT publish failed: {err}"));
+                self.fault_reported = true;
+            }
+        }
+    }
+
+    /// The command for a message on `<prefix>/<device>/set`, or why it was refused.
+    fn command(&self, topic: &str, payload: &[u8]) -> Result<Incoming, String> {
+        let level = topic
+            .strip_prefix(&self.prefix)
+            .and_then(|rest| rest.strip_prefix('/'))
+            .and_then(|rest| rest.strip_suffix("/set"))
+            .ok_or_else(|| format!("MQTT message on unexpected topic {topic}"))?;
+        let (device, _) = self
+            .devices
+            .iter()
+            .find(|(_, device_level)| device_level == level)
+            .ok_or_else(|| format!("MQTT command for unknown device {level}"))?;
+        let step: MacroStep = serde_json::from_slice(payload)
+            .map_err(|err| format!("MQTT command on {topic} not understood: {err}"))?;
+        let command = match step {
+            MacroStep::Power(on) => TransportCommand::SetPower(on),
+            MacroStep::Flow(flow) => TransportCommand::SetTargetFlow(flow),
+            MacroStep::Beeper(on) => TransportCommand::SetBeeper(on),
+            MacroStep::Wait(_) => return Err(format!("MQTT command on {topic} cannot wait")),
+        };
+        Ok(Incoming::Command {
+            device: device.clone(),
+            command,
+            source: "MQTT",
+        })
+    }
+}
+
+impl Exporter for MqttExporter {
+    fn poll(&mut self, device: &str, app: &AppState, outcome: PollOutcome, now: SystemTime) {
+        let base = format!("{}/{}", self.prefix, topic_level(device));
+        let report = PollReport::new(device, app, outcome, now);
+        if let Ok(payload) = serde_json::to_vec(&report) {
+            self.publish(format!("{base}/status"), payload);
+        }
+        for (alarm, raised) in self.alarms.update(device, app) {
+            let message = AlarmMessage {
+                time: timestamp(now),
+                alarm: &alarm,
+                raised,
+            };
+            if let Ok(payload) = serde_json::to_vec(&message) {
+                self.publish(format!("{base}/alarm"), payload);
+            }
+        }
+    }
+
+    fn incoming(&mut self) -> Vec<Incoming> {
+        let mut incoming: Vec<Incoming> =
+            self.fault.take().map(Incoming::Fault).into_iter().collect();
+        while let Ok(heard) = self.heard.try_recv() {
+            incoming.push(match heard {
+                Heard::Publish { topic, payload } => self
+                    .command(&topic, &payload)
+                    .unwrap_or_else(Incoming::Fault),
+                Heard::Fault(fault) => Incoming::Fault(fault),
+            });
+        }
+        incoming
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MqttConfig, MqttExporter};
+    use crate::export::{Exporter, Incoming};
+    use crate::transport::TransportCommand;
+
+    #[test]
+    fn set_messages_become_commands_for_their_device() {
+        let config = MqttConfig {
+            broker: String::from("127.0.0.1"),
+            port: 9,
+            topic_prefix: String::from("fans"),
+            ..MqttConfig::default()
+        };
+        let devices = [String::from("/dev/ttyUSB0"), String::from("lab")];
+        let exporter = MqttExporter::connect(&config, &devices, false);
+        assert_eq!(
+            exporter.command("fans/dev_ttyUSB0/set", br#"{"flow": 70}"#),
+            Ok(Incoming::Command {
+                device: String::from("/dev/ttyUSB0"),
+                command: TransportCommand::SetTargetFlow(70),
+                source: "MQTT",
+            })
+        );
+        assert_eq!(
+            exporter
+                .command("fans/lab/set", br#"{"power": false}"#)
+                .map(|incoming| matches!(
+                    incoming,
+                    Incoming::Command {
+                        command: TransportCommand::SetPower(false),
+                        ..
+                    }
+                )),
+            Ok(true)
+        );
+        assert!(
+            exporter
+                .command("fans/hall/set", br#"{"flow": 70}"#)
+                .is_err()
+        );
+        assert!(exporter.command("fans/lab/set", br#"{"wait": 5}"#).is_err());
+        assert!(exporter.command("fans/lab/set", b"70").is_err());
+    }
+
+    #[test]
+    fn publish_failures_are_reported_once_per_outage() {
+        let config = MqttConfig {
+            broker: String::from("127.0.0.1"),
+            port: 9,
+            ..MqttConfig::default()
+        };
+        let mut exporter = MqttExporter::connect(&config, &[String::from("lab")], true);
+        let publish_faults = |exporter: &mut MqttExporter| {
+            exporter
+                .incoming()
+                .into_iter()
+                .filter(|incoming| {
+                    matches!(incoming, Incoming::Fault(fault) if fault.starts_with("MQTT publish"))
+                })
+                .count()
+        };
+        exporter.published(Err(String::from("queue full")));
+        exporter.published(Err(String::from("queue full")));
+        assert_eq!(publish_faults(&mut exporter), 1);
+        exporter.published(Err(String::from("queue full")));
+        assert_eq!(publish_faults(&mut exporter), 0);
+        exporter.published(Ok(()));
+        exporter.published(Err(String::from("queue full")));
+        assert_eq!(publish_faults(&mut exporter), 1);
+    }
+}
@@ -0,0 +1,385 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use super::{Incoming, Snapshot, find};
+use crate::config_editor::guarded;
+use crate::data::{Filter, OperatingMode};
+use crate::interface::InterfaceMode;
+use crate::transport::{TransportCommand, TransportConfig};
+
+/// Version of the method set, raised when a method changes in a way old callers would notice.
+pub const API_VERSION: u32 = 1;
+
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// A well-formed call the monitor will not carry out, such as a write under `--read-only`.
+const REFUSED: i64 = -32000;
+
+/// Every method, for telling an unknown one from bad parameters.
+const METHODS: [&str; 12] = [
+    "version",
+    "config",
+    "status",
+    "history",
+    "set_power",
+    "set_target_flow",
+    "set_beeper",
+    "set_mode",
+    "reset_filter",
+    "write_register",
+    "set_poll_interval",
+    "poll_now",
+];
+
+/// A device's connection as the `config` method reports it.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceInfo {
+    pub device: String,
+    pub port: Option<String>,
+    pub interface: InterfaceMode,
+    pub address: u8,
+    pub baud: u32,
+    pub poll_interval_ms: u128,
+    pub model: &'static str,
+    pub flow_min: u16,
+    pub flow_max: u16,
+}
+
+impl DeviceInfo {
+    pub fn new(name: &str, transport: &TransportConfig) -> Self {
+        Self {
+            device: name.to_string(),
+            port: transport.port.clone(),
+            interface: transport.interface,
+            address: transport.address,
+            baud: transport.baud,
+            poll_interval_ms: transport.poll_interval.as_millis(),
+            model: transport.profile.name,
+            flow_min: transport.profile.flow_min,
+            flow_max: transport.profile.flow_max,
+        }
+    }
+}
+
+/// What a call can see and do.
+#[derive(Debug)]
+pub struct Context<'a> {
+    pub devices: &'a [Snapshot],
+    pub config: &'a [DeviceInfo],
+    pub read_only: bool,
+}
+
+/// The methods with their named parameters; `device` may be left out with a single device.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+enum Call {
+    Version {},
+    Config {},
+    Status {
+        device: Option<String>,
+    },
+    History {
+        device: Option<String>,
+        /// Only the newest this many samples.
+        limit: Option<usize>,
+    },
+    SetPower {
+        on: bool,
+        device: Option<String>,
+    },
+    SetTargetFlow {
+        flow: u16,
+        device: Option<String>,
+    },
+    SetBeeper {
+        on: bool,
+        device: Option<String>,
+    },
+    SetMode {
+        mode: OperatingMode,
+        device: Option<String>,
+    },
+    ResetFilter {
+        filter: Filter,
+        device: Option<String>,
+    },
+    WriteRegister {
+        register: u16,
+        value: u16,
+        device: Option<String>,
+    },
+    SetPollInterval {
+        ms: u64,
+        device: Option<String>,
+    },
+    PollNow {
+        device: Option<String>,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Whether a line is meant as JSON-RPC rather than a plain control socket request.
+pub fn is_rpc(message: &Value) -> bool {
+    message.is_array() || message.get("jsonrpc").is_some()
+}
+
+/// Answers a JSON-RPC 2.0 call or batch, adding the writes it asks for to `commands`; `None`
+/// when it held only notifications.
+pub fn handle(message: Value, context: &Context, commands: &mut Vec<Incoming>) -> Option<Value> {
+    match message {
+        Value::Array(calls) if calls.is_empty() => Some(reply(
+            &Value::Null,
+            Err(RpcError::new(INVALID_REQUEST, "empty batch")),
+        )),
+        Value::Array(calls) => {
+            let replies: Vec<Value> = calls
+                .into_iter()
+                .filter_map(|call| handle_call(&call, context, commands))
+                .collect();
+            (!replies.is_empty()).then_some(Value::Array(replies))
+        }
+        call => handle_call(&call, context, commands),
+    }
+}
+
+fn handle_call(call: &Value, context: &Context, commands: &mut Vec<Incoming>) -> Option<Value> {
+    let Value::Object(fields) = call else {
+        return Some(reply(
+            &Value::Null,
+            Err(RpcError::new(INVALID_REQUEST, "a call is an object")),
+        ));
+    };
+    let outcome = parse(fields).and_then(|call| run(call, context, commands));
+    // A call without an id is a notification and gets no reply, even when it failed.
+    fields.get("id").map(|id| reply(id, outcome))
+}
+
+fn parse(fields: &Map<String, Value>) -> Result<Call, RpcError> {
+    if fields.get("jsonrpc") != Some(&json!("2.0")) {
+        return Err(RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\""));
+    }
+    let Some(Value::String(method)) = fields.get("method") else {
+        return Err(RpcError::new(INVALID_REQUEST, "method must be a string"));
+    };
+    if !METHODS.contains(&method.as_str()) {
+        return Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("no method {method}"),
+        ));
+    }
+    let params = match fields.get("params") {
+        None => json!({}),
+        Some(params @ Value::Object(_)) => params.clone(),
+        Some(_) => {
+            return Err(RpcError::new(
+                INVALID_PARAMS,
+                "params are passed by name, as an object",
+            ));
+        }
+    };
+    serde_json::from_value(json!({ "method": method, "params": params }))
+        .map_err(|err| RpcError::new(INVALID_PARAMS, err.to_string()))
+}
+
+fn run(call: Call, context: &Context, commands: &mut Vec<Incoming>) -> Result<Value, RpcError> {
+    let (device, command) = match call {
+        Call::Version {} => {
+            return Ok(json!({ "api": API_VERSION, "monitor": env!("CARGO_PKG_VERSION") }));
+        }
+        Call::Config {} => {
+            return Ok(json!({ "read_only": context.read_only, "devices": context.config }));
+        }
+        Call::Status { device: None } => {
+            return Ok(context.devices.iter().map(Snapshot::report).collect());
+        }
+        Call::Status {
+            device: Some(wanted),
+        } => return Ok(select(context, Some(&wanted))?.report()),
+        Call::History { device, limit } => {
+            let mut history = select(context, device.as_deref())?.history();
+            let oldest = history.len().saturating_sub(limit.unwrap_or(usize::MAX));
+            return Ok(json!(history.split_off(oldest)));
+        }
+        _ if context.read_only => {
+            return Err(RpcError::new(REFUSED, "writes are disabled by --read-only"));
+        }
+        Call::SetPower { on, device } => (device, TransportCommand::SetPower(on)),
+        Call::SetTargetFlow { flow, device } => (device, TransportCommand::SetTargetFlow(flow)),
+        Call::SetBeeper { on, device } => (device, TransportCommand::SetBeeper(on)),
+        Call::SetMode { mode, device } => (device, TransportCommand::SetMode(mode)),
+        Call::ResetFilter { filter, device } => (device, TransportCommand::ResetFilter(filter)),
+        Call::WriteRegister { register, .. } if guarded(register) => {
+            return Err(RpcError::new(
+                REFUSED,
+                format!("register {register:#06x} changes the link or calibration"),
+            ));
+        }
+        Call::WriteRegister {
+            register,
+            value,
+            device,
+        } => (device, TransportCommand::WriteRegister { register, value }),
+        Call::SetPollInterval { ms: 0, .. } => {
+            return Err(RpcError::new(INVALID_PARAMS, "ms must be above 0"));
+        }
+        Call::SetPollInterval { ms, device } => (
+            device,
+            TransportCommand::SetPollInterval(Duration::from_millis(ms)),
+        ),
+        Call::PollNow { device } => (device, TransportCommand::PollNow),
+    };
+    let name = select(context, device.as_deref())?.name.clone();
+    commands.push(Incoming::Command {
+        device: name.clone(),
+        command,
+        source: "JSON-RPC",
+    });
+    Ok(json!({ "device": name, "queued": true }))
+}
+
+/// The device named by `wanted`, or the only one when not named.
+fn select<'a>(context: &Context<'a>, wanted: Option<&str>) -> Result<&'a Snapshot, RpcError> {
+    match (wanted, context.devices) {
+        (None, [only]) => Ok(only),
+        (None, _) => Err(RpcError::new(INVALID_PARAMS, "name a device with `device`")),
+        (Some(wanted), devices) => find(devices, wanted)
+            .ok_or_else(|| RpcError::new(REFUSED, format!("no device {wanted}"))),
+    }
+}
+
+fn reply(id: &Value, outcome: Result<Value, RpcError>) -> Value {
+    match outcome {
+        Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+        Err(error) => json!({
+            "jsonrpc": "2.0",
+            "error": { "code": error.code, "message": error.message },
+            "id": id,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::{Context, handle};
+    use crate::export::{Incoming, lock, snapshots};
+    use crate::transport::TransportCommand;
+
+    fn call(message: Value, names: &[&str], read_only: bool) -> (Option<Value>, Vec<Incoming>) {
+        let names: Vec<String> = names.iter().map(ToString::to_string).collect();
+        let devices = snapshots(&names);
+        let context = Context {
+            devices: &lock(&devices),
+            config: &[],
+            read_only,
+        };
+        let mut commands = Vec::new();
+        let reply = handle(message, &context, &mut commands);
+        (reply, commands)
+    }
+
+    #[test]
+    fn calls_mirror_transport_commands_and_report_errors_by_code() {
+        let (reply, commands) = call(
+            json!({ "jsonrpc": "2.0", "method": "reset_filter", "params": { "filter": "medium" }, "id": 7 }),
+            &["lab"],
+            false,
+        );
+        assert_eq!(
+            reply,
+            Some(
+                json!({ "jsonrpc": "2.0", "result": { "device": "lab", "queued": true }, "id": 7 })
+            )
+        );
+        assert!(matches!(
+            commands.as_slice(),
+            [Incoming::Command {
+                command: TransportCommand::ResetFilter(crate::data::Filter::Medium),
+                ..
+            }]
+        ));
+
+        let code = |message: Value, names: &[&str], read_only: bool| {
+            call(message, names, read_only).0.expect("call is answered")["error"]["code"].clone()
+        };
+        assert_eq!(
+            code(
+                json!({ "jsonrpc": "2.0", "method": "reboot", "id": 1 }),
+                &["lab"],
+                false
+            ),
+            -32601
+        );
+        assert_eq!(
+            code(
+                json!({ "jsonrpc": "2.0", "method": "set_target_flow", "params": { "flow": "high" }, "id": 1 }),
+                &["lab"],
+                false
+            ),
+            -32602
+        );
+        assert_eq!(
+            code(
+                json!({ "jsonrpc": "2.0", "method": "set_power", "params": { "on": true }, "id": 1 }),
+                &["lab"],
+                true
+            ),
+            -32000
+        );
+        assert_eq!(
+            code(
+                json!({ "jsonrpc": "2.0", "method": "set_power", "params": { "on": true }, "id": 1 }),
+                &["lab", "hall"],
+                false
+            ),
+            -32602
+        );
+        assert_eq!(
+            code(json!({ "method": "status", "id": 1 }), &["lab"], false),
+            -32600
+        );
+    }
+
+    #[test]
+    fn batches_answer_every_call_but_notifications() {
+        let (reply, commands) = call(
+            json!([
+                { "jsonrpc": "2.0", "method": "version", "id": 1 },
+                { "jsonrpc": "2.0", "method": "poll_now", "params": { "device": "hall" } },
+                { "jsonrpc": "2.0", "method": "status", "params": { "device": "lab" }, "id": 2 },
+            ]),
+            &["lab", "hall"],
+            false,
+        );
+        let replies = reply.expect("batch is answered");
+        assert_eq!(replies.as_array().map(Vec::len), Some(2));
+        assert_eq!(replies[0]["result"]["api"], 1);
+        assert_eq!(replies[1]["result"]["device"], "lab");
+        assert_eq!(commands.len(), 1);
+
+        let (reply, _) = call(
+            json!({ "jsonrpc": "2.0", "method": "poll_now" }),
+            &["lab"],
+            false,
+        );
+        assert_eq!(reply, None);
+    }
+}
@@ -0,0 +1,425 @@
+use std::fmt;
+use std::fs;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::SystemTime;
+
+use color_eyre::eyre::{self, WrapErr};
+use serde::Deserialize;
+
+use super::rpc::{self, Context, DeviceInfo};
+use super::{Exporter, Incoming, Snapshot, Snapshots, find, lock, record};
+use crate::app::AppState;
+use crate::headless::PollReport;
+use crate::transport::{PollOutcome, TransportCommand};
+
+/// One plain line read from the control socket; JSON-RPC lines go to [`rpc::handle`].
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case", deny_unknown_fields)]
+enum Request {
+    Status { device: Option<String> },
+    SetFlow { flow: u16, device: Option<String> },
+    SetPower { power: bool, device: Option<String> },
+}
+
+/// What every client thread shares.
+#[derive(Debug, Clone)]
+struct Shared {
+    devices: Snapshots,
+    config: Arc<[DeviceInfo]>,
+    read_only: bool,
+    commands: Sender<Incoming>,
+}
+
+/// Answers newline-delimited JSON requests, plain or JSON-RPC 2.0, from other processes on a
+/// Unix socket or a loopback TCP port: the status, history and config, and writes that go to
+/// the devices like keyboard ones.
+pub struct ControlSocket {
+    /// The Unix socket file, removed when the monitor exits.
+    path: Option<PathBuf>,
+    devices: Snapshots,
+    commands: Receiver<Incoming>,
+}
+
+impl fmt::Debug for ControlSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ControlSocket")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ControlSocket {
+    /// Creates the socket at `path`, taking over one left behind by a monitor that is gone, and
+    /// serves each client from its own thread.
+    pub fn bind(path: &Path, devices: Vec<DeviceInfo>, read_only: bool) -> eyre::Result<Self> {
+        if UnixStream::connect(path).is_ok() {
+            return Err(eyre::eyre!(
+                "control socket {} is in use by another monitor",
+                path.display()
+            ));
+        }
+        match fs::remove_file(path) {
+            Err(err) if err.kind() != ErrorKind::NotFound => {
+                return Err(err)
+                    .wrap_err_with(|| format!("remove stale control socket {}", path.display()));
+            }
+            _ => {}
+        }
+        let listener = UnixListener::bind(path)
+            .wrap_err_with(|| format!("bind control socket {}", path.display()))?;
+        let (mut socket, shared) = Self::new(devices, read_only);
+        thread::spawn(move || {
+            for client in listener.incoming().flatten() {
+                let shared = shared.clone();
+                thread::spawn(move || serve(&client, &client, &shared));
+            }
+        });
+        socket.path = Some(path.to_path_buf());
+        Ok(socket)
+    }
+
+    /// Serves the same requests on a TCP port, which has no authentication and so must be a
+    /// loopback address.
+    pub fn listen(
+        address: SocketAddr,
+        devices: Vec<DeviceInfo>,
+        read_only: bool,
+    ) -> eyre::Result<Self> {
+        if !address.ip().is_loopback() {
+            return Err(eyre::eyre!(
+                "control port {address} must be on a loopback address, as it takes commands \
+                 without a token"
+            ));
+        }
+        let listener = TcpListener::bind(address)
+            .wrap_err_with(|| format!("listen for control connections on {address}"))?;
+        let (socket, shared) = Self::new(devices, read_only);
+        thread::spawn(move || {
+            for client in listener.incoming().flatten() {
+                let shared = shared.clone();
+                thread::spawn(move || serve(&client, &client, &shared));
+            }
+        });
+        Ok(socket)
+    }
+
+    fn new(devices: Vec<DeviceInfo>, read_only: bool) -> (Self, Shared) {
+        let names: Vec<String> = devices.iter().map(|info| info.device.clone()).collect();
+        let snapshots = super::snapshots(&names);
+        let (commands, received) = mpsc::channel();
+        let shared = Shared {
+            devices: Arc::clone(&snapshots),
+            config: devices.into(),
+            read_only,
+            commands,
+        };
+        let socket = Self {
+            path: None,
+            devices: snapshots,
+            commands: received,
+        };
+        (socket, shared)
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        if let Some(path) = &self.path {
+            fs::remove_file(path).ok();
+        }
+    }
+}
+
+impl Exporter for ControlSocket {
+    fn poll(&mut self, device: &str, app: &AppState, outcome: PollOutcome, now: SystemTime) {
+        let report = serde_json::to_value(PollReport::new(device, app, outcome, now)).ok();
+        record(&self.devices, device, app, report);
+    }
+
+    fn incoming(&mut self) -> Vec<Incoming> {
+        self.commands.try_iter().collect()
+    }
+}
+
+/// Answers a client line by line until it hangs up, or until a line is no request at all: a
+/// browser made to post here sends its HTTP request line first, so nothing after it is run.
+fn serve(reader: impl Read, mut writer: impl Write, shared: &Shared) {
+    for line in BufReader::new(reader).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (reply, commands) = match answer(&line, shared) {
+            Ok(answered) => answered,
+            Err(refusal) => {
+                writeln!(writer, "{refusal}").ok();
+                return;
+            }
+        };
+        for command in commands {
+            if shared.commands.send(command).is_err() {
+                return;
+            }
+        }
+        if let Some(reply) = reply
+            && writeln!(writer, "{reply}").is_err()
+        {
+            return;
+        }
+    }
+}
+
+/// The reply to one line, if any, and the writes it asked for; `Err` is the last word to a
+/// line that is not a request, after which the client is dropped.
+fn answer(
+    line: &str,
+    shared: &Shared,
+) -> Result<(Option<serde_json::Value>, Vec<Incoming>), serde_json::Value> {
+    let refusal = |error: String| serde_json::json!({ "ok": false, "error": error });
+    let message = match serde_json::from_str::<serde_json::Value>(line) {
+        Ok(message) => message,
+        Err(_) if is_http(line) => {
+            return Err(refusal(String::from(
+                "this is the control socket, not an HTTP server",
+            )));
+        }
+        Err(err) => return Err(refusal(format!("request not understood: {err}"))),
+    };
+    let devices = lock(&shared.devices);
+    let mut commands = Vec::new();
+    if rpc::is_rpc(&message) {
+        let context = Context {
+            devices: &devices,
+            config: &shared.config,
+            read_only: shared.read_only,
+        };
+        let reply = rpc::handle(message, &context, &mut commands);
+        return Ok((reply, commands));
+    }
+    let request = serde_json::from_value(message)
+        .map_err(|err| refusal(format!("request not understood: {err}")))?;
+    let reply =
+        plain(request, &devices, shared.read_only).map_or_else(refusal, |(reply, command)| {
+            commands.extend(command);
+            reply
+        });
+    Ok((Some(reply), commands))
+}
+
+/// Whether `line` is an HTTP request line, such as `POST / HTTP/1.1`.
+fn is_http(line: &str) -> bool {
+    let mut parts = line.split_whitespace();
+    parts
+        .next()
+        .is_some_and(|method| method.bytes().all(|byte| byte.is_ascii_uppercase()))
+        && parts.next().is_some()
+        && parts
+            .next()
+            .is_some_and(|version| version.starts_with("HTTP/"))
+        && parts.next().is_none()
+}
+
+/// The reply to a plain request, and the write it asked for.
+fn plain(
+    request: Request,
+    devices: &[Snapshot],
+    read_only: bool,
+) -> Result<(serde_json::Value, Option<Incoming>), String> {
+    let (device, command) = match request {
+        Request::Status { device: None } => {
+            let status: Vec<_> = devices.iter().map(Snapshot::report).collect();
+            return Ok((serde_json::json!({ "ok": true, "status": status }), None));
+        }
+        Request::Status {
+            device: Some(wanted),
+        } => {
+            let status = select(devices, Some(&wanted))?.report();
+            return Ok((serde_json::json!({ "ok": true, "status": status }), None));
+        }
+        _ if read_only => return Err(String::from("writes are disabled by --read-only")),
+        Request::SetFlow { flow, device } => (device, TransportCommand::SetTargetFlow(flow)),
+        Request::SetPower { power, device } => (device, TransportCommand::SetPower(power)),
+    };
+    let name = &select(devices, device.as_deref())?.name;
+    let reply = serde_json::json!({ "ok": true, "device": name, "queued": true });
+    Ok((
+        reply,
+        Some(Incoming::Command {
+            device: name.clone(),
+            command,
+            source: "control socket",
+        }),
+    ))
+}
+
+/// The device named by `wanted`, by name or topic level, or the only one when not named.
+fn select<'a>(devices: &'a [Snapshot], wanted: Option<&str>) -> Result<&'a Snapshot, String> {
+    match (wanted, devices) {
+        (None, [only]) => Ok(only),
+        (None, _) => Err(String::from("name a device with \"device\"")),
+        (Some(wanted), _) => find(devices, wanted).ok_or_else(|| format!("no device {wanted}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpStream;
+    use std::os::unix::net::UnixStream;
+    use std::sync::mpsc;
+
+    use super::{ControlSocket, Shared, answer};
+    use crate::export::rpc::DeviceInfo;
+    use crate::export::{Exporter, Incoming, snapshots};
+    use crate::interface::InterfaceMode;
+    use crate::transport::TransportCommand;
+
+    fn shared(names: &[&str], read_only: bool) -> Shared {
+        let names: Vec<String> = names.iter().map(ToString::to_string).collect();
+        Shared {
+            devices: snapshots(&names),
+            config: Vec::new().into(),
+            read_only,
+            commands: mpsc::channel().0,
+        }
+    }
+
+    fn info(name: &str) -> DeviceInfo {
+        DeviceInfo {
+            device: name.to_string(),
+            port: None,
+            interface: InterfaceMode::Simulation,
+            address: 2,
+            baud: 19_200,
+            poll_interval_ms: 500,
+            model: "Quick 6101A2",
+            flow_min: 0,
+            flow_max: 100,
+        }
+    }
+
+    #[test]
+    fn lines_become_status_replies_and_queued_writes() {
+        let shared = shared(&["/dev/ttyUSB0", "lab"], false);
+        let (reply, commands) = answer(r#"{"command": "status"}"#, &shared).expect("request");
+        assert_eq!(reply.expect("status answers")["status"][1]["device"], "lab");
+        assert!(commands.is_empty());
+
+        let (reply, commands) = answer(
+            r#"{"command": "set_flow", "flow": 70, "device": "dev_ttyUSB0"}"#,
+            &shared,
+        )
+        .expect("request");
+        assert_eq!(reply.expect("write answers")["queued"], true);
+        assert_eq!(
+            commands,
+            [Incoming::Command {
+                device: String::from("/dev/ttyUSB0"),
+                command: TransportCommand::SetTargetFlow(70),
+                source: "control socket",
+            }]
+        );
+
+        let failed = |line: &str, shared: &Shared| {
+            answer(line, shared)
+                .expect("request")
+                .0
+                .expect("line answers")["ok"]
+                == false
+        };
+        assert!(failed(
+            r#"{"command": "set_power", "power": true}"#,
+            &shared
+        ));
+        assert!(answer(r#"{"command": "reboot"}"#, &shared).is_err());
+        assert!(answer("status", &shared).is_err());
+        let http = answer("POST / HTTP/1.1", &shared).expect_err("HTTP is refused");
+        assert_eq!(
+            http["error"],
+            "this is the control socket, not an HTTP server"
+        );
+        let read_only = self::shared(&["lab"], true);
+        assert!(failed(
+            r#"{"command": "set_power", "power": true}"#,
+            &read_only
+        ));
+
+        let (reply, _) = answer(
+            r#"{"jsonrpc": "2.0", "method": "version", "id": 1}"#,
+            &shared,
+        )
+        .expect("request");
+        assert_eq!(reply.expect("call answers")["result"]["api"], 1);
+    }
+
+    #[test]
+    fn clients_talk_over_the_socket_until_it_is_dropped() {
+        let path = std::env::temp_dir().join(format!("qu6101a2-{}.sock", std::process::id()));
+        let mut socket =
+            ControlSocket::bind(&path, vec![info("lab")], false).expect("socket binds");
+        assert!(ControlSocket::bind(&path, Vec::new(), false).is_err());
+
+        let mut client = UnixStream::connect(&path).expect("client connects");
+        writeln!(client, r#"{{"command": "set_power", "power": false}}"#).expect("request sent");
+        let mut reply = String::new();
+        BufReader::new(&client)
+            .read_line(&mut reply)
+            .expect("reply read");
+        assert!(reply.contains(r#""queued":true"#), "{reply}");
+        assert!(matches!(
+            socket.incoming().as_slice(),
+            [Incoming::Command {
+                command: TransportCommand::SetPower(false),
+                ..
+            }]
+        ));
+
+        drop(socket);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn the_tcp_port_stays_on_loopback() {
+        let anywhere = "0.0.0.0:0".parse().expect("address parses");
+        assert!(ControlSocket::listen(anywhere, Vec::new(), false).is_err());
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("free port");
+        let address = listener.local_addr().expect("bound address");
+        drop(listener);
+        let mut socket =
+            ControlSocket::listen(address, vec![info("lab")], false).expect("loopback listens");
+
+        let mut client = TcpStream::connect(address).expect("client connects");
+        writeln!(
+            client,
+            r#"{{"jsonrpc": "2.0", "method": "config", "id": "c"}}"#
+        )
+        .expect("request sent");
+        let mut reply = String::new();
+        BufReader::new(&client)
+            .read_line(&mut reply)
+            .expect("reply read");
+        let reply: serde_json::Value = serde_json::from_str(&reply).expect("JSON reply");
+        assert_eq!(reply["id"], "c");
+        assert_eq!(reply["result"]["devices"][0]["baud"], 19_200);
+
+        let mut browser = TcpStream::connect(address).expect("client connects");
+        write!(
+            browser,
+            "POST / HTTP/1.1\r\nContent-Type: text/plain\r\n\r\n\
+             {{\"command\": \"set_power\", \"power\": false}}\n"
+        )
+        .expect("request sent");
+        let mut replies = String::new();
+        browser.read_to_string(&mut replies).expect("replies read");
+        assert_eq!(replies.lines().count(), 1, "{replies}");
+        assert!(socket.incoming().is_empty());
+    }
+}
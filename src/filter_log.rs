@@ -0,0 +1,186 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use color_eyre::eyre::{self, WrapErr};
+
+use crate::data::Filter;
+
+/// Least time between recorded points, keeping a year of history in a few tens of thousands.
+pub const RECORD_INTERVAL: Duration = Duration::from_mins(15);
+
+/// Shortest logged span a growth rate is worked out over.
+const MIN_RATE_SPAN: Duration = Duration::from_hours(1);
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// Filter totals in km3 at one point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilterPoint {
+    pub timestamp: SystemTime,
+    pub totals: [u16; 3],
+}
+
+impl FilterPoint {
+    pub fn seconds(&self) -> f64 {
+        self.timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64()
+    }
+
+    pub const fn total(&self, filter: Filter) -> u16 {
+        self.totals[filter as usize]
+    }
+}
+
+/// Long-horizon filter totals, appended to a file as `<unix seconds> <P> <M> <C>` lines when one
+/// is configured.
+#[derive(Debug, Default)]
+pub struct FilterLog {
+    points: Vec<FilterPoint>,
+    path: Option<PathBuf>,
+}
+
+impl FilterLog {
+    /// Reads the points stored in `path`, or starts empty if the file does not exist yet.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => {
+                return Err(err)
+                    .wrap_err_with(|| format!("read filter history {}", path.display()));
+            }
+        };
+        let points = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                parse_point(line).ok_or_else(|| {
+                    eyre::eyre!("parse filter history {} line {}", path.display(), index + 1)
+                })
+            })
+            .collect::<eyre::Result<_>>()?;
+        Ok(Self {
+            points,
+            path: Some(path.to_path_buf()),
+        })
+    }
+
+    pub fn points(&self) -> &[FilterPoint] {
+        &self.points
+    }
+
+    /// Records `totals` once they changed and `RECORD_INTERVAL` passed since the last point,
+    /// appending them to the file.
+    pub fn record(&mut self, timestamp: SystemTime, totals: [u16; 3]) -> eyre::Result<bool> {
+        if let Some(last) = self.points.last()
+            && (last.totals == totals
+                || timestamp.duration_since(last.timestamp).unwrap_or_default() < RECORD_INTERVAL)
+        {
+            return Ok(false);
+        }
+        let point = FilterPoint { timestamp, totals };
+        self.points.push(point);
+        if let Some(path) = &self.path {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .wrap_err_with(|| format!("open filter history {}", path.display()))?;
+            let [pre, medium, chemical] = totals;
+            writeln!(file, "{:.0} {pre} {medium} {chemical}", point.seconds())
+                .wrap_err_with(|| format!("write filter history {}", path.display()))?;
+        }
+        Ok(true)
+    }
+
+    /// Average growth of a filter total in km3 per day since its last reset, once the log spans
+    /// an hour.
+    pub fn rate_per_day(&self, filter: Filter) -> Option<f64> {
+        let last = self.points.last()?;
+        // A replaced filter starts over from zero; only the run since then counts.
+        let first = self
+            .points
+            .windows(2)
+            .rposition(|pair| pair[1].total(filter) < pair[0].total(filter))
+            .map_or(self.points.first()?, |index| &self.points[index + 1]);
+        let span = last.timestamp.duration_since(first.timestamp).ok()?;
+        if span < MIN_RATE_SPAN {
+            return None;
+        }
+        let growth = f64::from(last.total(filter)) - f64::from(first.total(filter));
+        Some(growth * SECONDS_PER_DAY / span.as_secs_f64())
+    }
+}
+
+fn parse_point(line: &str) -> Option<FilterPoint> {
+    let mut fields = line.split_whitespace();
+    let seconds = fields.next()?.parse::<u64>().ok()?;
+    let mut totals = [0; 3];
+    for total in &mut totals {
+        *total = fields.next()?.parse().ok()?;
+    }
+    fields.next().is_none().then_some(FilterPoint {
+        timestamp: UNIX_EPOCH + Duration::from_secs(seconds),
+        totals,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{FilterLog, RECORD_INTERVAL};
+    use crate::data::Filter;
+
+    #[test]
+    fn records_changed_totals_and_reloads_them() {
+        let path = std::env::temp_dir().join(format!("filter-log-{}.txt", std::process::id()));
+        fs::remove_file(&path).ok();
+        let mut log = FilterLog::load(&path).expect("missing file starts empty");
+        let start = UNIX_EPOCH + Duration::from_secs(1_000);
+        assert!(log.record(start, [1, 2, 3]).expect("first point"));
+        assert!(
+            !log.record(start + RECORD_INTERVAL, [1, 2, 3])
+                .expect("unchanged")
+        );
+        assert!(
+            !log.record(start + Duration::from_mins(1), [2, 2, 3])
+                .expect("too soon")
+        );
+        assert!(
+            log.record(start + RECORD_INTERVAL, [2, 2, 3])
+                .expect("changed")
+        );
+
+        let reloaded = FilterLog::load(&path).expect("history should load");
+        fs::remove_file(&path).ok();
+        assert_eq!(reloaded.points(), log.points());
+        assert_eq!(reloaded.points()[1].total(Filter::Pre), 2);
+    }
+
+    #[test]
+    fn rate_starts_over_after_a_filter_reset() {
+        let mut log = FilterLog::default();
+        let day = Duration::from_hours(24);
+        log.record(UNIX_EPOCH, [10, 40, 0]).expect("in memory");
+        log.record(UNIX_EPOCH + day, [12, 44, 0])
+            .expect("in memory");
+        log.record(UNIX_EPOCH + day * 2, [14, 0, 0])
+            .expect("in memory");
+        log.record(UNIX_EPOCH + day * 4, [18, 6, 0])
+            .expect("in memory");
+        assert_eq!(log.rate_per_day(Filter::Pre), Some(2.0));
+        assert_eq!(log.rate_per_day(Filter::Medium), Some(3.0));
+        assert_eq!(log.rate_per_day(Filter::Chemical), Some(0.0));
+
+        let mut short = FilterLog::default();
+        short.record(UNIX_EPOCH, [1, 1, 1]).expect("in memory");
+        assert_eq!(short.rate_per_day(Filter::Pre), None);
+    }
+}
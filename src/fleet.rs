@@ -0,0 +1,539 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::JoinHandle;
+use std::time::SystemTime;
+
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
+use ratatui::layout::Rect;
+
+use crate::app::AppState;
+use crate::dialog::Restart;
+use crate::event_log::{EventSource, Severity};
+use crate::export::{Exporter, Incoming};
+use crate::input::{handle_key_event, handle_mouse_event, handle_paste, vi_arrow};
+use crate::keymap::Action;
+use crate::transport::{
+    PollOutcome, TransportCommand, TransportConfig, TransportEvent, spawn_worker,
+};
+use crate::ui::update_layout;
+
+/// A poll or write a worker finished, handed to callers that run without a dashboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Finished {
+    Poll(PollOutcome),
+    /// The write the backend accepted, or why it was refused or failed.
+    Write(Result<TransportCommand, String>),
+}
+
+/// One monitored device with its own dashboard state and serial worker.
+#[derive(Debug)]
+pub struct Device {
+    pub name: String,
+    pub app: AppState,
+    transport: TransportConfig,
+    command_tx: Sender<TransportCommand>,
+    event_rx: Receiver<TransportEvent>,
+    worker: Option<JoinHandle<()>>,
+    worker_running: bool,
+    /// Outcome of the poll in progress, reported once its connection event arrives.
+    polled: Option<PollOutcome>,
+}
+
+impl Device {
+    pub fn spawn(name: String, transport: TransportConfig, app: AppState) -> Self {
+        let (command_tx, command_rx) = mpsc::channel();
+        let (event_tx, event_rx) = mpsc::channel();
+        let worker = spawn_worker(transport.clone(), command_rx, event_tx);
+        Self {
+            name,
+            app,
+            transport,
+            command_tx,
+            event_rx,
+            worker: Some(worker),
+            worker_running: true,
+            polled: None,
+        }
+    }
+
+    /// Starts a fresh worker for the restart chosen on the error screen or with the interface
+    /// key, keeping the link settings and poll interval in effect unless the interface changes.
+    fn restart(&mut self, restart: Restart) {
+        self.command_tx.send(TransportCommand::Terminate).ok();
+        if let Some(worker) = self.worker.take() {
+            worker.join().ok();
+        }
+        match restart {
+            Restart::Retry => {}
+            Restart::Port(port) => self.transport.port = Some(port),
+            Restart::Interface(interface) => {
+                self.transport.interface = interface;
+                self.app.interface = interface;
+                self.app.link_address = interface.default_address();
+                self.app.link_baud = interface.default_baud();
+            }
+        }
+        self.transport.address = self.app.link_address;
+        self.transport.baud = self.app.link_baud;
+        self.transport.poll_interval = self.app.poll_interval;
+        let message = match &self.transport.port {
+            Some(port) => {
+                let interface = format!("{:?}", self.transport.interface).to_lowercase();
+                format!("reconnecting to {port} via {interface}")
+            }
+            None => String::from("reconnecting to simulation"),
+        };
+        self.app
+            .event_log
+            .push(SystemTime::now(), EventSource::User, message);
+        let (command_tx, command_rx) = mpsc::channel();
+        let (event_tx, event_rx) = mpsc::channel();
+        self.worker = Some(spawn_worker(self.transport.clone(), command_rx, event_tx));
+        self.command_tx = command_tx;
+        self.event_rx = event_rx;
+        self.worker_running = true;
+        self.polled = None;
+    }
+
+    /// The connection the worker was started with.
+    pub fn transport(&self) -> &TransportConfig {
+        &self.transport
+    }
+
+    /// Queues a command for the device's worker.
+    pub fn send(&self, command: TransportCommand) {
+        self.command_tx.send(command).ok();
+    }
+
+    /// `true` until the worker stops.
+    pub const fn polling(&self) -> bool {
+        self.worker_running
+    }
+
+    /// Hands every event the worker sent since the last call to the dashboard state; `true`
+    /// when there was any. `report` sees the device after each finished poll or write.
+    fn drain_events(&mut self, report: &mut impl FnMut(&Self, Finished)) -> bool {
+        let mut changed = false;
+        loop {
+            match self.event_rx.try_recv() {
+                Ok(event) => {
+                    let finished = match &event {
+                        TransportEvent::Poll(outcome) => {
+                            self.polled = Some(*outcome);
+                            None
+                        }
+                        TransportEvent::Connection(_) => self.polled.take().map(Finished::Poll),
+                        TransportEvent::Applied(command) => {
+                            Some(Finished::Write(Ok(command.clone())))
+                        }
+                        TransportEvent::Rejected(reason) => {
+                            Some(Finished::Write(Err(reason.clone())))
+                        }
+                        // A fault outside a poll comes from a failed write.
+                        TransportEvent::Fault(fault) if self.polled.is_none() => {
+                            Some(Finished::Write(Err(fault.clone())))
+                        }
+                        _ => None,
+                    };
+                    self.app.handle_transport_event(event);
+                    if let Some(finished) = finished {
+                        report(self, finished);
+                    }
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    if self.worker_running {
+                        self.app
+                            .handle_transport_fault(String::from("serial worker stopped"), true);
+                        self.worker_running = false;
+                    } else {
+                        break;
+                    }
+                }
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    fn shutdown(mut self) {
+        self.command_tx.send(TransportCommand::Terminate).ok();
+        if let Some(worker) = self.worker.take() {
+            worker.join().ok();
+        }
+    }
+}
+
+/// Every configured device, shown side by side on the overview or one at a time expanded.
+#[derive(Debug)]
+pub struct Fleet {
+    pub devices: Vec<Device>,
+    pub selected: usize,
+    /// Columns for all devices instead of the selected device's full dashboard.
+    pub overview: bool,
+    /// Sinks fed every finished poll, such as an MQTT broker.
+    pub exporters: Vec<Box<dyn Exporter>>,
+}
+
+impl Fleet {
+    /// Starts on the overview when there is more than one device.
+    pub fn new(devices: Vec<Device>) -> Self {
+        let overview = devices.len() > 1;
+        Self {
+            devices,
+            selected: 0,
+            overview,
+            exporters: Vec::new(),
+        }
+    }
+
+    pub fn selected(&self) -> &Device {
+        &self.devices[self.selected]
+    }
+
+    pub fn selected_mut(&mut self) -> &mut Device {
+        &mut self.devices[self.selected]
+    }
+
+    /// Handles a key press; `true` when the monitor should exit.
+    pub fn handle_key(&mut self, key: impl Into<KeyEvent>) -> bool {
+        let key = key.into();
+        let action = self.selected().app.keymap.action(key);
+        if self.overview {
+            match vi_arrow(key.code) {
+                KeyCode::Left => {
+                    self.selected = self.selected.saturating_sub(1);
+                }
+                KeyCode::Right => {
+                    self.selected = (self.selected + 1).min(self.devices.len() - 1);
+                }
+                KeyCode::Enter => self.overview = false,
+                _ if action == Some(Action::PreviousDevice) => self.cycle(false),
+                _ if action == Some(Action::NextDevice) => self.cycle(true),
+                _ if action == Some(Action::DeviceOverview) => self.overview = false,
+                _ if action == Some(Action::Quit) => return true,
+                _ => {}
+            }
+            return false;
+        }
+        let multiple = self.devices.len() > 1;
+        let device = self.selected_mut();
+        if multiple && !device.app.modal_open() {
+            match action {
+                Some(Action::DeviceOverview) => self.overview = true,
+                Some(Action::PreviousDevice) => self.cycle(false),
+                Some(Action::NextDevice) => self.cycle(true),
+                _ => return handle_key_event(key, &mut device.app, &device.command_tx),
+            }
+            return false;
+        }
+        handle_key_event(key, &mut device.app, &device.command_tx)
+    }
+
+    /// Focuses the next or previous device, wrapping around, and names it in a toast when
+    /// its dashboard is shown.
+    fn cycle(&mut self, forward: bool) {
+        let count = self.devices.len();
+        self.selected = if forward {
+            (self.selected + 1) % count
+        } else {
+            (self.selected + count - 1) % count
+        };
+        if !self.overview {
+            let device = self.selected_mut();
+            let message = device.app.locale.format("Showing {}", &[&device.name]);
+            device
+                .app
+                .toasts
+                .push(SystemTime::now(), Severity::Info, message);
+        }
+    }
+
+    /// Pasted text goes to the expanded device; the overview has no text fields.
+    pub fn handle_paste(&mut self, text: &str) {
+        if self.overview {
+            return;
+        }
+        let device = self.selected_mut();
+        handle_paste(text, &mut device.app, &device.command_tx);
+    }
+
+    /// Mouse input goes to the expanded device; the overview has none.
+    pub fn handle_mouse(&mut self, event: MouseEvent) {
+        if self.overview {
+            return;
+        }
+        let device = self.selected_mut();
+        handle_mouse_event(event, &mut device.app, &device.command_tx);
+    }
+
+    /// Takes in the devices' transport events and runs their periodic work; `true` when any
+    /// device had news to draw.
+    pub fn tick(&mut self, now: SystemTime) -> bool {
+        self.tick_reporting(now, |_, _| {})
+    }
+
+    /// [`Fleet::tick`], handing each device to `report` as its polls and writes finish.
+    pub fn tick_reporting(
+        &mut self,
+        now: SystemTime,
+        mut report: impl FnMut(&Device, Finished),
+    ) -> bool {
+        let mut changed = false;
+        for device in &mut self.devices {
+            if let Some(restart) = device.app.restart.take() {
+                device.restart(restart);
+                changed = true;
+            }
+            let exporters = &mut self.exporters;
+            changed |= device.drain_events(&mut |device, finished| {
+                if let Finished::Poll(outcome) = &finished {
+                    for exporter in exporters.iter_mut() {
+                        exporter.poll(&device.name, &device.app, *outcome, now);
+                    }
+                }
+                report(device, finished);
+            });
+            if let Some(target) = device.app.target_batch.settled(now) {
+                device
+                    .command_tx
+                    .send(TransportCommand::SetTargetFlow(target))
+                    .ok();
+                changed = true;
+            }
+            for command in device.app.macro_commands(now) {
+                device.command_tx.send(command).ok();
+                changed = true;
+            }
+            changed |= device.app.tick(now);
+        }
+        changed | self.take_incoming(now)
+    }
+
+    /// Sends the writes the exporters received and logs their faults; `true` when there was
+    /// any.
+    fn take_incoming(&mut self, now: SystemTime) -> bool {
+        let incoming: Vec<Incoming> = self
+            .exporters
+            .iter_mut()
+            .flat_map(|exporter| exporter.incoming())
+            .collect();
+        for message in &incoming {
+            match message {
+                Incoming::Command {
+                    device,
+                    command,
+                    source,
+                } => {
+                    if let Some(device) = self.devices.iter_mut().find(|d| d.name == *device) {
+                        device.app.event_log.push(
+                            now,
+                            EventSource::User,
+                            format!("{command:?} requested over {source}"),
+                        );
+                        device.send(command.clone());
+                    }
+                }
+                Incoming::Fault(fault) => self.devices[0].app.event_log.push_with_severity(
+                    now,
+                    EventSource::Monitor,
+                    Severity::Warning,
+                    fault.clone(),
+                ),
+            }
+        }
+        !incoming.is_empty()
+    }
+
+    /// Lays every device's dashboard out for a resized terminal, not only the one shown.
+    pub fn resize(&mut self, area: Rect) {
+        for device in &mut self.devices {
+            update_layout(&mut device.app, area);
+        }
+    }
+
+    pub fn apps_mut(&mut self) -> impl Iterator<Item = &mut AppState> {
+        self.devices.iter_mut().map(|device| &mut device.app)
+    }
+
+    /// `true` while any device still has a worker polling it.
+    pub fn polling(&self) -> bool {
+        self.devices.iter().any(Device::polling)
+    }
+
+    pub fn should_quit(&self) -> bool {
+        self.devices.iter().any(|device| device.app.should_quit)
+    }
+
+    /// Stops every worker and waits for it to close its port.
+    pub fn shutdown(self) {
+        for device in self.devices {
+            device.shutdown();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::{self, Sender};
+    use std::time::{Duration, SystemTime};
+
+    use crossterm::event::KeyCode;
+
+    use super::{Device, Finished, Fleet};
+    use crate::app::AppState;
+    use crate::interface::InterfaceMode;
+    use crate::profile::QUICK_6101A2;
+    use crate::transport::{PollOutcome, TransportCommand, TransportConfig, TransportEvent};
+
+    fn device(name: &str) -> (Device, Sender<TransportEvent>) {
+        let (command_tx, _) = mpsc::channel();
+        let (event_tx, event_rx) = mpsc::channel();
+        let device = Device {
+            name: name.to_string(),
+            app: AppState::new(InterfaceMode::Remote, false),
+            transport: TransportConfig {
+                port: None,
+                baud: 19_200,
+                address: 2,
+                poll_interval: Duration::from_millis(500),
+                read_only: false,
+                interface: InterfaceMode::Simulation,
+                profile: &QUICK_6101A2,
+            },
+            command_tx,
+            event_rx,
+            worker: None,
+            worker_running: true,
+            polled: None,
+        };
+        (device, event_tx)
+    }
+
+    #[test]
+    fn overview_selects_and_expands_devices() {
+        let mut fleet = Fleet::new(vec![device("hall").0, device("bench").0]);
+        assert!(fleet.overview);
+        fleet.handle_key(KeyCode::Left);
+        assert_eq!(fleet.selected, 0);
+        fleet.handle_key(KeyCode::Right);
+        fleet.handle_key(KeyCode::Right);
+        assert_eq!(fleet.selected().name, "bench");
+
+        fleet.handle_key(KeyCode::Enter);
+        assert!(!fleet.overview);
+        fleet.selected_mut().app.help_open = true;
+        fleet.handle_key(KeyCode::Char('o'));
+        assert!(!fleet.overview, "o closes the help overlay first");
+        fleet.handle_key(KeyCode::Char('o'));
+        assert!(fleet.overview);
+        assert!(fleet.handle_key(KeyCode::Char('q')));
+    }
+
+    #[test]
+    fn brackets_cycle_the_focused_device_and_wrap() {
+        let mut fleet = Fleet::new(vec![device("hall").0, device("bench").0, device("lab").0]);
+        fleet.handle_key(KeyCode::Char('['));
+        assert_eq!(fleet.selected().name, "lab");
+        fleet.handle_key(KeyCode::Enter);
+        fleet.handle_key(KeyCode::Char(']'));
+        assert!(!fleet.overview);
+        assert_eq!(fleet.selected().name, "hall");
+        let toast = fleet
+            .selected()
+            .app
+            .toasts
+            .iter()
+            .next()
+            .map(|toast| toast.message.clone());
+        assert_eq!(toast.as_deref(), Some("Showing hall"));
+    }
+
+    #[test]
+    fn single_device_starts_expanded_and_stopped_worker_is_reported_once() {
+        let (single, event_tx) = device("hall");
+        let mut fleet = Fleet::new(vec![single]);
+        assert!(!fleet.overview);
+        fleet.handle_key(KeyCode::Char('o'));
+        assert!(!fleet.overview);
+
+        assert!(!fleet.tick(SystemTime::now()));
+        drop(event_tx);
+        assert!(fleet.tick(SystemTime::now()));
+        assert!(!fleet.tick(SystemTime::now()));
+        let faults = fleet.selected().app.event_log.iter().len();
+        assert_eq!(faults, 1);
+    }
+
+    #[test]
+    fn finished_polls_and_writes_are_reported() {
+        let (single, event_tx) = device("hall");
+        let mut fleet = Fleet::new(vec![single]);
+        event_tx
+            .send(TransportEvent::Poll(PollOutcome::Timeout))
+            .expect("fleet listens");
+        event_tx
+            .send(TransportEvent::Fault(String::from("no reply")))
+            .expect("fleet listens");
+        let mut reports = Vec::new();
+        fleet.tick_reporting(SystemTime::now(), |device, finished| {
+            reports.push((device.name.clone(), finished));
+        });
+        assert!(reports.is_empty(), "the poll has not finished");
+
+        event_tx
+            .send(TransportEvent::Connection(false))
+            .expect("fleet listens");
+        fleet.tick_reporting(SystemTime::now(), |device, finished| {
+            assert!(!device.app.connected);
+            reports.push((device.name.clone(), finished));
+        });
+        assert_eq!(
+            reports,
+            [(String::from("hall"), Finished::Poll(PollOutcome::Timeout))]
+        );
+
+        let mut writes = Vec::new();
+        for event in [
+            TransportEvent::Applied(TransportCommand::SetPower(true)),
+            TransportEvent::Fault(String::from("SetBeeper(true) failed: timeout")),
+            TransportEvent::Connection(false),
+        ] {
+            event_tx.send(event).expect("fleet listens");
+        }
+        fleet.tick_reporting(SystemTime::now(), |_, finished| writes.push(finished));
+        assert_eq!(
+            writes,
+            [
+                Finished::Write(Ok(TransportCommand::SetPower(true))),
+                Finished::Write(Err(String::from("SetBeeper(true) failed: timeout"))),
+            ]
+        );
+        assert!(fleet.polling());
+        drop(event_tx);
+        fleet.tick(SystemTime::now());
+        assert!(!fleet.polling());
+    }
+
+    #[test]
+    fn retry_on_the_error_screen_starts_a_new_worker() {
+        let (single, event_tx) = device("hall");
+        let mut fleet = Fleet::new(vec![single]);
+        drop(event_tx);
+        fleet.tick(SystemTime::now());
+        assert!(fleet.selected().app.error_screen.is_some());
+
+        fleet.handle_key(KeyCode::Char('r'));
+        assert!(fleet.selected().app.error_screen.is_none());
+        fleet.tick(SystemTime::now());
+        let device = fleet.selected();
+        assert!(device.worker_running);
+        assert!(
+            device
+                .app
+                .event_log
+                .iter()
+                .any(|entry| entry.message == "reconnecting to simulation")
+        );
+        fleet.shutdown();
+    }
+}
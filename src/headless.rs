@@ -0,0 +1,436 @@
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Local, SecondsFormat};
+use color_eyre::eyre::{self, WrapErr};
+use serde::Serialize;
+
+use crate::app::AppState;
+use crate::constants::{STATE_OFF, STATE_ON};
+use crate::data::{DeviceStatus, Filter};
+use crate::fleet::{Finished, Fleet};
+use crate::scan::{OutputFormat, csv_field};
+use crate::transport::{PollOutcome, TransportCommand};
+
+/// How often the workers' events are taken in without a terminal to wait on.
+const IDLE: Duration = Duration::from_millis(50);
+
+/// One finished poll as a JSON line: the reading, the raised alarms and the link state.
+#[derive(Debug, Serialize)]
+pub struct PollReport<'a> {
+    /// Local time with its offset, in RFC 3339.
+    pub time: String,
+    pub device: &'a str,
+    /// `answered`, `timeout`, `crc` or `failed`.
+    pub poll: &'static str,
+    pub latency_ms: Option<u128>,
+    pub connected: bool,
+    /// The reading from this poll; `null` when it went unanswered.
+    pub status: Option<&'a DeviceStatus>,
+    /// Raised alarms, acknowledged or not.
+    pub alarms: Vec<&'a str>,
+    pub deviation: bool,
+    pub filters_at_limit: &'a [Filter],
+    pub filters_due: Vec<Filter>,
+    pub timeouts: u64,
+    pub crc_errors: u64,
+    pub other_errors: u64,
+}
+
+impl<'a> PollReport<'a> {
+    pub fn new(device: &'a str, app: &'a AppState, outcome: PollOutcome, now: SystemTime) -> Self {
+        let latency = match outcome {
+            PollOutcome::Answered(latency) => Some(latency.as_millis()),
+            _ => None,
+        };
+        Self {
+            time: timestamp(now),
+            device,
+            poll: outcome.name(),
+            latency_ms: latency,
+            connected: app.connected,
+            status: app.status.as_ref().filter(|_| latency.is_some()),
+            alarms: app
+                .alarms
+                .active()
+                .chain(app.alarms.acknowledged())
+                .collect(),
+            deviation: app.deviation.is_active(),
+            filters_at_limit: app.filter_banner.over_limit(),
+            filters_due: app.reminders.due().collect(),
+            timeouts: app.poll_stats.timeouts,
+            crc_errors: app.poll_stats.crc_errors,
+            other_errors: app.poll_stats.other_errors,
+        }
+    }
+}
+
+/// Header of the CSV rows written by `watch` and `--log-csv`.
+pub const CSV_HEADER: &str =
+    "time,device,state,target_m3h,real_m3h,speed_rpm,p_filter_total,m_filter_total,c_filter_total";
+
+/// Polls every device without drawing anything, writing a line per finished poll to `out`
+/// until the workers stop or the reader goes away. JSON has a line for every poll; CSV and
+/// table rows only for answered ones.
+pub fn run(fleet: &mut Fleet, format: OutputFormat, mut out: impl Write) -> eyre::Result<()> {
+    let header = match format {
+        OutputFormat::Table => Some(table_row(&TABLE_HEADER.map(String::from))),
+        OutputFormat::Csv => Some(String::from(CSV_HEADER)),
+        OutputFormat::Json => None,
+    };
+    let mut written = header.map_or(Ok(()), |header| write_line(&mut out, &header));
+    while written.is_ok() && fleet.polling() {
+        let now = SystemTime::now();
+        fleet.tick_reporting(now, |device, finished| {
+            if let Finished::Poll(outcome) = finished
+                && written.is_ok()
+                && let Some(line) = poll_line(format, &device.name, &device.app, outcome, now)
+            {
+                written = line
+                    .map_err(io::Error::from)
+                    .and_then(|line| write_line(&mut out, &line));
+            }
+        });
+        thread::sleep(IDLE);
+    }
+    match written {
+        // A pipeline such as `| head` that stopped reading ends the run quietly.
+        Err(err) if err.kind() != io::ErrorKind::BrokenPipe => {
+            Err(err).wrap_err("write poll report")
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Column titles of the table rows written by `watch`.
+const TABLE_HEADER: [&str; 9] = [
+    "Time", "Device", "State", "Target", "Real", "RPM", "P-Filter", "M-Filter", "C-Filter",
+];
+
+/// The line for one finished poll; `None` for an unanswered poll outside JSON.
+fn poll_line(
+    format: OutputFormat,
+    device: &str,
+    app: &AppState,
+    outcome: PollOutcome,
+    now: SystemTime,
+) -> Option<serde_json::Result<String>> {
+    if format == OutputFormat::Json {
+        return Some(serde_json::to_string(&PollReport::new(
+            device, app, outcome, now,
+        )));
+    }
+    let cells = cells(device, app, outcome, now)?;
+    Some(Ok(match format {
+        OutputFormat::Csv => csv_row(&cells),
+        _ => table_row(&cells),
+    }))
+}
+
+/// The cells of an answered poll's CSV or table row.
+pub fn cells(
+    device: &str,
+    app: &AppState,
+    outcome: PollOutcome,
+    now: SystemTime,
+) -> Option<[String; 9]> {
+    let status = app
+        .status
+        .as_ref()
+        .filter(|_| matches!(outcome, PollOutcome::Answered(_)))?;
+    Some([
+        timestamp(now),
+        device.to_string(),
+        state_label(status.state),
+        status.target_flow.to_string(),
+        status.real_flow.to_string(),
+        status.speed_rpm.to_string(),
+        status.p_filter_total.to_string(),
+        status.m_filter_total.to_string(),
+        status.c_filter_total.to_string(),
+    ])
+}
+
+/// Cells joined into a CSV line, quoted where needed.
+pub fn csv_row(cells: &[String]) -> String {
+    cells
+        .iter()
+        .map(|cell| csv_field(cell))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Cells padded to fixed widths, the time and device wide enough for their usual values.
+fn table_row(cells: &[String; 9]) -> String {
+    let widths = [30, 14, 7, 7, 6, 6, 9, 9, 0];
+    let mut row = String::new();
+    for (cell, width) in cells.iter().zip(widths) {
+        let _ = write!(row, "{cell:<width$}");
+    }
+    row.trim_end().to_string()
+}
+
+/// Local time with its offset and milliseconds, in RFC 3339.
+pub fn timestamp(now: SystemTime) -> String {
+    DateTime::<Local>::from(now).to_rfc3339_opts(SecondsFormat::Millis, false)
+}
+
+fn state_label(state: u16) -> String {
+    match state {
+        STATE_ON => String::from("on"),
+        STATE_OFF => String::from("off"),
+        other => format!("state {other}"),
+    }
+}
+
+/// Polls every device once and prints its status, as text or a JSON line each; `true` when
+/// every device answered.
+pub fn status(fleet: &mut Fleet, json: bool, mut out: impl Write) -> eyre::Result<bool> {
+    for device in &fleet.devices {
+        device.send(TransportCommand::PollNow);
+    }
+    let names: Vec<String> = fleet
+        .devices
+        .iter()
+        .map(|device| device.name.clone())
+        .collect();
+    let mut outcomes: Vec<Option<PollOutcome>> = vec![None; names.len()];
+    let waiting = |outcomes: &[Option<PollOutcome>], fleet: &Fleet| {
+        outcomes
+            .iter()
+            .zip(&fleet.devices)
+            .any(|(outcome, device)| outcome.is_none() && device.polling())
+    };
+    while waiting(&outcomes, fleet) {
+        fleet.tick_reporting(SystemTime::now(), |device, finished| {
+            if let Finished::Poll(outcome) = finished
+                && let Some(index) = names.iter().position(|name| *name == device.name)
+            {
+                outcomes[index].get_or_insert(outcome);
+            }
+        });
+        thread::sleep(IDLE);
+    }
+    let now = SystemTime::now();
+    let mut answered = true;
+    for (device, outcome) in fleet.devices.iter().zip(outcomes) {
+        let outcome = outcome.unwrap_or(PollOutcome::Failed);
+        answered &= matches!(outcome, PollOutcome::Answered(_));
+        let line = if json {
+            serde_json::to_string(&PollReport::new(&device.name, &device.app, outcome, now))?
+        } else {
+            status_text(&device.name, &device.app, outcome)
+        };
+        writeln!(out, "{line}").wrap_err("write status")?;
+    }
+    Ok(answered)
+}
+
+/// Sends `command` to every device and prints whether each applied it; `true` when all did.
+pub fn write(
+    fleet: &mut Fleet,
+    command: &TransportCommand,
+    mut out: impl Write,
+) -> eyre::Result<bool> {
+    for device in &fleet.devices {
+        device.send(command.clone());
+    }
+    let names: Vec<String> = fleet
+        .devices
+        .iter()
+        .map(|device| device.name.clone())
+        .collect();
+    let mut results: Vec<Option<Result<TransportCommand, String>>> = vec![None; names.len()];
+    while results
+        .iter()
+        .zip(&fleet.devices)
+        .any(|(result, device)| result.is_none() && device.polling())
+    {
+        fleet.tick_reporting(SystemTime::now(), |device, finished| {
+            if let Finished::Write(result) = finished
+                && let Some(index) = names.iter().position(|name| *name == device.name)
+            {
+                results[index].get_or_insert(result);
+            }
+        });
+        thread::sleep(IDLE);
+    }
+    let mut applied = true;
+    for (device, result) in fleet.devices.iter().zip(results) {
+        let line = match result {
+            Some(Ok(command)) => written_text(&device.app, &command),
+            Some(Err(reason)) => {
+                applied = false;
+                reason
+            }
+            None => {
+                applied = false;
+                device.app.error_screen.as_ref().map_or_else(
+                    || String::from("serial worker stopped"),
+                    |screen| screen.message.clone(),
+                )
+            }
+        };
+        writeln!(out, "{}: {line}", device.name).wrap_err("write result")?;
+    }
+    Ok(applied)
+}
+
+/// "Target set to 70 m3/h", as the dashboard's toast puts it.
+fn written_text(app: &AppState, command: &TransportCommand) -> String {
+    let Some((register, value)) = command.register_write() else {
+        return format!("{command:?} applied");
+    };
+    match app.profile.register_info(register) {
+        Some(info) => format!(
+            "{} set to {}",
+            info.name,
+            format!("{} {}", info.format_value(value), info.unit).trim_end()
+        ),
+        None => format!("0x{register:04X} set to {value}"),
+    }
+}
+
+/// The device's reading on one line with its filters on the next, or why it did not answer.
+fn status_text(device: &str, app: &AppState, outcome: PollOutcome) -> String {
+    let status = app
+        .status
+        .as_ref()
+        .filter(|_| matches!(outcome, PollOutcome::Answered(_)));
+    let Some(status) = status else {
+        let reason = match outcome {
+            PollOutcome::Timeout => String::from("no answer"),
+            PollOutcome::Crc => String::from("CRC error"),
+            _ => app.error_screen.as_ref().map_or_else(
+                || String::from("poll failed"),
+                |screen| screen.message.clone(),
+            ),
+        };
+        return format!("{device}: unreachable ({reason})");
+    };
+    let state = state_label(status.state);
+    let unit = app.flow_unit;
+    let filters: Vec<String> = Filter::ALL
+        .iter()
+        .map(|filter| {
+            format!(
+                "{} {}/{}",
+                filter.label(),
+                status.filter_total(*filter),
+                status.filter_limit(*filter)
+            )
+        })
+        .collect();
+    format!(
+        "{device}: {state}, target {}, real flow {}, {} rpm\n  {}",
+        unit.format(status.target_flow),
+        unit.format(status.real_flow),
+        status.speed_rpm,
+        filters.join(", ")
+    )
+}
+
+fn write_line(out: &mut impl Write, line: &str) -> io::Result<()> {
+    writeln!(out, "{line}")?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{CSV_HEADER, PollReport, poll_line, status_text};
+    use crate::app::AppState;
+    use crate::constants::STATUS_POLL_REG_COUNT;
+    use crate::data::DeviceStatus;
+    use crate::interface::InterfaceMode;
+    use crate::scan::OutputFormat;
+    use crate::transport::{PollOutcome, TransportEvent};
+
+    #[test]
+    fn report_carries_the_reading_only_for_an_answered_poll() {
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        let status = DeviceStatus::from_registers(vec![0; STATUS_POLL_REG_COUNT as usize])
+            .expect("status should parse");
+        app.handle_transport_event(TransportEvent::Status(status));
+        app.handle_transport_event(TransportEvent::Connection(true));
+        let now = UNIX_EPOCH + Duration::from_mins(1);
+
+        let answered = PollReport::new(
+            "hall",
+            &app,
+            PollOutcome::Answered(Duration::from_millis(42)),
+            now,
+        );
+        let json = serde_json::to_value(&answered).expect("report serializes");
+        assert_eq!(json["device"], "hall");
+        assert_eq!(json["poll"], "answered");
+        assert_eq!(json["latency_ms"], 42);
+        assert_eq!(json["connected"], true);
+        assert!(json["status"].is_object());
+        assert_eq!(json["alarms"], serde_json::json!([]));
+        assert!(json["time"].as_str().is_some_and(|time| time.contains('T')));
+
+        let timed_out = PollReport::new("hall", &app, PollOutcome::Timeout, now);
+        let json = serde_json::to_value(&timed_out).expect("report serializes");
+        assert_eq!(json["poll"], "timeout");
+        assert!(json["status"].is_null());
+        assert!(json["latency_ms"].is_null());
+    }
+
+    #[test]
+    fn status_text_shows_the_reading_or_why_it_is_missing() {
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        assert_eq!(
+            status_text("hall", &app, PollOutcome::Timeout),
+            "hall: unreachable (no answer)"
+        );
+        let mut status = DeviceStatus::from_registers(vec![0; STATUS_POLL_REG_COUNT as usize])
+            .expect("status should parse");
+        status.state = 1;
+        status.target_flow = 75;
+        status.real_flow = 74;
+        status.speed_rpm = 1450;
+        status.p_filter_total = 50;
+        status.p_filter_limit = 200;
+        app.handle_transport_event(TransportEvent::Status(status));
+        let text = status_text(
+            "hall",
+            &app,
+            PollOutcome::Answered(Duration::from_millis(9)),
+        );
+        assert_eq!(
+            text,
+            "hall: on, target 75 m3/h, real flow 74 m3/h, 1450 rpm\n  \
+             P-Filter 50/200, M-Filter 0/0, C-Filter 0/0"
+        );
+    }
+
+    #[test]
+    fn csv_rows_follow_the_header_and_skip_unanswered_polls() {
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        let mut status = DeviceStatus::from_registers(vec![0; STATUS_POLL_REG_COUNT as usize])
+            .expect("status should parse");
+        status.state = 1;
+        status.target_flow = 80;
+        status.real_flow = 78;
+        status.speed_rpm = 1500;
+        status.m_filter_total = 12;
+        app.handle_transport_event(TransportEvent::Status(status));
+        let now = UNIX_EPOCH;
+        let answered = PollOutcome::Answered(Duration::from_millis(5));
+
+        let row = poll_line(OutputFormat::Csv, "bench, left", &app, answered, now)
+            .expect("answered polls have a row")
+            .expect("row renders");
+        let (time, rest) = row.split_once(',').expect("time comes first");
+        assert!(time.starts_with("19"), "{time}");
+        assert_eq!(rest, "\"bench, left\",on,80,78,1500,0,12,0");
+        assert_eq!(CSV_HEADER.split(',').count(), row.split(',').count() - 1);
+
+        assert!(poll_line(OutputFormat::Csv, "hall", &app, PollOutcome::Crc, now).is_none());
+        assert!(poll_line(OutputFormat::Json, "hall", &app, PollOutcome::Crc, now).is_some());
+    }
+}
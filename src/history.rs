@@ -0,0 +1,597 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::{DeviceStatus, Filter};
+
+pub const DEFAULT_HISTORY_SAMPLES: usize = 120;
+
+/// Stored points above which the older half of the history is decimated.
+pub const MAX_STORED_POINTS: usize = 4_096;
+
+/// How far back trends look, and the span averaged there.
+pub const TREND_LOOKBACK: Duration = Duration::from_mins(1);
+pub const TREND_WINDOW: Duration = Duration::from_secs(10);
+
+/// Time span the charts show, ending at the newest sample.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChartWindow {
+    OneMinute,
+    TenMinutes,
+    OneHour,
+    /// The whole retained history.
+    #[default]
+    All,
+}
+
+impl ChartWindow {
+    pub const fn next(self) -> Self {
+        match self {
+            Self::OneMinute => Self::TenMinutes,
+            Self::TenMinutes => Self::OneHour,
+            Self::OneHour => Self::All,
+            Self::All => Self::OneMinute,
+        }
+    }
+
+    pub const fn span(self) -> Option<Duration> {
+        match self {
+            Self::OneMinute => Some(Duration::from_mins(1)),
+            Self::TenMinutes => Some(Duration::from_mins(10)),
+            Self::OneHour => Some(Duration::from_hours(1)),
+            Self::All => None,
+        }
+    }
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::OneMinute => "1 min",
+            Self::TenMinutes => "10 min",
+            Self::OneHour => "1 h",
+            Self::All => "all",
+        }
+    }
+}
+
+/// One polled reading stamped with the wall-clock time it arrived.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub timestamp: SystemTime,
+    pub real_flow: f64,
+    /// Real flow after optional EMA smoothing; equals `real_flow` when smoothing is off.
+    pub smoothed_flow: f64,
+    pub target_flow: f64,
+    pub speed_rpm: f64,
+    /// Filter totals in km3, in `Filter::ALL` order.
+    pub filter_totals: [f64; 3],
+}
+
+impl Sample {
+    pub fn from_status(status: &DeviceStatus, timestamp: SystemTime) -> Self {
+        Self {
+            timestamp,
+            real_flow: f64::from(status.real_flow),
+            smoothed_flow: f64::from(status.real_flow),
+            target_flow: f64::from(status.target_flow),
+            speed_rpm: f64::from(status.speed_rpm),
+            filter_totals: Filter::ALL.map(|filter| f64::from(status.filter_total(filter))),
+        }
+    }
+
+    pub fn filter_total(&self, filter: Filter) -> f64 {
+        self.filter_totals[filter as usize]
+    }
+
+    /// Seconds since the Unix epoch, used as the chart X coordinate.
+    pub fn seconds(&self) -> f64 {
+        self.timestamp
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |elapsed| elapsed.as_secs_f64())
+    }
+}
+
+/// Retained samples, decimated with min/max preservation once it grows large.
+#[derive(Debug, Clone)]
+pub struct History {
+    samples: VecDeque<Sample>,
+    weights: VecDeque<usize>,
+    represented: usize,
+    capacity: usize,
+    max_points: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::new(),
+            weights: VecDeque::new(),
+            represented: 0,
+            capacity: capacity.max(1),
+            max_points: MAX_STORED_POINTS,
+        }
+    }
+
+    /// Sets how many raw samples the history should span.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        self.trim();
+    }
+
+    pub fn push(&mut self, sample: Sample) {
+        self.samples.push_back(sample);
+        self.weights.push_back(1);
+        self.represented += 1;
+        self.trim();
+        if self.samples.len() > self.max_points {
+            self.compact();
+        }
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Sample> + ExactSizeIterator {
+        self.samples.iter()
+    }
+
+    /// The samples within `window` of the newest one.
+    pub fn window(&self, window: ChartWindow) -> impl Iterator<Item = &Sample> {
+        let from = window
+            .span()
+            .and_then(|span| self.samples.back()?.timestamp.checked_sub(span));
+        self.samples
+            .iter()
+            .skip_while(move |sample| from.is_some_and(|from| sample.timestamp < from))
+    }
+
+    /// How `value` at the newest sample compares with its mean over the `TREND_WINDOW` starting
+    /// `TREND_LOOKBACK` earlier; `None` until the history reaches back that far.
+    pub fn trend(&self, tolerance: f64, value: impl Fn(&Sample) -> f64) -> Option<Trend> {
+        let latest = self.samples.back()?;
+        let from = latest.timestamp.checked_sub(TREND_LOOKBACK)?;
+        let to = from + TREND_WINDOW;
+        let (sum, count) = self
+            .samples
+            .iter()
+            .filter(|sample| (from..=to).contains(&sample.timestamp))
+            .fold((0.0, 0.0), |(sum, count), sample| {
+                (sum + value(sample), count + 1.0)
+            });
+        (count > 0.0).then(|| Trend::new(value(latest) - sum / count, tolerance))
+    }
+
+    fn trim(&mut self) {
+        while self.represented > self.capacity {
+            let Some(weight) = self.weights.pop_front() else {
+                break;
+            };
+            self.samples.pop_front();
+            self.represented -= weight;
+        }
+    }
+
+    /// Halves the older half of the history, keeping the extremes of every group of four.
+    fn compact(&mut self) {
+        let older = (self.samples.len() / 2) / 4 * 4;
+        let mut samples = VecDeque::with_capacity(self.samples.len());
+        let mut weights = VecDeque::with_capacity(self.weights.len());
+        let drained: Vec<Sample> = self.samples.drain(..older).collect();
+        let drained_weights: Vec<usize> = self.weights.drain(..older).collect();
+        for (group, group_weights) in drained.chunks(4).zip(drained_weights.chunks(4)) {
+            let weight: usize = group_weights.iter().sum();
+            let (low, high) = min_max_pair(group);
+            samples.push_back(low);
+            weights.push_back(weight - weight / 2);
+            samples.push_back(high);
+            weights.push_back(weight / 2);
+        }
+        samples.append(&mut self.samples);
+        weights.append(&mut self.weights);
+        self.samples = samples;
+        self.weights = weights;
+    }
+}
+
+/// The samples holding the group's lowest and highest real flow, in time order.
+fn min_max_pair(group: &[Sample]) -> (Sample, Sample) {
+    let mut low = 0;
+    let mut high = 0;
+    for (index, sample) in group.iter().enumerate() {
+        if sample.real_flow < group[low].real_flow {
+            low = index;
+        }
+        if sample.real_flow > group[high].real_flow {
+            high = index;
+        }
+    }
+    if low == high {
+        // Flat group: keep its endpoints.
+        (low, high) = (0, group.len() - 1);
+    }
+    (group[low.min(high)], group[low.max(high)])
+}
+
+/// Which way a value moved, and by how much, against its average a minute earlier.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trend {
+    Rising(f64),
+    Falling(f64),
+    Steady,
+}
+
+impl Trend {
+    /// Changes within `tolerance` count as steady.
+    pub fn new(delta: f64, tolerance: f64) -> Self {
+        if delta > tolerance {
+            Self::Rising(delta)
+        } else if delta < -tolerance {
+            Self::Falling(-delta)
+        } else {
+            Self::Steady
+        }
+    }
+
+    pub const fn arrow(self) -> &'static str {
+        match self {
+            Self::Rising(_) => "↑",
+            Self::Falling(_) => "↓",
+            Self::Steady => "→",
+        }
+    }
+}
+
+/// Exponential moving average over roughly `window` samples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ema {
+    alpha: f64,
+    value: Option<f64>,
+}
+
+impl Ema {
+    #[allow(clippy::cast_precision_loss)]
+    pub fn new(window: usize) -> Self {
+        Self {
+            alpha: 2.0 / (window.max(1) as f64 + 1.0),
+            value: None,
+        }
+    }
+
+    pub fn update(&mut self, sample: f64) -> f64 {
+        let next = self
+            .value
+            .map_or(sample, |value| value + self.alpha * (sample - value));
+        self.value = Some(next);
+        next
+    }
+}
+
+/// Full scale of the fan speed gauge and chart.
+///
+/// A configured or rated maximum keeps the scale fixed across sessions; without one the
+/// scale follows the highest speed seen so far, rounded up to `SPEED_SCALE_STEP`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SpeedScale {
+    fixed: Option<f64>,
+    peak: f64,
+}
+
+/// Rounding of the observed speed scale, in RPM.
+pub const SPEED_SCALE_STEP: f64 = 500.0;
+
+impl SpeedScale {
+    pub fn new(fixed: Option<u16>) -> Self {
+        Self {
+            fixed: fixed.filter(|max| *max > 0).map(f64::from),
+            peak: 0.0,
+        }
+    }
+
+    /// Changes the configured maximum, keeping the observed peak.
+    pub fn set_fixed(&mut self, fixed: Option<u16>) {
+        self.fixed = Self::new(fixed).fixed;
+    }
+
+    pub fn observe(&mut self, speed_rpm: u16) {
+        self.peak = self.peak.max(f64::from(speed_rpm));
+    }
+
+    pub fn max(&self) -> f64 {
+        self.fixed.unwrap_or_else(|| {
+            ((self.peak / SPEED_SCALE_STEP).ceil() * SPEED_SCALE_STEP).max(SPEED_SCALE_STEP)
+        })
+    }
+
+    pub fn is_fixed(&self) -> bool {
+        self.fixed.is_some()
+    }
+}
+
+/// Polls kept per register for the sparklines in the Registers view.
+pub const REGISTER_TRAIL_LEN: usize = 6;
+
+/// The latest values of every register, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegisterTrail {
+    values: Vec<VecDeque<u16>>,
+}
+
+impl RegisterTrail {
+    pub fn push(&mut self, registers: &[u16]) {
+        self.values.resize_with(registers.len(), VecDeque::new);
+        for (trail, value) in self.values.iter_mut().zip(registers) {
+            if trail.len() == REGISTER_TRAIL_LEN {
+                trail.pop_front();
+            }
+            trail.push_back(*value);
+        }
+    }
+
+    pub fn values(&self, register: usize) -> impl Iterator<Item = u16> + '_ {
+        self.values.get(register).into_iter().flatten().copied()
+    }
+}
+
+/// Reduces a series to at most two points per bucket, keeping each bucket's minimum and maximum.
+pub fn downsample(points: &[(f64, f64)], buckets: usize) -> Vec<(f64, f64)> {
+    let buckets = buckets.max(1);
+    if points.len() <= buckets * 2 {
+        return points.to_vec();
+    }
+    let per_bucket = points.len().div_ceil(buckets);
+    let mut out = Vec::with_capacity(buckets * 2);
+    for bucket in points.chunks(per_bucket) {
+        let mut low = bucket[0];
+        let mut high = bucket[0];
+        for point in bucket {
+            if point.1 < low.1 {
+                low = *point;
+            }
+            if point.1 > high.1 {
+                high = *point;
+            }
+        }
+        if low.0 <= high.0 {
+            out.push(low);
+            if high != low {
+                out.push(high);
+            }
+        } else {
+            out.push(high);
+            out.push(low);
+        }
+    }
+    out
+}
+
+/// How much history to retain, either as a sample count or a time span.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryDepth {
+    Samples(usize),
+    Duration(Duration),
+}
+
+impl Default for HistoryDepth {
+    fn default() -> Self {
+        Self::Samples(DEFAULT_HISTORY_SAMPLES)
+    }
+}
+
+impl HistoryDepth {
+    /// Number of samples to keep for the given poll interval.
+    pub fn samples(self, poll_interval: Duration) -> usize {
+        match self {
+            Self::Samples(count) => count.max(1),
+            Self::Duration(span) => {
+                let interval = poll_interval.as_millis().max(1);
+                usize::try_from(span.as_millis().div_ceil(interval))
+                    .unwrap_or(usize::MAX)
+                    .max(1)
+            }
+        }
+    }
+}
+
+impl FromStr for HistoryDepth {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let split = value
+            .find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(value.len());
+        let (number, suffix) = value.split_at(split);
+        let number: u64 = number
+            .parse()
+            .map_err(|_| format!("invalid history depth: {value}"))?;
+        if number == 0 {
+            return Err("history depth must be greater than zero".to_string());
+        }
+        let unit_seconds: u64 = match suffix {
+            "" => {
+                let samples = usize::try_from(number)
+                    .map_err(|_| format!("history depth too large: {value}"))?;
+                return Ok(Self::Samples(samples));
+            }
+            "s" => 1,
+            "m" => 60,
+            "h" => 3_600,
+            "d" => 86_400,
+            _ => {
+                return Err(format!(
+                    "unknown history unit '{suffix}' (use s, m, h, or d)"
+                ));
+            }
+        };
+        let seconds = number
+            .checked_mul(unit_seconds)
+            .ok_or_else(|| format!("history depth too large: {value}"))?;
+        Ok(Self::Duration(Duration::from_secs(seconds)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{
+        ChartWindow, Ema, History, HistoryDepth, REGISTER_TRAIL_LEN, RegisterTrail, Sample,
+        SpeedScale, Trend, downsample,
+    };
+
+    #[test]
+    fn parses_sample_counts_and_durations() {
+        assert_eq!("600".parse(), Ok(HistoryDepth::Samples(600)));
+        assert_eq!(
+            "2h".parse(),
+            Ok(HistoryDepth::Duration(Duration::from_hours(2)))
+        );
+        assert!("10x".parse::<HistoryDepth>().is_err());
+        assert!("0".parse::<HistoryDepth>().is_err());
+        assert!("99999999999999999d".parse::<HistoryDepth>().is_err());
+    }
+
+    #[test]
+    fn history_compacts_instead_of_dropping_old_samples() {
+        let mut history = History::new(10_000);
+        history.max_points = 16;
+        for index in 0..40u32 {
+            history.push(sample(index, f64::from(index % 7)));
+        }
+        assert!(history.iter().len() <= 16);
+        let first = history.iter().next().expect("history is not empty");
+        assert_eq!(first.timestamp, UNIX_EPOCH);
+        let peak = history.iter().map(|s| s.real_flow).fold(0.0, f64::max);
+        assert!((peak - 6.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn chart_window_keeps_the_newest_span() {
+        let mut history = History::new(10_000);
+        for seconds in (0..7_200).step_by(30) {
+            history.push(sample(seconds, 60.0));
+        }
+        let first = |window| history.window(window).next().map(Sample::seconds);
+        assert_eq!(first(ChartWindow::OneMinute), Some(7_110.0));
+        assert_eq!(first(ChartWindow::OneHour), Some(3_570.0));
+        assert_eq!(first(ChartWindow::All), Some(0.0));
+        assert_eq!(ChartWindow::All.next(), ChartWindow::OneMinute);
+    }
+
+    #[test]
+    fn compacted_points_are_real_samples() {
+        let mut history = History::new(10_000);
+        history.max_points = 16;
+        let mut pushed = Vec::new();
+        for index in 0..40u32 {
+            let mut next = sample(index, f64::from(index % 5));
+            next.speed_rpm = f64::from(index * 10);
+            pushed.push(next);
+            history.push(next);
+        }
+        assert!(history.iter().all(|stored| pushed.contains(stored)));
+    }
+
+    #[test]
+    fn history_drops_samples_beyond_capacity() {
+        let mut history = History::new(3);
+        for index in 0..5u32 {
+            history.push(sample(index, 1.0));
+        }
+        let times: Vec<f64> = history.iter().map(Sample::seconds).collect();
+        assert_eq!(times, vec![2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn downsample_keeps_bucket_extremes() {
+        let points: Vec<(f64, f64)> = (0..100u32)
+            .map(|x| (f64::from(x), if x == 37 { 99.0 } else { 1.0 }))
+            .collect();
+        let reduced = downsample(&points, 10);
+        assert!(reduced.len() <= 20);
+        assert!(reduced.contains(&(37.0, 99.0)));
+    }
+
+    #[test]
+    fn ema_follows_steps_gradually() {
+        let mut ema = Ema::new(3);
+        assert!((ema.update(10.0) - 10.0).abs() < 1e-9);
+        assert!((ema.update(20.0) - 15.0).abs() < 1e-9);
+        assert!((ema.update(20.0) - 17.5).abs() < 1e-9);
+        let mut passthrough = Ema::new(1);
+        passthrough.update(10.0);
+        assert!((passthrough.update(20.0) - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn duration_is_converted_with_poll_interval() {
+        let depth = HistoryDepth::Duration(Duration::from_mins(1));
+        assert_eq!(depth.samples(Duration::from_millis(500)), 120);
+        assert_eq!(
+            HistoryDepth::Samples(42).samples(Duration::from_secs(1)),
+            42
+        );
+    }
+
+    fn sample(seconds: u32, real_flow: f64) -> Sample {
+        Sample {
+            timestamp: UNIX_EPOCH + Duration::from_secs(u64::from(seconds)),
+            real_flow,
+            smoothed_flow: real_flow,
+            target_flow: 70.0,
+            speed_rpm: 2000.0,
+            filter_totals: [0.0; 3],
+        }
+    }
+
+    #[test]
+    fn speed_scale_is_fixed_or_follows_the_peak() {
+        let mut scale = SpeedScale::new(None);
+        assert!((scale.max() - 500.0).abs() < f64::EPSILON);
+        scale.observe(1720);
+        scale.observe(900);
+        assert!((scale.max() - 2000.0).abs() < f64::EPSILON);
+
+        let mut scale = SpeedScale::new(Some(3200));
+        scale.observe(4000);
+        assert!((scale.max() - 3200.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn trend_compares_against_the_average_a_minute_earlier() {
+        let mut history = History::new(200);
+        history.push(sample(0, 50.0));
+        assert_eq!(history.trend(0.5, |sample| sample.real_flow), None);
+        for second in 1..=10 {
+            history.push(sample(second, 52.0));
+        }
+        for second in 11..60 {
+            history.push(sample(second, 60.0));
+        }
+        history.push(sample(60, 55.0));
+        let trend = history.trend(0.5, |sample| sample.real_flow);
+        assert_eq!(
+            trend,
+            Some(Trend::Rising(55.0 - (50.0 + 52.0 * 10.0) / 11.0))
+        );
+
+        history.push(sample(61, 52.7));
+        assert_eq!(
+            history.trend(0.5, |sample| sample.real_flow),
+            Some(Trend::Steady)
+        );
+        assert_eq!(Trend::new(-3.0, 0.5), Trend::Falling(3.0));
+    }
+
+    #[test]
+    fn register_trail_keeps_the_latest_values() {
+        let mut trail = RegisterTrail::default();
+        for value in 0..10u16 {
+            trail.push(&[value, 7]);
+        }
+        let first: Vec<u16> = trail.values(0).collect();
+        assert_eq!(first.len(), REGISTER_TRAIL_LEN);
+        assert_eq!(first, [4, 5, 6, 7, 8, 9]);
+        assert!(trail.values(1).all(|value| value == 7));
+        assert_eq!(trail.values(2).count(), 0);
+    }
+}
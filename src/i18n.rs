@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use clap::ValueEnum;
+use color_eyre::eyre::{self, WrapErr};
+
+/// UI language selected with `--lang`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Lang {
+    #[default]
+    En,
+    De,
+    Fi,
+}
+
+impl Lang {
+    /// Locale file of the language; English strings are the lookup keys, so it needs none.
+    const fn file(self) -> Option<&'static str> {
+        match self {
+            Self::En => None,
+            Self::De => Some(include_str!("../locales/de.toml")),
+            Self::Fi => Some(include_str!("../locales/fi.toml")),
+        }
+    }
+}
+
+/// Translations of UI strings, keyed by their English text; missing entries fall back to English.
+#[derive(Debug, Default)]
+pub struct Locale {
+    strings: HashMap<String, String>,
+}
+
+impl Locale {
+    pub fn new(lang: Lang) -> eyre::Result<Self> {
+        let strings = match lang.file() {
+            Some(file) => {
+                toml::from_str(file).wrap_err_with(|| format!("parse {lang:?} locale"))?
+            }
+            None => HashMap::new(),
+        };
+        Ok(Self { strings })
+    }
+
+    pub fn text<'a>(&'a self, english: &'a str) -> &'a str {
+        self.strings.get(english).map_or(english, String::as_str)
+    }
+
+    /// Translates `english` and fills its `{}` placeholders with `args`, in order.
+    pub fn format(&self, english: &str, args: &[&dyn Display]) -> String {
+        let mut parts = self.text(english).split("{}");
+        let mut out = parts.next().unwrap_or_default().to_string();
+        for (index, part) in parts.enumerate() {
+            if let Some(arg) = args.get(index) {
+                out.push_str(&arg.to_string());
+            }
+            out.push_str(part);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{Lang, Locale};
+    use crate::app::Tab;
+    use crate::keymap::{BINDINGS, Scope};
+    use crate::setup::SetupField;
+
+    #[test]
+    fn locales_translate_the_same_strings_with_the_same_placeholders() {
+        let german = Locale::new(Lang::De).expect("German locale should parse");
+        let finnish = Locale::new(Lang::Fi).expect("Finnish locale should parse");
+        let keys = |locale: &Locale| locale.strings.keys().cloned().collect::<BTreeSet<_>>();
+        assert_eq!(keys(&german), keys(&finnish));
+        for locale in [&german, &finnish] {
+            for (english, translated) in &locale.strings {
+                assert_eq!(
+                    english.matches("{}").count(),
+                    translated.matches("{}").count(),
+                    "{english}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn key_bindings_views_and_setup_fields_are_translated() {
+        let german = Locale::new(Lang::De).expect("German locale should parse");
+        let labels = BINDINGS
+            .iter()
+            .flat_map(|binding| [Some(binding.action), binding.brief])
+            .flatten()
+            .chain(Scope::ALL.map(Scope::title))
+            .chain(Tab::ALL.map(Tab::title))
+            .chain(SetupField::ALL.map(SetupField::label));
+        for label in labels {
+            assert!(german.strings.contains_key(label), "{label}");
+        }
+    }
+
+    #[test]
+    fn placeholders_are_filled_and_english_is_the_fallback() {
+        let german = Locale::new(Lang::De).expect("German locale should parse");
+        assert_eq!(german.text("Connected"), "Verbunden");
+        assert_eq!(german.text("not translated"), "not translated");
+        assert_eq!(german.format("Flow ({})", &[&"m3/h"]), "Luftstrom (m3/h)");
+        let english = Locale::default();
+        assert_eq!(
+            english.format("address {}, {} baud", &[&2, &19_200]),
+            "address 2, 19200 baud"
+        );
+    }
+}
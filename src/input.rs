@@ -1,121 +1,885 @@
 use std::sync::mpsc::Sender;
+use std::time::SystemTime;
 
-use color_eyre::eyre::{self, WrapErr};
-use crossterm::event::KeyCode;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Position;
 
-use crate::app::AppState;
-use crate::constants::{STATE_ON, TARGET_FLOW_MAX, TARGET_FLOW_MIN};
+use crate::app::{AppState, Pane, Panel, Tab};
+use crate::config_editor::{ConfigEditor, PendingWrite};
+use crate::constants::{BEEPER_ON, REG_BEEPER, REG_MODE, STATE_ON};
+use crate::data::DeviceStatus;
+use crate::dialog::{ErrorChoice, FilterResetDialog, RESET_WORD, ResetStage, Restart};
+use crate::keymap::{Action, KeyChord};
+use crate::profile::DeviceProfile;
+use crate::register_editor::REGISTER_COLUMNS;
 use crate::transport::TransportCommand;
+use crate::wizard::{LinkWizard, WizardStep};
 
 pub fn handle_key_event(
-    code: KeyCode,
+    key: impl Into<KeyEvent>,
     app: &mut AppState,
     command_tx: &Sender<TransportCommand>,
-) -> eyre::Result<bool> {
-    if app.input_mode {
-        handle_input_event(code, app, command_tx)?;
-        return Ok(false);
+) -> bool {
+    let key = key.into();
+    let code = key.code;
+    app.write_error = None;
+    let after_g = std::mem::take(&mut app.pending_g);
+    if app.target_preview.take().is_some() && code == KeyCode::Esc {
+        return false;
+    }
+
+    if handle_modal_event(code, app, command_tx) {
+        return false;
+    }
+
+    if code == KeyCode::Esc && app.stop_macro(SystemTime::now()) {
+        return false;
+    }
+
+    if let Some(pane) = app.focused_pane()
+        && handle_pane_event(pane, code, after_g, app, command_tx)
+    {
+        return false;
+    }
+
+    if !app.read_only && app.start_macro(KeyChord::new(code, key.modifiers), SystemTime::now()) {
+        return false;
     }
 
     match code {
-        KeyCode::Char('q') => {
+        KeyCode::F(number @ 1..=5) => app.tab = Tab::ALL[usize::from(number) - 1],
+        KeyCode::Char('1'..='9') if !app.read_only => handle_preset_key(code, app, command_tx),
+        _ => {
+            if let Some(action) = app.keymap.action(key)
+                && !handle_view_action(action, app)
+            {
+                return handle_action(action, app, command_tx);
+            }
+        }
+    }
+
+    false
+}
+
+/// Runs an action that talks to the device or leaves the monitor; `true` when it should exit.
+fn handle_action(
+    action: Action,
+    app: &mut AppState,
+    command_tx: &Sender<TransportCommand>,
+) -> bool {
+    match action {
+        Action::Quit => {
             app.should_quit = true;
-            return Ok(true);
+            return true;
         }
-        KeyCode::Char(' ') => {
+        Action::TogglePower => {
             if app.read_only {
-                return Ok(false);
+                return false;
             }
             if let Some(status) = &app.status {
                 let next_state = status.state != STATE_ON;
-                command_tx
-                    .send(TransportCommand::SetPower(next_state))
-                    .wrap_err("send power toggle")?;
+                if !next_state && app.confirm_power_off {
+                    app.power_off_prompt = true;
+                    return false;
+                }
+                send(command_tx, TransportCommand::SetPower(next_state));
             }
         }
-        KeyCode::Left => {
+        Action::ToggleBeeper => {
             if app.read_only {
-                return Ok(false);
+                return false;
             }
-            if app.target_flow > TARGET_FLOW_MIN {
-                app.target_flow -= 1;
-                send_target_flow(command_tx, app.target_flow)?;
+            if let Some(beeper) = app.status.as_ref().and_then(|s| s.register(REG_BEEPER)) {
+                send(command_tx, TransportCommand::SetBeeper(beeper != BEEPER_ON));
             }
         }
-        KeyCode::Right => {
-            if app.read_only {
-                return Ok(false);
+        Action::ToggleMode => {
+            let writable = app
+                .profile
+                .register_info(REG_MODE)
+                .is_some_and(|info| info.writable);
+            if app.read_only || !writable {
+                return false;
             }
-            if app.target_flow < TARGET_FLOW_MAX {
-                app.target_flow += 1;
-                send_target_flow(command_tx, app.target_flow)?;
+            if let Some(mode) = app.status.as_ref().and_then(DeviceStatus::mode) {
+                send(command_tx, TransportCommand::SetMode(mode.toggled()));
             }
         }
-        KeyCode::Char('d') => {
-            app.show_debug = !app.show_debug;
+        Action::DecFlow | Action::IncFlow if !app.read_only => {
+            step_target(action == Action::IncFlow, 1, app, command_tx);
         }
-        KeyCode::Char('t') => {
-            if !app.read_only {
-                app.input_mode = true;
-                app.input_buffer.clear();
+        Action::DecFlowMedium | Action::IncFlowMedium if !app.read_only => {
+            let step = app.flow_step.medium;
+            step_target(action == Action::IncFlowMedium, step, app, command_tx);
+        }
+        Action::DecFlowLarge | Action::IncFlowLarge if !app.read_only => {
+            let step = app.flow_step.large;
+            step_target(action == Action::IncFlowLarge, step, app, command_tx);
+        }
+        Action::TypeTarget if !app.read_only => {
+            app.input_mode = true;
+            app.target_input.clear();
+        }
+        Action::LongerPoll | Action::ShorterPoll => {
+            if let Some(interval) = app.step_poll_interval(action == Action::LongerPoll) {
+                send(command_tx, TransportCommand::SetPollInterval(interval));
             }
         }
+        Action::RecordMacro if !app.read_only => app.toggle_recording(SystemTime::now()),
+        Action::PollNow => {
+            send(command_tx, TransportCommand::PollNow);
+        }
+        Action::OpenEditor if !app.read_only => {
+            app.config_editor = Some(ConfigEditor::default());
+        }
+        Action::LinkWizard if !app.read_only => {
+            app.link_wizard = Some(LinkWizard::new(app.link_address, app.link_baud));
+        }
+        Action::ResetFilter if !app.read_only => {
+            app.filter_reset = Some(FilterResetDialog::new(app.profile.filters));
+        }
         _ => {}
     }
 
-    Ok(false)
+    false
 }
 
-fn handle_input_event(
+/// Sends the key to the open popup, if any, returning whether one was open.
+fn handle_modal_event(
     code: KeyCode,
     app: &mut AppState,
     command_tx: &Sender<TransportCommand>,
-) -> eyre::Result<()> {
+) -> bool {
+    if app.error_screen.is_some() {
+        handle_error_screen_event(code, app);
+        return true;
+    }
+
+    if app.help_open {
+        app.help_open = false;
+        return true;
+    }
+
+    if app.power_off_prompt {
+        app.power_off_prompt = false;
+        if matches!(code, KeyCode::Char('y') | KeyCode::Enter) {
+            send(command_tx, TransportCommand::SetPower(false));
+        }
+        return true;
+    }
+
+    if app.input_mode {
+        handle_input_event(code, app, command_tx);
+        return true;
+    }
+
+    if app.config_editor.is_some() {
+        handle_config_event(code, app, command_tx);
+        return true;
+    }
+
+    if app.link_wizard.is_some() {
+        handle_wizard_event(code, app, command_tx);
+        return true;
+    }
+
+    if app.filter_reset.is_some() {
+        handle_filter_reset_event(code, app, command_tx);
+        return true;
+    }
+
+    if app.filter_detail.is_some() {
+        handle_filter_detail_event(code, app);
+        return true;
+    }
+
+    false
+}
+
+/// Actions that only change what is shown; returns whether `action` was one of them.
+fn handle_view_action(action: Action, app: &mut AppState) -> bool {
+    match action {
+        Action::NextView => app.tab = app.tab.next(),
+        Action::PreviousView => app.tab = app.tab.previous(),
+        Action::ToggleRegisters => app.tab = app.tab.toggle(Tab::Registers),
+        Action::ToggleLog => app.tab = app.tab.toggle(Tab::Log),
+        Action::CycleUnits => app.flow_unit = app.flow_unit.next(),
+        Action::AcknowledgeBanner => app.acknowledge_alarms(SystemTime::now()),
+        Action::SnoozeAlarms => app.toggle_snooze(SystemTime::now()),
+        Action::Colorblind => app.colorblind = !app.colorblind,
+        Action::PauseCharts => app.toggle_pause(),
+        Action::Compact => app.compact = !app.compact,
+        Action::CycleMarker => app.marker = app.marker.next(),
+        Action::CombinedChart => app.combined_chart = !app.combined_chart,
+        Action::CycleChartWindow => app.chart_window = app.chart_window.next(),
+        Action::HideStatus => app.panels.toggle(Panel::Status),
+        Action::HideSlider => app.panels.toggle(Panel::Slider),
+        Action::HideFlow => app.panels.toggle(Panel::Flow),
+        Action::HideSpeed => app.panels.toggle(Panel::Speed),
+        Action::HideFilters => app.panels.toggle(Panel::Filters),
+        Action::CopyRegisters => app.copy_registers(SystemTime::now()),
+        Action::SwitchInterface => app.switch_interface(SystemTime::now()),
+        Action::Help => app.help_open = true,
+        _ => return false,
+    }
+    true
+}
+
+/// Closes the filter detail popup, opening the reset dialog for that filter on `x`, or the
+/// typed one on `R`.
+fn handle_filter_detail_event(code: KeyCode, app: &mut AppState) {
+    let Some(filter) = app.filter_detail.take() else {
+        return;
+    };
+    if code == KeyCode::Char('R') && !app.read_only {
+        app.filter_reset = Some(FilterResetDialog::typed(app.profile.filters, filter));
+    } else if code == KeyCode::Char('x') && !app.read_only {
+        let mut dialog = FilterResetDialog::new(app.profile.filters);
+        dialog.selected = app
+            .profile
+            .filters
+            .iter()
+            .position(|candidate| *candidate == filter)
+            .unwrap_or_default();
+        app.filter_reset = Some(dialog);
+    }
+}
+
+/// Handles clicks on the dashboard and scroll-wheel target flow adjustment.
+pub fn handle_mouse_event(
+    event: MouseEvent,
+    app: &mut AppState,
+    command_tx: &Sender<TransportCommand>,
+) {
+    if app.modal_open() || app.tab != Tab::Dashboard {
+        return;
+    }
+    if app.filter_detail.is_some() {
+        if matches!(event.kind, MouseEventKind::Down(_)) {
+            app.filter_detail = None;
+        }
+        return;
+    }
+
+    let position = Position::new(event.column, event.row);
+    match event.kind {
+        MouseEventKind::ScrollUp | MouseEventKind::ScrollDown if !app.read_only => {
+            let step = if event.modifiers.contains(KeyModifiers::CONTROL) {
+                app.flow_step.large
+            } else if event.modifiers.contains(KeyModifiers::SHIFT) {
+                app.flow_step.medium
+            } else {
+                1
+            };
+            let up = event.kind == MouseEventKind::ScrollUp;
+            step_target(up, step, app, command_tx);
+        }
+        MouseEventKind::Down(MouseButton::Left) => {
+            if app.hit_areas.target_flow.contains(position) && !app.read_only {
+                app.input_mode = true;
+                app.target_input.clear();
+            } else if let Some(target) = app.hit_areas.target_at(position, app.profile) {
+                if !app.read_only {
+                    app.target_preview = Some(target);
+                }
+            } else if let Some(filter) = app.hit_areas.filter_at(position) {
+                app.filter_detail = Some(filter);
+            }
+        }
+        MouseEventKind::Drag(MouseButton::Left) if app.target_preview.is_some() => {
+            if let Some(target) = app.hit_areas.target_at(position, app.profile) {
+                app.target_preview = Some(target);
+            }
+        }
+        MouseEventKind::Up(MouseButton::Left) => {
+            if let Some(target) = app.target_preview.take() {
+                app.target_flow = target;
+                send_target_flow(command_tx, target);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Moves the target flow `step` m3/h up or down, stopping at the ends of the model's range.
+fn step_target(up: bool, step: u16, app: &mut AppState, command_tx: &Sender<TransportCommand>) {
+    let target = if up {
+        app.target_flow.saturating_add(step)
+    } else {
+        app.target_flow.saturating_sub(step)
+    };
+    let target = app.profile.clamp_flow(target);
+    if target == app.target_flow {
+        return;
+    }
+    app.target_flow = target;
+    if let Some(target) = app.target_batch.step(target, SystemTime::now()) {
+        send_target_flow(command_tx, target);
+    }
+}
+
+/// Jumps to the numbered preset, if one is configured for the key.
+fn handle_preset_key(code: KeyCode, app: &mut AppState, command_tx: &Sender<TransportCommand>) {
+    let KeyCode::Char(digit @ '1'..='9') = code else {
+        return;
+    };
+    let index = digit as usize - '1' as usize;
+    let Some(preset) = app.presets.get(index) else {
+        return;
+    };
+    let target = app.profile.clamp_flow(preset.flow);
+    app.target_flow = target;
+    send_target_flow(command_tx, target);
+}
+
+/// The target flow popup's line editor; `↑`/`↓` recall earlier typed targets and `+`/`-`
+/// nudge the typed value by one display unit within the device range, starting from the
+/// current target when nothing is typed.
+fn handle_input_event(code: KeyCode, app: &mut AppState, command_tx: &Sender<TransportCommand>) {
+    let input = &mut app.target_input;
+    let history = &mut app.target_history;
+    let unit = app.flow_unit;
     match code {
         KeyCode::Esc => {
             app.input_mode = false;
-            app.input_buffer.clear();
+            input.clear();
+            history.reset();
         }
         KeyCode::Enter => {
-            if let Ok(value) = app.input_buffer.parse::<u16>() {
-                let clamped = value.clamp(TARGET_FLOW_MIN, TARGET_FLOW_MAX);
+            if let Some(value) = unit.parse_m3h(input.text()) {
+                let clamped = app.profile.clamp_flow(value);
                 app.target_flow = clamped;
-                send_target_flow(command_tx, clamped)?;
+                history.push(clamped);
+                send_target_flow(command_tx, clamped);
             }
             app.input_mode = false;
-            app.input_buffer.clear();
+            input.clear();
+            history.reset();
         }
+        KeyCode::Left => input.left(),
+        KeyCode::Right => input.right(),
+        KeyCode::Home => input.home(),
+        KeyCode::End => input.end(),
+        KeyCode::Up => {
+            if let Some(target) = history.older(input.text()) {
+                input.set(&unit.whole(target).to_string());
+            }
+        }
+        KeyCode::Down => match history.newer() {
+            Some(Ok(target)) => input.set(&unit.whole(target).to_string()),
+            Some(Err(draft)) => input.set(&draft),
+            None => {}
+        },
+        code => {
+            history.reset();
+            match code {
+                KeyCode::Backspace => input.backspace(),
+                KeyCode::Delete => input.delete(),
+                KeyCode::Char(sign @ ('+' | '-')) => {
+                    let (low, high) = unit.input_range(app.profile.flow_min, app.profile.flow_max);
+                    let typed = input
+                        .text()
+                        .parse::<u32>()
+                        .unwrap_or_else(|_| unit.whole(app.target_flow));
+                    let nudged = if sign == '+' {
+                        typed.saturating_add(1)
+                    } else {
+                        typed.saturating_sub(1)
+                    };
+                    input.set(&nudged.clamp(low, high).to_string());
+                }
+                KeyCode::Char(ch) if ch.is_ascii_digit() => {
+                    input.insert(ch, unit.input_digits(app.profile.flow_max));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Pasted text: its digits go into the target flow being typed, and other text fields take it
+/// as if typed. With no text field open the paste is dropped rather than run as hotkeys.
+pub fn handle_paste(text: &str, app: &mut AppState, command_tx: &Sender<TransportCommand>) {
+    if app.input_mode {
+        let max = app.flow_unit.input_digits(app.profile.flow_max);
+        for ch in text.chars().filter(char::is_ascii_digit) {
+            app.target_input.insert(ch, max);
+        }
+        return;
+    }
+    for ch in text.chars().filter(|ch| !ch.is_control()) {
+        if !app.typing() {
+            break;
+        }
+        handle_key_event(KeyCode::Char(ch), app, command_tx);
+    }
+}
+
+fn handle_config_event(code: KeyCode, app: &mut AppState, command_tx: &Sender<TransportCommand>) {
+    let Some(editor) = app.config_editor.as_mut() else {
+        return;
+    };
+
+    if editor.pending.is_some() {
+        handle_retype_event(
+            code,
+            &mut editor.pending,
+            &mut editor.error,
+            app.profile,
+            command_tx,
+        );
+        return;
+    }
+
+    if editor.editing {
+        match code {
+            KeyCode::Esc => editor.cancel(),
+            KeyCode::Enter => editor.stage(app.profile),
+            KeyCode::Backspace => {
+                editor.buffer.pop();
+            }
+            KeyCode::Char(ch)
+                if (ch.is_ascii_digit() || ch == '.' || ch == '-') && editor.buffer.len() < 8 =>
+            {
+                editor.buffer.push(ch);
+            }
+            _ => {}
+        }
+        return;
+    }
+
+    match vi_arrow(code) {
+        KeyCode::Esc | KeyCode::Char('c') => app.config_editor = None,
+        KeyCode::Up => editor.select_previous(),
+        KeyCode::Down => editor.select_next(),
+        KeyCode::Enter => {
+            let register = editor.selected_register();
+            let current = app
+                .status
+                .as_ref()
+                .and_then(|status| status.register(register));
+            editor.begin_edit(current, app.profile);
+        }
+        _ => {}
+    }
+}
+
+/// Typing a guarded write's value the second time; Enter sends the write when it matches the
+/// first, Esc drops it.
+fn handle_retype_event(
+    code: KeyCode,
+    pending: &mut Option<PendingWrite>,
+    error: &mut Option<String>,
+    profile: &DeviceProfile,
+    command_tx: &Sender<TransportCommand>,
+) {
+    let Some(write) = pending.as_mut() else {
+        return;
+    };
+    match code {
+        KeyCode::Esc => *pending = None,
+        KeyCode::Enter => match write.confirm(profile) {
+            Ok((register, value)) => {
+                send(
+                    command_tx,
+                    TransportCommand::WriteRegister { register, value },
+                );
+                *pending = None;
+                *error = None;
+            }
+            Err(mismatch) => *error = Some(mismatch),
+        },
         KeyCode::Backspace => {
-            app.input_buffer.pop();
+            write.retyped.pop();
+        }
+        KeyCode::Char(ch)
+            if (ch.is_ascii_alphanumeric() || ch == '.' || ch == '-')
+                && write.retyped.len() < 8 =>
+        {
+            write.retyped.push(ch);
+        }
+        _ => {}
+    }
+}
+
+/// Keys for the focused pane: typing into a register edit or search first, then vi motions,
+/// the page keys every pane shares and the pane's own keys. Returns whether the key was used.
+fn handle_pane_event(
+    pane: Pane,
+    code: KeyCode,
+    after_g: bool,
+    app: &mut AppState,
+    command_tx: &Sender<TransportCommand>,
+) -> bool {
+    if pane == Pane::Registers && handle_register_input_event(code, app, command_tx) {
+        return true;
+    }
+    let Some(code) = pane_key(code, after_g, app) else {
+        return true;
+    };
+    if handle_page_key(pane, code, app) {
+        return true;
+    }
+    match pane {
+        Pane::Registers => handle_register_grid_event(code, app),
+        Pane::Filters => handle_filters_event(code, app),
+        Pane::Log => handle_log_event(code, app),
+    }
+}
+
+/// PageUp/PageDown scroll the pane a page; Home/End go to its first and last entry.
+fn handle_page_key(pane: Pane, code: KeyCode, app: &mut AppState) -> bool {
+    match pane {
+        Pane::Registers => {
+            let count = app
+                .status
+                .as_ref()
+                .map_or(0, |status| status.registers.len());
+            let editor = &mut app.register_editor;
+            let shown = editor.shown(app.profile, count);
+            match code {
+                KeyCode::PageUp => editor.scroll_by(-editor.page()),
+                KeyCode::PageDown => editor.scroll_by(editor.page()),
+                KeyCode::Home => editor.move_within(isize::MIN, &shown),
+                KeyCode::End => editor.move_within(isize::MAX, &shown),
+                _ => return false,
+            }
+        }
+        Pane::Filters => match code {
+            KeyCode::Home => app.filter_cursor = 0,
+            KeyCode::End => app.filter_cursor = app.profile.filters.len() - 1,
+            _ => return false,
+        },
+        Pane::Log => {
+            let view = &mut app.log_view;
+            let entries = app.event_log.iter().len();
+            match code {
+                KeyCode::PageUp => view.scroll_by(view.page(), entries),
+                KeyCode::PageDown => view.scroll_by(-view.page(), entries),
+                KeyCode::Home => view.scroll_by(isize::MAX, entries),
+                KeyCode::End => view.scroll_back = 0,
+                _ => return false,
+            }
+        }
+    }
+    true
+}
+
+/// Typing into the register being edited or the search, returning whether either was open.
+fn handle_register_input_event(
+    code: KeyCode,
+    app: &mut AppState,
+    command_tx: &Sender<TransportCommand>,
+) -> bool {
+    let editor = &mut app.register_editor;
+    if editor.pending.is_some() {
+        handle_retype_event(
+            code,
+            &mut editor.pending,
+            &mut editor.error,
+            app.profile,
+            command_tx,
+        );
+        return true;
+    }
+    if editor.editing {
+        match code {
+            KeyCode::Esc => editor.cancel(),
+            KeyCode::Enter => {
+                if let Some((register, value)) = editor.commit(app.profile) {
+                    send(
+                        command_tx,
+                        TransportCommand::WriteRegister { register, value },
+                    );
+                }
+            }
+            KeyCode::Backspace => {
+                editor.buffer.pop();
+            }
+            KeyCode::Char(ch)
+                if (ch.is_ascii_alphanumeric() || ch == '.' || ch == '-')
+                    && editor.buffer.len() < 8 =>
+            {
+                editor.buffer.push(ch);
+            }
+            _ => {}
+        }
+        return true;
+    }
+
+    if editor.searching {
+        let count = app
+            .status
+            .as_ref()
+            .map_or(0, |status| status.registers.len());
+        match code {
+            KeyCode::Esc => editor.clear_search(),
+            KeyCode::Enter => editor.searching = false,
+            KeyCode::Backspace => editor.edit_search(
+                |query| {
+                    query.pop();
+                },
+                app.profile,
+                count,
+            ),
+            KeyCode::Char(ch) if editor.query.len() < 16 => {
+                editor.edit_search(|query| query.push(ch), app.profile, count);
+            }
+            _ => {}
+        }
+        return true;
+    }
+    false
+}
+
+/// Cursor movement, search and pinning on the Registers tab, returning whether the key was used.
+fn handle_register_grid_event(code: KeyCode, app: &mut AppState) -> bool {
+    let count = app
+        .status
+        .as_ref()
+        .map_or(0, |status| status.registers.len());
+    let editor = &mut app.register_editor;
+    let shown = editor.shown(app.profile, count);
+    #[allow(clippy::cast_possible_wrap)]
+    let row = REGISTER_COLUMNS as isize;
+    match code {
+        KeyCode::Up => editor.move_within(-row, &shown),
+        KeyCode::Down => editor.move_within(row, &shown),
+        KeyCode::Left => editor.move_within(-1, &shown),
+        KeyCode::Right => editor.move_within(1, &shown),
+        KeyCode::Char('/') => editor.begin_search(),
+        KeyCode::Esc if !editor.query.is_empty() => editor.clear_search(),
+        KeyCode::Char('f') if editor.selected < count => {
+            let register = editor.selected_register();
+            app.toggle_pin(register);
+        }
+        KeyCode::Enter if !app.read_only => {
+            let current = app
+                .status
+                .as_ref()
+                .and_then(|status| status.register(editor.selected_register()));
+            editor.begin_edit(current, app.profile);
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// Vi motions in the scrollable views: `gg` as `Home`, `G` as `End` and `h`/`j`/`k`/`l` as the
+/// arrows. A first `g` is held for the next key and gives `None`.
+fn pane_key(code: KeyCode, after_g: bool, app: &mut AppState) -> Option<KeyCode> {
+    match code {
+        KeyCode::Char('g') if after_g => Some(KeyCode::Home),
+        KeyCode::Char('g') => {
+            app.pending_g = true;
+            None
+        }
+        KeyCode::Char('G') => Some(KeyCode::End),
+        code => Some(vi_arrow(code)),
+    }
+}
+
+/// `h`/`j`/`k`/`l` as the arrow keys, for lists and dialogs that take no typed letters.
+pub const fn vi_arrow(code: KeyCode) -> KeyCode {
+    match code {
+        KeyCode::Char('h') => KeyCode::Left,
+        KeyCode::Char('j') => KeyCode::Down,
+        KeyCode::Char('k') => KeyCode::Up,
+        KeyCode::Char('l') => KeyCode::Right,
+        code => code,
+    }
+}
+
+/// Selects a filter on the Filters tab and starts a typed reset of it with `R`, returning
+/// whether the key was used.
+fn handle_filters_event(code: KeyCode, app: &mut AppState) -> bool {
+    let last = app.profile.filters.len() - 1;
+    match code {
+        KeyCode::Up => app.filter_cursor = app.filter_cursor.saturating_sub(1),
+        KeyCode::Down => app.filter_cursor = (app.filter_cursor + 1).min(last),
+        KeyCode::Char('R') if !app.read_only => {
+            let filter = app.profile.filters[app.filter_cursor.min(last)];
+            app.filter_reset = Some(FilterResetDialog::typed(app.profile.filters, filter));
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// Scrolls the Log tab a line at a time, returning whether the key was used.
+fn handle_log_event(code: KeyCode, app: &mut AppState) -> bool {
+    let view = &mut app.log_view;
+    let entries = app.event_log.iter().len();
+    match code {
+        KeyCode::Up => view.scroll_by(1, entries),
+        KeyCode::Down => view.scroll_by(-1, entries),
+        _ => return false,
+    }
+    true
+}
+
+fn handle_wizard_event(code: KeyCode, app: &mut AppState, command_tx: &Sender<TransportCommand>) {
+    let Some(wizard) = app.link_wizard.as_mut() else {
+        return;
+    };
+
+    let mut close = false;
+    match (&wizard.step, code) {
+        (step, KeyCode::Char(ch))
+            if step.typed() && ch.is_ascii_digit() && wizard.buffer.len() < 6 =>
+        {
+            wizard.buffer.push(ch);
+        }
+        (step, KeyCode::Backspace) if step.typed() => {
+            wizard.buffer.pop();
         }
-        KeyCode::Char(ch) if ch.is_ascii_digit() => {
-            if app.input_buffer.len() < 3 {
-                app.input_buffer.push(ch);
+        (step, KeyCode::Enter) if step.typed() => {
+            if let Some(command) = wizard.submit() {
+                send(command_tx, command);
             }
         }
+        (WizardStep::Rollback, KeyCode::Char('y')) => {
+            send(command_tx, wizard.rollback());
+        }
+        (WizardStep::Applying, _) => {}
+        (WizardStep::Done(_) | WizardStep::Failed(_), KeyCode::Enter)
+        | (WizardStep::Rollback, KeyCode::Char('n'))
+        | (_, KeyCode::Esc) => close = true,
         _ => {}
     }
-    Ok(())
+    if close {
+        app.link_wizard = None;
+    }
 }
 
-fn send_target_flow(command_tx: &Sender<TransportCommand>, value: u16) -> eyre::Result<()> {
-    command_tx
-        .send(TransportCommand::SetTargetFlow(value))
-        .wrap_err("send target flow")
+fn handle_filter_reset_event(
+    code: KeyCode,
+    app: &mut AppState,
+    command_tx: &Sender<TransportCommand>,
+) {
+    let Some(dialog) = app.filter_reset.as_mut() else {
+        return;
+    };
+
+    let mut close = false;
+    if dialog.stage == ResetStage::Type {
+        match code {
+            KeyCode::Enter => {
+                if let Some(filter) = dialog.accept() {
+                    send(command_tx, TransportCommand::ResetFilter(filter));
+                    close = true;
+                }
+            }
+            KeyCode::Esc => close = true,
+            KeyCode::Backspace => {
+                dialog.typed.pop();
+            }
+            KeyCode::Char(ch) if dialog.typed.len() < RESET_WORD.len() => dialog.typed.push(ch),
+            _ => {}
+        }
+    } else {
+        match (dialog.stage, vi_arrow(code)) {
+            (ResetStage::Select, KeyCode::Left) => dialog.select_previous(),
+            (ResetStage::Select, KeyCode::Right) => dialog.select_next(),
+            (ResetStage::Select, KeyCode::Enter)
+            | (ResetStage::Confirm | ResetStage::ConfirmAgain, KeyCode::Char('y')) => {
+                if let Some(filter) = dialog.accept() {
+                    send(command_tx, TransportCommand::ResetFilter(filter));
+                    close = true;
+                }
+            }
+            (_, KeyCode::Esc | KeyCode::Char('n')) => close = true,
+            _ => {}
+        }
+    }
+    if close {
+        app.filter_reset = None;
+    }
+}
+
+/// Retry, change port or quit after the serial worker stopped; Esc keeps the session without
+/// a link.
+fn handle_error_screen_event(code: KeyCode, app: &mut AppState) {
+    let Some(screen) = app.error_screen.as_mut() else {
+        return;
+    };
+    if let Some(port) = screen.port.as_mut() {
+        match code {
+            KeyCode::Enter if !port.trim().is_empty() => {
+                app.restart = Some(Restart::Port(port.trim().to_string()));
+                app.error_screen = None;
+            }
+            KeyCode::Esc => screen.port = None,
+            KeyCode::Backspace => {
+                port.pop();
+            }
+            KeyCode::Char(ch) => port.push(ch),
+            _ => {}
+        }
+        return;
+    }
+    let choice = match vi_arrow(code) {
+        KeyCode::Left | KeyCode::BackTab => {
+            screen.select_previous();
+            return;
+        }
+        KeyCode::Right | KeyCode::Tab => {
+            screen.select_next();
+            return;
+        }
+        KeyCode::Enter => screen.choice(),
+        KeyCode::Char('r') => ErrorChoice::Retry,
+        KeyCode::Char('p') => ErrorChoice::ChangePort,
+        KeyCode::Char('q') => ErrorChoice::Quit,
+        KeyCode::Esc => {
+            app.error_screen = None;
+            return;
+        }
+        _ => return,
+    };
+    match choice {
+        ErrorChoice::Retry => {
+            app.restart = Some(Restart::Retry);
+            app.error_screen = None;
+        }
+        ErrorChoice::ChangePort => screen.port = Some(String::new()),
+        ErrorChoice::Quit => app.should_quit = true,
+    }
+}
+
+fn send_target_flow(command_tx: &Sender<TransportCommand>, value: u16) {
+    send(command_tx, TransportCommand::SetTargetFlow(value));
+}
+
+/// Hands `command` to the worker. A worker that stopped has no link to write to and the error
+/// screen already says so, so the command is dropped, as [`crate::fleet::Device::send`] does.
+fn send(command_tx: &Sender<TransportCommand>, command: TransportCommand) {
+    command_tx.send(command).ok();
 }
 
 #[cfg(test)]
 mod tests {
     use std::sync::mpsc;
+    use std::time::{SystemTime, UNIX_EPOCH};
 
-    use crossterm::event::KeyCode;
+    use crossterm::event::{
+        KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    };
+    use ratatui::layout::Rect;
 
-    use crate::app::AppState;
-    use crate::constants::{STATE_OFF, STATE_ON};
-    use crate::data::DeviceStatus;
-    use crate::input::handle_key_event;
+    use crate::app::{AppState, Tab};
+    use crate::config::Preset;
+    use crate::constants::{BEEPER_ON, REG_BEEPER, REG_CALIBRATION_FACTOR, STATE_OFF, STATE_ON};
+    use crate::data::{DeviceStatus, Filter};
+    use crate::dialog::{FilterResetDialog, Restart};
+    use crate::event_log::EventSource;
+    use crate::input::{handle_key_event, handle_mouse_event, handle_paste};
     use crate::interface::InterfaceMode;
+    use crate::line_editor::LineEditor;
+    use crate::register_editor::REGISTER_COLUMNS;
+    use crate::repeat::TargetBatch;
     use crate::transport::TransportCommand;
+    use crate::units::FlowUnit;
 
     #[test]
     fn read_only_mode_does_not_emit_write_commands() {
@@ -123,20 +887,56 @@ mod tests {
         let mut app = AppState::new(InterfaceMode::Remote, true);
         app.status = Some(sample_status(STATE_OFF));
 
-        handle_key_event(KeyCode::Char(' '), &mut app, &tx).expect("space key should work");
-        handle_key_event(KeyCode::Left, &mut app, &tx).expect("left key should work");
-        handle_key_event(KeyCode::Right, &mut app, &tx).expect("right key should work");
+        handle_key_event(KeyCode::Char(' '), &mut app, &tx);
+        handle_key_event(KeyCode::Left, &mut app, &tx);
+        handle_key_event(KeyCode::Right, &mut app, &tx);
 
         assert!(rx.try_recv().is_err());
     }
 
+    #[test]
+    fn r_polls_at_once_even_when_read_only() {
+        let (tx, rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, true);
+
+        handle_key_event(KeyCode::Char('r'), &mut app, &tx);
+
+        assert!(matches!(rx.try_recv(), Ok(TransportCommand::PollNow)));
+    }
+
+    #[test]
+    fn keys_after_the_worker_stopped_keep_the_session() {
+        let (tx, rx) = mpsc::channel();
+        drop(rx);
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        app.status = Some(sample_status(STATE_ON));
+
+        for key in ['r', '1', '+', 'q'] {
+            handle_key_event(KeyCode::Char(key), &mut app, &tx);
+        }
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn shift_i_reconnects_through_the_other_interface() {
+        let (tx, _rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Exttool, true);
+
+        handle_key_event(KeyCode::Char('I'), &mut app, &tx);
+        assert_eq!(app.restart, Some(Restart::Interface(InterfaceMode::Remote)));
+
+        let mut simulated = AppState::new(InterfaceMode::Simulation, false);
+        handle_key_event(KeyCode::Char('I'), &mut simulated, &tx);
+        assert_eq!(simulated.restart, None);
+    }
+
     #[test]
     fn power_toggle_sends_expected_command() {
         let (tx, rx) = mpsc::channel();
         let mut app = AppState::new(InterfaceMode::Remote, false);
         app.status = Some(sample_status(STATE_ON));
 
-        handle_key_event(KeyCode::Char(' '), &mut app, &tx).expect("space key should work");
+        handle_key_event(KeyCode::Char(' '), &mut app, &tx);
 
         assert_eq!(
             rx.recv().expect("command expected"),
@@ -144,14 +944,63 @@ mod tests {
         );
     }
 
+    #[test]
+    fn power_off_waits_for_confirmation_when_configured() {
+        let (tx, rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        app.confirm_power_off = true;
+        app.status = Some(sample_status(STATE_ON));
+
+        handle_key_event(KeyCode::Char(' '), &mut app, &tx);
+        assert!(app.power_off_prompt);
+        handle_key_event(KeyCode::Char(' '), &mut app, &tx);
+        assert!(!app.power_off_prompt);
+        assert!(rx.try_recv().is_err());
+
+        handle_key_event(KeyCode::Char(' '), &mut app, &tx);
+        handle_key_event(KeyCode::Char('y'), &mut app, &tx);
+        assert_eq!(rx.try_recv(), Ok(TransportCommand::SetPower(false)));
+
+        app.status = Some(sample_status(STATE_OFF));
+        handle_key_event(KeyCode::Char(' '), &mut app, &tx);
+        assert_eq!(rx.try_recv(), Ok(TransportCommand::SetPower(true)));
+    }
+
+    #[test]
+    fn beeper_toggle_follows_register_value() {
+        let (tx, rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        let mut status = sample_status(STATE_ON);
+        status.registers[usize::from(REG_BEEPER)] = BEEPER_ON;
+        app.status = Some(status);
+
+        handle_key_event(KeyCode::Char('b'), &mut app, &tx);
+
+        assert_eq!(
+            rx.recv().expect("command expected"),
+            TransportCommand::SetBeeper(false)
+        );
+    }
+
+    #[test]
+    fn mode_toggle_is_inert_while_mode_register_is_read_only() {
+        let (tx, rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        app.status = Some(sample_status(STATE_ON));
+
+        handle_key_event(KeyCode::Char('m'), &mut app, &tx);
+
+        assert!(rx.try_recv().is_err());
+    }
+
     #[test]
     fn typed_target_flow_is_clamped_before_send() {
         let (tx, rx) = mpsc::channel();
         let mut app = AppState::new(InterfaceMode::Remote, false);
         app.input_mode = true;
-        app.input_buffer = String::from("999");
+        app.target_input = LineEditor::new("999");
 
-        handle_key_event(KeyCode::Enter, &mut app, &tx).expect("enter key should work");
+        handle_key_event(KeyCode::Enter, &mut app, &tx);
 
         assert_eq!(
             rx.recv().expect("command expected"),
@@ -159,6 +1008,481 @@ mod tests {
         );
     }
 
+    #[test]
+    fn typed_target_flow_is_converted_from_display_unit() {
+        let (tx, rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        app.flow_unit = FlowUnit::Lpm;
+        app.input_mode = true;
+        app.target_input = LineEditor::new("1000");
+
+        handle_key_event(KeyCode::Enter, &mut app, &tx);
+
+        assert_eq!(
+            rx.recv().expect("command expected"),
+            TransportCommand::SetTargetFlow(60)
+        );
+    }
+
+    #[test]
+    fn typed_target_flow_is_edited_at_the_cursor_nudged_and_pasted() {
+        let (tx, rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        app.target_flow = 60;
+        handle_key_event(KeyCode::Char('t'), &mut app, &tx);
+        handle_key_event(KeyCode::Char('+'), &mut app, &tx);
+        assert_eq!(
+            app.target_input.text(),
+            "61",
+            "starts from the current target"
+        );
+
+        handle_key_event(KeyCode::Home, &mut app, &tx);
+        handle_key_event(KeyCode::Delete, &mut app, &tx);
+        handle_key_event(KeyCode::Char('9'), &mut app, &tx);
+        assert_eq!(app.target_input.text(), "91");
+        for _ in 0..12 {
+            handle_key_event(KeyCode::Char('+'), &mut app, &tx);
+        }
+        assert_eq!(
+            app.target_input.text(),
+            "100",
+            "nudging stops at the maximum"
+        );
+
+        app.target_input.clear();
+        handle_paste("4 5\n7", &mut app, &tx);
+        assert_eq!(app.target_input.text(), "457", "only digits, up to three");
+        app.target_input.clear();
+        handle_paste("75", &mut app, &tx);
+        handle_key_event(KeyCode::Enter, &mut app, &tx);
+        assert_eq!(
+            rx.recv().expect("command expected"),
+            TransportCommand::SetTargetFlow(75)
+        );
+
+        handle_paste("q", &mut app, &tx);
+        assert!(!app.should_quit, "a paste is not run as hotkeys");
+    }
+
+    #[test]
+    fn up_and_down_recall_earlier_targets() {
+        let (tx, rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        for typed in ["60", "85"] {
+            handle_key_event(KeyCode::Char('t'), &mut app, &tx);
+            handle_paste(typed, &mut app, &tx);
+            handle_key_event(KeyCode::Enter, &mut app, &tx);
+        }
+        while rx.try_recv().is_ok() {}
+
+        handle_key_event(KeyCode::Char('t'), &mut app, &tx);
+        handle_key_event(KeyCode::Char('4'), &mut app, &tx);
+        handle_key_event(KeyCode::Up, &mut app, &tx);
+        assert_eq!(app.target_input.text(), "85");
+        handle_key_event(KeyCode::Up, &mut app, &tx);
+        assert_eq!(app.target_input.text(), "60");
+        handle_key_event(KeyCode::Down, &mut app, &tx);
+        handle_key_event(KeyCode::Down, &mut app, &tx);
+        assert_eq!(app.target_input.text(), "4");
+
+        app.flow_unit = FlowUnit::Cfm;
+        handle_key_event(KeyCode::Up, &mut app, &tx);
+        assert_eq!(app.target_input.text(), "50", "recalled in the unit shown");
+        handle_key_event(KeyCode::Enter, &mut app, &tx);
+        assert_eq!(app.target_history.entries(), [60, 85]);
+    }
+
+    #[test]
+    fn recorded_macro_plays_on_its_key_until_esc() {
+        let (tx, rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        let record = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        handle_key_event(record, &mut app, &tx);
+        app.handle_applied_command(&TransportCommand::SetPower(true));
+        app.handle_applied_command(&TransportCommand::SetTargetFlow(80));
+        handle_key_event(record, &mut app, &tx);
+        assert!(app.macro_recorder.is_none());
+        assert_eq!(app.macros.len(), 1);
+        assert_eq!(app.macros[0].key, "F6");
+
+        handle_key_event(KeyCode::F(6), &mut app, &tx);
+        handle_key_event(KeyCode::Esc, &mut app, &tx);
+        assert!(app.macro_run.is_none());
+        handle_key_event(KeyCode::F(6), &mut app, &tx);
+        assert_eq!(
+            app.macro_commands(UNIX_EPOCH),
+            [
+                TransportCommand::SetPower(true),
+                TransportCommand::SetTargetFlow(80)
+            ]
+        );
+        assert!(app.macro_run.is_none(), "finished");
+        assert_eq!(app.target_flow, 80);
+        assert!(rx.try_recv().is_err(), "the fleet sends the steps");
+    }
+
+    #[test]
+    fn a_acknowledges_and_shift_a_snoozes_the_alarms() {
+        let (tx, _rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, true);
+        let mut status = sample_status(STATE_ON);
+        status.p_filter_limit = 100;
+        status.p_filter_total = 100;
+        app.update_status(status);
+        assert!(app.filter_banner_shown());
+
+        handle_key_event(KeyCode::Char('A'), &mut app, &tx);
+        assert!(!app.filter_banner_shown());
+        assert!(app.tick(SystemTime::now() + app.snooze));
+        assert!(app.filter_banner_shown());
+        handle_key_event(KeyCode::Char('a'), &mut app, &tx);
+        assert!(!app.filter_banner_shown());
+
+        let messages: Vec<&str> = app
+            .event_log
+            .iter()
+            .map(|entry| entry.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "alarms snoozed for 15 min",
+                "alarm snooze over",
+                "acknowledged P-Filter limit"
+            ]
+        );
+    }
+
+    #[test]
+    fn config_write_requires_the_value_typed_twice() {
+        let (tx, rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        app.status = Some(sample_status(STATE_ON));
+
+        handle_key_event(KeyCode::Char('c'), &mut app, &tx);
+        handle_key_event(KeyCode::Enter, &mut app, &tx);
+        assert_eq!(
+            app.config_editor.as_ref().map(|e| e.buffer.as_str()),
+            Some("0.00")
+        );
+        for _ in 0..4 {
+            handle_key_event(KeyCode::Backspace, &mut app, &tx);
+        }
+        for key in ['1', '.', '2'] {
+            handle_key_event(KeyCode::Char(key), &mut app, &tx);
+        }
+        handle_key_event(KeyCode::Enter, &mut app, &tx);
+        assert!(rx.try_recv().is_err());
+
+        for key in ['y', '1', '.', '3'] {
+            handle_key_event(KeyCode::Char(key), &mut app, &tx);
+        }
+        handle_key_event(KeyCode::Enter, &mut app, &tx);
+        assert!(rx.try_recv().is_err(), "a different value is not written");
+        assert!(
+            app.config_editor
+                .as_ref()
+                .is_some_and(|e| e.error.is_some())
+        );
+
+        for key in ['1', '.', '2'] {
+            handle_key_event(KeyCode::Char(key), &mut app, &tx);
+        }
+        handle_key_event(KeyCode::Enter, &mut app, &tx);
+        assert_eq!(
+            rx.recv().expect("command expected"),
+            TransportCommand::WriteRegister {
+                register: REG_CALIBRATION_FACTOR,
+                value: 120,
+            }
+        );
+    }
+
+    #[test]
+    fn filter_reset_is_sent_after_double_confirmation() {
+        let (tx, rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+
+        handle_key_event(KeyCode::Char('x'), &mut app, &tx);
+        handle_key_event(KeyCode::Right, &mut app, &tx);
+        handle_key_event(KeyCode::Enter, &mut app, &tx);
+        handle_key_event(KeyCode::Char('y'), &mut app, &tx);
+        assert!(rx.try_recv().is_err());
+        handle_key_event(KeyCode::Char('y'), &mut app, &tx);
+
+        assert_eq!(
+            rx.recv().expect("command expected"),
+            TransportCommand::ResetFilter(Filter::Medium)
+        );
+        assert!(app.filter_reset.is_none());
+    }
+
+    #[test]
+    fn shift_and_ctrl_arrows_take_bigger_steps_up_to_the_range_end() {
+        let (tx, rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        app.target_flow = 40;
+
+        let shift_right = KeyEvent::new(KeyCode::Right, KeyModifiers::SHIFT);
+        handle_key_event(shift_right, &mut app, &tx);
+        assert_eq!(rx.try_recv(), Ok(TransportCommand::SetTargetFlow(45)));
+        let ctrl_left = KeyEvent::new(KeyCode::Left, KeyModifiers::CONTROL);
+        for expected in [35, 30, 30] {
+            handle_key_event(ctrl_left, &mut app, &tx);
+            assert_eq!(app.target_flow, expected);
+        }
+        assert!(
+            rx.try_recv().is_err(),
+            "quick repeats wait for the burst to end"
+        );
+        assert_eq!(app.target_batch.pending(), Some(30));
+
+        app.target_batch = TargetBatch::default();
+        app.flow_step.medium = 20;
+        handle_key_event(shift_right, &mut app, &tx);
+        assert_eq!(rx.try_recv(), Ok(TransportCommand::SetTargetFlow(50)));
+    }
+
+    #[test]
+    fn number_keys_select_presets() {
+        let (tx, rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        app.presets = vec![
+            Preset {
+                name: String::from("soldering"),
+                flow: 70,
+            },
+            Preset {
+                name: String::from("standby"),
+                flow: 35,
+            },
+        ];
+
+        handle_key_event(KeyCode::Char('2'), &mut app, &tx);
+        assert_eq!(
+            rx.recv().expect("command expected"),
+            TransportCommand::SetTargetFlow(35)
+        );
+        handle_key_event(KeyCode::Char('3'), &mut app, &tx);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn tab_keys_cycle_and_jump_between_views() {
+        let (tx, _rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+
+        handle_key_event(KeyCode::Tab, &mut app, &tx);
+        assert_eq!(app.tab, Tab::Registers);
+        handle_key_event(KeyCode::BackTab, &mut app, &tx);
+        handle_key_event(KeyCode::BackTab, &mut app, &tx);
+        assert_eq!(app.tab, Tab::Log);
+        handle_key_event(KeyCode::F(3), &mut app, &tx);
+        assert_eq!(app.tab, Tab::Config);
+        handle_key_event(KeyCode::Char('d'), &mut app, &tx);
+        handle_key_event(KeyCode::Char('d'), &mut app, &tx);
+        assert_eq!(app.tab, Tab::Dashboard);
+    }
+
+    #[test]
+    fn vi_keys_move_the_register_cursor_and_jump_with_gg_and_g() {
+        let (tx, _rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        app.status = Some(sample_status(STATE_ON));
+        app.tab = Tab::Registers;
+        let last = app
+            .status
+            .as_ref()
+            .map_or(0, |status| status.registers.len() - 1);
+
+        handle_key_event(KeyCode::Char('j'), &mut app, &tx);
+        handle_key_event(KeyCode::Char('l'), &mut app, &tx);
+        assert_eq!(app.register_editor.selected, REGISTER_COLUMNS + 1);
+        assert_eq!(
+            app.tab,
+            Tab::Registers,
+            "l moves instead of opening the log"
+        );
+        handle_key_event(KeyCode::Char('G'), &mut app, &tx);
+        assert_eq!(app.register_editor.selected, last);
+        handle_key_event(KeyCode::Char('g'), &mut app, &tx);
+        assert_eq!(app.register_editor.selected, last);
+        handle_key_event(KeyCode::Char('g'), &mut app, &tx);
+        assert_eq!(app.register_editor.selected, 0);
+
+        handle_key_event(KeyCode::Char('k'), &mut app, &tx);
+        handle_key_event(KeyCode::Char('g'), &mut app, &tx);
+        handle_key_event(KeyCode::Char('j'), &mut app, &tx);
+        handle_key_event(KeyCode::Char('g'), &mut app, &tx);
+        assert_eq!(app.register_editor.selected, REGISTER_COLUMNS);
+    }
+
+    #[test]
+    fn page_keys_scroll_only_the_focused_pane() {
+        let (tx, _rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        for index in 0..30 {
+            app.event_log
+                .push(UNIX_EPOCH, EventSource::Monitor, format!("entry {index}"));
+        }
+        app.log_view.fit(10, 30);
+
+        handle_key_event(KeyCode::PageUp, &mut app, &tx);
+        assert_eq!(app.log_view.scroll_back, 0);
+
+        app.tab = Tab::Log;
+        handle_key_event(KeyCode::PageUp, &mut app, &tx);
+        assert_eq!(app.log_view.scroll_back, 10);
+        handle_key_event(KeyCode::Home, &mut app, &tx);
+        assert_eq!(app.log_view.scroll_back, 20);
+        handle_key_event(KeyCode::PageDown, &mut app, &tx);
+        assert_eq!(app.log_view.scroll_back, 10);
+        handle_key_event(KeyCode::End, &mut app, &tx);
+        assert_eq!(app.log_view.scroll_back, 0);
+    }
+
+    #[test]
+    fn capital_r_on_the_filters_tab_resets_after_reset_is_typed() {
+        let (tx, rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        app.tab = Tab::Filters;
+        let filter = app.profile.filters[1];
+
+        handle_key_event(KeyCode::Char('j'), &mut app, &tx);
+        handle_key_event(KeyCode::Char('R'), &mut app, &tx);
+        for ch in "RESEX".chars() {
+            handle_key_event(KeyCode::Char(ch), &mut app, &tx);
+        }
+        handle_key_event(KeyCode::Enter, &mut app, &tx);
+        assert!(rx.try_recv().is_err());
+        assert!(app.filter_reset.is_some());
+
+        handle_key_event(KeyCode::Backspace, &mut app, &tx);
+        handle_key_event(KeyCode::Char('T'), &mut app, &tx);
+        handle_key_event(KeyCode::Enter, &mut app, &tx);
+        assert_eq!(rx.try_recv(), Ok(TransportCommand::ResetFilter(filter)));
+        assert!(app.filter_reset.is_none());
+    }
+
+    #[test]
+    fn register_tab_writes_guarded_value_once_typed_twice() {
+        let (tx, rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        app.status = Some(sample_status(STATE_ON));
+        app.tab = Tab::Registers;
+        app.register_editor.selected = usize::from(REG_CALIBRATION_FACTOR) - 2;
+
+        handle_key_event(KeyCode::Down, &mut app, &tx);
+        handle_key_event(KeyCode::Enter, &mut app, &tx);
+        assert!(app.register_editor.editing);
+        for _ in 0..4 {
+            handle_key_event(KeyCode::Backspace, &mut app, &tx);
+        }
+        for ch in "0x6E".chars() {
+            handle_key_event(KeyCode::Char(ch), &mut app, &tx);
+        }
+        handle_key_event(KeyCode::Enter, &mut app, &tx);
+        assert!(
+            rx.try_recv().is_err(),
+            "guarded register waits for the retype"
+        );
+        assert!(app.typing());
+        for ch in "1.10".chars() {
+            handle_key_event(KeyCode::Char(ch), &mut app, &tx);
+        }
+        handle_key_event(KeyCode::Enter, &mut app, &tx);
+        assert_eq!(
+            rx.try_recv().expect("command expected"),
+            TransportCommand::WriteRegister {
+                register: REG_CALIBRATION_FACTOR,
+                value: 0x6E,
+            }
+        );
+    }
+
+    #[test]
+    fn register_tab_pins_and_unpins_the_selected_register() {
+        let (tx, _rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        app.status = Some(sample_status(STATE_ON));
+        app.tab = Tab::Registers;
+        app.register_editor.selected = 9;
+        handle_key_event(KeyCode::Char('f'), &mut app, &tx);
+        app.register_editor.selected = 2;
+        handle_key_event(KeyCode::Char('f'), &mut app, &tx);
+        assert_eq!(app.pinned, [2, 9]);
+        handle_key_event(KeyCode::Char('f'), &mut app, &tx);
+        assert_eq!(app.pinned, [9]);
+    }
+
+    #[test]
+    fn mouse_clicks_open_popups_and_wheel_adjusts_target() {
+        let (tx, rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        app.target_flow = 60;
+        app.hit_areas.target_flow = Rect::new(20, 3, 18, 1);
+        app.hit_areas
+            .filters
+            .push((Filter::Medium, Rect::new(0, 30, 40, 6)));
+        let mouse = |kind, column, row| MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        };
+
+        handle_mouse_event(mouse(MouseEventKind::ScrollUp, 0, 0), &mut app, &tx);
+        assert_eq!(
+            rx.recv().expect("command expected"),
+            TransportCommand::SetTargetFlow(61)
+        );
+
+        let click = MouseEventKind::Down(MouseButton::Left);
+        handle_mouse_event(mouse(click, 10, 32), &mut app, &tx);
+        assert_eq!(app.filter_detail, Some(Filter::Medium));
+        handle_key_event(KeyCode::Char('x'), &mut app, &tx);
+        assert_eq!(
+            app.filter_reset.as_ref().map(FilterResetDialog::filter),
+            Some(Filter::Medium)
+        );
+        app.filter_reset = None;
+
+        handle_mouse_event(mouse(click, 25, 3), &mut app, &tx);
+        assert!(app.input_mode);
+    }
+
+    #[test]
+    fn slider_and_chart_presses_preview_the_target_until_release() {
+        let (tx, rx) = mpsc::channel();
+        let mut app = AppState::new(InterfaceMode::Remote, false);
+        app.target_flow = 60;
+        app.hit_areas.slider = Rect::new(10, 5, 71, 1);
+        app.hit_areas.flow_chart = Rect::new(5, 8, 60, 11);
+        let mouse = |kind, column, row| MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        };
+        let left = MouseButton::Left;
+
+        handle_mouse_event(mouse(MouseEventKind::Down(left), 45, 5), &mut app, &tx);
+        assert_eq!(app.target_preview, Some(65));
+        handle_mouse_event(mouse(MouseEventKind::Drag(left), 20, 13), &mut app, &tx);
+        assert_eq!(app.target_preview, Some(50));
+        assert!(rx.try_recv().is_err(), "nothing is sent before release");
+        handle_mouse_event(mouse(MouseEventKind::Up(left), 20, 13), &mut app, &tx);
+        assert_eq!(rx.try_recv(), Ok(TransportCommand::SetTargetFlow(50)));
+        assert_eq!(app.target_flow, 50);
+
+        handle_mouse_event(mouse(MouseEventKind::Down(left), 20, 18), &mut app, &tx);
+        assert_eq!(app.target_preview, Some(30), "clamped to the model minimum");
+        handle_key_event(KeyCode::Esc, &mut app, &tx);
+        handle_mouse_event(mouse(MouseEventKind::Up(left), 20, 18), &mut app, &tx);
+        assert!(rx.try_recv().is_err());
+    }
+
     fn sample_status(state: u16) -> DeviceStatus {
         DeviceStatus {
             state,
@@ -1,6 +1,8 @@
 use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum InterfaceMode {
     #[default]
     Remote,
@@ -16,6 +18,15 @@ impl InterfaceMode {
         }
     }
 
+    /// The other serial interface of the device; the simulation has none.
+    pub const fn other(self) -> Option<Self> {
+        match self {
+            Self::Remote => Some(Self::Exttool),
+            Self::Exttool => Some(Self::Remote),
+            Self::Simulation => None,
+        }
+    }
+
     pub const fn default_address(self) -> u8 {
         match self {
             Self::Remote | Self::Simulation => 2,
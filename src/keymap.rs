@@ -0,0 +1,718 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use color_eyre::eyre;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+/// Dashboard commands the config file's `[keys]` table can move to other keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    TogglePower,
+    DecFlow,
+    IncFlow,
+    DecFlowMedium,
+    IncFlowMedium,
+    DecFlowLarge,
+    IncFlowLarge,
+    TypeTarget,
+    ToggleBeeper,
+    ToggleMode,
+    OpenEditor,
+    LinkWizard,
+    ResetFilter,
+    NextView,
+    PreviousView,
+    ToggleRegisters,
+    ToggleLog,
+    CycleUnits,
+    AcknowledgeBanner,
+    SnoozeAlarms,
+    Colorblind,
+    CycleMarker,
+    CombinedChart,
+    CycleChartWindow,
+    PauseCharts,
+    LongerPoll,
+    ShorterPoll,
+    PollNow,
+    CopyRegisters,
+    RecordMacro,
+    Compact,
+    DeviceOverview,
+    PreviousDevice,
+    NextDevice,
+    SwitchInterface,
+    HideStatus,
+    HideSlider,
+    HideFlow,
+    HideSpeed,
+    HideFilters,
+    Help,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Self; 42] = [
+        Self::TogglePower,
+        Self::DecFlow,
+        Self::IncFlow,
+        Self::DecFlowMedium,
+        Self::IncFlowMedium,
+        Self::DecFlowLarge,
+        Self::IncFlowLarge,
+        Self::TypeTarget,
+        Self::ToggleBeeper,
+        Self::ToggleMode,
+        Self::OpenEditor,
+        Self::LinkWizard,
+        Self::ResetFilter,
+        Self::NextView,
+        Self::PreviousView,
+        Self::ToggleRegisters,
+        Self::ToggleLog,
+        Self::CycleUnits,
+        Self::AcknowledgeBanner,
+        Self::SnoozeAlarms,
+        Self::Colorblind,
+        Self::CycleMarker,
+        Self::CombinedChart,
+        Self::CycleChartWindow,
+        Self::PauseCharts,
+        Self::LongerPoll,
+        Self::ShorterPoll,
+        Self::PollNow,
+        Self::CopyRegisters,
+        Self::RecordMacro,
+        Self::Compact,
+        Self::DeviceOverview,
+        Self::PreviousDevice,
+        Self::NextDevice,
+        Self::SwitchInterface,
+        Self::HideStatus,
+        Self::HideSlider,
+        Self::HideFlow,
+        Self::HideSpeed,
+        Self::HideFilters,
+        Self::Help,
+        Self::Quit,
+    ];
+
+    /// Name in the `[keys]` table.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::TogglePower => "toggle_power",
+            Self::DecFlow => "dec_flow",
+            Self::IncFlow => "inc_flow",
+            Self::DecFlowMedium => "dec_flow_medium",
+            Self::IncFlowMedium => "inc_flow_medium",
+            Self::DecFlowLarge => "dec_flow_large",
+            Self::IncFlowLarge => "inc_flow_large",
+            Self::TypeTarget => "type_target",
+            Self::ToggleBeeper => "toggle_beeper",
+            Self::ToggleMode => "toggle_mode",
+            Self::OpenEditor => "open_editor",
+            Self::LinkWizard => "link_wizard",
+            Self::ResetFilter => "reset_filter",
+            Self::NextView => "next_view",
+            Self::PreviousView => "previous_view",
+            Self::ToggleRegisters => "toggle_registers",
+            Self::ToggleLog => "toggle_log",
+            Self::CycleUnits => "cycle_units",
+            Self::AcknowledgeBanner => "acknowledge_banner",
+            Self::SnoozeAlarms => "snooze_alarms",
+            Self::Colorblind => "colorblind",
+            Self::CycleMarker => "cycle_marker",
+            Self::CombinedChart => "combined_chart",
+            Self::CycleChartWindow => "cycle_chart_window",
+            Self::PauseCharts => "pause_charts",
+            Self::LongerPoll => "longer_poll",
+            Self::ShorterPoll => "shorter_poll",
+            Self::PollNow => "poll_now",
+            Self::CopyRegisters => "copy_registers",
+            Self::RecordMacro => "record_macro",
+            Self::Compact => "compact",
+            Self::DeviceOverview => "device_overview",
+            Self::PreviousDevice => "previous_device",
+            Self::NextDevice => "next_device",
+            Self::SwitchInterface => "switch_interface",
+            Self::HideStatus => "hide_status",
+            Self::HideSlider => "hide_slider",
+            Self::HideFlow => "hide_flow",
+            Self::HideSpeed => "hide_speed",
+            Self::HideFilters => "hide_filters",
+            Self::Help => "help",
+            Self::Quit => "quit",
+        }
+    }
+
+    /// Keys bound when the config file leaves the action alone.
+    const fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Self::TogglePower => &["space"],
+            Self::DecFlow => &["left"],
+            Self::IncFlow => &["right"],
+            Self::DecFlowMedium => &["shift+left"],
+            Self::IncFlowMedium => &["shift+right"],
+            Self::DecFlowLarge => &["ctrl+left"],
+            Self::IncFlowLarge => &["ctrl+right"],
+            Self::TypeTarget => &["t"],
+            Self::ToggleBeeper => &["b"],
+            Self::ToggleMode => &["m"],
+            Self::OpenEditor => &["c"],
+            Self::LinkWizard => &["w"],
+            Self::ResetFilter => &["x"],
+            Self::NextView => &["tab"],
+            Self::PreviousView => &["shift+tab"],
+            Self::ToggleRegisters => &["d"],
+            Self::ToggleLog => &["l"],
+            Self::CycleUnits => &["u"],
+            Self::AcknowledgeBanner => &["a"],
+            Self::SnoozeAlarms => &["A"],
+            Self::Colorblind => &["v"],
+            Self::CycleMarker => &["M"],
+            Self::CombinedChart => &["C"],
+            Self::CycleChartWindow => &["W"],
+            Self::PauseCharts => &["p"],
+            Self::LongerPoll => &["+", "="],
+            Self::ShorterPoll => &["-"],
+            Self::PollNow => &["r"],
+            Self::CopyRegisters => &["y"],
+            Self::RecordMacro => &["ctrl+r"],
+            Self::Compact => &["z"],
+            Self::DeviceOverview => &["o"],
+            Self::PreviousDevice => &["["],
+            Self::NextDevice => &["]"],
+            Self::SwitchInterface => &["I"],
+            Self::HideStatus => &["S"],
+            Self::HideSlider => &["T"],
+            Self::HideFlow => &["F"],
+            Self::HideSpeed => &["N"],
+            Self::HideFilters => &["G"],
+            Self::Help => &["?"],
+            Self::Quit => &["q"],
+        }
+    }
+}
+
+/// A key with the Ctrl, Alt and Shift modifiers held down with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// Shift is already part of a typed character and of `BackTab`, so only other keys keep it.
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers =
+            modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        if matches!(code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self { code, modifiers }
+    }
+
+    /// Parses a key such as `q`, `space`, `F2`, `shift+left` or `ctrl+p`.
+    pub fn parse(text: &str) -> eyre::Result<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text;
+        while let Some((prefix, tail)) = rest.split_once('+')
+            && !tail.is_empty()
+        {
+            modifiers |= match prefix.to_ascii_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(eyre::eyre!("unknown modifier in key `{text}`")),
+            };
+            rest = tail;
+        }
+        let mut chars = rest.chars();
+        let code = if let (Some(character), None) = (chars.next(), chars.next()) {
+            KeyCode::Char(character)
+        } else {
+            named_key(rest).ok_or_else(|| eyre::eyre!("unknown key `{text}`"))?
+        };
+        let code = match code {
+            KeyCode::Char(character) if modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(character.to_ascii_uppercase())
+            }
+            KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            code => code,
+        };
+        Ok(Self::new(code, modifiers))
+    }
+
+    /// Number keys pick presets and function keys views; neither can be rebound.
+    pub fn reserved(self) -> bool {
+        self.modifiers.is_empty()
+            && matches!(self.code, KeyCode::Char('1'..='9') | KeyCode::F(1..=5))
+    }
+}
+
+fn named_key(name: &str) -> Option<KeyCode> {
+    let code = match name.to_ascii_lowercase().as_str() {
+        "space" => KeyCode::Char(' '),
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        other => KeyCode::F(
+            other
+                .strip_prefix('f')?
+                .parse()
+                .ok()
+                .filter(|n| (1..=12).contains(n))?,
+        ),
+    };
+    Some(code)
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            f.write_str("Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(character) => write!(f, "{character}"),
+            KeyCode::Left => f.write_str("←"),
+            KeyCode::Right => f.write_str("→"),
+            KeyCode::Up => f.write_str("↑"),
+            KeyCode::Down => f.write_str("↓"),
+            KeyCode::BackTab => f.write_str("Shift+Tab"),
+            KeyCode::PageUp => f.write_str("PgUp"),
+            KeyCode::PageDown => f.write_str("PgDn"),
+            code => write!(f, "{code}"),
+        }
+    }
+}
+
+/// One key or a list of keys for an action in the `[keys]` table.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Which key runs which action: the defaults, with the actions the config file moved.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "BTreeMap<Action, Keys>")]
+pub struct Keymap {
+    bindings: Vec<(KeyChord, Action)>,
+    /// Actions bound to other keys than their defaults.
+    moved: Vec<Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::try_from(BTreeMap::new()).expect("default keys should not conflict")
+    }
+}
+
+impl TryFrom<BTreeMap<Action, Keys>> for Keymap {
+    type Error = eyre::Report;
+
+    /// Binds every action to its configured keys or its defaults, refusing a key bound twice.
+    fn try_from(overrides: BTreeMap<Action, Keys>) -> eyre::Result<Self> {
+        let mut bindings: Vec<(KeyChord, Action)> = Vec::new();
+        for action in Action::ALL {
+            let keys: Vec<&str> = match overrides.get(&action) {
+                Some(Keys::One(key)) => vec![key],
+                Some(Keys::Many(keys)) => keys.iter().map(String::as_str).collect(),
+                None => action.default_keys().to_vec(),
+            };
+            for key in keys {
+                let chord = KeyChord::parse(key)?;
+                if chord.reserved() {
+                    return Err(eyre::eyre!(
+                        "`{key}` for {} is reserved for presets and views",
+                        action.name()
+                    ));
+                }
+                if let Some((_, other)) = bindings.iter().find(|(bound, _)| *bound == chord) {
+                    return Err(eyre::eyre!(
+                        "`{key}` is bound to both {} and {}",
+                        other.name(),
+                        action.name()
+                    ));
+                }
+                bindings.push((chord, action));
+            }
+        }
+        Ok(Self {
+            bindings,
+            moved: overrides.into_keys().collect(),
+        })
+    }
+}
+
+impl Keymap {
+    pub fn action(&self, key: KeyEvent) -> Option<Action> {
+        self.bound(KeyChord::new(key.code, key.modifiers))
+    }
+
+    pub fn bound(&self, chord: KeyChord) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == chord)
+            .map(|(_, action)| *action)
+    }
+
+    /// The binding's own keys, or the configured ones once the config file moved its actions;
+    /// `-` stands for an action left without a key.
+    pub fn label(&self, binding: &Binding) -> Cow<'static, str> {
+        if !binding
+            .runs
+            .iter()
+            .any(|action| self.moved.contains(action))
+        {
+            return Cow::Borrowed(binding.keys);
+        }
+        let labels: Vec<String> = binding
+            .runs
+            .iter()
+            .map(|action| {
+                let keys: Vec<String> = self
+                    .bindings
+                    .iter()
+                    .filter(|(_, bound)| bound == action)
+                    .map(|(chord, _)| chord.to_string())
+                    .collect();
+                if keys.is_empty() {
+                    String::from("-")
+                } else {
+                    keys.join(" ")
+                }
+            })
+            .collect();
+        Cow::Owned(labels.join("/"))
+    }
+}
+
+/// Where a key binding applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Global,
+    Registers,
+    Filters,
+    Log,
+    Popup,
+}
+
+impl Scope {
+    pub const ALL: [Self; 5] = [
+        Self::Global,
+        Self::Registers,
+        Self::Filters,
+        Self::Log,
+        Self::Popup,
+    ];
+
+    pub const fn title(self) -> &'static str {
+        match self {
+            Self::Global => "Everywhere",
+            Self::Registers => "Registers view",
+            Self::Filters => "Filters view",
+            Self::Log => "Log view",
+            Self::Popup => "Dialogs",
+        }
+    }
+}
+
+/// A key, or group of keys, and what it does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Binding {
+    pub keys: &'static str,
+    pub action: &'static str,
+    pub scope: Scope,
+    /// Sends a write to the device, so it is inactive with `--read-only`.
+    pub writes: bool,
+    /// Short label on the Controls bar; bindings without one appear only in the overlay.
+    pub brief: Option<&'static str>,
+    /// Actions behind the keys, whose configured keys replace `keys` in the help.
+    pub runs: &'static [Action],
+}
+
+impl Binding {
+    const fn on_bar(self, brief: &'static str) -> Self {
+        Self {
+            brief: Some(brief),
+            ..self
+        }
+    }
+
+    const fn runs(self, runs: &'static [Action]) -> Self {
+        Self { runs, ..self }
+    }
+}
+
+const fn binding(keys: &'static str, action: &'static str, scope: Scope) -> Binding {
+    Binding {
+        keys,
+        action,
+        scope,
+        writes: false,
+        brief: None,
+        runs: &[],
+    }
+}
+
+const fn write(keys: &'static str, action: &'static str, scope: Scope) -> Binding {
+    Binding {
+        writes: true,
+        ..binding(keys, action, scope)
+    }
+}
+
+/// Every key `input::handle_key_event` reacts to, in help overlay order.
+pub const BINDINGS: &[Binding] = &[
+    write("Space", "toggle power", Scope::Global)
+        .runs(&[Action::TogglePower])
+        .on_bar("toggle power"),
+    write("←/→", "adjust target flow", Scope::Global)
+        .runs(&[Action::DecFlow, Action::IncFlow])
+        .on_bar("adjust target flow"),
+    write(
+        "Shift/Ctrl+←/→",
+        "adjust target flow in bigger steps",
+        Scope::Global,
+    )
+    .runs(&[
+        Action::DecFlowMedium,
+        Action::IncFlowMedium,
+        Action::DecFlowLarge,
+        Action::IncFlowLarge,
+    ]),
+    write("t", "type target flow", Scope::Global)
+        .runs(&[Action::TypeTarget])
+        .on_bar("type target"),
+    write("1-9", "apply preset", Scope::Global),
+    write("b", "toggle beeper", Scope::Global)
+        .runs(&[Action::ToggleBeeper])
+        .on_bar("beeper"),
+    write("m", "auto/manual mode", Scope::Global)
+        .runs(&[Action::ToggleMode])
+        .on_bar("auto/manual"),
+    write("c", "configuration", Scope::Global)
+        .runs(&[Action::OpenEditor])
+        .on_bar("config"),
+    write("w", "link setup wizard", Scope::Global)
+        .runs(&[Action::LinkWizard])
+        .on_bar("link setup"),
+    write("x", "reset filter counter", Scope::Global)
+        .runs(&[Action::ResetFilter])
+        .on_bar("reset filter"),
+    binding("Tab/Shift+Tab", "next/previous view", Scope::Global)
+        .runs(&[Action::NextView, Action::PreviousView]),
+    binding("F1-F5", "jump to view", Scope::Global).on_bar("views"),
+    binding("d", "toggle registers view", Scope::Global)
+        .runs(&[Action::ToggleRegisters])
+        .on_bar("registers"),
+    binding("l", "toggle log view", Scope::Global)
+        .runs(&[Action::ToggleLog])
+        .on_bar("log"),
+    binding("u", "cycle flow unit", Scope::Global)
+        .runs(&[Action::CycleUnits])
+        .on_bar("units"),
+    binding("a", "acknowledge alarms", Scope::Global).runs(&[Action::AcknowledgeBanner]),
+    binding("A", "snooze alarms", Scope::Global).runs(&[Action::SnoozeAlarms]),
+    binding("v", "colorblind palette", Scope::Global)
+        .runs(&[Action::Colorblind])
+        .on_bar("colorblind"),
+    binding("M", "cycle chart marker", Scope::Global).runs(&[Action::CycleMarker]),
+    binding("C", "combined flow and RPM chart", Scope::Global).runs(&[Action::CombinedChart]),
+    binding("W", "chart window 1 min/10 min/1 h/all", Scope::Global)
+        .runs(&[Action::CycleChartWindow]),
+    binding("p", "pause charts", Scope::Global)
+        .runs(&[Action::PauseCharts])
+        .on_bar("pause charts"),
+    binding("+/-", "longer/shorter poll interval", Scope::Global)
+        .runs(&[Action::LongerPoll, Action::ShorterPoll]),
+    binding("r", "poll now", Scope::Global).runs(&[Action::PollNow]),
+    binding("y", "copy registers", Scope::Global).runs(&[Action::CopyRegisters]),
+    write("Ctrl+r", "record a macro", Scope::Global).runs(&[Action::RecordMacro]),
+    write("F6-F12", "play a macro", Scope::Global),
+    binding("z", "compact layout", Scope::Global)
+        .runs(&[Action::Compact])
+        .on_bar("compact"),
+    binding("o", "device overview", Scope::Global).runs(&[Action::DeviceOverview]),
+    binding("[/]", "previous/next device", Scope::Global)
+        .runs(&[Action::PreviousDevice, Action::NextDevice]),
+    binding("I", "switch interface remote/exttool", Scope::Global).runs(&[Action::SwitchInterface]),
+    binding(
+        "S/T/F/N/G",
+        "hide status/slider/flow/speed/filters",
+        Scope::Global,
+    )
+    .runs(&[
+        Action::HideStatus,
+        Action::HideSlider,
+        Action::HideFlow,
+        Action::HideSpeed,
+        Action::HideFilters,
+    ]),
+    binding("?", "this help", Scope::Global)
+        .runs(&[Action::Help])
+        .on_bar("all keys"),
+    binding("q", "quit", Scope::Global)
+        .runs(&[Action::Quit])
+        .on_bar("quit"),
+    binding("Arrows h/j/k/l", "move cursor", Scope::Registers),
+    binding("Home/End gg/G", "first/last register", Scope::Registers),
+    write("Enter", "edit register", Scope::Registers),
+    binding("f", "pin to dashboard", Scope::Registers),
+    binding("/", "search by name or address", Scope::Registers),
+    binding("Esc", "clear search", Scope::Registers),
+    binding("PgUp/PgDn", "scroll a page", Scope::Registers),
+    binding("↑/↓ j/k", "select filter", Scope::Filters),
+    write("R", "reset with typed confirmation", Scope::Filters),
+    binding("↑/↓ j/k", "scroll", Scope::Log),
+    binding("PgUp/PgDn", "scroll a page", Scope::Log),
+    binding("Home/End gg/G", "oldest/newest", Scope::Log),
+    binding("h/j/k/l", "move like the arrows", Scope::Popup),
+    binding("Enter", "confirm", Scope::Popup),
+    binding("Esc", "cancel", Scope::Popup),
+];
+
+/// Bindings summarised on the Controls bar, leaving out writes in read-only mode.
+pub fn bar(read_only: bool) -> impl Iterator<Item = (&'static Binding, &'static str)> {
+    BINDINGS
+        .iter()
+        .filter(move |binding| !(read_only && binding.writes))
+        .filter_map(|binding| binding.brief.map(|brief| (binding, brief)))
+}
+
+pub fn in_scope(scope: Scope) -> impl Iterator<Item = &'static Binding> {
+    BINDINGS
+        .iter()
+        .filter(move |binding| binding.scope == scope)
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::{Action, BINDINGS, KeyChord, Keymap, Scope, bar, in_scope};
+
+    #[test]
+    fn every_binding_belongs_to_a_listed_scope() {
+        let listed: usize = Scope::ALL
+            .iter()
+            .map(|scope| in_scope(*scope).count())
+            .sum();
+        assert_eq!(listed, BINDINGS.len());
+    }
+
+    #[test]
+    fn read_only_bar_leaves_out_writes() {
+        assert!(bar(false).any(|(binding, _)| binding.keys == "Space"));
+        assert!(!bar(true).any(|(binding, _)| binding.keys == "Space"));
+        assert!(bar(true).any(|(binding, _)| binding.keys == "q"));
+    }
+
+    #[test]
+    fn keys_parse_with_modifiers() {
+        let parse = |text| KeyChord::parse(text).expect("key should parse");
+        assert_eq!(
+            parse("ctrl+p"),
+            KeyChord::new(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(parse("shift+x"), parse("X"));
+        assert_eq!(
+            parse("Shift+Tab"),
+            KeyChord::new(KeyCode::BackTab, KeyModifiers::SHIFT)
+        );
+        assert_eq!(
+            parse("+"),
+            KeyChord::new(KeyCode::Char('+'), KeyModifiers::NONE)
+        );
+        assert_eq!(parse("ctrl++").to_string(), "Ctrl++");
+        assert_eq!(parse("shift+left").to_string(), "Shift+←");
+        assert_eq!(
+            parse("f2"),
+            KeyChord::new(KeyCode::F(2), KeyModifiers::NONE)
+        );
+        assert!(KeyChord::parse("hyper+q").is_err());
+        assert!(KeyChord::parse("f13").is_err());
+    }
+
+    #[test]
+    fn moved_actions_free_their_default_keys_and_relabel_the_help() {
+        let keymap: Keymap = toml::from_str(
+            r#"
+            quit = "ctrl+q"
+            toggle_power = ["p", "enter"]
+            pause_charts = []
+            "#,
+        )
+        .expect("keymap should parse");
+        let ctrl_q = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action(ctrl_q), Some(Action::Quit));
+        assert_eq!(keymap.action(KeyCode::Char('q').into()), None);
+        assert_eq!(
+            keymap.action(KeyCode::Char('p').into()),
+            Some(Action::TogglePower)
+        );
+        assert_eq!(keymap.action(KeyCode::Char(' ').into()), None);
+
+        let label = |keys| {
+            let binding = BINDINGS.iter().find(|binding| binding.keys == keys);
+            keymap.label(binding.expect("binding exists")).into_owned()
+        };
+        assert_eq!(label("Space"), "p Enter");
+        assert_eq!(label("q"), "Ctrl+q");
+        assert_eq!(label("p"), "-");
+        assert_eq!(label("←/→"), "←/→");
+    }
+
+    #[test]
+    fn keys_bound_twice_or_reserved_are_refused() {
+        let conflict = toml::from_str::<Keymap>(r#"reset_filter = "q""#)
+            .expect_err("q is still bound to quit");
+        assert!(
+            conflict
+                .to_string()
+                .contains("bound to both reset_filter and quit")
+        );
+        assert!(toml::from_str::<Keymap>(r#"help = "3""#).is_err());
+        assert!(
+            toml::from_str::<Keymap>(
+                r#"reset_filter = "q"
+quit = "ctrl+q""#
+            )
+            .is_ok()
+        );
+        assert!(toml::from_str::<Keymap>(r#"launch = "l""#).is_err());
+    }
+
+    #[test]
+    fn action_names_match_the_config_keys() {
+        for action in Action::ALL {
+            let parsed: Action = toml::Value::String(action.name().to_string())
+                .try_into()
+                .expect("name should deserialize");
+            assert_eq!(parsed, action);
+        }
+    }
+}
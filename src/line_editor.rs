@@ -0,0 +1,116 @@
+/// A single line of typed text with a movable cursor.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LineEditor {
+    text: String,
+    /// Position in characters, from 0 before the first to the length after the last.
+    cursor: usize,
+}
+
+impl LineEditor {
+    /// Starts with `text` and the cursor after it.
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            cursor: text.chars().count(),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Replaces the text, leaving the cursor after it.
+    pub fn set(&mut self, text: &str) {
+        *self = Self::new(text);
+    }
+
+    /// Inserts `ch` at the cursor unless the line already holds `max` characters.
+    pub fn insert(&mut self, ch: char, max: usize) -> bool {
+        if self.len() >= max {
+            return false;
+        }
+        let at = self.byte_offset(self.cursor);
+        self.text.insert(at, ch);
+        self.cursor += 1;
+        true
+    }
+
+    /// Removes the character before the cursor.
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.text.remove(self.byte_offset(self.cursor));
+        }
+    }
+
+    /// Removes the character under the cursor.
+    pub fn delete(&mut self) {
+        if self.cursor < self.len() {
+            self.text.remove(self.byte_offset(self.cursor));
+        }
+    }
+
+    pub const fn left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.len());
+    }
+
+    pub const fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.len();
+    }
+
+    /// The text split around the cursor: before it, the character under it and after it.
+    pub fn split(&self) -> (&str, Option<char>, &str) {
+        let at = self.byte_offset(self.cursor);
+        let (before, rest) = self.text.split_at(at);
+        let mut chars = rest.chars();
+        let under = chars.next();
+        (before, under, chars.as_str())
+    }
+
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    fn byte_offset(&self, cursor: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.text.len(), |(offset, _)| offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineEditor;
+
+    #[test]
+    fn edits_happen_at_the_cursor() {
+        let mut line = LineEditor::new("75");
+        line.home();
+        assert!(line.insert('1', 3));
+        assert!(!line.insert('2', 3), "line is full");
+        assert_eq!(line.text(), "175");
+        line.right();
+        line.delete();
+        assert_eq!(line.text(), "17");
+        line.left();
+        line.backspace();
+        assert_eq!(line.split(), ("", Some('7'), ""));
+        line.backspace();
+        line.end();
+        line.right();
+        assert_eq!(line.split(), ("7", None, ""));
+    }
+}
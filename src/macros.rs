@@ -0,0 +1,268 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use color_eyre::eyre::{self, WrapErr};
+use crossterm::event::{KeyCode, KeyModifiers};
+use serde::Deserialize;
+
+use crate::keymap::{KeyChord, Keymap};
+use crate::profile::DeviceProfile;
+use crate::transport::TransportCommand;
+
+/// One step of a macro, written as an inline table such as `{ flow = 80 }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MacroStep {
+    /// Switches the fan on or off.
+    Power(bool),
+    /// Sets the target flow in m3/h.
+    Flow(u16),
+    Beeper(bool),
+    /// Waits this many seconds before the next step.
+    Wait(u64),
+}
+
+impl MacroStep {
+    /// The step an accepted command is recorded as; other writes are not recorded.
+    pub const fn from_command(command: &TransportCommand) -> Option<Self> {
+        match *command {
+            TransportCommand::SetPower(on) => Some(Self::Power(on)),
+            TransportCommand::SetTargetFlow(flow) => Some(Self::Flow(flow)),
+            TransportCommand::SetBeeper(on) => Some(Self::Beeper(on)),
+            _ => None,
+        }
+    }
+
+    /// The command for the step; `None` for a wait.
+    pub fn command(self, profile: &DeviceProfile) -> Option<TransportCommand> {
+        match self {
+            Self::Power(on) => Some(TransportCommand::SetPower(on)),
+            Self::Flow(flow) => Some(TransportCommand::SetTargetFlow(profile.clamp_flow(flow))),
+            Self::Beeper(on) => Some(TransportCommand::SetBeeper(on)),
+            Self::Wait(_) => None,
+        }
+    }
+
+    fn to_toml(self) -> String {
+        match self {
+            Self::Power(on) => format!("{{ power = {on} }}"),
+            Self::Flow(flow) => format!("{{ flow = {flow} }}"),
+            Self::Beeper(on) => format!("{{ beeper = {on} }}"),
+            Self::Wait(seconds) => format!("{{ wait = {seconds} }}"),
+        }
+    }
+}
+
+/// A named sequence of steps replayed with one key, from a `[[macro]]` table.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Macro {
+    pub name: String,
+    pub key: String,
+    pub steps: Vec<MacroStep>,
+}
+
+impl Macro {
+    pub fn chord(&self) -> eyre::Result<KeyChord> {
+        KeyChord::parse(&self.key)
+    }
+
+    /// The `[[macro]]` table for the config file.
+    pub fn to_toml(&self) -> String {
+        let steps: Vec<String> = self.steps.iter().map(|step| step.to_toml()).collect();
+        format!(
+            "[[macro]]\nname = {}\nkey = {}\nsteps = [{}]\n",
+            toml::Value::String(self.name.clone()),
+            toml::Value::String(self.key.clone()),
+            steps.join(", ")
+        )
+    }
+
+    /// Adds the macro to the end of the config file, leaving the rest of it untouched.
+    pub fn append_to(&self, path: &Path) -> eyre::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .wrap_err_with(|| format!("open config {}", path.display()))?;
+        write!(file, "\n{}", self.to_toml())
+            .wrap_err_with(|| format!("write config {}", path.display()))
+    }
+}
+
+/// The first of `F6`-`F12` that neither an action nor a macro uses.
+pub fn free_key(keymap: &Keymap, macros: &[Macro]) -> Option<String> {
+    (6..=12)
+        .map(|number| KeyChord::new(KeyCode::F(number), KeyModifiers::NONE))
+        .find(|chord| {
+            keymap.bound(*chord).is_none()
+                && !macros
+                    .iter()
+                    .any(|other| other.chord().is_ok_and(|bound| bound == *chord))
+        })
+        .map(|chord| chord.to_string())
+}
+
+/// A macro being played; a wait holds the next step back until it is over.
+#[derive(Debug)]
+pub struct MacroRun {
+    pub name: String,
+    steps: Vec<MacroStep>,
+    next: usize,
+    resume_at: Option<SystemTime>,
+}
+
+impl MacroRun {
+    pub fn new(played: &Macro) -> Self {
+        Self {
+            name: played.name.clone(),
+            steps: played.steps.clone(),
+            next: 0,
+            resume_at: None,
+        }
+    }
+
+    /// The next step to run at `now`, passing over waits that are over; `None` while waiting
+    /// or once every step ran.
+    pub fn due(&mut self, now: SystemTime) -> Option<MacroStep> {
+        loop {
+            if let Some(resume_at) = self.resume_at {
+                if now < resume_at {
+                    return None;
+                }
+                self.resume_at = None;
+            }
+            let step = *self.steps.get(self.next)?;
+            self.next += 1;
+            match step {
+                MacroStep::Wait(seconds) => {
+                    self.resume_at = Some(now + Duration::from_secs(seconds));
+                }
+                step => return Some(step),
+            }
+        }
+    }
+
+    pub const fn finished(&self) -> bool {
+        self.next >= self.steps.len() && self.resume_at.is_none()
+    }
+
+    /// Steps started so far and the step count.
+    pub const fn progress(&self) -> (usize, usize) {
+        (self.next, self.steps.len())
+    }
+}
+
+/// The accepted commands since recording started, with the pauses between them.
+#[derive(Debug)]
+pub struct MacroRecorder {
+    last: SystemTime,
+    steps: Vec<MacroStep>,
+}
+
+impl MacroRecorder {
+    pub const fn new(now: SystemTime) -> Self {
+        Self {
+            last: now,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Records the command as a step, after a wait for the whole seconds since the previous
+    /// step. The time before the first step is left out.
+    pub fn record(&mut self, command: &TransportCommand, now: SystemTime) {
+        let Some(step) = MacroStep::from_command(command) else {
+            return;
+        };
+        let pause = now.duration_since(self.last).unwrap_or_default();
+        let seconds = (pause + Duration::from_millis(500)).as_secs();
+        if seconds > 0 && !self.steps.is_empty() {
+            self.steps.push(MacroStep::Wait(seconds));
+        }
+        self.last = now;
+        self.steps.push(step);
+    }
+
+    pub const fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// The recording as a macro; `None` when nothing was recorded.
+    pub fn finish(self, name: String, key: String) -> Option<Macro> {
+        if self.steps.is_empty() {
+            return None;
+        }
+        Some(Macro {
+            name,
+            key,
+            steps: self.steps,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{Macro, MacroRecorder, MacroRun, MacroStep, free_key};
+    use crate::config::Config;
+    use crate::keymap::Keymap;
+    use crate::transport::TransportCommand;
+
+    #[test]
+    fn playback_waits_between_steps() {
+        let warm_up = Macro {
+            name: String::from("warm-up"),
+            key: String::from("f6"),
+            steps: vec![
+                MacroStep::Power(true),
+                MacroStep::Flow(80),
+                MacroStep::Wait(30),
+                MacroStep::Flow(60),
+            ],
+        };
+        let at = |seconds| UNIX_EPOCH + Duration::from_secs(seconds);
+        let mut run = MacroRun::new(&warm_up);
+        assert_eq!(run.due(at(0)), Some(MacroStep::Power(true)));
+        assert_eq!(run.due(at(0)), Some(MacroStep::Flow(80)));
+        assert_eq!(run.due(at(0)), None);
+        assert_eq!(run.due(at(29)), None);
+        assert!(!run.finished());
+        assert_eq!(run.due(at(30)), Some(MacroStep::Flow(60)));
+        assert_eq!(run.due(at(30)), None);
+        assert!(run.finished());
+    }
+
+    #[test]
+    fn recording_keeps_the_pauses_and_survives_the_config_file() {
+        let at = |millis| UNIX_EPOCH + Duration::from_millis(millis);
+        let mut recorder = MacroRecorder::new(at(0));
+        recorder.record(&TransportCommand::SetPower(true), at(5_000));
+        recorder.record(&TransportCommand::SetTargetFlow(80), at(5_300));
+        recorder.record(
+            &TransportCommand::SetPollInterval(Duration::from_secs(1)),
+            at(6_000),
+        );
+        recorder.record(&TransportCommand::SetTargetFlow(60), at(35_600));
+        let bench_macro = recorder
+            .finish(String::from("bench \"daily\""), String::from("f6"))
+            .expect("steps were recorded");
+        assert_eq!(
+            bench_macro.steps,
+            [
+                MacroStep::Power(true),
+                MacroStep::Flow(80),
+                MacroStep::Wait(30),
+                MacroStep::Flow(60),
+            ]
+        );
+        let config = Config::parse(&bench_macro.to_toml()).expect("appended table should parse");
+        assert_eq!(config.macros, [bench_macro]);
+        assert_eq!(
+            free_key(&Keymap::default(), &config.macros).as_deref(),
+            Some("F7")
+        );
+    }
+}
@@ -1,37 +1,93 @@
+mod alarm;
 mod app;
 mod backend;
+mod clipboard;
+mod config;
+mod config_editor;
 mod constants;
 mod data;
-mod interface;
+mod dialog;
+mod event_log;
+mod export;
+mod filter_log;
+mod fleet;
+mod headless;
+mod history;
+mod i18n;
 mod input;
+mod interface;
+mod keymap;
+mod line_editor;
+mod macros;
+mod prefs;
+mod profile;
+mod ramp;
+mod register_editor;
+mod reminder;
+mod repeat;
 mod rtu;
+mod scan;
+mod setup;
+mod target_history;
+mod theme;
+mod toast;
+mod totalizer;
 mod transport;
 mod ui;
+mod units;
+mod wizard;
 
 #[cfg(debug_assertions)]
 mod sim;
 
 use std::io;
-use std::sync::mpsc;
-use std::time::Duration;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant, SystemTime};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use color_eyre::eyre::{self, WrapErr};
-use crossterm::event::{self, Event};
+use crossterm::event::{
+    self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+    Event, KeyCode, KeyEventKind,
+};
 use crossterm::execute;
 use crossterm::terminal::{
-    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
 };
-use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::Rect;
 
 use app::AppState;
+use config::{Config, ConfigWatcher, DeviceConfig};
+use data::Filter;
+use event_log::{EventSource, Severity};
+use export::{CsvLog, DeviceInfo, HttpApi, InfluxExporter, MqttExporter};
+use filter_log::FilterLog;
+use fleet::{Device, Fleet};
+use history::{Ema, HistoryDepth};
+use i18n::{Lang, Locale};
 use interface::InterfaceMode;
-use input::handle_key_event;
-use transport::{spawn_worker, TransportConfig, TransportEvent};
-use ui::render_ui;
+use prefs::Prefs;
+use profile::{DeviceModel, DeviceProfile};
+use scan::{MAX_ADDRESS, OutputFormat};
+use setup::{SetupAction, SetupScreen};
+use target_history::TargetHistory;
+use theme::{ChartMarker, Theme, ThemeName};
+use totalizer::Totalizer;
+use transport::{TransportCommand, TransportConfig, validate_command};
+use ui::{banner_phase, render_overview, render_setup, render_ui, update_layout};
+use units::FlowUnit;
+
+/// Longest wait for terminal input before transport events are taken in.
+const TICK_RATE: Duration = Duration::from_millis(100);
+/// Longest time between frames while nothing changes.
+const KEEPALIVE_FRAME: Duration = Duration::from_secs(1);
 
 #[derive(Parser, Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)]
 #[command(author, version, about = "Quick 6101A2 TUI monitor")]
 struct Args {
     /// Serial port path (e.g. /dev/ttyUSB0)
@@ -46,151 +102,742 @@ struct Args {
     #[arg(short, long)]
     address: Option<u8>,
 
-    /// Poll interval in milliseconds
-    #[arg(short = 'i', long, default_value_t = 500)]
-    poll_interval: u64,
+    /// Poll interval in milliseconds [default: 500]
+    #[arg(short = 'i', long)]
+    poll_interval: Option<u64>,
 
-    /// Device interface
-    #[arg(short = 'I', long, value_enum, default_value_t = InterfaceMode::Remote)]
-    interface: InterfaceMode,
+    /// Device interface [default: remote]
+    #[arg(short = 'I', long, value_enum)]
+    interface: Option<InterfaceMode>,
 
     /// Disable write commands
     #[arg(short = 'r', long, default_value_t = false)]
     read_only: bool,
+
+    /// Flow unit used for display and input [default: m3h, or the unit used last]
+    #[arg(long, value_enum)]
+    units: Option<FlowUnit>,
+
+    /// Device model
+    #[arg(short = 'm', long, value_enum, default_value_t = DeviceModel::Quick6101A2)]
+    model: DeviceModel,
+
+    /// History to keep, as a sample count (600) or a duration (30s, 10m, 2h, 1d)
+    #[arg(long, default_value = "120")]
+    history: HistoryDepth,
+
+    /// Smooth real flow with an exponential moving average over this many samples
+    #[arg(long, value_name = "SAMPLES")]
+    smoothing: Option<usize>,
+
+    /// TOML configuration file with the connection, alarm rules and presets; its values yield to
+    /// the command line [default: config.toml in the user's config directory, when present]
+    #[arg(short = 'c', long)]
+    config: Option<PathBuf>,
+
+    /// Colour theme, overriding the config file's `[theme] base`; remembered for later launches
+    #[arg(long, value_enum)]
+    theme: Option<ThemeName>,
+
+    /// Start with sparklines and a single status line, for small terminal panes
+    #[arg(long, default_value_t = false)]
+    compact: bool,
+
+    /// Chart point marker, overriding the config file's `[theme] marker`
+    #[arg(long, value_enum)]
+    marker: Option<ChartMarker>,
+
+    /// Draw with plain ASCII instead of Braille markers and box-drawing borders
+    #[arg(long, default_value_t = false)]
+    ascii: bool,
+
+    /// User interface language
+    #[arg(long, value_enum, default_value_t = Lang::En)]
+    lang: Lang,
+
+    /// Skip the TUI and print one JSON object per poll to stdout
+    #[arg(long, default_value_t = false)]
+    headless: bool,
+
+    /// Serve the REST API on this address (e.g. 0.0.0.0:8080), overriding the config file's
+    /// `[http] listen`
+    #[arg(long, value_name = "ADDRESS")]
+    http_listen: Option<String>,
+
+    /// Take newline-delimited JSON commands on this Unix socket, overriding the config file's
+    /// `control_socket`
+    #[arg(long, value_name = "PATH")]
+    control_socket: Option<PathBuf>,
+
+    /// Take the control socket's requests on this loopback TCP address (e.g. 127.0.0.1:7070),
+    /// overriding the config file's `control_listen`
+    #[arg(long, value_name = "ADDRESS")]
+    control_listen: Option<SocketAddr>,
+
+    /// Append a CSV row per answered poll to this file while running, overriding the config
+    /// file's `log_csv`
+    #[arg(long, value_name = "PATH")]
+    log_csv: Option<PathBuf>,
+
+    /// End each `--log-csv` row with every polled register
+    #[arg(long, default_value_t = false)]
+    log_registers: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// One-shot commands that run without the TUI.
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// Poll once, print the status and exit with 1 when a device does not answer
+    Status {
+        /// Print a JSON object instead of text
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    /// Write one validated value and exit with 1 when a device refuses it
+    Set {
+        #[command(subcommand)]
+        write: SetCommand,
+    },
+    /// Print a row per answered poll until interrupted, for plotting or a spreadsheet
+    Watch {
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+        /// Poll interval in milliseconds, overriding `--poll-interval`
+        #[arg(long)]
+        interval: Option<u64>,
+    },
+    /// Probe the bus for devices and list their addresses and identification
+    Scan {
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+        /// First address to probe
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=i64::from(MAX_ADDRESS)))]
+        from: u8,
+        /// Last address to probe
+        #[arg(long, default_value_t = MAX_ADDRESS, value_parser = clap::value_parser!(u8).range(1..=i64::from(MAX_ADDRESS)))]
+        to: u8,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum SetCommand {
+    /// Target flow in the `--units` unit
+    Flow { value: String },
+    /// Fan power
+    Power { state: Switch },
+    /// Any writable register, by address (0x12 or 18) and value (engineering or 0x raw)
+    Register {
+        address: String,
+        value: String,
+        /// Allow registers that change the link or calibration, which the TUI asks to retype
+        #[arg(long, default_value_t = false)]
+        force: bool,
+    },
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Switch {
+    On,
+    Off,
+}
+
+impl SetCommand {
+    /// The write to send, parsed and checked against the register map.
+    fn command(&self, profile: &DeviceProfile, unit: FlowUnit) -> eyre::Result<TransportCommand> {
+        let command = match self {
+            Self::Flow { value } => unit
+                .parse_m3h(value)
+                .map(TransportCommand::SetTargetFlow)
+                .ok_or_else(|| eyre::eyre!("invalid flow {value:?}"))?,
+            Self::Power { state } => TransportCommand::SetPower(*state == Switch::On),
+            Self::Register {
+                address,
+                value,
+                force,
+            } => {
+                let register = match address.strip_prefix("0x") {
+                    Some(hex) => u16::from_str_radix(hex, 16).ok(),
+                    None => address.parse().ok(),
+                }
+                .ok_or_else(|| eyre::eyre!("invalid register address {address:?}"))?;
+                let info = profile
+                    .register_info(register)
+                    .ok_or_else(|| eyre::eyre!("register 0x{register:04X} is not catalogued"))?;
+                eyre::ensure!(
+                    *force || !config_editor::guarded(register),
+                    "{} changes the link or calibration; pass --force to write it",
+                    info.name
+                );
+                let value = register_editor::parse_input(&info, value)
+                    .ok_or_else(|| eyre::eyre!("invalid value {value:?} for {}", info.name))?;
+                TransportCommand::WriteRegister { register, value }
+            }
+        };
+        validate_command(&command, profile).map_err(|reason| eyre::eyre!(reason))?;
+        Ok(command)
+    }
 }
 
 #[derive(Debug, Clone)]
 struct RuntimeArgs {
     transport: TransportConfig,
     read_only: bool,
+    units: Option<FlowUnit>,
+    history_samples: usize,
+    smoothing: Option<usize>,
+    config: Config,
+    /// The config file in use, watched for edits and extended by recorded macros.
+    config_path: Option<PathBuf>,
 }
 
-fn main() -> eyre::Result<()> {
+fn main() -> eyre::Result<ExitCode> {
     color_eyre::install()?;
     let args = Args::parse();
-    let runtime = resolve_runtime_args(&args)?;
+    let config_path = args
+        .config
+        .clone()
+        .or_else(|| Config::default_path().filter(|path| path.exists()));
+    let mut runtime = resolve_runtime_args(&args, config_path)?;
+    let locale = Locale::new(args.lang)?;
+    let prefs_path = Prefs::default_path();
+    let saved_prefs = prefs_path.as_deref().map(Prefs::load).transpose();
+    let prefs = saved_prefs.as_ref().ok().cloned().flatten().flatten();
+
+    let primary_missing = runtime.transport.port.is_none()
+        && runtime.transport.interface != InterfaceMode::Simulation;
+    let setup_needed = primary_missing && runtime.config.devices.is_empty();
+    if args.headless || args.command.is_some() {
+        eyre::ensure!(
+            !setup_needed,
+            "without the TUI a port is needed from --port or the config file"
+        );
+        return run_headless(&args, &runtime);
+    }
 
-    enable_raw_mode().wrap_err("enable raw mode")?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen).wrap_err("enter alternate screen")?;
+    let mut terminal = init_terminal()?;
+
+    if setup_needed {
+        let theme = runtime
+            .config
+            .theme
+            .resolve(args.theme.or(prefs.as_ref().and_then(|prefs| prefs.theme)));
+        if !run_setup(
+            &mut terminal,
+            &mut runtime.transport,
+            &theme,
+            &locale,
+            args.ascii,
+        )? {
+            restore_terminal(&mut terminal);
+            return Ok(ExitCode::SUCCESS);
+        }
+    }
 
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    let mut fleet = match spawn_devices(&args, &runtime, prefs.as_ref()) {
+        Ok(fleet) => fleet,
+        Err(err) => {
+            restore_terminal(&mut terminal);
+            return Err(err);
+        }
+    };
+    if let Err(err) = saved_prefs {
+        fleet.devices[0].app.event_log.push_with_severity(
+            SystemTime::now(),
+            EventSource::Monitor,
+            Severity::Warning,
+            format!("preferences not restored: {err:#}"),
+        );
+    }
+    if let Err(err) = start_exporters(&mut fleet, &runtime) {
+        fleet.shutdown();
+        restore_terminal(&mut terminal);
+        return Err(err);
+    }
+    // A failed dashboard still saves, stops the workers and restores the terminal.
+    let mut exit_error = run_dashboard(&mut terminal, &mut fleet, &args, &runtime).err();
+
+    if let Err(err) = save_on_exit(
+        &fleet.devices[0].app,
+        &runtime.config,
+        prefs_path.as_deref(),
+    ) {
+        exit_error.get_or_insert(err);
+    }
 
-    let (command_tx, command_rx) = mpsc::channel();
-    let (event_tx, event_rx) = mpsc::channel();
+    fleet.shutdown();
 
-    let serial_handle = spawn_worker(runtime.transport.clone(), command_rx, event_tx);
+    restore_terminal(&mut terminal);
 
-    let tick_rate = Duration::from_millis(100);
-    let mut app = AppState::new(runtime.transport.interface, runtime.read_only);
-    let mut exit_error: Option<eyre::Report> = None;
+    if let Some(err) = exit_error {
+        return Err(err);
+    }
 
-    loop {
-        terminal.draw(|frame| render_ui(frame, &app))?;
+    Ok(ExitCode::SUCCESS)
+}
 
-        if event::poll(tick_rate)?
-            && let Event::Key(key) = event::read()?
-            && handle_key_event(key.code, &mut app, &command_tx)?
-        {
-            break;
+/// Connects the exporters the config file sets up; they are not reloaded with the file.
+fn start_exporters(fleet: &mut Fleet, runtime: &RuntimeArgs) -> eyre::Result<()> {
+    let names: Vec<String> = fleet
+        .devices
+        .iter()
+        .map(|device| device.name.clone())
+        .collect();
+    if let Some(mqtt) = &runtime.config.mqtt {
+        let exporter = MqttExporter::connect(mqtt, &names, runtime.read_only);
+        fleet.exporters.push(Box::new(exporter));
+    }
+    if let Some(influx) = &runtime.config.influx {
+        fleet
+            .exporters
+            .push(Box::new(InfluxExporter::start(influx)));
+    }
+    if let Some(http) = &runtime.config.http
+        && http.listen.is_some()
+    {
+        let api = HttpApi::serve(http, &names, runtime.read_only)?;
+        fleet.exporters.push(Box::new(api));
+    }
+    if let Some(path) = &runtime.config.log_csv {
+        let log = CsvLog::open(path, runtime.config.log_csv_registers)?;
+        fleet.exporters.push(Box::new(log));
+    }
+    let infos: Vec<DeviceInfo> = fleet
+        .devices
+        .iter()
+        .map(|device| DeviceInfo::new(&device.name, device.transport()))
+        .collect();
+    #[cfg(unix)]
+    if let Some(path) = &runtime.config.control_socket {
+        let socket = export::ControlSocket::bind(path, infos.clone(), runtime.read_only)?;
+        fleet.exporters.push(Box::new(socket));
+    }
+    #[cfg(unix)]
+    if let Some(address) = runtime.config.control_listen {
+        let socket = export::ControlSocket::listen(address, infos, runtime.read_only)?;
+        fleet.exporters.push(Box::new(socket));
+    }
+    Ok(())
+}
+
+/// Draws and drives the dashboards until quit.
+fn run_dashboard(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    fleet: &mut Fleet,
+    args: &Args,
+    runtime: &RuntimeArgs,
+) -> eyre::Result<()> {
+    let mut config_watcher = runtime.config_path.clone().map(ConfigWatcher::new);
+
+    // Frames are drawn when something changed, and at least every `KEEPALIVE_FRAME` so ages,
+    // clocks and toasts keep moving.
+    let mut dirty = true;
+    let mut last_frame = Instant::now();
+    let mut drawn_phase = 0;
+    loop {
+        if dirty || last_frame.elapsed() >= KEEPALIVE_FRAME {
+            terminal.draw(|frame| {
+                if fleet.overview {
+                    render_overview(frame, fleet);
+                } else {
+                    let app = &mut fleet.selected_mut().app;
+                    update_layout(app, frame.area());
+                    render_ui(frame, app);
+                }
+            })?;
+            dirty = false;
+            last_frame = Instant::now();
+            drawn_phase = banner_phase(SystemTime::now());
         }
 
-        match event_rx.try_recv() {
-            Ok(TransportEvent::Status(status)) => app.update_status(status),
-            Ok(TransportEvent::Connection(connected)) => app.connected = connected,
-            Ok(TransportEvent::Error(err)) => {
-                exit_error = Some(err.wrap_err("serial connection failed"));
-                app.should_quit = true;
-            }
-            Err(mpsc::TryRecvError::Empty) => {}
-            Err(mpsc::TryRecvError::Disconnected) => {
-                exit_error = Some(eyre::eyre!("serial thread disconnected"));
-                app.should_quit = true;
+        if event::poll(TICK_RATE)? {
+            match event::read()? {
+                // Terminals that report releases would otherwise step everything twice.
+                Event::Key(key) if key.kind == KeyEventKind::Release => {}
+                Event::Key(key) if fleet.handle_key(key) => break,
+                Event::Mouse(mouse) => fleet.handle_mouse(mouse),
+                Event::Paste(text) => fleet.handle_paste(&text),
+                // Lay out again at once, before the next poll or keepalive frame.
+                Event::Resize(width, height) => {
+                    terminal.autoresize()?;
+                    fleet.resize(Rect::new(0, 0, width, height));
+                }
+                _ => {}
             }
+            dirty = true;
+        }
+
+        dirty |= fleet.tick(SystemTime::now());
+        // The filter banner flashes faster than the keepalive frame.
+        dirty |= !fleet.overview
+            && fleet.selected().app.filter_banner_shown()
+            && banner_phase(SystemTime::now()) != drawn_phase;
+        if let Some(watcher) = &mut config_watcher {
+            dirty |= reload_config(fleet, watcher, args);
         }
 
-        if app.should_quit {
+        save_progress(&mut fleet.devices[0].app, &runtime.config);
+
+        if fleet.should_quit() {
             break;
         }
     }
+    Ok(())
+}
 
-    command_tx.send(transport::TransportCommand::Terminate).ok();
-    serial_handle.join().ok();
+/// Saves the totalizer when due and logs changed filter totals; failures are logged.
+fn save_progress(app: &mut AppState, config: &Config) {
+    if let Some(path) = &config.totalizer_file
+        && let Err(err) = app.totalizer.save_if_due(path, SystemTime::now())
+    {
+        app.event_log.push(
+            SystemTime::now(),
+            EventSource::Monitor,
+            format!("totalizer not saved: {err}"),
+        );
+    }
 
-    disable_raw_mode().ok();
-    execute!(terminal.backend_mut(), LeaveAlternateScreen).ok();
-    terminal.show_cursor().ok();
+    if let Some(status) = &app.status
+        && let Err(err) = app.filter_log.record(
+            SystemTime::now(),
+            Filter::ALL.map(|filter| status.filter_total(filter)),
+        )
+    {
+        app.event_log.push(
+            SystemTime::now(),
+            EventSource::Monitor,
+            format!("filter history not saved: {err}"),
+        );
+    }
+}
 
-    if let Some(err) = exit_error {
-        return Err(err);
+/// Writes the lifetime total and the UI preferences before exiting.
+fn save_on_exit(app: &AppState, config: &Config, prefs_path: Option<&Path>) -> eyre::Result<()> {
+    let totalizer = config
+        .totalizer_file
+        .as_ref()
+        .map_or(Ok(()), |path| app.totalizer.save(path));
+    let prefs = prefs_path.map_or(Ok(()), |path| Prefs::capture(app).save(path));
+    totalizer.and(prefs)
+}
+
+/// Starts a worker for the device on the command line, if one was given, and for every
+/// `[[device]]` in the config file. Only the first device keeps the totalizer and filter log.
+fn spawn_devices(args: &Args, runtime: &RuntimeArgs, prefs: Option<&Prefs>) -> eyre::Result<Fleet> {
+    let transport = &runtime.transport;
+    let mut devices = Vec::new();
+    if transport.port.is_some() || transport.interface == InterfaceMode::Simulation {
+        let name = transport
+            .port
+            .clone()
+            .unwrap_or_else(|| String::from("simulation"));
+        let app = build_app(args, runtime, Locale::new(args.lang)?, prefs)?;
+        devices.push(Device::spawn(name, transport.clone(), app));
     }
+    for device in &runtime.config.devices {
+        let mut device_runtime = runtime.clone();
+        device_runtime.transport = device_transport(transport, device);
+        if !devices.is_empty() {
+            device_runtime.config.totalizer_file = None;
+            device_runtime.config.filter_history_file = None;
+        }
+        let app = build_app(args, &device_runtime, Locale::new(args.lang)?, prefs)?;
+        devices.push(Device::spawn(
+            device.name.clone(),
+            device_runtime.transport,
+            app,
+        ));
+    }
+    Ok(Fleet::new(devices))
+}
 
-    Ok(())
+/// Connection of a `[[device]]`, sharing the poll interval and model of the command line.
+fn device_transport(base: &TransportConfig, device: &DeviceConfig) -> TransportConfig {
+    let interface = device.interface;
+    TransportConfig {
+        port: device.port.clone(),
+        baud: device.baud.unwrap_or(interface.default_baud()),
+        address: device.address.unwrap_or(interface.default_address()),
+        interface,
+        ..base.clone()
+    }
 }
 
-fn resolve_runtime_args(args: &Args) -> eyre::Result<RuntimeArgs> {
-    let interface = {
-        #[cfg(debug_assertions)]
-        {
-            resolve_interface_mode(args)
+/// Dashboard state configured from the command line, the config file and the UI state saved
+/// by the previous session; command line options win over saved ones.
+fn build_app(
+    args: &Args,
+    runtime: &RuntimeArgs,
+    locale: Locale,
+    prefs: Option<&Prefs>,
+) -> eyre::Result<AppState> {
+    let mut app = AppState::new(runtime.transport.interface, runtime.read_only);
+    app.locale = locale;
+    app.ascii = args.ascii;
+    app.profile = runtime.transport.profile;
+    app.apply_config(
+        &runtime.config,
+        args.theme.or(prefs.and_then(|prefs| prefs.theme)),
+        args.marker,
+    );
+    app.flow_unit = runtime.units.unwrap_or_default();
+    app.compact = args.compact;
+    if let Some(prefs) = prefs {
+        app.tab = prefs.tab;
+        app.panels = prefs.panels;
+        app.flow_unit = runtime.units.unwrap_or(prefs.units);
+        app.compact |= prefs.compact;
+        app.colorblind = prefs.colorblind;
+        app.marker = args.marker.unwrap_or(prefs.marker);
+        app.combined_chart = prefs.combined_chart;
+        app.chart_window = prefs.chart_window;
+        app.pinned.clone_from(&prefs.pinned);
+        app.target_history = TargetHistory::new(&prefs.target_history);
+    }
+    app.history.set_capacity(runtime.history_samples);
+    app.smoothing = runtime.smoothing.map(Ema::new);
+    app.set_poll_interval(runtime.transport.poll_interval);
+    if let Some(path) = &runtime.config.totalizer_file {
+        app.totalizer = Totalizer::load(path)?;
+    }
+    if let Some(path) = &runtime.config.filter_history_file {
+        app.filter_log = FilterLog::load(path)?;
+    }
+    app.link_address = runtime.transport.address;
+    app.link_baud = runtime.transport.baud;
+    app.config_path.clone_from(&runtime.config_path);
+    Ok(app)
+}
+
+/// Reapplies the config file after it was edited; a broken edit keeps the previous settings.
+/// Devices are set up once at launch; added or removed `[[device]]` entries need a restart.
+/// `true` when the file changed.
+fn reload_config(fleet: &mut Fleet, watcher: &mut ConfigWatcher, args: &Args) -> bool {
+    let Some(result) = watcher.poll() else {
+        return false;
+    };
+    for app in fleet.apps_mut() {
+        match &result {
+            Ok(config) => {
+                app.apply_config(config, app.theme_name, args.marker);
+                app.event_log.push(
+                    SystemTime::now(),
+                    EventSource::Monitor,
+                    String::from("config reloaded"),
+                );
+            }
+            Err(err) => app.event_log.push_with_severity(
+                SystemTime::now(),
+                EventSource::Monitor,
+                Severity::Warning,
+                format!("config not reloaded: {err:#}"),
+            ),
         }
-        #[cfg(not(debug_assertions))]
-        {
-            resolve_interface_mode(args)?
+    }
+    true
+}
+
+/// Raw mode on the alternate screen, with mouse reports and pastes delivered as one event.
+fn init_terminal() -> eyre::Result<Terminal<CrosstermBackend<io::Stdout>>> {
+    enable_raw_mode().wrap_err("enable raw mode")?;
+    let mut stdout = io::stdout();
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )
+    .wrap_err("enter alternate screen")?;
+    Ok(Terminal::new(CrosstermBackend::new(stdout))?)
+}
+
+fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) {
+    disable_raw_mode().ok();
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste
+    )
+    .ok();
+    terminal.show_cursor().ok();
+}
+
+/// Runs the subcommand, or polls without a terminal, streaming a JSON line per poll to stdout
+/// until the workers stop.
+fn run_headless(args: &Args, runtime: &RuntimeArgs) -> eyre::Result<ExitCode> {
+    if let Some(Command::Scan { format, from, to }) = args.command {
+        eyre::ensure!(from <= to, "--from {from} is above --to {to}");
+        let found = scan::scan(&runtime.transport, from..=to)?;
+        print!("{}", scan::render(&found, format)?);
+        return Ok(ExitCode::SUCCESS);
+    }
+    let write = match &args.command {
+        Some(Command::Set { write }) => {
+            eyre::ensure!(!runtime.read_only, "writes are disabled by --read-only");
+            let unit = runtime.units.unwrap_or_default();
+            Some(write.command(runtime.transport.profile, unit)?)
         }
+        _ => None,
+    };
+    let mut runtime = runtime.clone();
+    if let Some(Command::Watch {
+        interval: Some(interval),
+        ..
+    }) = args.command
+    {
+        eyre::ensure!(interval > 0, "--interval must be above 0 ms");
+        runtime.transport.poll_interval = Duration::from_millis(interval);
+    }
+    let mut fleet = spawn_devices(args, &runtime, None)?;
+    let stdout = io::stdout().lock();
+    let result = match (&args.command, write) {
+        (Some(Command::Status { json }), _) => headless::status(&mut fleet, *json, stdout),
+        (_, Some(command)) => headless::write(&mut fleet, &command, stdout),
+        (Some(Command::Watch { format, .. }), _) => start_exporters(&mut fleet, &runtime)
+            .and_then(|()| headless::run(&mut fleet, *format, stdout))
+            .map(|()| true),
+        _ => start_exporters(&mut fleet, &runtime)
+            .and_then(|()| headless::run(&mut fleet, OutputFormat::Json, stdout))
+            .map(|()| true),
     };
-    let baud = args.baud.unwrap_or(interface.default_baud());
-    let address = args.address.unwrap_or(interface.default_address());
+    fleet.shutdown();
+    result.map(|succeeded| {
+        if succeeded {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        }
+    })
+}
 
+/// Asks for the connection parameters missing from the command line; `false` when the user quits.
+fn run_setup(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    transport: &mut TransportConfig,
+    theme: &Theme,
+    locale: &Locale,
+    ascii: bool,
+) -> eyre::Result<bool> {
+    let mut setup = SetupScreen::new(
+        SetupScreen::available_ports(),
+        transport.interface,
+        transport.baud,
+        transport.address,
+    );
+    loop {
+        terminal.draw(|frame| render_setup(frame, &setup, theme, locale, ascii))?;
+        let code = match event::read()? {
+            Event::Key(key) => key.code,
+            Event::Paste(text) => {
+                for ch in text.chars().filter(|ch| !ch.is_control()) {
+                    setup.handle_key(KeyCode::Char(ch));
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        match setup.handle_key(code) {
+            SetupAction::Connect => {
+                transport.port = setup.needs_port().then(|| setup.port.trim().to_string());
+                transport.baud = setup.baud;
+                transport.address = setup.address;
+                transport.interface = setup.interface;
+                return Ok(true);
+            }
+            SetupAction::Quit => return Ok(false),
+            SetupAction::None => {}
+        }
+    }
+}
+
+/// Connection and display settings from the command line, falling back to the config file at
+/// `config_path` and then to the interface defaults.
+fn resolve_runtime_args(args: &Args, config_path: Option<PathBuf>) -> eyre::Result<RuntimeArgs> {
+    let mut config = match &config_path {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    let interface = args.interface.or(config.interface).unwrap_or_default();
+    #[cfg(not(debug_assertions))]
+    if interface == InterfaceMode::Simulation {
+        return Err(eyre::eyre!(
+            "simulation interface is only available in debug builds"
+        ));
+    }
+    let baud = args
+        .baud
+        .or(config.baud)
+        .unwrap_or(interface.default_baud());
+    let address = args
+        .address
+        .or(config.address)
+        .unwrap_or(interface.default_address());
+
+    // A missing port is asked for on the setup screen.
     let port = match interface {
         InterfaceMode::Simulation => None,
-        _ => Some(
-            args.port
-                .clone()
-                .ok_or_else(|| eyre::eyre!("serial port required unless using simulation interface"))?,
-        ),
+        _ => args.port.clone().or_else(|| config.port.clone()),
     };
 
+    let poll_interval =
+        Duration::from_millis(args.poll_interval.or(config.poll_interval).unwrap_or(500));
+
+    if let Some(listen) = &args.http_listen {
+        let http = config.http.get_or_insert_default();
+        eyre::ensure!(
+            !http.token.is_empty(),
+            "--http-listen needs a `token` in the config file's [http] table"
+        );
+        http.listen = Some(listen.clone());
+    }
+    if let Some(path) = &args.control_socket {
+        config.control_socket = Some(path.clone());
+    }
+    if let Some(address) = args.control_listen {
+        config.control_listen = Some(address);
+    }
+    if let Some(path) = &args.log_csv {
+        config.log_csv = Some(path.clone());
+    }
+    config.log_csv_registers |= args.log_registers;
+
     Ok(RuntimeArgs {
         transport: TransportConfig {
             port,
             baud,
             address,
-            poll_interval: Duration::from_millis(args.poll_interval),
+            poll_interval,
             read_only: args.read_only,
             interface,
+            profile: args.model.profile(),
         },
         read_only: args.read_only,
+        units: args.units,
+        history_samples: args.history.samples(poll_interval),
+        smoothing: args.smoothing,
+        config,
+        config_path,
     })
 }
 
-#[cfg(debug_assertions)]
-fn resolve_interface_mode(args: &Args) -> InterfaceMode {
-    args.interface
-}
-
-#[cfg(not(debug_assertions))]
-fn resolve_interface_mode(args: &Args) -> eyre::Result<InterfaceMode> {
-    if args.interface == InterfaceMode::Simulation {
-        return Err(eyre::eyre!(
-            "simulation interface is only available in debug builds"
-        ));
-    }
-    Ok(args.interface)
-}
-
 #[cfg(test)]
 mod tests {
+    use std::fs;
+
     use clap::Parser;
 
-    use super::{Args, InterfaceMode, resolve_runtime_args};
+    use super::{Args, Command, InterfaceMode, TransportCommand, resolve_runtime_args};
+    use crate::constants::{REG_BEEPER, REG_CALIBRATION_FACTOR};
+    use crate::profile::QUICK_6101A2;
 
     #[test]
     fn remote_defaults_match_existing_behavior() {
-        let args = Args::try_parse_from(["bin", "--port", "/dev/ttyUSB0"])
-            .expect("args should parse");
-        let runtime = resolve_runtime_args(&args).expect("runtime should resolve");
+        let args =
+            Args::try_parse_from(["bin", "--port", "/dev/ttyUSB0"]).expect("args should parse");
+        let runtime = resolve_runtime_args(&args, None).expect("runtime should resolve");
         assert_eq!(runtime.transport.interface, InterfaceMode::Remote);
         assert_eq!(runtime.transport.baud, 19_200);
         assert_eq!(runtime.transport.address, 2);
@@ -198,15 +845,10 @@ mod tests {
 
     #[test]
     fn exttool_defaults_are_selected_from_interface() {
-        let args = Args::try_parse_from([
-            "bin",
-            "--port",
-            "/dev/ttyUSB0",
-            "--interface",
-            "exttool",
-        ])
-        .expect("args should parse");
-        let runtime = resolve_runtime_args(&args).expect("runtime should resolve");
+        let args =
+            Args::try_parse_from(["bin", "--port", "/dev/ttyUSB0", "--interface", "exttool"])
+                .expect("args should parse");
+        let runtime = resolve_runtime_args(&args, None).expect("runtime should resolve");
         assert_eq!(runtime.transport.interface, InterfaceMode::Exttool);
         assert_eq!(runtime.transport.baud, 38_400);
         assert_eq!(runtime.transport.address, 1);
@@ -217,7 +859,7 @@ mod tests {
     fn simulation_interface_works_without_port() {
         let args =
             Args::try_parse_from(["bin", "--interface", "simulation"]).expect("args should parse");
-        let runtime = resolve_runtime_args(&args).expect("runtime should resolve");
+        let runtime = resolve_runtime_args(&args, None).expect("runtime should resolve");
         assert_eq!(runtime.transport.interface, InterfaceMode::Simulation);
         assert!(runtime.transport.port.is_none());
     }
@@ -236,15 +878,90 @@ mod tests {
             "7",
         ])
         .expect("args should parse");
-        let runtime = resolve_runtime_args(&args).expect("runtime should resolve");
+        let runtime = resolve_runtime_args(&args, None).expect("runtime should resolve");
         assert_eq!(runtime.transport.baud, 57_600);
         assert_eq!(runtime.transport.address, 7);
     }
 
     #[test]
-    fn serial_interfaces_require_port() {
+    fn history_duration_is_resolved_against_poll_interval() {
+        let args = Args::try_parse_from([
+            "bin",
+            "--port",
+            "/dev/ttyUSB0",
+            "--poll-interval",
+            "1000",
+            "--history",
+            "1h",
+        ])
+        .expect("args should parse");
+        let runtime = resolve_runtime_args(&args, None).expect("runtime should resolve");
+        assert_eq!(runtime.history_samples, 3_600);
+    }
+
+    #[test]
+    fn config_file_fills_in_what_the_command_line_leaves_out() {
+        let path = std::env::temp_dir().join(format!("config-{}.toml", std::process::id()));
+        fs::write(
+            &path,
+            "port = \"/dev/ttyUSB3\"\ninterface = \"exttool\"\naddress = 4\npoll_interval = 2000\n",
+        )
+        .expect("config should be written");
+        let args = Args::try_parse_from(["bin", "--address", "9"]).expect("args should parse");
+        let runtime = resolve_runtime_args(&args, Some(path.clone()));
+        fs::remove_file(&path).ok();
+        let runtime = runtime.expect("runtime should resolve");
+        assert_eq!(runtime.transport.port.as_deref(), Some("/dev/ttyUSB3"));
+        assert_eq!(runtime.transport.interface, InterfaceMode::Exttool);
+        assert_eq!(runtime.transport.baud, 38_400);
+        assert_eq!(runtime.transport.address, 9, "the command line wins");
+        assert_eq!(runtime.transport.poll_interval.as_millis(), 2_000);
+    }
+
+    #[test]
+    fn missing_port_is_left_for_the_setup_screen() {
         let args = Args::try_parse_from(["bin"]).expect("args should parse");
-        let err = resolve_runtime_args(&args).expect_err("port should be required");
-        assert!(err.to_string().contains("serial port required"));
+        let runtime = resolve_runtime_args(&args, None).expect("runtime should resolve");
+        assert_eq!(runtime.transport.interface, InterfaceMode::Remote);
+        assert!(runtime.transport.port.is_none());
+    }
+
+    #[test]
+    fn set_subcommands_parse_into_validated_writes() {
+        let write = |line: &[&str]| {
+            let args = Args::try_parse_from(line).expect("args should parse");
+            let Some(Command::Set { write }) = args.command else {
+                panic!("set subcommand expected");
+            };
+            write.command(&QUICK_6101A2, args.units.unwrap_or_default())
+        };
+        assert_eq!(
+            write(&["bin", "set", "flow", "70"]).expect("flow is in range"),
+            TransportCommand::SetTargetFlow(70)
+        );
+        assert_eq!(
+            write(&["bin", "--units", "cfm", "set", "flow", "50"]).expect("flow is in range"),
+            TransportCommand::SetTargetFlow(85)
+        );
+        assert_eq!(
+            write(&["bin", "set", "power", "off"]).expect("power parses"),
+            TransportCommand::SetPower(false)
+        );
+        assert_eq!(
+            write(&["bin", "set", "register", "0xD", "1"]).expect("beeper is writable"),
+            TransportCommand::WriteRegister {
+                register: REG_BEEPER,
+                value: 1
+            }
+        );
+        assert!(write(&["bin", "set", "flow", "500"]).is_err());
+        assert!(write(&["bin", "set", "register", "0x13", "1.0"]).is_err());
+        assert_eq!(
+            write(&["bin", "set", "register", "19", "1.0", "--force"]).ok(),
+            Some(TransportCommand::WriteRegister {
+                register: REG_CALIBRATION_FACTOR,
+                value: 100
+            })
+        );
     }
 }
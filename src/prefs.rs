@@ -0,0 +1,124 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{self, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{AppState, Panels, Tab};
+use crate::config::config_dir;
+use crate::history::ChartWindow;
+use crate::theme::{ChartMarker, ThemeName};
+use crate::units::FlowUnit;
+
+/// UI state saved on exit and restored on the next launch.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Prefs {
+    pub tab: Tab,
+    pub panels: Panels,
+    /// Built-in theme picked with `--theme`; unset follows the config file.
+    pub theme: Option<ThemeName>,
+    pub units: FlowUnit,
+    pub compact: bool,
+    pub colorblind: bool,
+    pub marker: ChartMarker,
+    pub combined_chart: bool,
+    pub chart_window: ChartWindow,
+    /// Registers shown on the dashboard, by address.
+    pub pinned: Vec<u16>,
+    /// Target flows typed in the popup, in m3/h, oldest first.
+    pub target_history: Vec<u16>,
+}
+
+impl Prefs {
+    /// `ui.toml` in the user's config directory.
+    pub fn default_path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("ui.toml"))
+    }
+
+    /// The saved preferences, or `None` before the first save.
+    pub fn load(path: &Path) -> eyre::Result<Option<Self>> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(err).wrap_err_with(|| format!("read preferences {}", path.display()));
+            }
+        };
+        toml::from_str(&text)
+            .map(Some)
+            .wrap_err_with(|| format!("parse preferences {}", path.display()))
+    }
+
+    /// Writes the preferences, creating the config directory when needed.
+    pub fn save(&self, path: &Path) -> eyre::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .wrap_err_with(|| format!("create config directory {}", dir.display()))?;
+        }
+        let text = toml::to_string(self).wrap_err("serialize preferences")?;
+        fs::write(path, text).wrap_err_with(|| format!("write preferences {}", path.display()))
+    }
+
+    pub fn capture(app: &AppState) -> Self {
+        Self {
+            tab: app.tab,
+            panels: app.panels,
+            theme: app.theme_name,
+            units: app.flow_unit,
+            compact: app.compact,
+            colorblind: app.colorblind,
+            marker: app.marker,
+            combined_chart: app.combined_chart,
+            chart_window: app.chart_window,
+            pinned: app.pinned.clone(),
+            target_history: app.target_history.entries().to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::Prefs;
+    use crate::app::{Panel, Panels, Tab};
+    use crate::history::ChartWindow;
+    use crate::theme::{ChartMarker, ThemeName};
+    use crate::units::FlowUnit;
+
+    #[test]
+    fn preferences_survive_a_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("prefs-{}", std::process::id()));
+        let path = dir.join("ui.toml");
+        assert_eq!(Prefs::load(&path).expect("missing file is fine"), None);
+
+        let mut panels = Panels::default();
+        panels.toggle(Panel::Speed);
+        let prefs = Prefs {
+            tab: Tab::Registers,
+            panels,
+            theme: Some(ThemeName::HighContrast),
+            units: FlowUnit::Cfm,
+            compact: true,
+            colorblind: true,
+            marker: ChartMarker::HalfBlock,
+            combined_chart: true,
+            chart_window: ChartWindow::TenMinutes,
+            pinned: vec![0x0002, 0x0009],
+            target_history: vec![60, 85],
+        };
+        prefs.save(&path).expect("preferences should save");
+        let loaded = Prefs::load(&path).expect("preferences should load");
+        fs::remove_dir_all(&dir).ok();
+        assert_eq!(loaded, Some(prefs));
+    }
+
+    #[test]
+    fn missing_keys_keep_their_defaults() {
+        let prefs: Prefs = toml::from_str(r#"tab = "log""#).expect("partial file should parse");
+        assert_eq!(prefs.tab, Tab::Log);
+        assert_eq!(prefs.panels, Panels::default());
+    }
+}
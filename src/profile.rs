@@ -0,0 +1,86 @@
+use clap::ValueEnum;
+
+use crate::constants::{REG_TARGET_FLOW, TARGET_FLOW_MAX, TARGET_FLOW_MIN};
+use crate::data::{Filter, REGISTERS, RegisterInfo};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default)]
+pub enum DeviceModel {
+    #[default]
+    #[value(name = "6101a2")]
+    Quick6101A2,
+}
+
+impl DeviceModel {
+    pub const fn profile(self) -> &'static DeviceProfile {
+        match self {
+            Self::Quick6101A2 => &QUICK_6101A2,
+        }
+    }
+}
+
+/// Model specific register layout, flow range, and filter set.
+#[derive(Debug, PartialEq)]
+pub struct DeviceProfile {
+    pub model: DeviceModel,
+    pub name: &'static str,
+    pub flow_min: u16,
+    pub flow_max: u16,
+    /// Rated fan speed in RPM, when the manufacturer documents one.
+    pub speed_max: Option<u16>,
+    pub filters: &'static [Filter],
+    pub registers: &'static [RegisterInfo],
+}
+
+impl DeviceProfile {
+    /// Looks up register metadata, with the target flow bounds taken from the profile.
+    pub fn register_info(&self, address: u16) -> Option<RegisterInfo> {
+        let mut info = *self.registers.iter().find(|info| info.address == address)?;
+        if address == REG_TARGET_FLOW {
+            info.min = self.flow_min;
+            info.max = self.flow_max;
+        }
+        Some(info)
+    }
+
+    pub fn clamp_flow(&self, value: u16) -> u16 {
+        value.clamp(self.flow_min, self.flow_max)
+    }
+}
+
+pub static QUICK_6101A2: DeviceProfile = DeviceProfile {
+    model: DeviceModel::Quick6101A2,
+    name: "6101A2",
+    flow_min: TARGET_FLOW_MIN,
+    flow_max: TARGET_FLOW_MAX,
+    // No rated speed is documented; the scale comes from `max_rpm` or observed speeds.
+    speed_max: None,
+    filters: &[Filter::Pre, Filter::Medium, Filter::Chemical],
+    registers: REGISTERS,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::DeviceModel;
+    use crate::constants::{REG_STATE, REG_TARGET_FLOW};
+
+    #[test]
+    fn target_register_bounds_follow_profile_flow_range() {
+        let profile = DeviceModel::Quick6101A2.profile();
+        let info = profile
+            .register_info(REG_TARGET_FLOW)
+            .expect("target flow is catalogued");
+        assert_eq!((info.min, info.max), (profile.flow_min, profile.flow_max));
+
+        let state = profile
+            .register_info(REG_STATE)
+            .expect("state is catalogued");
+        assert_eq!((state.min, state.max), (0, 1));
+    }
+
+    #[test]
+    fn clamp_flow_uses_model_range() {
+        let profile = DeviceModel::Quick6101A2.profile();
+        assert_eq!(profile.clamp_flow(150), 100);
+        assert_eq!(profile.clamp_flow(10), 30);
+    }
+}
@@ -0,0 +1,98 @@
+use std::time::{Duration, SystemTime};
+
+use crate::constants::STATE_ON;
+use crate::data::DeviceStatus;
+
+/// Real flow this close to the target, in m3/h, has settled.
+pub const SETTLE_TOLERANCE: f64 = 2.0;
+
+/// Real flow on its way from where it was to a newly set target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ramp {
+    pub from: f64,
+    pub target: f64,
+    pub started_at: SystemTime,
+    /// Share of the way covered, 0 to 1.
+    pub progress: f64,
+    /// Time left at the pace so far; unknown until real flow has moved towards the target.
+    pub remaining: Option<Duration>,
+}
+
+/// Notices target flow changes and follows real flow until it settles at the new target.
+#[derive(Debug, Default)]
+pub struct RampTracker {
+    target: Option<u16>,
+    ramp: Option<Ramp>,
+}
+
+impl RampTracker {
+    pub fn observe(&mut self, status: &DeviceStatus, now: SystemTime) {
+        let real = f64::from(status.real_flow);
+        let target = f64::from(status.target_flow);
+        let changed = self
+            .target
+            .is_some_and(|previous| previous != status.target_flow);
+        self.target = Some(status.target_flow);
+        if status.state != STATE_ON || (real - target).abs() <= SETTLE_TOLERANCE {
+            self.ramp = None;
+            return;
+        }
+        if changed {
+            self.ramp = Some(Ramp {
+                from: real,
+                target,
+                started_at: now,
+                progress: 0.0,
+                remaining: None,
+            });
+        }
+        if let Some(ramp) = &mut self.ramp {
+            ramp.progress = ((real - ramp.from) / (ramp.target - ramp.from)).clamp(0.0, 1.0);
+            let elapsed = now.duration_since(ramp.started_at).unwrap_or_default();
+            ramp.remaining = (ramp.progress > 0.0)
+                .then(|| elapsed.mul_f64((1.0 - ramp.progress) / ramp.progress));
+        }
+    }
+
+    pub const fn ramp(&self) -> Option<&Ramp> {
+        self.ramp.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::RampTracker;
+    use crate::constants::{STATE_ON, STATUS_POLL_REG_COUNT};
+    use crate::data::DeviceStatus;
+
+    fn status(real_flow: u16, target_flow: u16) -> DeviceStatus {
+        let mut status = DeviceStatus::from_registers(vec![0; STATUS_POLL_REG_COUNT as usize])
+            .expect("status should parse");
+        status.state = STATE_ON;
+        status.target_flow = target_flow;
+        status.real_flow = real_flow;
+        status
+    }
+
+    #[test]
+    fn ramp_tracks_progress_until_real_flow_settles() {
+        let mut tracker = RampTracker::default();
+        tracker.observe(&status(40, 40), UNIX_EPOCH);
+        assert!(tracker.ramp().is_none());
+
+        tracker.observe(&status(40, 80), UNIX_EPOCH + Duration::from_secs(1));
+        let ramp = tracker.ramp().expect("target change starts a ramp");
+        assert!(ramp.progress.abs() < f64::EPSILON);
+        assert_eq!(ramp.remaining, None);
+
+        tracker.observe(&status(50, 80), UNIX_EPOCH + Duration::from_secs(3));
+        let ramp = tracker.ramp().expect("still ramping");
+        assert!((ramp.progress - 0.25).abs() < f64::EPSILON);
+        assert_eq!(ramp.remaining, Some(Duration::from_secs(6)));
+
+        tracker.observe(&status(79, 80), UNIX_EPOCH + Duration::from_secs(9));
+        assert!(tracker.ramp().is_none());
+    }
+}
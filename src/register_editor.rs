@@ -0,0 +1,321 @@
+use crate::config_editor::{PendingWrite, guarded};
+use crate::data::RegisterInfo;
+use crate::profile::DeviceProfile;
+
+/// Registers shown per row in the register grid.
+pub const REGISTER_COLUMNS: usize = 2;
+
+/// Cursor and inline edit state of the Registers tab.
+#[derive(Debug, Default)]
+pub struct RegisterEditor {
+    pub selected: usize,
+    pub editing: bool,
+    pub buffer: String,
+    /// A guarded register's write waiting for the value to be typed again.
+    pub pending: Option<PendingWrite>,
+    pub error: Option<String>,
+    /// First visible line of the register pane.
+    pub scroll: usize,
+    /// Pane height and total line count, set by `fit` before each draw.
+    pub viewport: usize,
+    pub content: usize,
+    /// Name or address the grid is narrowed to; empty shows every register.
+    pub query: String,
+    /// The search line takes the keys.
+    pub searching: bool,
+}
+
+impl RegisterEditor {
+    pub fn selected_register(&self) -> u16 {
+        u16::try_from(self.selected).unwrap_or(u16::MAX)
+    }
+
+    /// Registers the grid shows: all `count` polled ones, or those the search matches.
+    pub fn shown(&self, profile: &DeviceProfile, count: usize) -> Vec<usize> {
+        (0..count)
+            .filter(|index| self.query.is_empty() || matches_query(profile, *index, &self.query))
+            .collect()
+    }
+
+    /// Moves the cursor by `offset` cells through the `shown` registers.
+    pub fn move_within(&mut self, offset: isize, shown: &[usize]) {
+        let Some(last) = shown.len().checked_sub(1) else {
+            return;
+        };
+        self.error = None;
+        let position = position(self.selected, shown)
+            .saturating_add_signed(offset)
+            .min(last);
+        self.selected = shown[position];
+        let row = position / REGISTER_COLUMNS;
+        let viewport = self.viewport.max(1);
+        self.scroll = self.scroll.clamp((row + 1).saturating_sub(viewport), row);
+    }
+
+    /// Takes the pane size from `ui::update_layout`, scrolling so the cursor stays in view when
+    /// the terminal shrinks and no blank lines show when it grows.
+    pub fn fit(&mut self, viewport: usize, content: usize, shown: &[usize]) {
+        self.viewport = viewport;
+        self.content = content;
+        let row = position(self.selected, shown) / REGISTER_COLUMNS;
+        self.scroll = self
+            .scroll
+            .min(content.saturating_sub(viewport))
+            .clamp((row + 1).saturating_sub(viewport.max(1)), row);
+    }
+
+    /// Opens the search line with an empty query.
+    pub fn begin_search(&mut self) {
+        self.searching = true;
+        self.query.clear();
+        self.error = None;
+    }
+
+    /// Edits the query and jumps the cursor to the first register it matches.
+    pub fn edit_search(
+        &mut self,
+        edit: impl FnOnce(&mut String),
+        profile: &DeviceProfile,
+        count: usize,
+    ) {
+        edit(&mut self.query);
+        if let Some(first) = self.shown(profile, count).first() {
+            self.selected = *first;
+            self.scroll = 0;
+        }
+    }
+
+    /// Drops the query, showing every register again.
+    pub fn clear_search(&mut self) {
+        self.searching = false;
+        self.query.clear();
+    }
+
+    /// Scrolls the pane by `lines`, keeping the last line at the bottom edge at most.
+    pub fn scroll_by(&mut self, lines: isize) {
+        let max = self.content.saturating_sub(self.viewport);
+        self.scroll = self.scroll.saturating_add_signed(lines).min(max);
+    }
+
+    pub fn page(&self) -> isize {
+        isize::try_from(self.viewport.max(1)).unwrap_or(isize::MAX)
+    }
+
+    /// Starts editing the selected register if the profile marks it writable.
+    pub fn begin_edit(&mut self, current: Option<u16>, profile: &DeviceProfile) {
+        let Some(info) = profile.register_info(self.selected_register()) else {
+            self.error = Some(format!("0x{:04X} is not a known register", self.selected));
+            return;
+        };
+        if !info.writable {
+            self.error = Some(format!("{} is read-only", info.name));
+            return;
+        }
+        self.editing = true;
+        self.error = None;
+        self.buffer = current
+            .map(|value| info.format_value(value))
+            .unwrap_or_default();
+    }
+
+    pub fn cancel(&mut self) {
+        self.editing = false;
+        self.pending = None;
+        self.buffer.clear();
+        self.error = None;
+    }
+
+    /// Parses the buffer as `0x`-prefixed raw hex or an engineering value and validates it.
+    /// A guarded register's value is held in `pending` instead until it is typed again.
+    pub fn commit(&mut self, profile: &DeviceProfile) -> Option<(u16, u16)> {
+        let register = self.selected_register();
+        let info = profile.register_info(register)?;
+        match parse_input(&info, &self.buffer) {
+            Some(value) if info.accepts(value) => {
+                self.cancel();
+                if guarded(register) {
+                    self.pending = Some(PendingWrite::new(register, value));
+                    return None;
+                }
+                Some((register, value))
+            }
+            Some(_) => {
+                self.error = Some(format!(
+                    "{}: value must be within {}..={}",
+                    info.name,
+                    info.format_value(info.min),
+                    info.format_value(info.max)
+                ));
+                None
+            }
+            None => {
+                self.error = Some(format!("{}: not a valid value", info.name));
+                None
+            }
+        }
+    }
+}
+
+/// Index of the first shown register at or after `selected`.
+fn position(selected: usize, shown: &[usize]) -> usize {
+    shown
+        .iter()
+        .position(|index| *index >= selected)
+        .unwrap_or(shown.len().saturating_sub(1))
+}
+
+/// Case-insensitive match on the name or description, or the address in hex with or without
+/// `0x`.
+fn matches_query(profile: &DeviceProfile, index: usize, query: &str) -> bool {
+    let query = query.trim().to_lowercase();
+    let hex = query.strip_prefix("0x").unwrap_or(&query);
+    let Ok(address) = u16::try_from(index) else {
+        return false;
+    };
+    u16::from_str_radix(hex, 16).is_ok_and(|wanted| wanted == address)
+        || profile.register_info(address).is_some_and(|info| {
+            info.name.to_lowercase().contains(&query)
+                || info.description.to_lowercase().contains(&query)
+        })
+}
+
+/// Parses `0x`-prefixed raw hex or an engineering value.
+pub fn parse_input(info: &RegisterInfo, input: &str) -> Option<u16> {
+    let input = input.trim();
+    match input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+    {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => info.parse_value(input),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RegisterEditor;
+    use crate::config_editor::PendingWrite;
+    use crate::constants::{
+        REG_CALIBRATION_FACTOR, REG_REAL_FLOW, REG_TARGET_FLOW, REG_THRESHOLD_A,
+    };
+    use crate::profile::QUICK_6101A2;
+
+    #[test]
+    fn accepts_hex_and_engineering_values() {
+        let mut editor = RegisterEditor {
+            selected: usize::from(REG_TARGET_FLOW),
+            ..RegisterEditor::default()
+        };
+        editor.begin_edit(Some(60), &QUICK_6101A2);
+        assert_eq!(editor.buffer, "60");
+        editor.buffer = String::from("75");
+        assert_eq!(editor.commit(&QUICK_6101A2), Some((REG_TARGET_FLOW, 75)));
+        assert!(!editor.editing);
+
+        editor.begin_edit(Some(75), &QUICK_6101A2);
+        editor.buffer = String::from("0x50");
+        assert_eq!(editor.commit(&QUICK_6101A2), Some((REG_TARGET_FLOW, 80)));
+    }
+
+    #[test]
+    fn guarded_registers_are_held_for_retyping() {
+        let mut editor = RegisterEditor {
+            selected: usize::from(REG_CALIBRATION_FACTOR),
+            ..RegisterEditor::default()
+        };
+        editor.begin_edit(Some(100), &QUICK_6101A2);
+        assert_eq!(editor.buffer, "1.00");
+        editor.buffer = String::from("1.05");
+        assert_eq!(editor.commit(&QUICK_6101A2), None);
+        assert!(!editor.editing);
+        assert_eq!(
+            editor.pending,
+            Some(PendingWrite::new(REG_CALIBRATION_FACTOR, 105))
+        );
+        editor.cancel();
+        assert_eq!(editor.pending, None);
+    }
+
+    #[test]
+    fn rejects_read_only_and_out_of_range_values() {
+        let mut editor = RegisterEditor {
+            selected: usize::from(REG_REAL_FLOW),
+            ..RegisterEditor::default()
+        };
+        editor.begin_edit(Some(50), &QUICK_6101A2);
+        assert!(!editor.editing);
+        assert!(editor.error.is_some());
+
+        editor.selected = usize::from(REG_THRESHOLD_A);
+        editor.begin_edit(Some(0), &QUICK_6101A2);
+        editor.buffer = String::from("0xFFFF0");
+        assert_eq!(editor.commit(&QUICK_6101A2), None);
+        editor.buffer = String::from("150");
+        assert_eq!(editor.commit(&QUICK_6101A2), None);
+        assert!(editor.editing);
+
+        editor.cancel();
+        assert_eq!(editor.error, None);
+        editor.selected = usize::from(REG_REAL_FLOW);
+        editor.begin_edit(Some(50), &QUICK_6101A2);
+        assert!(editor.error.is_some());
+        editor.move_within(1, &(0..24).collect::<Vec<_>>());
+        assert_eq!(editor.error, None);
+    }
+
+    #[test]
+    fn cursor_stays_within_polled_registers() {
+        let all: Vec<usize> = (0..24).collect();
+        let mut editor = RegisterEditor::default();
+        editor.move_within(-2, &all);
+        assert_eq!(editor.selected, 0);
+        editor.move_within(30, &all);
+        assert_eq!(editor.selected, 23);
+    }
+
+    #[test]
+    fn scrolling_is_clamped_and_follows_cursor() {
+        let all: Vec<usize> = (0..24).collect();
+        let mut editor = RegisterEditor {
+            viewport: 5,
+            content: 16,
+            ..RegisterEditor::default()
+        };
+        editor.scroll_by(100);
+        assert_eq!(editor.scroll, 11);
+        editor.scroll_by(-editor.page());
+        assert_eq!(editor.scroll, 6);
+
+        editor.move_within(-20, &all);
+        assert_eq!(editor.scroll, 0);
+        editor.move_within(20, &all);
+        assert_eq!(editor.selected / 2, 10);
+        assert_eq!(editor.scroll, 6);
+
+        editor.fit(3, 16, &all);
+        assert_eq!(editor.scroll, 8);
+        editor.fit(16, 16, &all);
+        assert_eq!(editor.scroll, 0);
+    }
+
+    #[test]
+    fn search_narrows_the_grid_and_moves_the_cursor() {
+        let mut editor = RegisterEditor {
+            selected: 5,
+            ..RegisterEditor::default()
+        };
+        editor.begin_search();
+        editor.edit_search(|query| query.push_str("filter"), &QUICK_6101A2, 24);
+        let shown = editor.shown(&QUICK_6101A2, 24);
+        assert_eq!(shown.len(), 6);
+        assert_eq!(editor.selected, shown[0]);
+        editor.move_within(1, &shown);
+        assert_eq!(editor.selected, shown[1]);
+
+        editor.clear_search();
+        editor.begin_search();
+        editor.edit_search(|query| query.push_str("0x0E"), &QUICK_6101A2, 24);
+        assert!(editor.shown(&QUICK_6101A2, 24).contains(&0x0E));
+        assert_eq!(editor.selected, 0x0E);
+    }
+}
@@ -0,0 +1,153 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::data::{DeviceStatus, Filter};
+
+/// A replacement schedule for one filter, independent of the device's own limits.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FilterReminder {
+    pub filter: Filter,
+    /// Replace on or after this date.
+    pub due: Option<NaiveDate>,
+    /// Replace once the device total reaches this many km3.
+    pub after_km3: Option<u16>,
+}
+
+/// What is left before a reminder falls due.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Countdown {
+    pub days_left: Option<i64>,
+    pub km3_left: Option<i64>,
+}
+
+impl Countdown {
+    pub fn is_due(self) -> bool {
+        self.days_left.is_some_and(|days| days <= 0) || self.km3_left.is_some_and(|km3| km3 <= 0)
+    }
+
+    /// Short text such as `12d 30km3 left` or `due`.
+    pub fn label(self) -> String {
+        if self.is_due() {
+            return String::from("due");
+        }
+        let mut parts = Vec::new();
+        if let Some(days) = self.days_left {
+            parts.push(format!("{days}d"));
+        }
+        if let Some(km3) = self.km3_left {
+            parts.push(format!("{km3}km3"));
+        }
+        format!("{} left", parts.join(" "))
+    }
+}
+
+impl FilterReminder {
+    pub fn countdown(&self, status: Option<&DeviceStatus>, today: NaiveDate) -> Countdown {
+        Countdown {
+            days_left: self.due.map(|due| (due - today).num_days()),
+            km3_left: self.after_km3.zip(status).map(|(limit, status)| {
+                i64::from(limit) - i64::from(status.filter_total(self.filter))
+            }),
+        }
+    }
+}
+
+/// Tracks which reminders have fallen due so each one is reported once.
+#[derive(Debug, Default)]
+pub struct ReminderSchedule {
+    reminders: Vec<FilterReminder>,
+    due: Vec<bool>,
+}
+
+impl ReminderSchedule {
+    pub fn new(reminders: Vec<FilterReminder>) -> Self {
+        let due = vec![false; reminders.len()];
+        Self { reminders, due }
+    }
+
+    /// Replaces the schedule if it changed, so an unchanged one does not report due filters again.
+    pub fn set_reminders(&mut self, reminders: Vec<FilterReminder>) {
+        if reminders != self.reminders {
+            *self = Self::new(reminders);
+        }
+    }
+
+    pub fn reminder(&self, filter: Filter) -> Option<&FilterReminder> {
+        self.reminders
+            .iter()
+            .find(|reminder| reminder.filter == filter)
+    }
+
+    /// Re-checks every reminder, returning the filters that became due just now.
+    ///
+    /// Without a status only the dates are checked, so usage limits wait for the first poll.
+    pub fn evaluate(&mut self, status: Option<&DeviceStatus>, today: NaiveDate) -> Vec<Filter> {
+        let mut newly_due = Vec::new();
+        for (reminder, due) in self.reminders.iter().zip(&mut self.due) {
+            let now_due = reminder.countdown(status, today).is_due();
+            if now_due && !*due {
+                newly_due.push(reminder.filter);
+            }
+            *due = now_due;
+        }
+        newly_due
+    }
+
+    /// Filters whose replacement is due.
+    pub fn due(&self) -> impl Iterator<Item = Filter> + '_ {
+        self.reminders
+            .iter()
+            .zip(&self.due)
+            .filter(|(_, due)| **due)
+            .map(|(reminder, _)| reminder.filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{FilterReminder, ReminderSchedule};
+    use crate::constants::STATUS_POLL_REG_COUNT;
+    use crate::data::{DeviceStatus, Filter};
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, day).expect("valid date")
+    }
+
+    #[test]
+    fn reminder_falls_due_by_date_or_usage_and_reports_once() {
+        let mut status = DeviceStatus::from_registers(vec![0; STATUS_POLL_REG_COUNT as usize])
+            .expect("status should parse");
+        status.p_filter_total = 120;
+        let mut schedule = ReminderSchedule::new(vec![
+            FilterReminder {
+                filter: Filter::Pre,
+                due: None,
+                after_km3: Some(150),
+            },
+            FilterReminder {
+                filter: Filter::Chemical,
+                due: Some(date(10)),
+                after_km3: None,
+            },
+        ]);
+
+        let countdown = schedule
+            .reminder(Filter::Pre)
+            .expect("reminder configured")
+            .countdown(Some(&status), date(1));
+        assert_eq!(countdown.label(), "30km3 left");
+        assert!(schedule.evaluate(Some(&status), date(1)).is_empty());
+        assert_eq!(schedule.evaluate(None, date(10)), vec![Filter::Chemical]);
+
+        status.p_filter_total = 150;
+        assert_eq!(
+            schedule.evaluate(Some(&status), date(10)),
+            vec![Filter::Pre]
+        );
+        assert!(schedule.evaluate(Some(&status), date(11)).is_empty());
+        assert_eq!(schedule.due().count(), 2);
+    }
+}
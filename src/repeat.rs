@@ -0,0 +1,64 @@
+use std::time::{Duration, SystemTime};
+
+/// Steps closer together than this come from a held key or a spun wheel and are batched.
+pub const REPEAT_WINDOW: Duration = Duration::from_millis(150);
+
+/// Coalesces a burst of target flow steps, such as a held arrow key, into one write.
+#[derive(Debug, Default)]
+pub struct TargetBatch {
+    last_step: Option<SystemTime>,
+    pending: Option<u16>,
+}
+
+impl TargetBatch {
+    /// The target to write for a step at `now`: a lone step goes out at once, steps following
+    /// it within `REPEAT_WINDOW` wait until the burst is over.
+    pub fn step(&mut self, target: u16, now: SystemTime) -> Option<u16> {
+        let repeated = self
+            .last_step
+            .is_some_and(|last| now.duration_since(last).unwrap_or_default() < REPEAT_WINDOW);
+        self.last_step = Some(now);
+        if repeated {
+            self.pending = Some(target);
+            None
+        } else {
+            self.pending = None;
+            Some(target)
+        }
+    }
+
+    /// The batched target once no step came for `REPEAT_WINDOW`.
+    pub fn settled(&mut self, now: SystemTime) -> Option<u16> {
+        let quiet = self
+            .last_step
+            .is_none_or(|last| now.duration_since(last).unwrap_or_default() >= REPEAT_WINDOW);
+        if quiet { self.pending.take() } else { None }
+    }
+
+    /// Target waiting for the burst to end.
+    pub const fn pending(&self) -> Option<u16> {
+        self.pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{REPEAT_WINDOW, TargetBatch};
+
+    #[test]
+    fn held_key_steps_are_written_once_the_burst_ends() {
+        let mut batch = TargetBatch::default();
+        let at = |millis| UNIX_EPOCH + Duration::from_millis(millis);
+        assert_eq!(batch.step(61, at(0)), Some(61));
+        assert_eq!(batch.step(62, at(500)), Some(62), "key repeat delay");
+        assert_eq!(batch.step(63, at(530)), None);
+        assert_eq!(batch.step(64, at(560)), None);
+        assert_eq!(batch.pending(), Some(64));
+        assert_eq!(batch.settled(at(600)), None);
+        assert_eq!(batch.settled(at(560) + REPEAT_WINDOW), Some(64));
+        assert_eq!(batch.settled(at(2000)), None);
+        assert_eq!(batch.step(65, at(2000)), Some(65));
+    }
+}
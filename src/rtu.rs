@@ -1,9 +1,34 @@
+use std::fmt;
 use std::io;
 use std::io::Read;
 use std::time::{Duration, Instant};
 
 use color_eyre::eyre;
 
+/// Link-level frame failures, kept typed so the transport can count them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameError {
+    Timeout { expected: usize, received: usize },
+    Crc { expected: u16, seen: u16 },
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout { expected, received } => write!(
+                f,
+                "read timeout while waiting for {expected} bytes (got {received})"
+            ),
+            Self::Crc { expected, seen } => write!(
+                f,
+                "invalid frame crc: expected 0x{expected:04X}, got 0x{seen:04X}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
 pub fn crc16_modbus(data: &[u8]) -> u16 {
     let mut crc: u16 = 0xFFFF;
     for byte in data {
@@ -36,9 +61,7 @@ pub fn validate_crc(frame: &[u8]) -> eyre::Result<()> {
     let expected = crc16_modbus(&frame[..body_len]);
     let seen = u16::from(frame[body_len]) | (u16::from(frame[body_len + 1]) << 8);
     if expected != seen {
-        return Err(eyre::eyre!(
-            "invalid frame crc: expected 0x{expected:04X}, got 0x{seen:04X}"
-        ));
+        return Err(FrameError::Crc { expected, seen }.into());
     }
     Ok(())
 }
@@ -54,11 +77,11 @@ pub fn read_exact_with_timeout(
 
     while read_total < size {
         if Instant::now() > deadline {
-            return Err(eyre::eyre!(
-                "read timeout while waiting for {} bytes (got {})",
-                size,
-                read_total
-            ));
+            return Err(FrameError::Timeout {
+                expected: size,
+                received: read_total,
+            }
+            .into());
         }
 
         match reader.read(&mut buffer[read_total..]) {
@@ -0,0 +1,157 @@
+use std::ops::RangeInclusive;
+
+use clap::ValueEnum;
+use color_eyre::eyre;
+use serde::Serialize;
+
+use crate::backend::build_backend;
+use crate::interface::InterfaceMode;
+use crate::transport::{PollOutcome, TransportConfig};
+
+/// Highest address a Modbus RTU device can take.
+pub const MAX_ADDRESS: u8 = 247;
+
+/// Listing layout for subcommands that print records.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Csv,
+}
+
+/// A device that answered during a scan.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Found {
+    pub address: u8,
+    /// Server ID from "report server ID"; `None` when the device only answered a status read.
+    pub server_id: Option<u8>,
+    /// Model and firmware text from the identification.
+    pub revision: Option<String>,
+}
+
+/// Probes every address in `addresses` on the configured port, one at a time, returning those
+/// that answered. The simulation answers on its own address only.
+pub fn scan(
+    transport: &TransportConfig,
+    addresses: RangeInclusive<u8>,
+) -> eyre::Result<Vec<Found>> {
+    let addresses = if transport.interface == InterfaceMode::Simulation {
+        transport.address..=transport.address
+    } else {
+        addresses
+    };
+    let mut found = Vec::new();
+    for address in addresses {
+        let mut config = transport.clone();
+        config.address = address;
+        let mut backend = build_backend(&config)?;
+        match backend.identify() {
+            Ok(identity) => found.push(Found {
+                address,
+                server_id: Some(identity.server_id),
+                revision: Some(identity.revision),
+            }),
+            // Silence means nobody is there; any other reply is worth a status read, since
+            // not every unit implements the identification request.
+            Err(err) if PollOutcome::from_error(&err) == PollOutcome::Timeout => {}
+            Err(_) => {
+                if backend.poll_status().is_ok() {
+                    found.push(Found {
+                        address,
+                        server_id: None,
+                        revision: None,
+                    });
+                }
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// The scan result as an aligned table, a JSON array or CSV with a header row.
+pub fn render(found: &[Found], format: OutputFormat) -> eyre::Result<String> {
+    let cells = |device: &Found| {
+        (
+            device
+                .server_id
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            device.revision.clone().unwrap_or_default(),
+        )
+    };
+    Ok(match format {
+        OutputFormat::Table => {
+            let header = format!("{:<9}{:<11}{}\n", "Address", "Server ID", "Identification");
+            let rows = found.iter().map(|device| {
+                let (server_id, revision) = cells(device);
+                let row = format!("{:<9}{server_id:<11}{revision}", device.address);
+                format!("{}\n", row.trim_end())
+            });
+            std::iter::once(header).chain(rows).collect()
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(found)? + "\n",
+        OutputFormat::Csv => {
+            let rows = found.iter().map(|device| {
+                let (server_id, revision) = cells(device);
+                format!("{},{server_id},{}\n", device.address, csv_field(&revision))
+            });
+            std::iter::once(String::from("address,server_id,revision\n"))
+                .chain(rows)
+                .collect()
+        }
+    })
+}
+
+/// Quotes a CSV field that holds a separator, quote or line break.
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Found, OutputFormat, render};
+
+    fn found() -> Vec<Found> {
+        vec![
+            Found {
+                address: 2,
+                server_id: Some(2),
+                revision: Some(String::from("6101A2, V1.3")),
+            },
+            Found {
+                address: 17,
+                server_id: None,
+                revision: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn listings_come_as_table_json_and_csv() {
+        let table = render(&found(), OutputFormat::Table).expect("table renders");
+        assert_eq!(
+            table.lines().collect::<Vec<_>>(),
+            [
+                "Address  Server ID  Identification",
+                "2        2          6101A2, V1.3",
+                "17",
+            ]
+        );
+
+        let csv = render(&found(), OutputFormat::Csv).expect("CSV renders");
+        assert_eq!(
+            csv,
+            "address,server_id,revision\n2,2,\"6101A2, V1.3\"\n17,,\n"
+        );
+
+        let json = render(&found(), OutputFormat::Json).expect("JSON renders");
+        let parsed: serde_json::Value = serde_json::from_str(&json).expect("valid JSON");
+        assert_eq!(parsed[0]["revision"], "6101A2, V1.3");
+        assert!(parsed[1]["server_id"].is_null());
+    }
+}
@@ -0,0 +1,249 @@
+use clap::ValueEnum;
+use crossterm::event::KeyCode;
+
+use crate::constants::BAUD_RATES;
+use crate::interface::InterfaceMode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupField {
+    Port,
+    Baud,
+    Address,
+    Interface,
+}
+
+impl SetupField {
+    pub const ALL: [Self; 4] = [Self::Port, Self::Baud, Self::Address, Self::Interface];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Port => "Port",
+            Self::Baud => "Baud",
+            Self::Address => "Address",
+            Self::Interface => "Interface",
+        }
+    }
+}
+
+/// What the setup screen wants after a key press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupAction {
+    None,
+    Connect,
+    Quit,
+}
+
+/// Connection form shown instead of the dashboard when `--port` is missing.
+#[derive(Debug)]
+pub struct SetupScreen {
+    /// Serial ports found on the system, cycled with ←/→ on the port field.
+    pub ports: Vec<String>,
+    pub port: String,
+    pub baud: u32,
+    pub address: u8,
+    pub interface: InterfaceMode,
+    pub field: SetupField,
+    pub error: Option<String>,
+}
+
+impl SetupScreen {
+    pub fn new(ports: Vec<String>, interface: InterfaceMode, baud: u32, address: u8) -> Self {
+        Self {
+            port: ports.first().cloned().unwrap_or_default(),
+            ports,
+            baud,
+            address,
+            interface,
+            field: SetupField::Port,
+            error: None,
+        }
+    }
+
+    /// Names of the serial ports the OS reports; empty when enumeration is unsupported.
+    pub fn available_ports() -> Vec<String> {
+        serialport::available_ports()
+            .map(|ports| ports.into_iter().map(|port| port.port_name).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode) -> SetupAction {
+        match code {
+            KeyCode::Esc => return SetupAction::Quit,
+            KeyCode::Enter if self.validate() => return SetupAction::Connect,
+            KeyCode::Up | KeyCode::BackTab => self.move_field(-1),
+            KeyCode::Down | KeyCode::Tab => self.move_field(1),
+            KeyCode::Left => self.cycle(-1),
+            KeyCode::Right => self.cycle(1),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Char(c) => self.type_char(c),
+            _ => {}
+        }
+        SetupAction::None
+    }
+
+    /// The simulation interface needs no port, so the field may be left empty.
+    pub fn needs_port(&self) -> bool {
+        self.interface != InterfaceMode::Simulation
+    }
+
+    fn move_field(&mut self, offset: isize) {
+        let index = SetupField::ALL
+            .iter()
+            .position(|field| *field == self.field)
+            .unwrap_or(0);
+        let count = SetupField::ALL.len();
+        let next = index.checked_add_signed(offset).unwrap_or(count - 1) % count;
+        self.field = SetupField::ALL[next];
+    }
+
+    fn cycle(&mut self, offset: isize) {
+        self.error = None;
+        match self.field {
+            SetupField::Port => {
+                if let Some(port) = step(&self.ports, &self.port, offset) {
+                    self.port = port;
+                }
+            }
+            SetupField::Baud => {
+                if let Some(baud) = step(BAUD_RATES, &self.baud, offset) {
+                    self.baud = baud;
+                }
+            }
+            SetupField::Address => {
+                self.address = self
+                    .address
+                    .saturating_add_signed(i8::try_from(offset).unwrap_or(0))
+                    .clamp(1, 247);
+            }
+            SetupField::Interface => {
+                let modes: Vec<InterfaceMode> = InterfaceMode::value_variants()
+                    .iter()
+                    .copied()
+                    .filter(|mode| cfg!(debug_assertions) || *mode != InterfaceMode::Simulation)
+                    .collect();
+                if let Some(interface) = step(&modes, &self.interface, offset) {
+                    self.interface = interface;
+                    self.baud = interface.default_baud();
+                    self.address = interface.default_address();
+                }
+            }
+        }
+    }
+
+    fn type_char(&mut self, c: char) {
+        self.error = None;
+        match self.field {
+            SetupField::Port => self.port.push(c),
+            SetupField::Address => {
+                if let Some(address) = format!("{}{c}", self.address)
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|address| *address <= 247)
+                    .or_else(|| c.to_digit(10).and_then(|digit| u8::try_from(digit).ok()))
+                {
+                    self.address = address;
+                }
+            }
+            SetupField::Baud | SetupField::Interface => {}
+        }
+    }
+
+    fn backspace(&mut self) {
+        match self.field {
+            SetupField::Port => {
+                self.port.pop();
+            }
+            SetupField::Address => self.address /= 10,
+            SetupField::Baud | SetupField::Interface => {}
+        }
+    }
+
+    fn validate(&mut self) -> bool {
+        self.error = if self.needs_port() && self.port.trim().is_empty() {
+            Some(String::from("enter a serial port"))
+        } else if !(1..=247).contains(&self.address) {
+            Some(String::from("address must be within 1..=247"))
+        } else {
+            None
+        };
+        self.error.is_none()
+    }
+}
+
+/// The item `offset` places after `current`, wrapping around; the first item when `current` is
+/// not listed.
+fn step<T: Clone + PartialEq>(items: &[T], current: &T, offset: isize) -> Option<T> {
+    let position = items.iter().position(|item| item == current);
+    let index = match position {
+        Some(index) => {
+            index
+                .checked_add_signed(offset)
+                .unwrap_or(items.len().saturating_sub(1))
+                % items.len().max(1)
+        }
+        None => 0,
+    };
+    items.get(index).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+
+    use super::{SetupAction, SetupField, SetupScreen};
+    use crate::interface::InterfaceMode;
+
+    fn screen() -> SetupScreen {
+        SetupScreen::new(
+            vec![String::from("/dev/ttyUSB0"), String::from("/dev/ttyUSB1")],
+            InterfaceMode::Remote,
+            19_200,
+            2,
+        )
+    }
+
+    #[test]
+    fn values_cycle_and_wrap() {
+        let mut setup = screen();
+        assert_eq!(setup.port, "/dev/ttyUSB0");
+        setup.handle_key(KeyCode::Left);
+        assert_eq!(setup.port, "/dev/ttyUSB1");
+
+        setup.handle_key(KeyCode::Down);
+        setup.handle_key(KeyCode::Right);
+        assert_eq!(setup.baud, 38_400);
+
+        setup.handle_key(KeyCode::Up);
+        setup.handle_key(KeyCode::Up);
+        assert_eq!(setup.field, SetupField::Interface);
+        setup.handle_key(KeyCode::Right);
+        assert_eq!(setup.interface, InterfaceMode::Exttool);
+        assert_eq!(setup.baud, 38_400);
+        assert_eq!(setup.address, 1);
+    }
+
+    #[test]
+    fn typed_address_stays_in_range() {
+        let mut setup = screen();
+        setup.field = SetupField::Address;
+        setup.handle_key(KeyCode::Char('4'));
+        assert_eq!(setup.address, 24);
+        setup.handle_key(KeyCode::Char('9'));
+        assert_eq!(setup.address, 9);
+        setup.handle_key(KeyCode::Backspace);
+        assert_eq!(setup.address, 0);
+        assert_eq!(setup.handle_key(KeyCode::Enter), SetupAction::None);
+        assert!(setup.error.is_some());
+    }
+
+    #[test]
+    fn connecting_requires_a_port() {
+        let mut setup = SetupScreen::new(Vec::new(), InterfaceMode::Remote, 19_200, 2);
+        assert_eq!(setup.handle_key(KeyCode::Enter), SetupAction::None);
+        for c in "/dev/ttyS0".chars() {
+            setup.handle_key(KeyCode::Char(c));
+        }
+        assert_eq!(setup.handle_key(KeyCode::Enter), SetupAction::Connect);
+        assert_eq!(setup.handle_key(KeyCode::Esc), SetupAction::Quit);
+    }
+}
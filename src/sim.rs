@@ -1,12 +1,15 @@
 use crate::constants::{
-    REG_C_FILTER_LIMIT, REG_C_FILTER_TOTAL, REG_M_FILTER_LIMIT, REG_M_FILTER_TOTAL,
-    REG_P_FILTER_LIMIT, REG_P_FILTER_TOTAL, REG_REAL_FLOW, REG_SPEED_RPM, REG_STATE,
-    REG_TARGET_FLOW, STATE_OFF, STATE_ON, STATUS_POLL_REG_COUNT, TARGET_FLOW_MAX, TARGET_FLOW_MIN,
+    REG_BAUD_RATE_LO, REG_BEEPER, REG_C_FILTER_LIMIT, REG_C_FILTER_TOTAL, REG_CALIBRATION_FACTOR,
+    REG_COMM_ADDRESS, REG_M_FILTER_LIMIT, REG_M_FILTER_TOTAL, REG_P_FILTER_LIMIT,
+    REG_P_FILTER_TOTAL, REG_REAL_FLOW, REG_SPEED_RPM, REG_STATE, REG_TARGET_FLOW, STATE_OFF,
+    STATE_ON, STATUS_POLL_REG_COUNT,
 };
 use crate::data::DeviceStatus;
+use crate::profile::DeviceProfile;
 
 #[derive(Debug, Clone)]
 pub struct SimState {
+    profile: &'static DeviceProfile,
     state: u16,
     target_flow: u16,
     real_flow: f64,
@@ -17,11 +20,13 @@ pub struct SimState {
     p_filter_limit: u16,
     m_filter_limit: u16,
     c_filter_limit: u16,
+    settings: Vec<u16>,
 }
 
 impl SimState {
-    pub fn new() -> Self {
+    pub fn new(profile: &'static DeviceProfile) -> Self {
         Self {
+            profile,
             state: STATE_OFF,
             target_flow: 0,
             real_flow: 0.0,
@@ -32,6 +37,7 @@ impl SimState {
             p_filter_limit: 200,
             m_filter_limit: 1200,
             c_filter_limit: 2400,
+            settings: default_settings(),
         }
     }
 
@@ -40,11 +46,26 @@ impl SimState {
     }
 
     pub fn set_target_flow(&mut self, value: u16) {
-        self.target_flow = value.clamp(TARGET_FLOW_MIN, TARGET_FLOW_MAX);
+        self.target_flow = self.profile.clamp_flow(value);
+    }
+
+    pub fn write_register(&mut self, register: u16, value: u16) {
+        match register {
+            REG_STATE => self.set_power(value == STATE_ON),
+            REG_TARGET_FLOW => self.set_target_flow(value),
+            REG_P_FILTER_TOTAL => self.p_filter_total = value,
+            REG_M_FILTER_TOTAL => self.m_filter_total = value,
+            REG_C_FILTER_TOTAL => self.c_filter_total = value,
+            _ => {
+                if let Some(slot) = self.settings.get_mut(register as usize) {
+                    *slot = value;
+                }
+            }
+        }
     }
 
     pub fn tick(&mut self) -> DeviceStatus {
-        let mut registers = vec![0u16; STATUS_POLL_REG_COUNT as usize];
+        let mut registers = self.settings.clone();
         if self.state == STATE_ON {
             let target = f64::from(self.target_flow);
             let delta = target - self.real_flow;
@@ -53,7 +74,7 @@ impl SimState {
             self.real_flow *= 0.6;
         }
 
-        self.real_flow = self.real_flow.clamp(0.0, f64::from(TARGET_FLOW_MAX));
+        self.real_flow = self.real_flow.clamp(0.0, f64::from(self.profile.flow_max));
         self.speed_rpm = if self.state == STATE_ON {
             self.real_flow * 120.0
         } else {
@@ -92,3 +113,12 @@ fn clamp_u16(value: f64) -> u16 {
     let clamped = value.round().clamp(0.0, f64::from(u16::MAX));
     clamped as u16
 }
+
+fn default_settings() -> Vec<u16> {
+    let mut settings = vec![0u16; STATUS_POLL_REG_COUNT as usize];
+    settings[REG_COMM_ADDRESS as usize] = 2;
+    settings[REG_BAUD_RATE_LO as usize] = 19_200;
+    settings[REG_BEEPER as usize] = 1;
+    settings[REG_CALIBRATION_FACTOR as usize] = 100;
+    settings
+}
@@ -0,0 +1,101 @@
+/// Typed target flows remembered, the oldest dropped first.
+pub const TARGET_HISTORY_LEN: usize = 20;
+
+/// Target flows entered in the popup, newest last, recalled with `↑/↓` like a shell history.
+#[derive(Debug, Default)]
+pub struct TargetHistory {
+    /// Targets in m3/h, so a recalled value follows the unit shown.
+    entries: Vec<u16>,
+    /// Entry on the line while browsing; `None` on the line being typed.
+    browsing: Option<usize>,
+    /// The line being typed, given back when browsing moves past the newest entry.
+    draft: String,
+}
+
+impl TargetHistory {
+    pub fn new(entries: &[u16]) -> Self {
+        let skip = entries.len().saturating_sub(TARGET_HISTORY_LEN);
+        Self {
+            entries: entries[skip..].to_vec(),
+            ..Self::default()
+        }
+    }
+
+    pub fn entries(&self) -> &[u16] {
+        &self.entries
+    }
+
+    /// Remembers an entered target, moving a repeated one to the newest place.
+    pub fn push(&mut self, target: u16) {
+        self.entries.retain(|entry| *entry != target);
+        if self.entries.len() == TARGET_HISTORY_LEN {
+            self.entries.remove(0);
+        }
+        self.entries.push(target);
+        self.reset();
+    }
+
+    /// Leaves browsing, for a popup that closes or a line that is edited.
+    pub fn reset(&mut self) {
+        self.browsing = None;
+        self.draft.clear();
+    }
+
+    /// The entry before the one shown, keeping `line` as the draft when browsing starts.
+    pub fn older(&mut self, line: &str) -> Option<u16> {
+        let index = match self.browsing {
+            None => {
+                self.draft = line.to_string();
+                self.entries.len().checked_sub(1)?
+            }
+            Some(index) => index.saturating_sub(1),
+        };
+        self.browsing = Some(index);
+        Some(self.entries[index])
+    }
+
+    /// The entry after the one shown, or the draft once past the newest.
+    pub fn newer(&mut self) -> Option<Result<u16, String>> {
+        let index = self.browsing? + 1;
+        if let Some(entry) = self.entries.get(index) {
+            self.browsing = Some(index);
+            return Some(Ok(*entry));
+        }
+        self.browsing = None;
+        Some(Err(std::mem::take(&mut self.draft)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TARGET_HISTORY_LEN, TargetHistory};
+
+    #[test]
+    fn browsing_walks_back_and_returns_to_the_draft() {
+        let mut history = TargetHistory::default();
+        assert_eq!(history.older("6"), None);
+        history.push(60);
+        history.push(85);
+        history.push(60);
+        assert_eq!(history.entries(), [85, 60]);
+
+        assert_eq!(history.newer(), None, "not browsing");
+        assert_eq!(history.older("7"), Some(60));
+        assert_eq!(history.older("ignored"), Some(85));
+        assert_eq!(history.older("ignored"), Some(85), "oldest stays");
+        assert_eq!(history.newer(), Some(Ok(60)));
+        assert_eq!(history.newer(), Some(Err(String::from("7"))));
+        assert_eq!(history.newer(), None);
+    }
+
+    #[test]
+    fn oldest_entries_make_room() {
+        let saved: Vec<u16> = (30..60).collect();
+        let mut history = TargetHistory::new(&saved);
+        assert_eq!(history.entries().len(), TARGET_HISTORY_LEN);
+        assert_eq!(history.entries()[0], 40);
+        history.push(100);
+        assert_eq!(history.entries()[0], 41);
+        assert_eq!(history.entries().last(), Some(&100));
+    }
+}
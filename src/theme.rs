@@ -0,0 +1,234 @@
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use ratatui::style::Color;
+use ratatui::symbols::Marker;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Built-in colour schemes selectable with `--theme` or `[theme] base`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    #[default]
+    Default,
+    Monochrome,
+    HighContrast,
+}
+
+/// How chart points are drawn; Braille is the finest but renders badly on some terminals.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChartMarker {
+    #[default]
+    Braille,
+    Dot,
+    Block,
+    HalfBlock,
+}
+
+impl ChartMarker {
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Braille => Self::Dot,
+            Self::Dot => Self::Block,
+            Self::Block => Self::HalfBlock,
+            Self::HalfBlock => Self::Braille,
+        }
+    }
+
+    pub const fn symbol(self) -> Marker {
+        match self {
+            Self::Braille => Marker::Braille,
+            Self::Dot => Marker::Dot,
+            Self::Block => Marker::Block,
+            Self::HalfBlock => Marker::HalfBlock,
+        }
+    }
+}
+
+/// Colours of every styled element, grouped by role rather than by widget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Titles, popup borders and the selected tab.
+    pub accent: Color,
+    /// Borders of the register and log panes and healthy filter gauges.
+    pub secondary: Color,
+    pub text: Color,
+    /// Field labels and inactive text.
+    pub muted: Color,
+    pub heading: Color,
+    /// Selected rows, edit buffers and values that just changed.
+    pub highlight: Color,
+    pub ok: Color,
+    pub error: Color,
+    pub warn: Color,
+    /// Chart series.
+    pub real: Color,
+    pub target: Color,
+    pub speed: Color,
+}
+
+impl Theme {
+    pub const DEFAULT: Self = Self {
+        accent: Color::LightMagenta,
+        secondary: Color::LightGreen,
+        text: Color::White,
+        muted: Color::Gray,
+        heading: Color::LightCyan,
+        highlight: Color::LightYellow,
+        ok: Color::Green,
+        error: Color::Red,
+        warn: Color::Yellow,
+        real: Color::LightCyan,
+        target: Color::LightYellow,
+        speed: Color::LightGreen,
+    };
+
+    /// Terminal default colours only; state is carried by text and bold/reversed styles.
+    pub const MONOCHROME: Self = Self {
+        accent: Color::Reset,
+        secondary: Color::Reset,
+        text: Color::Reset,
+        muted: Color::Gray,
+        heading: Color::Reset,
+        highlight: Color::Reset,
+        ok: Color::Reset,
+        error: Color::Reset,
+        warn: Color::Reset,
+        real: Color::Reset,
+        target: Color::Gray,
+        speed: Color::Reset,
+    };
+
+    /// Bright colours only, for wall displays and washed-out screens.
+    pub const HIGH_CONTRAST: Self = Self {
+        accent: Color::White,
+        secondary: Color::LightGreen,
+        text: Color::White,
+        muted: Color::White,
+        heading: Color::LightCyan,
+        highlight: Color::LightYellow,
+        ok: Color::LightGreen,
+        error: Color::LightRed,
+        warn: Color::LightYellow,
+        real: Color::LightCyan,
+        target: Color::LightYellow,
+        speed: Color::LightGreen,
+    };
+
+    pub const fn builtin(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Default => Self::DEFAULT,
+            ThemeName::Monochrome => Self::MONOCHROME,
+            ThemeName::HighContrast => Self::HIGH_CONTRAST,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// The `[theme]` config table: a built-in base with optional per-role overrides.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThemeConfig {
+    #[serde(default)]
+    pub base: ThemeName,
+    /// Start with the colorblind-friendly series and state styles.
+    #[serde(default)]
+    pub colorblind: bool,
+    #[serde(default)]
+    pub marker: ChartMarker,
+    #[serde(default, deserialize_with = "color")]
+    pub accent: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub secondary: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub text: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub muted: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub heading: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub highlight: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub ok: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub error: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub warn: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub real: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub target: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub speed: Option<Color>,
+}
+
+impl ThemeConfig {
+    /// Applies the overrides on top of `base`, or of the configured base when `None`.
+    pub fn resolve(&self, base: Option<ThemeName>) -> Theme {
+        let theme = Theme::builtin(base.unwrap_or(self.base));
+        Theme {
+            accent: self.accent.unwrap_or(theme.accent),
+            secondary: self.secondary.unwrap_or(theme.secondary),
+            text: self.text.unwrap_or(theme.text),
+            muted: self.muted.unwrap_or(theme.muted),
+            heading: self.heading.unwrap_or(theme.heading),
+            highlight: self.highlight.unwrap_or(theme.highlight),
+            ok: self.ok.unwrap_or(theme.ok),
+            error: self.error.unwrap_or(theme.error),
+            warn: self.warn.unwrap_or(theme.warn),
+            real: self.real.unwrap_or(theme.real),
+            target: self.target.unwrap_or(theme.target),
+            speed: self.speed.unwrap_or(theme.speed),
+        }
+    }
+}
+
+/// Parses a colour name (`light-cyan`), palette index (`208`) or hex value (`#ff8800`).
+fn color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Color>, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    Color::from_str(&text)
+        .map(Some)
+        .map_err(|_| serde::de::Error::custom(format!("unknown colour `{text}`")))
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::Color;
+
+    use super::{ChartMarker, Theme, ThemeConfig, ThemeName};
+
+    #[test]
+    fn overrides_apply_on_top_of_the_base_theme() {
+        let config: ThemeConfig = toml::from_str(
+            r##"
+            base = "monochrome"
+            marker = "half-block"
+            accent = "#ff8800"
+            real = "light-cyan"
+            "##,
+        )
+        .expect("theme should parse");
+        let theme = config.resolve(None);
+        assert_eq!(config.marker, ChartMarker::HalfBlock);
+        assert_eq!(theme.accent, Color::Rgb(0xFF, 0x88, 0x00));
+        assert_eq!(theme.real, Color::LightCyan);
+        assert_eq!(theme.target, Theme::MONOCHROME.target);
+
+        let theme = config.resolve(Some(ThemeName::HighContrast));
+        assert_eq!(theme.error, Theme::HIGH_CONTRAST.error);
+        assert_eq!(theme.accent, Color::Rgb(0xFF, 0x88, 0x00));
+    }
+
+    #[test]
+    fn unknown_colours_are_rejected() {
+        let err = toml::from_str::<ThemeConfig>(r#"accent = "ultraviolet""#)
+            .expect_err("colour should be rejected");
+        assert!(err.to_string().contains("ultraviolet"));
+    }
+}
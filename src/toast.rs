@@ -0,0 +1,93 @@
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime};
+
+use crate::event_log::Severity;
+
+/// How long a toast stays on screen.
+pub const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+/// Toasts shown at once; the oldest makes room for a new one.
+pub const MAX_TOASTS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Toast {
+    pub message: String,
+    pub severity: Severity,
+    pub shown_at: SystemTime,
+}
+
+/// Short-lived notifications in the top-right corner, oldest first.
+#[derive(Debug, Default)]
+pub struct Toasts {
+    toasts: VecDeque<Toast>,
+}
+
+impl Toasts {
+    /// Shows `message`, or restarts the timer of the newest toast when it says the same.
+    pub fn push(&mut self, now: SystemTime, severity: Severity, message: String) {
+        if let Some(last) = self.toasts.back_mut()
+            && last.message == message
+        {
+            last.shown_at = now;
+            return;
+        }
+        if self.toasts.len() == MAX_TOASTS {
+            self.toasts.pop_front();
+        }
+        self.toasts.push_back(Toast {
+            message,
+            severity,
+            shown_at: now,
+        });
+    }
+
+    /// Drops the toasts shown longer than `TOAST_DURATION` ago; `true` when any went.
+    pub fn expire(&mut self, now: SystemTime) -> bool {
+        let shown = self.toasts.len();
+        self.toasts.retain(|toast| {
+            now.duration_since(toast.shown_at).unwrap_or_default() < TOAST_DURATION
+        });
+        self.toasts.len() != shown
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Toast> {
+        self.toasts.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{MAX_TOASTS, TOAST_DURATION, Toasts};
+    use crate::event_log::Severity;
+
+    #[test]
+    fn toasts_expire_and_make_room_for_new_ones() {
+        let mut toasts = Toasts::default();
+        for index in 0..=MAX_TOASTS {
+            let at = UNIX_EPOCH + Duration::from_secs(u64::try_from(index).unwrap_or_default());
+            toasts.push(at, Severity::Info, format!("toast {index}"));
+        }
+        let messages: Vec<&str> = toasts.iter().map(|toast| toast.message.as_str()).collect();
+        assert_eq!(messages, ["toast 1", "toast 2", "toast 3"]);
+
+        assert!(toasts.expire(UNIX_EPOCH + TOAST_DURATION + Duration::from_secs(2)));
+        assert_eq!(toasts.iter().count(), 1);
+        assert!(!toasts.expire(UNIX_EPOCH + TOAST_DURATION + Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn repeated_message_restarts_the_newest_toast() {
+        let mut toasts = Toasts::default();
+        toasts.push(UNIX_EPOCH, Severity::Error, String::from("write rejected"));
+        toasts.push(
+            UNIX_EPOCH + TOAST_DURATION,
+            Severity::Error,
+            String::from("write rejected"),
+        );
+        assert_eq!(toasts.iter().count(), 1);
+        toasts.expire(UNIX_EPOCH + TOAST_DURATION + Duration::from_secs(1));
+        assert_eq!(toasts.iter().count(), 1);
+    }
+}
@@ -0,0 +1,134 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use color_eyre::eyre::{self, WrapErr};
+
+/// Polls further apart than this are treated as a gap and not integrated.
+const MAX_GAP: Duration = Duration::from_mins(1);
+
+/// How often the lifetime total is written back while running, bounding the loss on a crash.
+pub const SAVE_INTERVAL: Duration = Duration::from_mins(5);
+
+/// Integrates real flow over time into an extracted air volume.
+#[derive(Debug, Default)]
+pub struct Totalizer {
+    /// Volume in m3 extracted during this session.
+    pub session_m3: f64,
+    /// Volume in m3 carried over from earlier sessions, when persistence is enabled.
+    pub carried_m3: Option<f64>,
+    last: Option<(SystemTime, f64)>,
+    last_saved: Option<SystemTime>,
+}
+
+impl Totalizer {
+    /// Starts from the total stored in `path`, or zero if the file does not exist yet.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let carried = match fs::read_to_string(path) {
+            Ok(text) => text
+                .trim()
+                .parse::<f64>()
+                .wrap_err_with(|| format!("parse totalizer {}", path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => 0.0,
+            Err(err) => {
+                return Err(err).wrap_err_with(|| format!("read totalizer {}", path.display()));
+            }
+        };
+        Ok(Self {
+            carried_m3: Some(carried),
+            ..Self::default()
+        })
+    }
+
+    /// Writes the lifetime total through a temporary file so a crash never leaves it truncated.
+    pub fn save(&self, path: &Path) -> eyre::Result<()> {
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+        let temp = PathBuf::from(temp);
+        fs::write(&temp, format!("{:.6}\n", self.lifetime_m3()))
+            .wrap_err_with(|| format!("write totalizer {}", temp.display()))?;
+        fs::rename(&temp, path).wrap_err_with(|| format!("replace totalizer {}", path.display()))
+    }
+
+    /// Saves when `SAVE_INTERVAL` has passed since the last periodic save.
+    pub fn save_if_due(&mut self, path: &Path, now: SystemTime) -> eyre::Result<bool> {
+        let Some(last_saved) = self.last_saved else {
+            self.last_saved = Some(now);
+            return Ok(false);
+        };
+        if now.duration_since(last_saved).unwrap_or_default() < SAVE_INTERVAL {
+            return Ok(false);
+        }
+        self.last_saved = Some(now);
+        self.save(path).map(|()| true)
+    }
+
+    /// Adds the volume moved since the previous sample using the trapezoidal rule.
+    pub fn add(&mut self, timestamp: SystemTime, real_flow_m3h: f64) {
+        if let Some((last_time, last_flow)) = self.last
+            && let Ok(elapsed) = timestamp.duration_since(last_time)
+            && elapsed <= MAX_GAP
+        {
+            let hours = elapsed.as_secs_f64() / 3_600.0;
+            self.session_m3 += hours * (last_flow + real_flow_m3h) / 2.0;
+        }
+        self.last = Some((timestamp, real_flow_m3h));
+    }
+
+    pub fn lifetime_m3(&self) -> f64 {
+        self.carried_m3.unwrap_or_default() + self.session_m3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{SAVE_INTERVAL, Totalizer};
+
+    #[test]
+    fn integrates_flow_and_skips_gaps() {
+        let mut totalizer = Totalizer::default();
+        totalizer.add(UNIX_EPOCH, 60.0);
+        totalizer.add(UNIX_EPOCH + Duration::from_secs(30), 60.0);
+        assert!((totalizer.session_m3 - 0.5).abs() < 1e-9);
+
+        totalizer.add(UNIX_EPOCH + Duration::from_hours(1), 60.0);
+        assert!((totalizer.session_m3 - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn persisted_total_roundtrips() {
+        let path = std::env::temp_dir().join(format!("totalizer-{}.txt", std::process::id()));
+        let mut totalizer = Totalizer::load(&path).expect("missing file starts at zero");
+        totalizer.add(UNIX_EPOCH, 36.0);
+        totalizer.add(UNIX_EPOCH + Duration::from_secs(10), 36.0);
+        totalizer.save(&path).expect("total should save");
+
+        let reloaded = Totalizer::load(&path).expect("total should load");
+        std::fs::remove_file(&path).ok();
+        assert!((reloaded.lifetime_m3() - 0.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn periodic_save_waits_for_the_interval() {
+        let path = std::env::temp_dir().join(format!("totalizer-due-{}.txt", std::process::id()));
+        let mut totalizer = Totalizer::default();
+        assert!(
+            !totalizer
+                .save_if_due(&path, UNIX_EPOCH)
+                .expect("no save yet")
+        );
+        assert!(!path.exists());
+
+        let due = UNIX_EPOCH + SAVE_INTERVAL;
+        assert!(
+            totalizer
+                .save_if_due(&path, due)
+                .expect("total should save")
+        );
+        assert!(path.exists());
+        std::fs::remove_file(&path).ok();
+        assert!(!totalizer.save_if_due(&path, due).expect("saved just now"));
+    }
+}
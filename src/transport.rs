@@ -1,27 +1,144 @@
 use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
 use color_eyre::eyre;
 
-use crate::backend::build_backend;
-use crate::data::DeviceStatus;
+use crate::backend::{Backend, DetachedBackend, build_backend, remote_write_for_command};
+use crate::constants::{REG_BAUD_RATE, REG_BAUD_RATE_LO, REG_COMM_ADDRESS};
+use crate::data::{DeviceIdentity, DeviceStatus, Filter, OperatingMode};
 use crate::interface::InterfaceMode;
+use crate::profile::DeviceProfile;
+use crate::rtu::FrameError;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TransportCommand {
     SetPower(bool),
     SetTargetFlow(u16),
+    SetBeeper(bool),
+    SetMode(OperatingMode),
+    ResetFilter(Filter),
+    WriteRegister {
+        register: u16,
+        value: u16,
+    },
+    Reconfigure {
+        address: u8,
+        baud: u32,
+    },
+    Reconnect {
+        address: u8,
+        baud: u32,
+    },
+    /// Changes how often the worker polls the device status.
+    SetPollInterval(Duration),
+    /// Reads the status at once instead of waiting for the next poll.
+    PollNow,
     Terminate,
 }
 
+impl TransportCommand {
+    /// The single register write this command performs on a Modbus device.
+    pub fn register_write(&self) -> Option<(u16, u16)> {
+        remote_write_for_command(self)
+    }
+}
+
 #[derive(Debug)]
 pub enum TransportEvent {
     Status(DeviceStatus),
     Connection(bool),
+    Link(LinkOutcome),
+    /// A write was refused before reaching the device.
+    Rejected(String),
+    /// Identification read whenever the link comes up.
+    Identity(DeviceIdentity),
+    /// A command was accepted by the backend.
+    Applied(TransportCommand),
+    /// Round-trip time or failure class of one status poll.
+    Poll(PollOutcome),
+    /// A recoverable link error, such as a failed poll or write.
+    Fault(String),
+    /// The worker stopped; no further polls follow.
     Error(eyre::Report),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PollOutcome {
+    Answered(Duration),
+    Timeout,
+    Crc,
+    /// Any other failure, such as an exception reply or a closed port.
+    Failed,
+}
+
+impl PollOutcome {
+    /// Classifies a failed request by the frame error underneath it.
+    pub fn from_error(err: &eyre::Report) -> Self {
+        match err
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<FrameError>())
+        {
+            Some(FrameError::Timeout { .. }) => Self::Timeout,
+            Some(FrameError::Crc { .. }) => Self::Crc,
+            None => Self::Failed,
+        }
+    }
+
+    /// `answered`, `timeout`, `crc` or `failed`, as exporters report it.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Answered(_) => "answered",
+            Self::Timeout => "timeout",
+            Self::Crc => "crc",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// Poll round-trip and error counters shown in the status bar.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PollStats {
+    pub last_latency: Option<Duration>,
+    pub last_success: Option<SystemTime>,
+    pub timeouts: u64,
+    pub crc_errors: u64,
+    pub other_errors: u64,
+}
+
+impl PollStats {
+    pub fn record(&mut self, outcome: PollOutcome, now: SystemTime) {
+        match outcome {
+            PollOutcome::Answered(latency) => {
+                self.last_latency = Some(latency);
+                self.last_success = Some(now);
+            }
+            PollOutcome::Timeout => self.timeouts += 1,
+            PollOutcome::Crc => self.crc_errors += 1,
+            PollOutcome::Failed => self.other_errors += 1,
+        }
+    }
+
+    /// Time since the last answered poll, or `None` if none has been answered yet.
+    pub fn since_success(&self, now: SystemTime) -> Option<Duration> {
+        self.last_success
+            .map(|at| now.duration_since(at).unwrap_or_default())
+    }
+}
+
+/// Result of changing the link address or baud rate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkOutcome {
+    /// The device answered a poll with the new parameters.
+    Verified { address: u8, baud: u32 },
+    /// The settings could not be written; the link is unchanged.
+    Rejected(String),
+    /// The link now uses the new parameters but the device does not answer.
+    Unresponsive { address: u8, baud: u32 },
+    /// Only some settings were written and could not be restored; the device state is unknown.
+    Indeterminate(String),
+}
+
 #[derive(Debug, Clone)]
 pub struct TransportConfig {
     pub port: Option<String>,
@@ -30,6 +147,7 @@ pub struct TransportConfig {
     pub poll_interval: Duration,
     pub read_only: bool,
     pub interface: InterfaceMode,
+    pub profile: &'static DeviceProfile,
 }
 
 pub fn spawn_worker(
@@ -48,42 +166,54 @@ pub fn spawn_worker(
 
 #[allow(clippy::needless_pass_by_value)]
 fn run_worker_loop(
-    config: TransportConfig,
+    mut config: TransportConfig,
     command_rx: Receiver<TransportCommand>,
     event_tx: &Sender<TransportEvent>,
 ) -> eyre::Result<()> {
     let mut backend = build_backend(&config)?;
+    if let Ok(identity) = backend.identify() {
+        event_tx.send(TransportEvent::Identity(identity)).ok();
+    }
+    // Repeats of the same poll failure are reported once, until a poll succeeds again.
+    let mut last_fault: Option<String> = None;
 
     loop {
         match command_rx.recv_timeout(config.poll_interval) {
-            Ok(TransportCommand::SetPower(on)) => {
-                if !config.read_only
-                    && backend
-                        .apply_command(&TransportCommand::SetPower(on))
-                        .is_err()
-                {
-                    event_tx.send(TransportEvent::Connection(false)).ok();
-                }
+            Ok(TransportCommand::Terminate) => break,
+            Ok(TransportCommand::SetPollInterval(interval)) => config.poll_interval = interval,
+            Ok(TransportCommand::PollNow) | Err(RecvTimeoutError::Timeout) => {
+                poll(backend.as_mut(), &mut last_fault, event_tx);
             }
-            Ok(TransportCommand::SetTargetFlow(value)) => {
-                if !config.read_only
-                    && backend
-                        .apply_command(&TransportCommand::SetTargetFlow(value))
-                        .is_err()
-                {
-                    event_tx.send(TransportEvent::Connection(false)).ok();
+            Ok(TransportCommand::Reconfigure { address, baud }) => {
+                if !config.read_only {
+                    let outcome = reconfigure(&mut backend, &mut config, address, baud, event_tx);
+                    event_tx.send(TransportEvent::Link(outcome)).ok();
                 }
             }
-            Ok(TransportCommand::Terminate) => break,
-            Err(RecvTimeoutError::Timeout) => match backend.poll_status() {
-                Ok(status) => {
-                    event_tx.send(TransportEvent::Status(status)).ok();
-                    event_tx.send(TransportEvent::Connection(true)).ok();
+            Ok(TransportCommand::Reconnect { address, baud }) => {
+                config.address = address;
+                config.baud = baud;
+                let outcome = reconnect(&mut backend, &config, event_tx);
+                event_tx.send(TransportEvent::Link(outcome)).ok();
+            }
+            Ok(command) => {
+                if config.read_only {
+                    continue;
                 }
-                Err(_) => {
+                if let Err(reason) = validate_command(&command, config.profile) {
+                    event_tx.send(TransportEvent::Rejected(reason)).ok();
+                } else if !backend.supports(&command) {
+                    let interface = format!("{:?}", config.interface).to_lowercase();
+                    let reason = format!("{command:?} is not supported over {interface}");
+                    event_tx.send(TransportEvent::Rejected(reason)).ok();
+                } else if let Err(err) = backend.apply_command(&command) {
+                    let fault = format!("{command:?} failed: {err}");
+                    event_tx.send(TransportEvent::Fault(fault)).ok();
                     event_tx.send(TransportEvent::Connection(false)).ok();
+                } else {
+                    event_tx.send(TransportEvent::Applied(command)).ok();
                 }
-            },
+            }
             Err(RecvTimeoutError::Disconnected) => {
                 return Err(eyre::eyre!("command channel closed"));
             }
@@ -92,3 +222,286 @@ fn run_worker_loop(
 
     Ok(())
 }
+
+/// Reads the status once and reports it; repeats of the same failure are reported once, until
+/// a poll succeeds again.
+fn poll(
+    backend: &mut dyn Backend,
+    last_fault: &mut Option<String>,
+    event_tx: &Sender<TransportEvent>,
+) {
+    let started = Instant::now();
+    match backend.poll_status() {
+        Ok(status) => {
+            *last_fault = None;
+            let outcome = PollOutcome::Answered(started.elapsed());
+            event_tx.send(TransportEvent::Poll(outcome)).ok();
+            event_tx.send(TransportEvent::Status(status)).ok();
+            event_tx.send(TransportEvent::Connection(true)).ok();
+        }
+        Err(err) => {
+            let outcome = PollOutcome::from_error(&err);
+            event_tx.send(TransportEvent::Poll(outcome)).ok();
+            let fault = format!("status poll failed: {err}");
+            if last_fault.as_ref() != Some(&fault) {
+                event_tx.send(TransportEvent::Fault(fault.clone())).ok();
+                *last_fault = Some(fault);
+            }
+            event_tx.send(TransportEvent::Connection(false)).ok();
+        }
+    }
+}
+
+/// Checks a command's register write against the profile's register metadata.
+pub fn validate_command(command: &TransportCommand, profile: &DeviceProfile) -> Result<(), String> {
+    match command.register_write() {
+        Some((register, value)) => validate_write(register, value, profile),
+        None => Ok(()),
+    }
+}
+
+fn validate_write(register: u16, value: u16, profile: &DeviceProfile) -> Result<(), String> {
+    let info = profile
+        .register_info(register)
+        .ok_or_else(|| format!("register 0x{register:04X} is not catalogued"))?;
+    if !info.writable {
+        return Err(format!("{} is read-only", info.name));
+    }
+    if !info.accepts(value) {
+        return Err(format!(
+            "{}: {} is outside {}..={}",
+            info.name,
+            info.format_value(value),
+            info.format_value(info.min),
+            info.format_value(info.max)
+        ));
+    }
+    Ok(())
+}
+
+/// Register writes that move the device to a new address and baud rate.
+///
+/// The address goes first and the baud rate last, high word before low word, so the link
+/// speed can change only with the final write. A device that applies the address at once
+/// stops answering the baud writes, which `reconfigure` reports as a partial change.
+pub fn reconfigure_writes(address: u8, baud: u32) -> [(u16, u16); 3] {
+    let [hi_hi, hi_lo, lo_hi, lo_lo] = baud.to_be_bytes();
+    [
+        (REG_COMM_ADDRESS, u16::from(address)),
+        (REG_BAUD_RATE, u16::from_be_bytes([hi_hi, hi_lo])),
+        (REG_BAUD_RATE_LO, u16::from_be_bytes([lo_hi, lo_lo])),
+    ]
+}
+
+fn reconfigure(
+    backend: &mut Box<dyn Backend + Send>,
+    config: &mut TransportConfig,
+    address: u8,
+    baud: u32,
+    event_tx: &Sender<TransportEvent>,
+) -> LinkOutcome {
+    let writes = reconfigure_writes(address, baud);
+    if let Some(reason) = writes
+        .iter()
+        .find_map(|(register, value)| validate_write(*register, *value, config.profile).err())
+    {
+        return LinkOutcome::Rejected(reason);
+    }
+    // Current values, so a sequence that fails halfway can be undone.
+    let Ok(before) = backend.poll_status() else {
+        return LinkOutcome::Rejected(String::from("device did not answer before the change"));
+    };
+    for (written, (register, value)) in writes.iter().enumerate() {
+        let write = TransportCommand::WriteRegister {
+            register: *register,
+            value: *value,
+        };
+        if let Err(err) = backend.apply_command(&write) {
+            return restore_link_registers(backend.as_mut(), &before, &writes[..written], &err);
+        }
+    }
+    config.address = address;
+    config.baud = baud;
+    reconnect(backend, config, event_tx)
+}
+
+/// Writes back the registers changed before `err`, newest first.
+fn restore_link_registers(
+    backend: &mut dyn Backend,
+    before: &DeviceStatus,
+    written: &[(u16, u16)],
+    err: &eyre::Report,
+) -> LinkOutcome {
+    for (register, _) in written.iter().rev() {
+        let restored = before.register(*register).is_some_and(|value| {
+            backend
+                .apply_command(&TransportCommand::WriteRegister {
+                    register: *register,
+                    value,
+                })
+                .is_ok()
+        });
+        if !restored {
+            return LinkOutcome::Indeterminate(format!("{err}"));
+        }
+    }
+    LinkOutcome::Rejected(format!("{err}"))
+}
+
+fn reconnect(
+    backend: &mut Box<dyn Backend + Send>,
+    config: &TransportConfig,
+    event_tx: &Sender<TransportEvent>,
+) -> LinkOutcome {
+    // Release the port before reopening it, serial ports are opened exclusively.
+    *backend = Box::new(DetachedBackend);
+    let unresponsive = LinkOutcome::Unresponsive {
+        address: config.address,
+        baud: config.baud,
+    };
+    let Ok(new_backend) = build_backend(config) else {
+        event_tx.send(TransportEvent::Connection(false)).ok();
+        return unresponsive;
+    };
+    *backend = new_backend;
+    if let Ok(status) = backend.poll_status() {
+        if let Ok(identity) = backend.identify() {
+            event_tx.send(TransportEvent::Identity(identity)).ok();
+        }
+        event_tx.send(TransportEvent::Status(status)).ok();
+        event_tx.send(TransportEvent::Connection(true)).ok();
+        LinkOutcome::Verified {
+            address: config.address,
+            baud: config.baud,
+        }
+    } else {
+        event_tx.send(TransportEvent::Connection(false)).ok();
+        unresponsive
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use color_eyre::eyre;
+
+    use std::time::{Duration, SystemTime};
+
+    use super::{
+        LinkOutcome, PollOutcome, PollStats, TransportCommand, reconfigure_writes,
+        restore_link_registers, validate_command,
+    };
+    use crate::backend::Backend;
+    use crate::constants::{REG_BAUD_RATE, REG_BAUD_RATE_LO, REG_COMM_ADDRESS, REG_REAL_FLOW};
+    use crate::data::DeviceStatus;
+    use crate::profile::QUICK_6101A2;
+    use crate::rtu::FrameError;
+
+    /// Records register writes and refuses the ones to `failing`.
+    struct RecordingBackend {
+        failing: Option<u16>,
+        writes: Vec<(u16, u16)>,
+    }
+
+    impl Backend for RecordingBackend {
+        fn poll_status(&mut self) -> eyre::Result<DeviceStatus> {
+            Err(eyre::eyre!("not polled"))
+        }
+
+        fn apply_command(&mut self, command: &TransportCommand) -> eyre::Result<()> {
+            let (register, value) = command.register_write().expect("register write");
+            if self.failing == Some(register) {
+                return Err(eyre::eyre!("timeout"));
+            }
+            self.writes.push((register, value));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn partial_reconfiguration_is_restored_or_reported() {
+        let mut before = DeviceStatus::from_registers(vec![0; 24]).expect("status should parse");
+        before.registers[usize::from(REG_COMM_ADDRESS)] = 2;
+        let written = &reconfigure_writes(5, 115_200)[..1];
+        let err = eyre::eyre!("timeout");
+
+        let mut backend = RecordingBackend {
+            failing: None,
+            writes: Vec::new(),
+        };
+        let outcome = restore_link_registers(&mut backend, &before, written, &err);
+        assert_eq!(outcome, LinkOutcome::Rejected(String::from("timeout")));
+        assert_eq!(backend.writes, vec![(REG_COMM_ADDRESS, 2)]);
+
+        backend.failing = Some(REG_COMM_ADDRESS);
+        let outcome = restore_link_registers(&mut backend, &before, written, &err);
+        assert_eq!(outcome, LinkOutcome::Indeterminate(String::from("timeout")));
+    }
+
+    #[test]
+    fn reconfigure_writes_split_baud_into_words() {
+        assert_eq!(
+            reconfigure_writes(5, 115_200),
+            [
+                (REG_COMM_ADDRESS, 5),
+                (REG_BAUD_RATE, 0x0001),
+                (REG_BAUD_RATE_LO, 0xC200),
+            ]
+        );
+    }
+
+    #[test]
+    fn validation_rejects_out_of_range_and_read_only_writes() {
+        assert!(validate_command(&TransportCommand::SetTargetFlow(75), &QUICK_6101A2).is_ok());
+        let err = validate_command(&TransportCommand::SetTargetFlow(120), &QUICK_6101A2)
+            .expect_err("6101A2 tops out at 100");
+        assert!(err.contains("30..=100"));
+        assert!(
+            validate_command(
+                &TransportCommand::WriteRegister {
+                    register: REG_REAL_FLOW,
+                    value: 1,
+                },
+                &QUICK_6101A2,
+            )
+            .is_err()
+        );
+        assert!(validate_command(&TransportCommand::Terminate, &QUICK_6101A2).is_ok());
+    }
+
+    #[test]
+    fn poll_errors_are_classified_through_context() {
+        let timeout = eyre::Report::from(FrameError::Timeout {
+            expected: 8,
+            received: 0,
+        })
+        .wrap_err("status poll failed");
+        assert_eq!(PollOutcome::from_error(&timeout), PollOutcome::Timeout);
+        let crc = eyre::Report::from(FrameError::Crc {
+            expected: 0x1234,
+            seen: 0x4321,
+        });
+        assert_eq!(PollOutcome::from_error(&crc), PollOutcome::Crc);
+        assert_eq!(
+            PollOutcome::from_error(&eyre::eyre!("exception 0x02")),
+            PollOutcome::Failed
+        );
+    }
+
+    #[test]
+    fn poll_stats_count_failures_and_keep_the_last_success() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+        let mut stats = PollStats::default();
+        assert_eq!(stats.since_success(start), None);
+        stats.record(PollOutcome::Answered(Duration::from_millis(42)), start);
+        stats.record(PollOutcome::Timeout, start + Duration::from_secs(1));
+        stats.record(PollOutcome::Crc, start + Duration::from_secs(2));
+        stats.record(PollOutcome::Timeout, start + Duration::from_secs(3));
+        assert_eq!(stats.last_latency, Some(Duration::from_millis(42)));
+        assert_eq!(stats.timeouts, 2);
+        assert_eq!(stats.crc_errors, 1);
+        assert_eq!(
+            stats.since_success(start + Duration::from_secs(3)),
+            Some(Duration::from_secs(3))
+        );
+    }
+}
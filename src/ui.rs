@@ -1,410 +1,2793 @@
-use std::collections::VecDeque;
+use std::fmt::Write;
+use std::rc::Rc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use chrono::{DateTime, Local};
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Direction, Layout, Margin, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{
-    Axis, Block, Borders, Chart, Clear, Dataset, Gauge, GraphType, Paragraph, Wrap,
+    Axis, Block, Borders, Chart, Clear, Dataset, Gauge, GraphType, Padding, Paragraph, Scrollbar,
+    ScrollbarOrientation, ScrollbarState, Sparkline, Tabs, Wrap,
 };
-use ratatui::{symbols, Frame};
+use ratatui::{Frame, symbols};
 
-use crate::app::AppState;
-use crate::constants::{STATE_OFF, STATE_ON, TARGET_FLOW_MAX};
-use crate::data::register_name;
+use crate::app::{AppState, HitAreas, Panels, Tab, slider_column};
+use crate::config::{ChartAxes, GaugeLevel};
+use crate::config_editor::{CONFIG_REGISTERS, PendingWrite};
+use crate::constants::{BEEPER_ON, REG_BEEPER, REG_STATUS_FLAGS, STATE_OFF, STATE_ON};
+use crate::data::{DeviceStatus, Filter, RegisterInfo, RegisterKind};
+use crate::dialog::{ErrorChoice, RESET_WORD, ResetStage};
+use crate::event_log::{EventSource, Severity};
+use crate::fleet::Fleet;
+use crate::history::{History, REGISTER_TRAIL_LEN, Sample, Trend, downsample};
+use crate::i18n::Locale;
 use crate::interface::InterfaceMode;
+use crate::keymap::{self, Scope};
+use crate::ramp::Ramp;
+use crate::register_editor::REGISTER_COLUMNS;
+use crate::reminder::Countdown;
+use crate::setup::{SetupField, SetupScreen};
+use crate::theme::Theme;
+use crate::wizard::{LinkWizard, WizardStep};
+
+/// Dashboard height below which the pinned registers row is left out.
+const PINNED_MIN_HEIGHT: u16 = 20;
+/// Smallest terminal the monitor draws into; below it only a hint is shown.
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 8;
+/// Terminal heights below which the help rows, then the tab bar, make room for the dashboard.
+const HELP_MIN_HEIGHT: u16 = 30;
+const TABS_MIN_HEIGHT: u16 = 14;
+/// Changes smaller than these show a steady trend arrow.
+const FLOW_TREND_TOLERANCE: f64 = 0.5;
+const SPEED_TREND_TOLERANCE: f64 = 10.0;
+const FILTER_TREND_TOLERANCE: f64 = 0.5;
+/// Flow chart height from which a history minimap row is drawn under it.
+const MINIMAP_MIN_HEIGHT: u16 = 10;
+
+fn too_small(area: Rect) -> bool {
+    area.width < MIN_WIDTH || area.height < MIN_HEIGHT
+}
+
+/// Header, tab bar, active tab, status bar and help rows; compact mode and short terminals
+/// drop the tab bar and help rows.
+fn screen_chunks(app: &AppState, area: Rect) -> Rc<[Rect]> {
+    let tabs = u16::from(!app.compact && area.height >= TABS_MIN_HEIGHT);
+    let help = if !app.compact && area.height >= HELP_MIN_HEIGHT {
+        3
+    } else {
+        0
+    };
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(tabs),
+            Constraint::Min(0),
+            Constraint::Length(1),
+            Constraint::Length(help),
+        ])
+        .split(area)
+}
+
+/// Stores the sizes input handling needs from the next frame, so rendering stays read-only.
+pub fn update_layout(app: &mut AppState, area: Rect) {
+    let content = screen_chunks(app, area)[2];
+    let viewport = usize::from(content.height.saturating_sub(2));
+    let shown = app.register_editor.shown(
+        app.profile,
+        app.status
+            .as_ref()
+            .map_or(0, |status| status.registers.len()),
+    );
+    app.register_editor.fit(
+        viewport,
+        register_line_count(app.status.as_ref(), shown.len(), app.register_changes.len()),
+        &shown,
+    );
+    app.log_view.fit(viewport, app.event_log.iter().len());
+
+    app.hit_areas = HitAreas::default();
+    if app.tab == Tab::Dashboard && !app.compact && !too_small(area) {
+        let dashboard = dashboard_chunks(app, content);
+        app.hit_areas.target_flow = target_flow_area(app, dashboard[0]);
+        app.hit_areas.slider = slider_layout(app, dashboard[1]).2;
+        let plot = flow_chart_areas(dashboard[2], app.speed_scale.max(), app).0;
+        app.hit_areas.flow_chart = Rect::new(
+            plot.x + 1,
+            plot.y + 1,
+            plot.width.saturating_sub(2),
+            plot_rows(plot),
+        )
+        .intersection(plot);
+        let filters = app.profile.filters;
+        app.hit_areas.filters = filters
+            .iter()
+            .copied()
+            .zip(filter_chunks(filters, dashboard[4]).iter().copied())
+            .collect();
+    }
+}
 
 pub fn render_ui(frame: &mut Frame, app: &AppState) {
-    let mut constraints = vec![
-        Constraint::Length(1),
-        Constraint::Length(3),
-        Constraint::Length(10),
-        Constraint::Length(10),
-        Constraint::Length(6),
-    ];
-    if app.show_debug {
-        let debug_lines = app
+    if too_small(frame.area()) {
+        render_too_small(frame, app);
+        if app.ascii {
+            asciify(frame.buffer_mut());
+        }
+        return;
+    }
+    let chunks = screen_chunks(app, frame.area());
+
+    render_header(frame, chunks[0], app);
+    render_tabs(frame, chunks[1], app);
+    match app.tab {
+        Tab::Dashboard if app.compact => render_compact_dashboard(frame, chunks[2], app),
+        Tab::Dashboard => render_dashboard(frame, chunks[2], app),
+        Tab::Registers => render_debug(frame, chunks[2], app),
+        Tab::Config => render_config_tab(frame, chunks[2], app),
+        Tab::Filters => render_filter_history(frame, chunks[2], app),
+        Tab::Log => render_log(frame, chunks[2], app),
+    }
+    render_status_bar(frame, chunks[3], app);
+    render_help(frame, chunks[4], app);
+
+    if app.input_mode {
+        render_target_popup(frame, app);
+    }
+    if app.config_editor.is_some() {
+        render_config_popup(frame, app);
+    }
+    if app.link_wizard.is_some() {
+        render_wizard_popup(frame, app);
+    }
+    if app.filter_reset.is_some() {
+        render_filter_reset_popup(frame, app);
+    }
+    if app.filter_detail.is_some() {
+        render_filter_detail_popup(frame, app);
+    }
+    if app.power_off_prompt {
+        render_power_off_popup(frame, app);
+    }
+    if app.help_open {
+        render_help_overlay(frame, app);
+    }
+    if app.error_screen.is_some() {
+        render_error_screen(frame, app);
+    }
+    render_toasts(frame, app);
+    if app.ascii {
+        asciify(frame.buffer_mut());
+    }
+}
+
+/// Recent notifications stacked in the top-right corner, newest at the bottom.
+fn render_toasts(frame: &mut Frame, app: &AppState) {
+    let theme = &app.theme;
+    let screen = frame.area();
+    let mut y = screen.y + 1;
+    for toast in app.toasts.iter() {
+        let width = u16::try_from(Span::raw(toast.message.as_str()).width() + 4)
+            .unwrap_or(u16::MAX)
+            .min(screen.width / 2);
+        let area =
+            Rect::new(screen.right().saturating_sub(width + 1), y, width, 3).intersection(screen);
+        let color = match toast.severity {
+            Severity::Info => theme.ok,
+            Severity::Warning => theme.warn,
+            Severity::Error => theme.error,
+        };
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(toast.message.as_str()).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(color))
+                    .padding(Padding::horizontal(1)),
+            ),
+            area,
+        );
+        y += 3;
+    }
+}
+
+/// The connection form shown before the worker starts when no port was given.
+pub fn render_setup(
+    frame: &mut Frame,
+    setup: &SetupScreen,
+    theme: &Theme,
+    locale: &Locale,
+    ascii: bool,
+) {
+    let area = centered_rect(60, 50, frame.area());
+    let label = Style::default().fg(theme.muted);
+    let mut content = vec![Line::from("")];
+    for field in SetupField::ALL {
+        let value = match field {
+            SetupField::Port if setup.port.is_empty() => String::from("_"),
+            SetupField::Port => setup.port.clone(),
+            SetupField::Baud => setup.baud.to_string(),
+            SetupField::Address => setup.address.to_string(),
+            SetupField::Interface => format!("{:?}", setup.interface),
+        };
+        let (marker, style) = if field == setup.field {
+            (
+                "> ",
+                Style::default()
+                    .fg(theme.highlight)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else {
+            ("  ", Style::default().fg(theme.text))
+        };
+        content.push(Line::from(vec![
+            Span::styled(marker, style),
+            Span::styled(format!("{:<10}", locale.text(field.label())), label),
+            Span::styled(format!("< {value} >"), style),
+        ]));
+    }
+    content.push(Line::from(""));
+    if setup.ports.is_empty() {
+        content.push(Line::from(Span::styled(
+            locale.text("No serial ports detected; type the path"),
+            Style::default().fg(theme.warn),
+        )));
+    }
+    if let Some(error) = &setup.error {
+        content.push(Line::from(Span::styled(
+            error.clone(),
+            Style::default().fg(theme.error),
+        )));
+    }
+    content.push(Line::from(Span::styled(
+        locale.text("↑/↓ field  ←/→ change  type to edit  Enter connect  Esc quit"),
+        label,
+    )));
+
+    let popup = Paragraph::new(content)
+        .block(
+            Block::default()
+                .title(format!(" {} ", locale.text("Connect")))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.accent)),
+        )
+        .wrap(Wrap { trim: true });
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+    if ascii {
+        asciify(frame.buffer_mut());
+    }
+}
+
+fn render_too_small(frame: &mut Frame, app: &AppState) {
+    let area = frame.area();
+    let text = app.locale.format(
+        "Terminal too small ({}x{}), need {}x{}",
+        &[&area.width, &area.height, &MIN_WIDTH, &MIN_HEIGHT],
+    );
+    let paragraph = Paragraph::new(text)
+        .style(Style::default().fg(app.theme.warn))
+        .wrap(Wrap { trim: true });
+    frame.render_widget(paragraph, area);
+}
+
+fn render_tabs(frame: &mut Frame, area: Rect, app: &AppState) {
+    let theme = &app.theme;
+    let titles = Tab::ALL
+        .iter()
+        .enumerate()
+        .map(|(index, tab)| format!("F{} {}", index + 1, app.locale.text(tab.title())));
+    let tabs = Tabs::new(titles)
+        .select(app.tab.index())
+        .style(Style::default().fg(theme.muted))
+        .highlight_style(
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::BOLD),
+        );
+    frame.render_widget(tabs, area);
+}
+
+/// Status, target slider, flow chart, speed chart and filter gauge rows of the dashboard, below the
+/// filter banner row while it is shown.
+/// The `dashboard_rows` panels, followed by the pinned registers row.
+fn dashboard_chunks(app: &AppState, area: Rect) -> Rc<[Rect]> {
+    let banner = u16::from(app.filter_banner_shown());
+    let pinned = if app.pinned.is_empty() || area.height < PINNED_MIN_HEIGHT {
+        0
+    } else {
+        3
+    };
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(banner),
+            Constraint::Length(pinned),
+            Constraint::Min(0),
+        ])
+        .split(area);
+    let mut chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(dashboard_rows(rows[2].height, app.shown_panels()).map(Constraint::Length))
+        .split(rows[2])
+        .to_vec();
+    chunks.push(rows[1]);
+    chunks.into()
+}
+
+/// Heights of the status, slider, flow chart, speed chart and filter rows for `height` rows,
+/// leaving hidden panels at zero.
+///
+/// The status line comes first, then the filter gauges and the target slider; the speed
+/// chart is dropped before the flow chart, and charts shrink to two halves before either
+/// disappears. Rows no chart can use go to the filter gauges.
+fn dashboard_rows(height: u16, panels: Panels) -> [u16; 5] {
+    const MIN_CHART: u16 = 6;
+    fn take(rest: &mut u16, rows: u16) -> u16 {
+        let taken = rows.min(*rest);
+        *rest -= taken;
+        taken
+    }
+
+    let mut rest = height;
+    let status = if panels.status { take(&mut rest, 3) } else { 0 };
+    let mut filters = if panels.filters {
+        take(&mut rest, 3)
+    } else {
+        0
+    };
+    let slider = if panels.slider && rest >= 3 {
+        take(&mut rest, 3)
+    } else {
+        0
+    };
+    let full_charts = if panels.flow { 8 } else { 0 } + if panels.speed { 10 } else { 0 };
+    if panels.filters && rest >= full_charts + 3 {
+        filters += take(&mut rest, 3);
+    }
+    let (flow, speed) = match (panels.flow, panels.speed) {
+        (true, true) if rest >= 8 + 10 => (rest - 10, 10),
+        (true, true) if rest >= 2 * MIN_CHART => (rest - rest / 2, rest / 2),
+        (true, _) if rest >= MIN_CHART => (rest, 0),
+        (false, true) if rest >= MIN_CHART => (0, rest),
+        _ => (0, 0),
+    };
+    rest -= flow + speed;
+    if panels.filters && flow + speed == 0 {
+        filters += rest.min(6 - filters);
+    }
+    [status, slider, flow, speed, filters]
+}
+
+fn render_dashboard(frame: &mut Frame, area: Rect, app: &AppState) {
+    let chunks = dashboard_chunks(app, area);
+
+    if app.filter_banner_shown() {
+        render_filter_banner(frame, Rect { height: 1, ..area }, app);
+    }
+    render_status(frame, chunks[0], app);
+    render_target_slider(frame, chunks[1], app);
+    render_flow_chart(frame, chunks[2], app);
+    render_speed_chart(frame, chunks[3], app);
+    render_filters(frame, chunks[4], app);
+    render_pinned(frame, chunks[5], app);
+}
+
+/// Live values of the registers pinned from the Registers view.
+fn render_pinned(frame: &mut Frame, area: Rect, app: &AppState) {
+    if area.is_empty() {
+        return;
+    }
+    let theme = &app.theme;
+    let mut spans = Vec::new();
+    for register in &app.pinned {
+        if !spans.is_empty() {
+            spans.push(Span::styled("  │  ", Style::default().fg(theme.muted)));
+        }
+        let info = app.profile.register_info(*register);
+        let value = app
             .status
             .as_ref()
-            .map_or(1, |status| status.registers.len().div_ceil(2));
-        let debug_height = u16::try_from(debug_lines + 2).unwrap_or(u16::MAX);
-        constraints.push(Constraint::Length(debug_height));
+            .and_then(|status| status.register(*register));
+        let label = info.map_or_else(|| format!("0x{register:04X}"), |info| info.name.to_string());
+        let text = match (value, info) {
+            (Some(value), Some(info)) => format!("{} {}", info.format_value(value), info.unit),
+            (Some(value), None) => format!("{value} (0x{value:04X})"),
+            (None, _) => String::from("--"),
+        };
+        spans.extend([
+            Span::styled(format!("{label}: "), Style::default().fg(theme.muted)),
+            Span::styled(
+                text.trim_end().to_string(),
+                Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+            ),
+        ]);
+    }
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(app.locale.text("Pinned"))
+        .border_style(Style::default().fg(theme.accent));
+    frame.render_widget(Paragraph::new(Line::from(spans)).block(block), area);
+}
+
+/// Scale labels and the track between them inside the slider block at `area`.
+fn slider_layout(app: &AppState, area: Rect) -> (String, String, Rect) {
+    let unit = app.flow_unit;
+    let min = format!("{:.0} ", unit.convert(f64::from(app.profile.flow_min)));
+    let max = format!(" {:.0}", unit.convert(f64::from(app.profile.flow_max)));
+    let inner = area.inner(Margin::new(1, 1));
+    let labels = u16::try_from(min.len() + max.len()).unwrap_or(u16::MAX);
+    let track = Rect::new(
+        inner.x + u16::try_from(min.len()).unwrap_or(u16::MAX),
+        inner.y,
+        inner.width.saturating_sub(labels),
+        inner.height.min(1),
+    )
+    .intersection(inner);
+    (min, max, track)
+}
+
+/// Target flow thumb and real flow marker on one `flow_min..=flow_max` scale.
+fn render_target_slider(frame: &mut Frame, area: Rect, app: &AppState) {
+    let theme = &app.theme;
+    let profile = app.profile;
+    let (min_label, max_label, track) = slider_layout(app, area);
+    let real_flow = app
+        .history
+        .iter()
+        .next_back()
+        .map(|sample| sample.smoothed_flow);
+    let target = slider_column(
+        app.target_flow,
+        profile.flow_min,
+        profile.flow_max,
+        track.width,
+    );
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let real = real_flow.map(|flow| {
+        let flow = flow.round().clamp(0.0, f64::from(u16::MAX)) as u16;
+        slider_column(flow, profile.flow_min, profile.flow_max, track.width)
+    });
+
+    let preview = app
+        .target_preview
+        .map(|flow| slider_column(flow, profile.flow_min, profile.flow_max, track.width));
+
+    let mut spans = vec![Span::styled(min_label, Style::default().fg(theme.muted))];
+    for column in 0..track.width {
+        spans.push(if Some(column) == preview {
+            Span::styled(
+                "◇",
+                Style::default()
+                    .fg(theme.highlight)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else if column == target {
+            Span::styled(
+                "◆",
+                Style::default()
+                    .fg(theme.target)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else if Some(column) == real {
+            Span::styled(
+                "┃",
+                Style::default().fg(theme.real).add_modifier(Modifier::BOLD),
+            )
+        } else if column < target {
+            Span::styled("━", Style::default().fg(theme.target))
+        } else {
+            Span::styled("─", Style::default().fg(theme.muted))
+        });
+    }
+    spans.push(Span::styled(max_label, Style::default().fg(theme.muted)));
+
+    let mut title = Line::from(vec![
+        Span::styled(
+            format!("{} ◆ ", app.locale.text("Target")),
+            Style::default().fg(theme.text),
+        ),
+        Span::styled(
+            app.flow_unit.format(app.target_flow),
+            Style::default().fg(theme.target),
+        ),
+        Span::styled(
+            format!("  {} ┃ ", app.locale.text("Real")),
+            Style::default().fg(theme.text),
+        ),
+        Span::styled(
+            real_flow.map_or_else(
+                || String::from("--"),
+                |flow| app.flow_unit.format_value(flow),
+            ),
+            Style::default().fg(theme.real),
+        ),
+    ]);
+    if app.target_batch.pending().is_some() {
+        title.push_span(Span::styled(
+            format!("  … {}", app.locale.text("sent when the key is released")),
+            Style::default().fg(theme.highlight),
+        ));
+    }
+    if let Some(flow) = app.target_preview {
+        title.push_span(Span::styled(
+            format!(
+                "  ◇ {}",
+                app.locale
+                    .format("release to set {}", &[&app.flow_unit.format(flow)])
+            ),
+            Style::default()
+                .fg(theme.highlight)
+                .add_modifier(Modifier::BOLD),
+        ));
     }
-    constraints.push(Constraint::Length(3));
+    let paragraph = Paragraph::new(Line::from(spans)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(Style::default().fg(theme.target)),
+    );
+    frame.render_widget(paragraph, area);
+}
 
+/// One status line and flow and speed sparklines, for panes of about ten rows.
+fn render_compact_dashboard(frame: &mut Frame, area: Rect, app: &AppState) {
+    let theme = &app.theme;
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints(constraints)
-        .split(frame.area());
-
-    let mut index = 0;
-    render_header(frame, chunks[index], app);
-    index += 1;
-    render_status(frame, chunks[index], app);
-    index += 1;
-    render_flow_chart(frame, chunks[index], app);
-    index += 1;
-    render_speed_chart(frame, chunks[index], app);
-    index += 1;
-    render_filters(frame, chunks[index], app);
-    index += 1;
-    if app.show_debug {
-        render_debug(frame, chunks[index], app);
-        index += 1;
-    }
-    render_help(frame, chunks[index]);
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Min(2),
+            Constraint::Min(2),
+        ])
+        .split(area);
 
-    if app.input_mode {
-        render_target_popup(frame, app);
+    let latest = app.chart_history().iter().next_back();
+    let (mut spans, _) = status_lead(app);
+    spans.extend([
+        Span::raw("  "),
+        Span::styled(
+            format!("{}: ", app.locale.text("Real")),
+            Style::default().fg(theme.muted),
+        ),
+        Span::raw(
+            app.flow_unit
+                .format_value(latest.map_or(0.0, |sample| sample.smoothed_flow)),
+        ),
+        flow_trend_span(app),
+        Span::raw("  "),
+        Span::styled("RPM: ", Style::default().fg(theme.muted)),
+        Span::raw(format!(
+            "{:.0}",
+            latest.map_or(0.0, |sample| sample.speed_rpm)
+        )),
+        trend_span(
+            theme,
+            app.history
+                .trend(SPEED_TREND_TOLERANCE, |sample| sample.speed_rpm),
+            |delta| format!("{delta:.0}"),
+        ),
+        paused_span(app),
+    ]);
+    mark_stale(app, &mut spans);
+    frame.render_widget(Paragraph::new(Line::from(spans)), chunks[0]);
+
+    let unit = app.flow_unit;
+    let flow = sparkline_data(app.chart_history(), chunks[1].width, |sample| {
+        unit.convert(sample.smoothed_flow)
+    });
+    let flow_max = unit.convert(f64::from(app.profile.flow_max));
+    let speed = sparkline_data(app.chart_history(), chunks[2].width, |sample| {
+        sample.speed_rpm
+    });
+    for (data, max, title, color, area) in [
+        (
+            &flow,
+            flow_max,
+            app.locale.format("Flow ({})", &[&unit.label()]),
+            theme.real,
+            chunks[1],
+        ),
+        (
+            &speed,
+            app.speed_scale.max(),
+            app.locale.text("Fan Speed (RPM)").to_string(),
+            theme.speed,
+            chunks[2],
+        ),
+    ] {
+        let sparkline = Sparkline::default()
+            .block(Block::default().title(Span::styled(title, Style::default().fg(theme.text))))
+            .data(data)
+            .max(sparkline_value(max))
+            .style(Style::default().fg(color));
+        frame.render_widget(sparkline, area);
+    }
+}
+
+/// One column per device with its status, a flow sparkline and filter bars.
+pub fn render_overview(frame: &mut Frame, fleet: &Fleet) {
+    let app = &fleet.selected().app;
+    if too_small(frame.area()) {
+        render_too_small(frame, app);
+    } else {
+        let [columns, hint] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let areas = Layout::horizontal(
+            fleet
+                .devices
+                .iter()
+                .map(|_| Constraint::Fill(1))
+                .collect::<Vec<_>>(),
+        )
+        .split(columns);
+        for (index, (device, area)) in fleet.devices.iter().zip(areas.iter()).enumerate() {
+            render_device_column(
+                frame,
+                *area,
+                &device.name,
+                &device.app,
+                index == fleet.selected,
+            );
+        }
+        frame.render_widget(
+            Paragraph::new(app.locale.text("←/→ select  Enter expand  q quit"))
+                .style(Style::default().fg(app.theme.muted)),
+            hint,
+        );
+    }
+    if app.ascii {
+        asciify(frame.buffer_mut());
     }
 }
 
-fn render_header(frame: &mut Frame, area: Rect, _app: &AppState) {
-    let title = Line::from(vec![Span::styled(
-        "Quick 6101A2 Monitor",
+fn render_device_column(frame: &mut Frame, area: Rect, name: &str, app: &AppState, selected: bool) {
+    let theme = &app.theme;
+    let (border, title) = if selected {
+        (
+            Style::default().fg(theme.accent),
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+        )
+    } else {
+        (
+            Style::default().fg(theme.muted),
+            Style::default().fg(theme.text),
+        )
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border)
+        .title(Span::styled(format!(" {name} "), title));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let filters = app.profile.filters;
+    let [status_area, flow_area, filter_area] = Layout::vertical([
+        Constraint::Length(4),
+        Constraint::Min(2),
+        Constraint::Length(u16::try_from(filters.len()).unwrap_or(u16::MAX)),
+    ])
+    .areas(inner);
+
+    // The state and target flow get a line each to fit narrow columns.
+    let (mut state, _) = status_lead(app);
+    let target = state.split_off(3);
+    state.pop();
+    let latest = app.chart_history().iter().next_back();
+    let real = vec![
+        Span::styled(
+            format!("{}: ", app.locale.text("Real")),
+            Style::default().fg(theme.muted),
+        ),
+        Span::raw(
+            app.flow_unit
+                .format_value(latest.map_or(0.0, |sample| sample.smoothed_flow)),
+        ),
+        flow_trend_span(app),
+    ];
+    let mut link = vec![connection_span(app)];
+    mark_stale(app, &mut link);
+    let lines: Vec<Line> = [state, target, real, link]
+        .into_iter()
+        .map(Line::from)
+        .collect();
+    frame.render_widget(Paragraph::new(lines), status_area);
+
+    let unit = app.flow_unit;
+    let flow = sparkline_data(app.chart_history(), flow_area.width, |sample| {
+        unit.convert(sample.smoothed_flow)
+    });
+    let sparkline = Sparkline::default()
+        .block(Block::default().title(Span::styled(
+            app.locale.format("Flow ({})", &[&unit.label()]),
+            Style::default().fg(theme.text),
+        )))
+        .data(&flow)
+        .max(sparkline_value(
+            unit.convert(f64::from(app.profile.flow_max)),
+        ))
+        .style(Style::default().fg(theme.real));
+    frame.render_widget(sparkline, flow_area);
+
+    let rows = Layout::vertical(filters.iter().map(|_| Constraint::Length(1))).split(filter_area);
+    for (filter, row) in filters.iter().zip(rows.iter()) {
+        let total = app.status.as_ref().map_or(0, |s| s.filter_total(*filter));
+        let limit = app.status.as_ref().map_or(0, |s| s.filter_limit(*filter));
+        let ratio = if limit > 0 {
+            (f64::from(total) / f64::from(limit)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let due = app.reminders.reminder(*filter).is_some_and(|reminder| {
+            reminder
+                .countdown(app.status.as_ref(), Local::now().date_naive())
+                .is_due()
+        });
+        let gauge = Gauge::default()
+            .gauge_style(Style::default().fg(gauge_color(app, ratio, due)))
+            .ratio(ratio)
+            .label(format!(
+                "{} {:.0}%",
+                app.locale.text(filter.label()),
+                ratio * 100.0
+            ));
+        frame.render_widget(gauge, *row);
+    }
+}
+
+/// The newest `width` samples as sparkline bar heights.
+fn sparkline_data(history: &History, width: u16, value: impl Fn(&Sample) -> f64) -> Vec<u64> {
+    let mut data: Vec<u64> = history
+        .iter()
+        .rev()
+        .take(usize::from(width))
+        .map(|sample| sparkline_value(value(sample)))
+        .collect();
+    data.reverse();
+    data
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn sparkline_value(value: f64) -> u64 {
+    value.max(0.0).round() as u64
+}
+
+/// Which half second of the filter banner's flash `now` falls in; a frame is due when it
+/// changes.
+pub fn banner_phase(now: SystemTime) -> u128 {
+    now.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+        / 500
+}
+
+/// Full-width banner flashing twice a second until acknowledged.
+fn render_filter_banner(frame: &mut Frame, area: Rect, app: &AppState) {
+    let theme = &app.theme;
+    let filters: Vec<&str> = app
+        .filter_banner
+        .over_limit()
+        .iter()
+        .map(|filter| app.locale.text(filter.label()))
+        .collect();
+    let phase = banner_phase(SystemTime::now());
+    let style = if phase.is_multiple_of(2) {
+        Style::default().fg(theme.text).bg(theme.error)
+    } else {
+        Style::default().fg(theme.error)
+    };
+    let banner = Paragraph::new(app.locale.format(
+        "FILTER LIMIT REACHED: {}  (a acknowledge, A snooze)",
+        &[&filters.join(", ")],
+    ))
+    .style(style.add_modifier(Modifier::BOLD))
+    .alignment(ratatui::layout::Alignment::Center);
+    frame.render_widget(banner, area);
+}
+
+fn render_config_tab(frame: &mut Frame, area: Rect, app: &AppState) {
+    let theme = &app.theme;
+    let heading = |text: &str| {
+        Line::from(Span::styled(
+            app.locale.text(text).to_string(),
+            Style::default()
+                .fg(theme.heading)
+                .add_modifier(Modifier::BOLD),
+        ))
+    };
+    let mut lines = vec![heading("Device configuration (c to edit)")];
+    for register in CONFIG_REGISTERS {
+        let Some(info) = app.profile.register_info(*register) else {
+            continue;
+        };
+        let current = app
+            .status
+            .as_ref()
+            .and_then(|status| status.register(*register))
+            .map_or_else(|| "--".to_string(), |value| info.format_value(value));
+        lines.push(Line::from(format!(
+            "  {:<12} {current:>8} {}",
+            info.name, info.unit
+        )));
+    }
+    lines.push(Line::from(format!(
+        "  {:<12} {:>8}   {}",
+        app.locale.text("Link"),
+        "",
+        app.locale.format(
+            "address {}, {} baud (w to change)",
+            &[&app.link_address, &app.link_baud]
+        )
+    )));
+
+    lines.push(Line::from(""));
+    lines.push(heading("Presets"));
+    for (index, preset) in app.presets.iter().enumerate() {
+        lines.push(Line::from(format!(
+            "  {} {:<12} {}",
+            index + 1,
+            preset.name,
+            app.flow_unit.format(preset.flow)
+        )));
+    }
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(app.locale.text("Configuration"))
+            .border_style(Style::default().fg(theme.accent)),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+fn render_header(frame: &mut Frame, area: Rect, app: &AppState) {
+    let theme = &app.theme;
+    let mut spans = vec![Span::styled(
+        app.locale.format("Quick {} Monitor", &[&app.profile.name]),
         Style::default()
-            .fg(Color::LightMagenta)
+            .fg(theme.accent)
             .add_modifier(Modifier::BOLD),
-    )]);
+    )];
+    if let Some(identity) = &app.identity {
+        spans.push(Span::styled(
+            format!("  FW {} (id {})", identity.revision, identity.server_id),
+            Style::default().fg(theme.muted),
+        ));
+    }
+    if app.deviation.is_active() {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled(
+            app.locale.text("FLOW DEVIATION"),
+            Style::default()
+                .fg(Color::Black)
+                .bg(theme.warn)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    spans.extend(alarm_spans(app));
+    let title = Line::from(spans);
+    let clock = clock_line(app, SystemTime::now());
+    let clock_width = u16::try_from(clock.width()).unwrap_or(u16::MAX);
+    let [title_area, clock_area] =
+        if area.width >= clock_width + u16::try_from(title.width()).unwrap_or(u16::MAX) + 4 {
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(clock_width)]).areas(area)
+        } else {
+            [area, Rect::default()]
+        };
 
     let paragraph = Paragraph::new(title).alignment(ratatui::layout::Alignment::Center);
-    frame.render_widget(paragraph, area);
+    frame.render_widget(paragraph, title_area);
+    frame.render_widget(Paragraph::new(clock), clock_area);
 }
 
-fn render_status(frame: &mut Frame, area: Rect, app: &AppState) {
+/// Raised alarms and due reminders, the acknowledged alarms muted after them; only a note
+/// while they are snoozed.
+fn alarm_spans(app: &AppState) -> Vec<Span<'_>> {
+    let theme = &app.theme;
+    let muted = Style::default().fg(theme.muted);
+    if let Some(until) = app.snoozed_until {
+        let until = DateTime::<Local>::from(until).format("%H:%M").to_string();
+        return vec![
+            Span::raw("  "),
+            Span::styled(
+                app.locale.format("alarms snoozed until {}", &[&until]),
+                muted,
+            ),
+        ];
+    }
+    let mut spans = Vec::new();
+    let mut active: Vec<String> = app.alarms.active().map(str::to_string).collect();
+    active.extend(app.reminders.due().map(|filter| {
+        app.locale
+            .format("{} replacement due", &[&app.locale.text(filter.label())])
+    }));
+    if !active.is_empty() {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled(
+            app.locale.format("ALARM: {}", &[&active.join(", ")]),
+            Style::default()
+                .fg(theme.text)
+                .bg(theme.error)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    let acknowledged: Vec<&str> = app.alarms.acknowledged().collect();
+    if !acknowledged.is_empty() {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled(
+            app.locale
+                .format("acknowledged: {}", &[&acknowledged.join(", ")]),
+            muted,
+        ));
+    }
+    spans
+}
+
+/// Wall-clock time, session length and when the power state last changed, so a screenshot
+/// tells when it was taken.
+fn clock_line(app: &AppState, now: SystemTime) -> Line<'_> {
+    let theme = &app.theme;
+    let label = Style::default().fg(theme.muted);
+    let mut spans = vec![
+        Span::styled(
+            DateTime::<Local>::from(now)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+            Style::default().fg(theme.text),
+        ),
+        Span::styled(format!("  {} ", app.locale.text("Session")), label),
+        Span::raw(elapsed_text(
+            now.duration_since(app.session_start).unwrap_or_default(),
+        )),
+    ];
+    if let Some(changed) = app.last_state_change {
+        spans.extend([
+            Span::styled(format!("  {} ", app.locale.text("State changed")), label),
+            Span::raw(
+                DateTime::<Local>::from(changed)
+                    .format("%H:%M:%S")
+                    .to_string(),
+            ),
+        ]);
+    }
+    spans.push(Span::raw(" "));
+    Line::from(spans)
+}
+
+/// `h:mm:ss`, with as many hours as needed.
+fn elapsed_text(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// State and target flow spans opening the status line, and the target label's column.
+fn status_lead(app: &AppState) -> (Vec<Span<'_>>, usize) {
+    let theme = &app.theme;
+    let locale = &app.locale;
     let (state_text, state_style) = match app.status.as_ref().map(|status| status.state) {
+        // Shape and brightness instead of green/red hue.
+        Some(STATE_ON) if app.colorblind => (
+            format!("■ {}", locale.text("ON")),
+            Style::default()
+                .fg(theme.text)
+                .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+        ),
+        Some(STATE_OFF) if app.colorblind => (
+            format!("□ {}", locale.text("OFF")),
+            Style::default().fg(theme.muted).add_modifier(Modifier::DIM),
+        ),
         Some(STATE_ON) => (
-            "ON",
+            locale.text("ON").to_string(),
+            Style::default().fg(theme.ok).add_modifier(Modifier::BOLD),
+        ),
+        Some(STATE_OFF) => (
+            locale.text("OFF").to_string(),
+            Style::default()
+                .fg(theme.error)
+                .add_modifier(Modifier::BOLD),
+        ),
+        _ => (String::from("--"), Style::default().fg(theme.muted)),
+    };
+
+    let target_flow = app.status.as_ref().map_or(0, |status| status.target_flow);
+    let mut spans = vec![
+        Span::styled(locale.text("State: "), Style::default().fg(theme.muted)),
+        Span::styled(state_text, state_style),
+        Span::raw("  "),
+    ];
+    let target_start = spans.iter().map(Span::width).sum::<usize>();
+    spans.push(Span::styled(
+        locale.text("Target Flow: "),
+        Style::default().fg(theme.muted),
+    ));
+    spans.push(Span::raw(app.flow_unit.format(target_flow)));
+    (spans, target_start)
+}
+
+/// Grays out a status line once polls stop answering and appends how old its values are.
+fn mark_stale(app: &AppState, spans: &mut Vec<Span<'_>>) {
+    let Some(age) = app.stale_age(SystemTime::now()) else {
+        return;
+    };
+    let stale = Style::default()
+        .fg(app.theme.muted)
+        .add_modifier(Modifier::DIM);
+    for span in spans.iter_mut() {
+        span.style = stale;
+    }
+    spans.extend([
+        Span::raw("  "),
+        Span::styled(
+            app.locale.format("data is {}s old", &[&age.as_secs()]),
+            Style::default()
+                .fg(app.theme.warn)
+                .add_modifier(Modifier::BOLD),
+        ),
+    ]);
+}
+
+/// Where the target flow label and value sit inside the status block at `area`.
+fn target_flow_area(app: &AppState, area: Rect) -> Rect {
+    let (spans, target_start) = status_lead(app);
+    let target_width = spans.iter().map(Span::width).sum::<usize>() - target_start;
+    Rect::new(
+        area.x + 1 + u16::try_from(target_start).unwrap_or(u16::MAX),
+        area.y + 1,
+        u16::try_from(target_width).unwrap_or(u16::MAX),
+        1,
+    )
+    .intersection(area)
+}
+
+fn render_status(frame: &mut Frame, area: Rect, app: &AppState) {
+    let theme = &app.theme;
+    let locale = &app.locale;
+    let real_flow = app
+        .history
+        .iter()
+        .next_back()
+        .map_or(0.0, |sample| sample.smoothed_flow);
+    let beeper_text = match app.status.as_ref().and_then(|s| s.register(REG_BEEPER)) {
+        Some(BEEPER_ON) => locale.text("ON"),
+        Some(_) => locale.text("OFF"),
+        None => "--",
+    };
+    let operating_mode = app
+        .status
+        .as_ref()
+        .and_then(DeviceStatus::mode)
+        .map_or("--", |mode| locale.text(mode.label()));
+    let (mode_label, mode_color) = match app.interface {
+        InterfaceMode::Remote => ("REMOTE", theme.heading),
+        InterfaceMode::Exttool => ("EXTTOOL", theme.heading),
+        InterfaceMode::Simulation => ("SIM", theme.warn),
+    };
+
+    let (mut spans, _) = status_lead(app);
+    spans.extend([
+        Span::raw("  "),
+        Span::styled(locale.text("Real Flow: "), Style::default().fg(theme.muted)),
+        Span::raw(app.flow_unit.format_value(real_flow)),
+        flow_trend_span(app),
+        Span::raw("  "),
+        Span::styled(locale.text("Volume: "), Style::default().fg(theme.muted)),
+        Span::raw(volume_text(app)),
+        Span::raw("  "),
+        Span::styled(locale.text("Mode: "), Style::default().fg(theme.muted)),
+        Span::raw(operating_mode),
+        Span::raw("  "),
+        Span::styled(locale.text("Beeper: "), Style::default().fg(theme.muted)),
+        Span::raw(beeper_text),
+        Span::raw("  "),
+        Span::styled(locale.text("Interface: "), Style::default().fg(theme.muted)),
+        Span::styled(
+            mode_label,
+            Style::default().fg(mode_color).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw("  "),
+        Span::styled(locale.text("Poll: "), Style::default().fg(theme.muted)),
+        Span::raw(format!("{} ms", app.poll_interval.as_millis())),
+        Span::raw("  "),
+        access_span(app),
+    ]);
+    mark_stale(app, &mut spans);
+    let line = Line::from(spans);
+
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(locale.text("Status"))
+        .border_style(Style::default().fg(theme.accent));
+    if let Some(ramp) = app.ramp.ramp() {
+        block = block.title(ramp_line(app, ramp).right_aligned());
+    }
+    block = block.title_bottom(indicator_line(app).right_aligned());
+    if let Some(error) = &app.write_error {
+        block = block.title_bottom(Span::styled(
+            format!(" {} ", locale.format("Write rejected: {}", &[error])),
             Style::default()
-                .fg(Color::Green)
+                .fg(theme.error)
                 .add_modifier(Modifier::BOLD),
+        ));
+    }
+    let paragraph = Paragraph::new(line).block(block);
+    frame.render_widget(paragraph, area);
+}
+
+/// Health indicators, told apart by symbol as well as colour, and the raw status flag word.
+fn indicator_line(app: &AppState) -> Line<'_> {
+    let theme = &app.theme;
+    let mut spans = vec![Span::raw(" ")];
+    for indicator in app.indicators(SystemTime::now()) {
+        let (symbol, color) = match indicator.ok {
+            Some(true) => ("●", theme.ok),
+            Some(false) => ("✕", theme.error),
+            None => ("○", theme.muted),
+        };
+        spans.extend([
+            Span::styled(
+                symbol,
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!(" {} ", app.locale.text(indicator.label)),
+                Style::default().fg(theme.text),
+            ),
+        ]);
+    }
+    if let Some(flags) = app
+        .status
+        .as_ref()
+        .and_then(|status| status.register(REG_STATUS_FLAGS))
+    {
+        spans.push(Span::styled(
+            format!("0x{flags:04X} "),
+            Style::default().fg(theme.muted),
+        ));
+    }
+    Line::from(spans)
+}
+
+/// Progress of real flow towards a newly set target and the time it should take to settle.
+fn ramp_line<'a>(app: &'a AppState, ramp: &Ramp) -> Line<'a> {
+    const CELLS: u8 = 10;
+    let theme = &app.theme;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let filled = (ramp.progress * f64::from(CELLS)).round() as usize;
+    let settle = ramp.remaining.map_or_else(
+        || String::from("--"),
+        |remaining| format!("~{}s", remaining.as_secs()),
+    );
+    Line::from(vec![
+        Span::styled(
+            format!(
+                " {} ",
+                app.locale
+                    .format("Ramp to {}", &[&app.flow_unit.format_value(ramp.target)])
+            ),
+            Style::default().fg(theme.muted),
+        ),
+        Span::styled("█".repeat(filled), Style::default().fg(theme.real)),
+        Span::styled(
+            "·".repeat(usize::from(CELLS) - filled),
+            Style::default().fg(theme.muted),
         ),
-        Some(STATE_OFF) => (
-            "OFF",
-            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        Span::raw(format!(" {:.0}% {settle} ", ramp.progress * 100.0)),
+    ])
+}
+
+fn volume_text(app: &AppState) -> String {
+    match app.totalizer.carried_m3 {
+        Some(_) => app.locale.format(
+            "{} m3 ({} m3 total)",
+            &[
+                &format!("{:.2}", app.totalizer.session_m3),
+                &format!("{:.1}", app.totalizer.lifetime_m3()),
+            ],
         ),
-        _ => ("--", Style::default().fg(Color::Gray)),
-    };
+        None => format!("{:.2} m3", app.totalizer.session_m3),
+    }
+}
 
-    let (connection_text, connection_style) = if app.connected {
-        (
-            "Connected",
+/// Link state, last poll round trip and error counters of the transport.
+fn render_status_bar(frame: &mut Frame, area: Rect, app: &AppState) {
+    let theme = &app.theme;
+    let locale = &app.locale;
+    let stats = &app.poll_stats;
+    let label = Style::default().fg(theme.muted);
+    let rtt = stats.last_latency.map_or_else(
+        || String::from("--"),
+        |latency| format!("{} ms", latency.as_millis()),
+    );
+    let last_ok = stats.since_success(SystemTime::now()).map_or_else(
+        || locale.text("never").to_string(),
+        |age| locale.format("{} s ago", &[&format!("{:.1}", age.as_secs_f64())]),
+    );
+    let counter = |count: u64| {
+        if count == 0 {
+            Span::raw(count.to_string())
+        } else {
+            Span::styled(count.to_string(), Style::default().fg(theme.warn))
+        }
+    };
+    let mut spans = vec![
+        Span::raw(" "),
+        connection_span(app),
+        Span::styled(format!("  {} ", locale.text("RTT")), label),
+        Span::raw(rtt),
+        Span::styled(format!("  {} ", locale.text("last OK")), label),
+        Span::raw(last_ok),
+        Span::styled(format!("  {} ", locale.text("timeouts")), label),
+        counter(stats.timeouts),
+        Span::styled(format!("  {} ", locale.text("CRC errors")), label),
+        counter(stats.crc_errors),
+        Span::styled(format!("  {} ", locale.text("other errors")), label),
+        counter(stats.other_errors),
+    ];
+    if let Some(recorder) = &app.macro_recorder {
+        spans.push(Span::styled(
+            format!("  ● {}", locale.format("REC {} steps", &[&recorder.len()])),
             Style::default()
-                .fg(Color::Green)
+                .fg(theme.error)
                 .add_modifier(Modifier::BOLD),
-        )
-    } else {
-        (
-            "Disconnected",
-            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-        )
-    };
-
-    let target_flow = app.status.as_ref().map_or(0, |status| status.target_flow);
-    let real_flow = app.status.as_ref().map_or(0, |status| status.real_flow);
-    let (mode_label, mode_color) = match app.interface {
-        InterfaceMode::Remote => ("REMOTE", Color::Blue),
-        InterfaceMode::Exttool => ("EXTTOOL", Color::Cyan),
-        InterfaceMode::Simulation => ("SIM", Color::Yellow),
-    };
+        ));
+    }
+    if let Some(run) = &app.macro_run {
+        let (step, steps) = run.progress();
+        spans.push(Span::styled(
+            format!("  ▸ {} {step}/{steps}", run.name),
+            Style::default().fg(theme.highlight),
+        ));
+    }
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
 
-    let line = Line::from(vec![
-        Span::styled("State: ", Style::default().fg(Color::Gray)),
-        Span::styled(state_text, state_style),
-        Span::raw("  "),
-        Span::styled("Link: ", Style::default().fg(Color::Gray)),
-        Span::styled(connection_text, connection_style),
-        Span::raw("  "),
-        Span::styled("Target Flow: ", Style::default().fg(Color::Gray)),
-        Span::raw(format!("{target_flow} m3/h")),
-        Span::raw("  "),
-        Span::styled("Real Flow: ", Style::default().fg(Color::Gray)),
-        Span::raw(format!("{real_flow} m3/h")),
-        Span::raw("  "),
-        Span::styled("Mode: ", Style::default().fg(Color::Gray)),
+fn connection_span(app: &AppState) -> Span<'_> {
+    let theme = &app.theme;
+    if app.connected {
         Span::styled(
-            mode_label,
-            Style::default().fg(mode_color).add_modifier(Modifier::BOLD),
-        ),
-        Span::raw("  "),
+            app.locale.text("Connected"),
+            Style::default().fg(theme.ok).add_modifier(Modifier::BOLD),
+        )
+    } else {
         Span::styled(
-            if app.read_only {
-                "Read-only"
-            } else {
-                "Writable"
-            },
+            app.locale.text("Disconnected"),
             Style::default()
-                .fg(if app.read_only {
-                    Color::Yellow
-                } else {
-                    Color::Green
-                })
+                .fg(theme.error)
                 .add_modifier(Modifier::BOLD),
-        ),
-    ]);
+        )
+    }
+}
 
-    let paragraph = Paragraph::new(line).block(
-        Block::default()
-            .borders(Borders::ALL)
-            .title("Status")
-            .border_style(Style::default().fg(Color::LightMagenta)),
-    );
-    frame.render_widget(paragraph, area);
+fn access_span(app: &AppState) -> Span<'_> {
+    let theme = &app.theme;
+    let (label, color) = if app.read_only {
+        ("Read-only", theme.warn)
+    } else {
+        ("Writable", theme.ok)
+    };
+    Span::styled(
+        app.locale.text(label),
+        Style::default().fg(color).add_modifier(Modifier::BOLD),
+    )
 }
 
 fn render_flow_chart(frame: &mut Frame, area: Rect, app: &AppState) {
-    let (real_data, target_data) = split_series(&app.flow_history);
-    let (min_tick, max_tick) = chart_bounds(&real_data, area);
+    let theme = &app.theme;
+    let unit = app.flow_unit;
+    let max_speed = app.speed_scale.max();
+    let (area, rpm_axis, minimap) = flow_chart_areas(area, max_speed, app);
+    let real_data = series(app, area, |sample| unit.convert(sample.smoothed_flow));
+    let target_data = series(app, area, |sample| unit.convert(sample.target_flow));
+    let (min_time, max_time) = chart_bounds(&real_data);
+    let max_flow = unit.convert(f64::from(app.profile.flow_max));
+    // Fan speed scaled onto the flow axis; its own scale is drawn on the right.
+    let rpm_data = if app.combined_chart {
+        series(app, area, |sample| sample.speed_rpm * max_flow / max_speed)
+    } else {
+        Vec::new()
+    };
 
-    let datasets = vec![
+    let (real_style, target_style, target_marker) = if app.colorblind {
+        (
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+            Style::default().fg(theme.muted).add_modifier(Modifier::DIM),
+            symbols::Marker::Dot,
+        )
+    } else {
+        (
+            Style::default().fg(theme.real),
+            Style::default().fg(theme.target),
+            chart_marker(app),
+        )
+    };
+    let preview_data: Vec<(f64, f64)> = app
+        .target_preview
+        .map(|flow| {
+            let flow = unit.convert(f64::from(flow));
+            vec![(min_time, flow), (max_time, flow)]
+        })
+        .unwrap_or_default();
+    let mut datasets = vec![
         Dataset::default()
-            .name("Target")
             .marker(symbols::Marker::Braille)
-            .style(Style::default().fg(Color::LightYellow))
+            .style(Style::default().fg(theme.highlight))
+            .graph_type(GraphType::Line)
+            .data(&preview_data),
+        Dataset::default()
+            .name(app.locale.text("Target"))
+            .marker(target_marker)
+            .style(target_style)
             .graph_type(GraphType::Line)
             .data(&target_data),
         Dataset::default()
-            .name("Real")
-            .marker(symbols::Marker::Braille)
-            .style(Style::default().fg(Color::LightCyan))
+            .name(app.locale.text("Real"))
+            .marker(chart_marker(app))
+            .style(real_style)
             .graph_type(GraphType::Line)
             .data(&real_data),
     ];
+    if app.combined_chart {
+        datasets.push(
+            Dataset::default()
+                .name("RPM")
+                .marker(chart_marker(app))
+                .style(Style::default().fg(theme.speed))
+                .graph_type(GraphType::Line)
+                .data(&rpm_data),
+        );
+    }
 
-    let chart_title = Line::from(vec![
-        Span::styled("Flow (m3/h)", Style::default().fg(Color::White)),
-        Span::raw("  "),
-        Span::styled("Real", Style::default().fg(Color::LightCyan)),
-        Span::raw("/"),
-        Span::styled("Target", Style::default().fg(Color::LightYellow)),
-    ]);
+    let rows = plot_rows(area);
+    let ticks = tick_rows(app.chart_axes, rows);
+    let grid = gridlines(app, &ticks, rows, max_flow, (min_time, max_time), area);
+    datasets.splice(
+        0..0,
+        grid.iter().map(|points| gridline_dataset(app, points)),
+    );
+
+    let chart_title = flow_chart_title(app, real_style, target_style);
 
     let chart = Chart::new(datasets)
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .title(chart_title)
-                .border_style(Style::default().fg(Color::LightCyan)),
+                .border_style(Style::default().fg(theme.real)),
         )
         .x_axis(
             Axis::default()
-                .bounds([min_tick, max_tick])
-                .labels(vec![Span::from("-"), Span::from("+")]),
+                .bounds([min_time, max_time])
+                .labels(time_labels(&real_data, min_time, max_time, "%H:%M:%S")),
         )
         .y_axis(
             Axis::default()
-                .bounds([f64::from(0), f64::from(TARGET_FLOW_MAX)])
-                .labels(vec![
-                    Span::from("0"),
-                    Span::from(format!("{TARGET_FLOW_MAX}")),
-                ]),
+                .bounds([0.0, max_flow])
+                .labels(value_labels(&ticks, rows, max_flow)),
         );
 
     frame.render_widget(chart, area);
+    if let Some(axis) = rpm_axis {
+        render_rpm_axis(frame, axis, &ticks, max_speed, app);
+    }
+    if let Some(minimap) = minimap {
+        render_minimap(frame, minimap, (min_time, max_time), app);
+    }
+}
+
+/// The plot, the right-hand RPM axis of the combined chart and the minimap row, when shown.
+fn flow_chart_areas(
+    area: Rect,
+    max_speed: f64,
+    app: &AppState,
+) -> (Rect, Option<Rect>, Option<Rect>) {
+    let (area, minimap) = if area.height >= MINIMAP_MIN_HEIGHT {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(area);
+        (chunks[0], Some(chunks[1]))
+    } else {
+        (area, None)
+    };
+    if !app.combined_chart {
+        return (area, None, minimap);
+    }
+    let width = u16::try_from(format!("{max_speed:.0}").len() + 1).unwrap_or(u16::MAX);
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(width)])
+        .split(area);
+    (chunks[0], Some(chunks[1]), minimap)
+}
+
+/// Eighth-block glyphs from empty to full, for the minimap and register sparklines.
+const BLOCK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One row under the flow chart with the whole retained history, the charted span highlighted.
+fn render_minimap(frame: &mut Frame, area: Rect, window: (f64, f64), app: &AppState) {
+    let theme = &app.theme;
+    let max_flow = f64::from(app.profile.flow_max);
+    let spans: Vec<Span> = minimap_cells(&app.history, area.width, max_flow, window)
+        .into_iter()
+        .map(|(symbol, charted)| {
+            let style = if charted {
+                Style::default()
+                    .fg(theme.real)
+                    .add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default().fg(theme.muted)
+            };
+            Span::styled(symbol.to_string(), style)
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+/// Mean real flow per column as a block glyph, and whether the column lies in the charted span.
+fn minimap_cells(
+    history: &History,
+    width: u16,
+    max_flow: f64,
+    window: (f64, f64),
+) -> Vec<(char, bool)> {
+    let (Some(first), Some(last)) = (history.iter().next(), history.iter().next_back()) else {
+        return vec![(' ', false); usize::from(width)];
+    };
+    let start = first.seconds();
+    let span = (last.seconds() - start).max(f64::EPSILON);
+    let columns = f64::from(width);
+    let mut sums = vec![(0.0, 0u32); usize::from(width)];
+    for sample in history.iter() {
+        let column = minimap_column((sample.seconds() - start) / span * columns, sums.len());
+        sums[column].0 += sample.smoothed_flow;
+        sums[column].1 += 1;
+    }
+    (0..width)
+        .zip(sums)
+        .map(|(column, (sum, count))| {
+            let from = start + span * f64::from(column) / columns;
+            let to = start + span * f64::from(column + 1) / columns;
+            let charted = from <= window.1 && to >= window.0;
+            if count == 0 {
+                return (' ', charted);
+            }
+            let level = (sum / f64::from(count) / max_flow * 8.0).floor();
+            (
+                BLOCK_LEVELS[minimap_column(level, BLOCK_LEVELS.len())],
+                charted,
+            )
+        })
+        .collect()
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn minimap_column(position: f64, count: usize) -> usize {
+    (position.max(0.0) as usize).min(count.saturating_sub(1))
+}
+
+/// Series legend of the flow chart, with the RPM series when it is combined.
+fn flow_chart_title(app: &AppState, real_style: Style, target_style: Style) -> Line<'_> {
+    let theme = &app.theme;
+    let locale = &app.locale;
+    let (real_label, target_label) = if app.colorblind {
+        (
+            format!("{} ⣿", locale.text("Real")),
+            format!("{} •", locale.text("Target")),
+        )
+    } else {
+        (
+            locale.text("Real").to_string(),
+            locale.text("Target").to_string(),
+        )
+    };
+    let mut title = vec![
+        Span::styled(
+            locale.format("Flow ({})", &[&app.flow_unit.label()]),
+            Style::default().fg(theme.text),
+        ),
+        Span::raw("  "),
+        Span::styled(real_label, real_style),
+        Span::raw("/"),
+        Span::styled(target_label, target_style),
+    ];
+    if app.combined_chart {
+        title.push(Span::raw("  "));
+        title.push(Span::styled(
+            locale.text("RPM (right axis)"),
+            Style::default().fg(theme.speed),
+        ));
+    }
+    title.push(window_span(app));
+    title.push(paused_span(app));
+    Line::from(title)
+}
+
+/// RPM scale to the right of the combined chart, level with its top and zero rows.
+fn render_rpm_axis(frame: &mut Frame, area: Rect, ticks: &[u16], max_speed: f64, app: &AppState) {
+    // Chart rows below the plot: x-axis line, labels and the bottom border.
+    if area.height < 5 {
+        return;
+    }
+    let style = Style::default().fg(app.theme.speed);
+    let rows = plot_rows(area);
+    let zero = area.bottom() - 4;
+    for (tick, label) in ticks.iter().zip(value_labels(ticks, rows, max_speed)) {
+        let row = Rect::new(area.x + 1, zero - tick, area.width - 1, 1);
+        frame.render_widget(Paragraph::new(Line::from(label)).style(style), row);
+    }
 }
 
 fn render_speed_chart(frame: &mut Frame, area: Rect, app: &AppState) {
-    let data: Vec<(f64, f64)> = app.speed_history.iter().copied().collect();
-    let (min_tick, max_tick) = chart_bounds(&data, area);
-    let max_speed = data
-        .iter()
-        .map(|(_, value)| *value)
-        .fold(0.0, f64::max)
-        .max(100.0);
+    let theme = &app.theme;
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(24)])
+        .split(area);
+    let area = chunks[0];
+    let data = series(app, area, |sample| sample.speed_rpm);
+    let (min_time, max_time) = chart_bounds(&data);
+    let max_speed = app.speed_scale.max();
 
-    let datasets = vec![Dataset::default()
-        .name("RPM")
-        .marker(symbols::Marker::Braille)
-        .style(Style::default().fg(Color::LightGreen))
-        .graph_type(GraphType::Line)
-        .data(&data)];
+    let rows = plot_rows(area);
+    let ticks = tick_rows(app.chart_axes, rows);
+    let grid = gridlines(app, &ticks, rows, max_speed, (min_time, max_time), area);
+    let mut datasets: Vec<Dataset> = grid
+        .iter()
+        .map(|points| gridline_dataset(app, points))
+        .collect();
+    datasets.push(
+        Dataset::default()
+            .name("RPM")
+            .marker(chart_marker(app))
+            .style(Style::default().fg(theme.speed))
+            .graph_type(GraphType::Line)
+            .data(&data),
+    );
 
     let chart = Chart::new(datasets)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Fan Speed (RPM)")
-                .border_style(Style::default().fg(Color::LightYellow)),
+                .title(Line::from(vec![
+                    Span::styled(
+                        app.locale.text("Fan Speed (RPM)"),
+                        Style::default().fg(theme.text),
+                    ),
+                    window_span(app),
+                    paused_span(app),
+                ]))
+                .border_style(Style::default().fg(theme.highlight)),
         )
         .x_axis(
             Axis::default()
-                .bounds([min_tick, max_tick])
-                .labels(vec![Span::from("-"), Span::from("+")]),
+                .bounds([min_time, max_time])
+                .labels(time_labels(&data, min_time, max_time, "%H:%M:%S")),
         )
         .y_axis(
             Axis::default()
                 .bounds([0.0, max_speed])
-                .labels(vec![Span::from("0"), Span::from(format!("{max_speed:.0}"))]),
+                .labels(value_labels(&ticks, rows, max_speed)),
         );
 
     frame.render_widget(chart, area);
+    render_speed_gauge(frame, chunks[1], app);
 }
 
-fn render_filters(frame: &mut Frame, area: Rect, app: &AppState) {
+/// Filter totals over the logged days, with each filter's growth and projected limit below.
+fn render_filter_history(frame: &mut Frame, area: Rect, app: &AppState) {
+    let theme = &app.theme;
+    let filters = app.profile.filters;
     let chunks = Layout::default()
-        .direction(Direction::Horizontal)
+        .direction(Direction::Vertical)
         .constraints([
-            Constraint::Percentage(33),
-            Constraint::Percentage(33),
-            Constraint::Percentage(34),
+            Constraint::Min(0),
+            Constraint::Length(u16::try_from(filters.len()).unwrap_or(u16::MAX)),
         ])
         .split(area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(app.locale.text("Filter history (km3)"))
+        .title_bottom(Line::from(app.locale.text("↑/↓ select, R reset")).right_aligned())
+        .border_style(Style::default().fg(theme.secondary));
+    let lines: Vec<Line> = filters
+        .iter()
+        .enumerate()
+        .map(|(index, filter)| filter_projection_line(app, *filter, index == app.filter_cursor))
+        .collect();
+    frame.render_widget(Paragraph::new(lines), chunks[1]);
+    let points = app.filter_log.points();
+    if points.is_empty() {
+        let empty = Paragraph::new(app.locale.text("No filter history yet")).block(block);
+        frame.render_widget(empty, chunks[0]);
+        return;
+    }
 
-    render_filter_gauge(
-        frame,
-        chunks[0],
-        "P-Filter",
-        app.status.as_ref().map(|s| s.p_filter_total),
-        app.status.as_ref().map(|s| s.p_filter_limit),
-    );
-    render_filter_gauge(
-        frame,
-        chunks[1],
-        "M-Filter",
-        app.status.as_ref().map(|s| s.m_filter_total),
-        app.status.as_ref().map(|s| s.m_filter_limit),
-    );
-    render_filter_gauge(
-        frame,
-        chunks[2],
-        "C-Filter",
-        app.status.as_ref().map(|s| s.c_filter_total),
-        app.status.as_ref().map(|s| s.c_filter_limit),
-    );
+    let area = chunks[0];
+    let data: Vec<Series> = filters
+        .iter()
+        .map(|filter| {
+            let totals: Series = points
+                .iter()
+                .map(|point| (point.seconds(), f64::from(point.total(*filter))))
+                .collect();
+            downsample(
+                &totals,
+                usize::from(area.width.saturating_sub(2).max(1)) * 2,
+            )
+        })
+        .collect();
+    let (min_time, max_time) = chart_bounds(&data[0]);
+    let max_total = filters
+        .iter()
+        .zip(&data)
+        .flat_map(|(filter, totals)| {
+            let limit = app.status.as_ref().map(|s| s.filter_limit(*filter));
+            totals
+                .iter()
+                .map(|(_, total)| *total)
+                .chain(limit.map(f64::from))
+        })
+        .fold(1.0, f64::max);
+
+    let rows = plot_rows(area);
+    let ticks = tick_rows(app.chart_axes, rows);
+    let grid = gridlines(app, &ticks, rows, max_total, (min_time, max_time), area);
+    let mut datasets: Vec<Dataset> = grid
+        .iter()
+        .map(|points| gridline_dataset(app, points))
+        .collect();
+    for ((filter, totals), color) in
+        filters
+            .iter()
+            .zip(&data)
+            .zip([theme.real, theme.target, theme.speed])
+    {
+        datasets.push(
+            Dataset::default()
+                .name(app.locale.text(filter.label()))
+                .marker(chart_marker(app))
+                .style(Style::default().fg(color))
+                .graph_type(GraphType::Line)
+                .data(totals),
+        );
+    }
+    let chart = Chart::new(datasets)
+        .block(block)
+        .x_axis(
+            Axis::default()
+                .bounds([min_time, max_time])
+                .labels(time_labels(&data[0], min_time, max_time, "%m-%d %H:%M")),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, max_total])
+                .labels(value_labels(&ticks, rows, max_total)),
+        );
+    frame.render_widget(chart, area);
+}
+
+/// A filter's logged growth per day and, at that pace, the days until it reaches its limit;
+/// the filter selected for `R` is marked.
+fn filter_projection_line(app: &AppState, filter: Filter, selected: bool) -> Line<'_> {
+    let locale = &app.locale;
+    let marker = if selected { "▸" } else { " " };
+    let mut text = format!("{marker} {}: ", locale.text(filter.label()));
+    let total = app.status.as_ref().map(|s| s.filter_total(filter));
+    let limit = app.status.as_ref().map(|s| s.filter_limit(filter));
+    match app.filter_log.rate_per_day(filter) {
+        Some(rate) => {
+            text.push_str(&locale.format("{} km3/day", &[&format!("{rate:.1}")]));
+            if let (Some(total), Some(limit)) = (total, limit)
+                && limit > total
+                && rate > 0.0
+            {
+                let days = f64::from(limit - total) / rate;
+                let _ = write!(
+                    text,
+                    ", {}",
+                    locale.format("limit in about {} days", &[&format!("{days:.0}")])
+                );
+            }
+        }
+        None => text.push_str(locale.text("not enough history yet")),
+    }
+    let style = if selected {
+        Style::default()
+            .fg(app.theme.text)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(app.theme.muted)
+    };
+    Line::from(Span::styled(text, style))
+}
+
+/// Current fan speed against the configured, rated or observed full scale.
+fn render_speed_gauge(frame: &mut Frame, area: Rect, app: &AppState) {
+    let theme = &app.theme;
+    let speed = app
+        .status
+        .as_ref()
+        .map_or(0.0, |status| f64::from(status.speed_rpm));
+    let max_speed = app.speed_scale.max();
+    let scale = if app.speed_scale.is_fixed() {
+        "RPM"
+    } else {
+        "RPM, peak"
+    };
+    let mut label = format!("{speed:.0}/{max_speed:.0}");
+    if let Some(trend) = app
+        .history
+        .trend(SPEED_TREND_TOLERANCE, |sample| sample.speed_rpm)
+    {
+        let _ = write!(
+            label,
+            " {}",
+            trend_text(trend, |delta| format!("{delta:.0}"))
+        );
+    }
+    let gauge = Gauge::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(app.locale.format("Speed ({})", &[&app.locale.text(scale)]))
+                .border_style(Style::default().fg(theme.highlight)),
+        )
+        .gauge_style(Style::default().fg(theme.speed))
+        .ratio((speed / max_speed).clamp(0.0, 1.0))
+        .label(label);
+    frame.render_widget(gauge, area);
+}
+
+/// Trend of the live real flow, converted to the display unit.
+fn flow_trend_span(app: &AppState) -> Span<'static> {
+    let unit = app.flow_unit;
+    trend_span(
+        &app.theme,
+        app.history
+            .trend(FLOW_TREND_TOLERANCE, |sample| sample.smoothed_flow),
+        |delta| format!("{:.1}", unit.convert(delta)),
+    )
+}
+
+/// Arrow and change after a live value; empty until the history reaches back far enough.
+fn trend_span(theme: &Theme, trend: Option<Trend>, delta: impl Fn(f64) -> String) -> Span<'static> {
+    trend.map_or_else(
+        || Span::raw(""),
+        |trend| {
+            Span::styled(
+                format!(" {}", trend_text(trend, delta)),
+                Style::default().fg(theme.muted),
+            )
+        },
+    )
+}
+
+fn trend_text(trend: Trend, delta: impl Fn(f64) -> String) -> String {
+    match trend {
+        Trend::Rising(change) | Trend::Falling(change) => {
+            format!("{}{}", trend.arrow(), delta(change))
+        }
+        Trend::Steady => trend.arrow().to_string(),
+    }
+}
+
+/// Chart title marker while the charts are frozen with `p`.
+/// The chart window, e.g. `[10 min]`.
+fn window_span(app: &AppState) -> Span<'_> {
+    Span::styled(
+        format!("  [{}]", app.locale.text(app.chart_window.label())),
+        Style::default().fg(app.theme.muted),
+    )
+}
+
+fn paused_span(app: &AppState) -> Span<'_> {
+    if app.frozen_history.is_some() {
+        Span::styled(
+            format!("  {}", app.locale.text("PAUSED")),
+            Style::default()
+                .fg(app.theme.warn)
+                .add_modifier(Modifier::BOLD),
+        )
+    } else {
+        Span::raw("")
+    }
+}
+
+/// One equal-width gauge area per profile filter.
+fn filter_chunks(filters: &[Filter], area: Rect) -> Rc<[Rect]> {
+    let count = u32::try_from(filters.len()).unwrap_or(1).max(1);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(filters.iter().map(|_| Constraint::Ratio(1, count)))
+        .split(area)
+}
+
+fn render_filters(frame: &mut Frame, area: Rect, app: &AppState) {
+    let filters = app.profile.filters;
+    let chunks = filter_chunks(filters, area);
+
+    let today = Local::now().date_naive();
+    for (filter, chunk) in filters.iter().zip(chunks.iter()) {
+        let countdown = app
+            .reminders
+            .reminder(*filter)
+            .map(|reminder| reminder.countdown(app.status.as_ref(), today));
+        let mut title = app.locale.text(filter.label()).to_string();
+        if let Some(trend) = app.history.trend(FILTER_TREND_TOLERANCE, |sample| {
+            sample.filter_total(*filter)
+        }) {
+            let _ = write!(
+                title,
+                " {}",
+                trend_text(trend, |delta| format!("{delta:.0}"))
+            );
+        }
+        if let Some(countdown) = countdown {
+            let _ = write!(title, " · {}", countdown.label());
+        }
+        render_filter_gauge(
+            frame,
+            app,
+            *chunk,
+            &title,
+            countdown.is_some_and(Countdown::is_due),
+            app.status.as_ref().map(|s| s.filter_total(*filter)),
+            app.status.as_ref().map(|s| s.filter_limit(*filter)),
+        );
+    }
 }
 
+/// Usage gauge labelled with the used share and remaining capacity, coloured by the
+/// configured thresholds.
 fn render_filter_gauge(
     frame: &mut Frame,
+    app: &AppState,
     area: Rect,
-    label: &str,
+    title: &str,
+    due: bool,
     total: Option<u16>,
     limit: Option<u16>,
 ) {
     let total = f64::from(total.unwrap_or(0));
-    let value = f64::from(limit.unwrap_or(0));
-    let ratio = if value > 0.0 {
-        (total / value).clamp(0.0, 1.0)
+    let limit = f64::from(limit.unwrap_or(0));
+    let (ratio, label) = if limit > 0.0 {
+        let percent = total * 100.0 / limit;
+        let remaining = (limit - total).max(0.0);
+        (
+            (total / limit).clamp(0.0, 1.0),
+            app.locale.format(
+                "{}% · {} km3 left",
+                &[&format!("{percent:.0}"), &format!("{remaining:.0}")],
+            ),
+        )
     } else {
-        0.0
+        (
+            0.0,
+            app.locale
+                .format("{} km3, no limit", &[&format!("{total:.0}")]),
+        )
     };
+    let color = gauge_color(app, ratio, due);
     let gauge = Gauge::default()
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(label)
-                .border_style(Style::default().fg(Color::LightGreen)),
+                .title(title)
+                .border_style(Style::default().fg(color)),
         )
-        .gauge_style(Style::default().fg(Color::LightGreen))
+        .gauge_style(Style::default().fg(color))
         .ratio(ratio)
-        .label(format!("{total:.0}/{value:.0} km3"));
+        .label(label);
     frame.render_widget(gauge, area);
 }
 
-fn render_debug(frame: &mut Frame, area: Rect, app: &AppState) {
-    let mut lines = Vec::new();
-    if let Some(status) = &app.status {
-        let column_width: usize = 36;
-        let mut row_spans: Vec<Span> = Vec::new();
-        for (index, value) in status.registers.iter().enumerate() {
-            let name = u16::try_from(index)
-                .ok()
-                .and_then(register_name)
-                .unwrap_or("-");
-            let address = format!("0x{index:04X} ");
-            let rest = format!("{name:<12} 0x{value:04X} {value:>5}");
-            let entry_len = address.len() + rest.len();
-
-            row_spans.push(Span::styled(
-                address,
-                Style::default()
-                    .fg(Color::LightCyan)
+fn gauge_color(app: &AppState, ratio: f64, due: bool) -> Color {
+    let theme = &app.theme;
+    match app.gauge_thresholds.level(ratio * 100.0) {
+        _ if due => theme.error,
+        GaugeLevel::Critical => theme.error,
+        GaugeLevel::Warn => theme.warn,
+        GaugeLevel::Normal => theme.secondary,
+    }
+}
+
+/// Registers laid out `REGISTER_COLUMNS` to a row, each with its recent values as a sparkline.
+fn register_grid_lines<'a>(app: &'a AppState, status: &DeviceStatus) -> Vec<Line<'a>> {
+    let theme = &app.theme;
+    let editor = &app.register_editor;
+    let column_width: usize = 60;
+    let mut lines = Vec::new();
+    let mut row_spans: Vec<Span> = Vec::new();
+    let shown = editor.shown(app.profile, status.registers.len());
+    if shown.is_empty() {
+        lines.push(Line::styled(
+            app.locale.text("No register matches the search"),
+            Style::default().fg(theme.muted),
+        ));
+    }
+    for (position, index) in shown.into_iter().enumerate() {
+        let value = &status.registers[index];
+        let info = u16::try_from(index)
+            .ok()
+            .and_then(|address| app.profile.register_info(address));
+        let name = info.map_or("-", |info| info.name);
+        let selected = index == editor.selected;
+        let pinned = u16::try_from(index).is_ok_and(|address| app.pinned.contains(&address));
+        let edit = match &editor.pending {
+            Some(pending) if selected => Some(format!("{}_", pending.retyped)),
+            _ => (selected && editor.editing).then(|| format!("{}_", editor.buffer)),
+        };
+        let mut style = if app.register_changed(index, *value) {
+            Style::default()
+                .fg(theme.highlight)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        if selected {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        let signed = info.is_some_and(|info| info.kind == RegisterKind::Signed);
+        let (sparkline, dynamic) = register_sparkline(app.register_trail.values(index), signed);
+        let sparkline_style = if dynamic {
+            Style::default().fg(theme.real)
+        } else {
+            Style::default().fg(theme.muted).add_modifier(Modifier::DIM)
+        };
+        let entry = [
+            Span::styled(
+                format!("0x{index:04X}{} ", if pinned { '*' } else { ' ' }),
+                Style::default()
+                    .fg(theme.heading)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!(
+                    "{name:<12} {}",
+                    register_columns(info.as_ref(), *value, edit)
+                ),
+                style,
+            ),
+            Span::styled(sparkline, sparkline_style),
+        ];
+        let entry_width: usize = entry.iter().map(Span::width).sum();
+        row_spans.extend(entry);
+
+        if position % REGISTER_COLUMNS == REGISTER_COLUMNS - 1 {
+            lines.push(Line::from(row_spans));
+            row_spans = Vec::new();
+        } else {
+            row_spans.push(Span::raw(
+                " ".repeat(column_width.saturating_sub(entry_width)),
+            ));
+        }
+    }
+    if !row_spans.is_empty() {
+        lines.push(Line::from(row_spans));
+    }
+    lines
+}
+
+/// Block glyphs of a register's recent values scaled between their own extremes, and whether
+/// they changed at all; unchanged registers draw a flat line.
+fn register_sparkline(values: impl Iterator<Item = u16>, signed: bool) -> (String, bool) {
+    let values: Vec<f64> = values
+        .map(|value| {
+            if signed {
+                f64::from(value.cast_signed())
+            } else {
+                f64::from(value)
+            }
+        })
+        .collect();
+    let low = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let high = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let dynamic = high > low;
+    let line: String = values
+        .iter()
+        .map(|value| {
+            if dynamic {
+                let level = (value - low) / (high - low) * 7.0;
+                BLOCK_LEVELS[minimap_column(level.round(), BLOCK_LEVELS.len())]
+            } else {
+                BLOCK_LEVELS[0]
+            }
+        })
+        .collect();
+    (format!(" {line:<REGISTER_TRAIL_LEN$}"), dynamic)
+}
+
+fn render_debug(frame: &mut Frame, area: Rect, app: &AppState) {
+    let theme = &app.theme;
+    let editor = &app.register_editor;
+    let mut lines = Vec::new();
+    if let Some(status) = &app.status {
+        lines.extend(register_grid_lines(app, status));
+    } else {
+        lines.push(Line::from(app.locale.text("No register data yet")));
+    }
+
+    lines.extend(register_change_lines(app));
+
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(app.locale.text(
+            "Registers (arrows or h/j/k/l select, Enter edit, 0x prefix for hex, PgUp/PgDn scroll)",
+        ))
+        .border_style(Style::default().fg(theme.secondary));
+    if let Some(error) = &editor.error {
+        block = block.title_bottom(Line::from(Span::styled(
+            format!(" {error} "),
+            Style::default().fg(theme.error),
+        )));
+    } else if editor.pending.is_some() {
+        block = block.title_bottom(Line::from(Span::styled(
+            format!(
+                " {} ",
+                app.locale
+                    .text("Type the value again and press Enter to write it, Esc to cancel")
+            ),
+            Style::default()
+                .fg(theme.error)
+                .add_modifier(Modifier::BOLD),
+        )));
+    }
+    if editor.searching || !editor.query.is_empty() {
+        let cursor = if editor.searching { "_" } else { "" };
+        block = block.title_bottom(
+            Line::from(Span::styled(
+                format!(" /{}{cursor} ", editor.query),
+                Style::default()
+                    .fg(theme.highlight)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .right_aligned(),
+        );
+    }
+
+    let viewport = editor.viewport;
+    let content = lines.len();
+    let scroll = editor.scroll.min(content.saturating_sub(viewport));
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .scroll((u16::try_from(scroll).unwrap_or(u16::MAX), 0));
+    frame.render_widget(paragraph, area);
+
+    if content > viewport {
+        let mut state = ScrollbarState::new(content - viewport).position(scroll);
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight),
+            area.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            }),
+            &mut state,
+        );
+    }
+}
+
+/// Raw word in hex and decimal next to the engineering value and unit; `edit` replaces the
+/// engineering value while the register is being edited.
+fn register_columns(info: Option<&RegisterInfo>, value: u16, edit: Option<String>) -> String {
+    let engineering = edit
+        .unwrap_or_else(|| info.map_or_else(|| value.to_string(), |info| info.format_value(value)));
+    let unit = info.map_or("", |info| info.unit);
+    format!("0x{value:04X} {value:>5} │ {engineering:>7} {unit:<5}")
+}
+
+/// Recent register changes listed under the register grid, newest first.
+fn register_change_lines(app: &AppState) -> Vec<Line<'_>> {
+    let theme = &app.theme;
+    let now = SystemTime::now();
+    let mut lines = Vec::new();
+    for change in app.register_changes.iter().rev() {
+        let name = app
+            .profile
+            .register_info(change.register)
+            .map_or("-", |info| info.name);
+        let age = now.duration_since(change.timestamp).unwrap_or_default();
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!(
+                    "{} ",
+                    app.locale
+                        .format("{}s ago", &[&format!("{:>4}", age.as_secs())])
+                ),
+                Style::default().fg(theme.muted),
+            ),
+            Span::styled(
+                format!("0x{:04X} ", change.register),
+                Style::default().fg(theme.heading),
+            ),
+            Span::raw(format!("{name:<12} {} → {}", change.old, change.new)),
+        ]));
+    }
+    lines
+}
+
+/// Lines in the register pane: the register grid, or its placeholder, then the change list.
+fn register_line_count(status: Option<&DeviceStatus>, shown: usize, changes: usize) -> usize {
+    status.map_or(1, |_| shown.div_ceil(REGISTER_COLUMNS).max(1)) + changes
+}
+
+fn render_log(frame: &mut Frame, area: Rect, app: &AppState) {
+    let theme = &app.theme;
+    let visible = usize::from(area.height.saturating_sub(2));
+    let entries = app.event_log.iter().len();
+    let end = entries.saturating_sub(app.log_view.scroll_back);
+    let lines: Vec<Line> = app
+        .event_log
+        .iter()
+        .take(end)
+        .skip(end.saturating_sub(visible))
+        .map(|entry| {
+            let source_color = match entry.source {
+                EventSource::User => theme.heading,
+                EventSource::Device => theme.highlight,
+                EventSource::Monitor => theme.accent,
+                EventSource::Transport => theme.muted,
+            };
+            let message_style = match entry.severity {
+                Severity::Info => Style::default(),
+                Severity::Warning => Style::default().fg(theme.warn),
+                Severity::Error => Style::default()
+                    .fg(theme.error)
                     .add_modifier(Modifier::BOLD),
-            ));
-            row_spans.push(Span::raw(rest));
+            };
+            Line::from(vec![
+                Span::styled(
+                    format!("{} ", entry.clock()),
+                    Style::default().fg(theme.muted),
+                ),
+                Span::styled(format!("{} ", entry.message), message_style),
+                Span::styled(
+                    format!("({})", entry.source.label()),
+                    Style::default().fg(source_color),
+                ),
+            ])
+        })
+        .collect();
 
-            if index % 2 == 0 {
-                let padding = column_width.saturating_sub(entry_len);
-                row_spans.push(Span::raw(" ".repeat(padding)));
-            } else {
-                lines.push(Line::from(row_spans));
-                row_spans = Vec::new();
-            }
-        }
-        if !row_spans.is_empty() {
-            lines.push(Line::from(row_spans));
-        }
+    let title = if app.log_view.scroll_back > 0 {
+        app.locale
+            .format("Event Log (↑{} newer)", &[&app.log_view.scroll_back])
     } else {
-        lines.push(Line::from("No register data yet"));
-    }
-
+        app.locale.text("Event Log").to_string()
+    };
     let paragraph = Paragraph::new(lines).block(
         Block::default()
             .borders(Borders::ALL)
-            .title("Registers")
-            .border_style(Style::default().fg(Color::LightGreen)),
+            .title(title)
+            .border_style(Style::default().fg(theme.secondary)),
     );
     frame.render_widget(paragraph, area);
 }
 
-fn render_help(frame: &mut Frame, area: Rect) {
-    let text = Line::from(vec![
-        Span::styled("Space", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw(" toggle power  "),
-        Span::styled("←/→", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw(" adjust target flow  "),
-        Span::styled("t", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw(" type target  "),
-        Span::styled("d", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw(" registers  "),
-        Span::styled("q", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw(" quit"),
-    ]);
+/// Controls bar summarising the key map, followed by the configured presets.
+fn render_help(frame: &mut Frame, area: Rect, app: &AppState) {
+    let theme = &app.theme;
+    let mut spans = Vec::new();
+    for (binding, brief) in keymap::bar(app.read_only) {
+        if !spans.is_empty() {
+            spans.push(Span::raw("  "));
+        }
+        spans.push(Span::styled(
+            app.keymap.label(binding),
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+        spans.push(Span::raw(format!(" {}", app.locale.text(brief))));
+    }
+    for (index, preset) in app.presets.iter().enumerate() {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled(
+            (index + 1).to_string(),
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+        spans.push(Span::raw(format!(
+            " {} {}",
+            preset.name,
+            app.flow_unit.format(preset.flow)
+        )));
+    }
+    let text = Line::from(spans);
     let paragraph = Paragraph::new(text)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Controls")
-                .border_style(Style::default().fg(Color::LightMagenta)),
+                .title(app.locale.text("Controls"))
+                .border_style(Style::default().fg(theme.accent)),
         )
         .wrap(Wrap { trim: true });
     frame.render_widget(paragraph, area);
 }
 
 fn render_target_popup(frame: &mut Frame, app: &AppState) {
-    let area = centered_rect(60, 20, frame.area());
-    let buffer = if app.input_buffer.is_empty() {
-        "_".to_string()
-    } else {
-        app.input_buffer.clone()
-    };
+    let theme = &app.theme;
+    let area = centered_rect(60, 30, frame.area());
+    let input = &app.target_input;
+    let unit = app.flow_unit;
+    let out_of_range = unit
+        .parse_m3h(input.text())
+        .is_some_and(|value| value != app.profile.clamp_flow(value));
+    let value_style = Style::default()
+        .fg(if out_of_range {
+            theme.error
+        } else {
+            theme.highlight
+        })
+        .add_modifier(Modifier::BOLD);
+    let (before, under, after) = input.split();
+    let (low, high) = unit.input_range(app.profile.flow_min, app.profile.flow_max);
+    let range = app
+        .locale
+        .format("allowed {}-{} {}", &[&low, &high, &unit.label()]);
 
     let content = vec![
         Line::from(Span::styled(
-            "Type target flow",
+            app.locale.text("Type target flow"),
             Style::default()
-                .fg(Color::LightCyan)
+                .fg(theme.heading)
                 .add_modifier(Modifier::BOLD),
         )),
         Line::from(""),
         Line::from(vec![
-            Span::styled("Value: ", Style::default().fg(Color::Gray)),
+            Span::styled(app.locale.text("Value: "), Style::default().fg(theme.muted)),
+            Span::styled(before.to_string(), value_style),
+            Span::styled(
+                under.unwrap_or(' ').to_string(),
+                value_style.add_modifier(Modifier::REVERSED),
+            ),
+            Span::styled(after.to_string(), value_style),
+            Span::raw(format!(" {}", unit.label())),
+        ]),
+        Line::from(Span::styled(
+            range,
+            Style::default().fg(if out_of_range {
+                theme.error
+            } else {
+                theme.muted
+            }),
+        )),
+        Line::from(""),
+        Line::from(
+            app.locale
+                .text("←/→ move the cursor, +/- nudge the value, ↑/↓ earlier values"),
+        ),
+        Line::from(app.locale.text("Enter to apply, Esc to cancel")),
+    ];
+
+    frame.render_widget(Clear, area);
+    let paragraph = Paragraph::new(content).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(app.locale.text("Target Flow"))
+            .border_style(Style::default().fg(theme.accent)),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+fn render_config_popup(frame: &mut Frame, app: &AppState) {
+    let theme = &app.theme;
+    let Some(editor) = &app.config_editor else {
+        return;
+    };
+    let area = centered_rect(70, 50, frame.area());
+
+    let mut content = Vec::new();
+    for (index, register) in CONFIG_REGISTERS.iter().enumerate() {
+        let Some(info) = app.profile.register_info(*register) else {
+            continue;
+        };
+        let current = app
+            .status
+            .as_ref()
+            .and_then(|status| status.register(*register))
+            .map_or_else(|| "--".to_string(), |value| info.format_value(value));
+        let style = if index == editor.selected {
+            Style::default()
+                .fg(theme.highlight)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        content.push(Line::from(vec![
+            Span::styled(format!("{:<12}", info.name), style),
+            Span::raw(format!("{current:>6}  ")),
+            Span::styled(
+                format!(
+                    "[{}..={}] {}",
+                    info.format_value(info.min),
+                    info.format_value(info.max),
+                    info.description
+                ),
+                Style::default().fg(theme.muted),
+            ),
+        ]));
+    }
+    content.push(Line::from(""));
+
+    if let Some(pending) = &editor.pending {
+        content.extend(retype_lines(app, pending));
+    } else if editor.editing {
+        let buffer = if editor.buffer.is_empty() {
+            "_"
+        } else {
+            editor.buffer.as_str()
+        };
+        content.push(Line::from(vec![
+            Span::styled(
+                app.locale.text("New value: "),
+                Style::default().fg(theme.muted),
+            ),
+            Span::styled(
+                buffer.to_string(),
+                Style::default()
+                    .fg(theme.highlight)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ]));
+        content.push(Line::from(
+            app.locale.text("Enter to validate, Esc to cancel"),
+        ));
+    } else {
+        content.push(Line::from(
+            app.locale.text("↑/↓ select, Enter to edit, Esc to close"),
+        ));
+    }
+    if let Some(error) = &editor.error {
+        content.push(Line::from(Span::styled(
+            error.clone(),
+            Style::default().fg(theme.error),
+        )));
+    }
+
+    frame.render_widget(Clear, area);
+    let paragraph = Paragraph::new(content).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(app.locale.text("Configuration"))
+            .border_style(Style::default().fg(theme.accent)),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+/// Prompt for typing a guarded write's value the second time.
+fn retype_lines<'a>(app: &'a AppState, pending: &PendingWrite) -> Vec<Line<'a>> {
+    let theme = &app.theme;
+    let name = app
+        .profile
+        .register_info(pending.register)
+        .map_or("register", |info| info.name);
+    vec![
+        Line::from(Span::styled(
+            app.locale.format(
+                "{} is guarded: type the same value again to write it",
+                &[&name],
+            ),
+            Style::default()
+                .fg(theme.error)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(vec![
+            Span::styled(
+                app.locale.text("Value again: "),
+                Style::default().fg(theme.muted),
+            ),
             Span::styled(
-                buffer,
+                format!("{}_", pending.retyped),
                 Style::default()
-                    .fg(Color::LightYellow)
+                    .fg(theme.highlight)
                     .add_modifier(Modifier::BOLD),
             ),
-            Span::raw(" m3/h"),
+        ]),
+        Line::from(app.locale.text("Enter to write, Esc to cancel")),
+    ]
+}
+
+fn render_wizard_popup(frame: &mut Frame, app: &AppState) {
+    let theme = &app.theme;
+    let locale = &app.locale;
+    let Some(wizard) = &app.link_wizard else {
+        return;
+    };
+    let area = centered_rect(60, 40, frame.area());
+    let mut content = vec![
+        Line::from(vec![
+            Span::styled(locale.text("Current: "), Style::default().fg(theme.muted)),
+            Span::raw(locale.format(
+                "address {}, {} baud",
+                &[&wizard.previous_address, &wizard.previous_baud],
+            )),
         ]),
         Line::from(""),
-        Line::from("Enter to apply, Esc to cancel"),
     ];
+    content.extend(wizard_step_lines(app, wizard));
+    if let Some(error) = &wizard.error {
+        content.push(Line::from(Span::styled(
+            error.clone(),
+            Style::default().fg(theme.error),
+        )));
+    }
+
+    frame.render_widget(Clear, area);
+    let paragraph = Paragraph::new(content).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(locale.text("Link Setup"))
+            .border_style(Style::default().fg(theme.accent)),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+/// Prompt of the link wizard's current step.
+fn wizard_step_lines<'a>(app: &'a AppState, wizard: &LinkWizard) -> Vec<Line<'a>> {
+    let theme = &app.theme;
+    let locale = &app.locale;
+    let highlight = Style::default()
+        .fg(theme.highlight)
+        .add_modifier(Modifier::BOLD);
+    let buffer = if wizard.buffer.is_empty() {
+        "_".to_string()
+    } else {
+        wizard.buffer.clone()
+    };
+    let mut content = Vec::new();
+    match &wizard.step {
+        WizardStep::Address => {
+            content.push(Line::from(vec![
+                Span::styled(
+                    locale.text("New address: "),
+                    Style::default().fg(theme.muted),
+                ),
+                Span::styled(buffer, highlight),
+            ]));
+            content.push(Line::from(locale.text("Enter to continue, Esc to cancel")));
+        }
+        WizardStep::Baud => {
+            content.push(Line::from(vec![
+                Span::styled(
+                    locale.text("New baud rate: "),
+                    Style::default().fg(theme.muted),
+                ),
+                Span::styled(buffer, highlight),
+            ]));
+            content.push(Line::from(locale.text("Enter to continue, Esc to cancel")));
+        }
+        WizardStep::RetypeAddress | WizardStep::RetypeBaud => {
+            let (label, hint) = if wizard.step == WizardStep::RetypeAddress {
+                ("Address again: ", "Enter to continue, Esc to cancel")
+            } else {
+                (
+                    "Baud rate again: ",
+                    "Enter to write and reconnect, Esc to cancel",
+                )
+            };
+            content.push(Line::from(Span::styled(
+                locale.text("A wrong address or baud rate cuts the link: type both again"),
+                Style::default()
+                    .fg(theme.error)
+                    .add_modifier(Modifier::BOLD),
+            )));
+            content.push(Line::from(vec![
+                Span::styled(locale.text(label), Style::default().fg(theme.muted)),
+                Span::styled(buffer, highlight),
+            ]));
+            content.push(Line::from(locale.text(hint)));
+        }
+        WizardStep::Applying => {
+            content.push(Line::from(
+                locale.text("Applying settings and verifying link..."),
+            ));
+        }
+        WizardStep::Rollback => {
+            content.push(Line::from(Span::styled(
+                locale.text("Device does not respond with the new settings."),
+                Style::default()
+                    .fg(theme.error)
+                    .add_modifier(Modifier::BOLD),
+            )));
+            content.push(Line::from(locale.format(
+                "Roll back to address {}, {} baud? y/n",
+                &[&wizard.previous_address, &wizard.previous_baud],
+            )));
+        }
+        WizardStep::Done(message) => {
+            content.push(Line::from(Span::styled(
+                message.clone(),
+                Style::default().fg(theme.ok),
+            )));
+            content.push(Line::from(locale.text("Enter to close")));
+        }
+        WizardStep::Failed(reason) => {
+            content.push(Line::from(Span::styled(
+                locale.format("Settings not changed: {}", &[reason]),
+                Style::default().fg(theme.error),
+            )));
+            content.push(Line::from(locale.text("Enter to close")));
+        }
+    }
+    content
+}
+
+fn render_filter_reset_popup(frame: &mut Frame, app: &AppState) {
+    let theme = &app.theme;
+    let locale = &app.locale;
+    let Some(dialog) = &app.filter_reset else {
+        return;
+    };
+    let area = centered_rect(60, 30, frame.area());
+    let warning = Style::default()
+        .fg(theme.error)
+        .add_modifier(Modifier::BOLD);
+    let filter = dialog.filter();
+
+    let content = match dialog.stage {
+        ResetStage::Select => {
+            let mut spans = Vec::new();
+            for (index, candidate) in dialog.filters.iter().enumerate() {
+                let style = if index == dialog.selected {
+                    Style::default()
+                        .fg(theme.highlight)
+                        .add_modifier(Modifier::BOLD | Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                spans.push(Span::styled(
+                    format!(" {} ", locale.text(candidate.label())),
+                    style,
+                ));
+                spans.push(Span::raw(" "));
+            }
+            vec![
+                Line::from(locale.text("Select the filter that was replaced:")),
+                Line::from(""),
+                Line::from(spans),
+                Line::from(""),
+                Line::from(locale.text("←/→ select, Enter to continue, Esc to cancel")),
+            ]
+        }
+        ResetStage::Confirm => vec![Line::from(Span::styled(
+            locale.format(
+                "Reset the {} counter to zero? y/n",
+                &[&locale.text(filter.label())],
+            ),
+            warning,
+        ))],
+        ResetStage::Type => vec![
+            Line::from(Span::styled(
+                locale.format(
+                    "The {} total cannot be restored.",
+                    &[&locale.text(filter.label())],
+                ),
+                warning,
+            )),
+            Line::from(locale.format(
+                "Type {} and press Enter to reset it, Esc to cancel",
+                &[&RESET_WORD],
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                format!("> {}_", dialog.typed),
+                Style::default()
+                    .fg(theme.highlight)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        ],
+        ResetStage::ConfirmAgain => vec![
+            Line::from(Span::styled(
+                locale.format(
+                    "The {} total cannot be restored.",
+                    &[&locale.text(filter.label())],
+                ),
+                warning,
+            )),
+            Line::from(locale.text("Really reset it? y/n")),
+        ],
+    };
 
     frame.render_widget(Clear, area);
     let paragraph = Paragraph::new(content).block(
         Block::default()
             .borders(Borders::ALL)
-            .title("Target Flow")
-            .border_style(Style::default().fg(Color::LightMagenta)),
+            .title(locale.text("Reset Filter Counter"))
+            .border_style(Style::default().fg(theme.accent)),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+fn render_filter_detail_popup(frame: &mut Frame, app: &AppState) {
+    let theme = &app.theme;
+    let locale = &app.locale;
+    let Some(filter) = app.filter_detail else {
+        return;
+    };
+    let area = centered_rect(50, 40, frame.area());
+    let total = app
+        .status
+        .as_ref()
+        .map(|status| status.filter_total(filter));
+    let limit = app
+        .status
+        .as_ref()
+        .map(|status| status.filter_limit(filter));
+    let usage = total
+        .zip(limit)
+        .filter(|(_, limit)| *limit > 0)
+        .map_or_else(
+            || String::from("--"),
+            |(total, limit)| format!("{:.0} %", f64::from(total) * 100.0 / f64::from(limit)),
+        );
+    let countdown = app
+        .reminders
+        .reminder(filter)
+        .map(|reminder| reminder.countdown(app.status.as_ref(), Local::now().date_naive()));
+    let value =
+        |value: Option<u16>| value.map_or_else(|| String::from("--"), |v| format!("{v} km3"));
+
+    let field = |label: &str, value: String| {
+        Line::from(format!("{:<11}{value}", format!("{}:", locale.text(label))))
+    };
+    let mut lines = vec![
+        field("Total", value(total)),
+        field("Limit", value(limit)),
+        field("Usage", usage),
+        field(
+            "Reminder",
+            countdown.map_or_else(|| locale.text("none").to_string(), Countdown::label),
+        ),
+        Line::from(""),
+    ];
+    lines.push(Line::from(locale.text(if app.read_only {
+        "Esc to close"
+    } else {
+        "x reset counter, Esc to close"
+    })));
+
+    frame.render_widget(Clear, area);
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(locale.text(filter.label()))
+            .border_style(Style::default().fg(theme.accent)),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+fn render_power_off_popup(frame: &mut Frame, app: &AppState) {
+    let theme = &app.theme;
+    let area = centered_rect(40, 20, frame.area());
+    let lines = vec![
+        Line::from(app.locale.text("Switch the extractor off?")),
+        Line::from(""),
+        Line::from(
+            app.locale
+                .text("y/Enter to confirm, any other key to cancel"),
+        ),
+    ];
+
+    frame.render_widget(Clear, area);
+    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(app.locale.text("Power Off"))
+            .border_style(Style::default().fg(theme.warn)),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+/// Why the serial worker stopped, with buttons to retry, pick another port or quit.
+fn render_error_screen(frame: &mut Frame, app: &AppState) {
+    let theme = &app.theme;
+    let locale = &app.locale;
+    let Some(screen) = &app.error_screen else {
+        return;
+    };
+    let area = centered_rect(60, 40, frame.area());
+    let mut lines = vec![
+        Line::from(Span::styled(
+            screen.message.as_str(),
+            Style::default().fg(theme.error),
+        )),
+        Line::from(""),
+    ];
+    if let Some(port) = &screen.port {
+        lines.extend([
+            Line::from(vec![
+                Span::styled(locale.text("Port: "), Style::default().fg(theme.muted)),
+                Span::raw(format!("{port}_")),
+            ]),
+            Line::from(""),
+            Line::from(locale.text("Enter to connect, Esc to go back")),
+        ]);
+    } else {
+        let mut buttons = Vec::new();
+        for (index, choice) in ErrorChoice::ALL.iter().enumerate() {
+            let style = if index == screen.selected {
+                Style::default()
+                    .fg(theme.highlight)
+                    .add_modifier(Modifier::BOLD | Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            buttons.push(Span::styled(
+                format!(" {} ", locale.text(choice.label())),
+                style,
+            ));
+            buttons.push(Span::raw(" "));
+        }
+        lines.extend([
+            Line::from(buttons),
+            Line::from(""),
+            Line::from(locale.text("r/p/q or ←/→ and Enter, Esc to stay offline")),
+        ]);
+    }
+
+    frame.render_widget(Clear, area);
+    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(locale.text("Connection Lost"))
+            .border_style(Style::default().fg(theme.error)),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+/// Every key binding grouped by scope, with writes dimmed in read-only mode.
+fn render_help_overlay(frame: &mut Frame, app: &AppState) {
+    let theme = &app.theme;
+    let area = centered_rect(60, 80, frame.area());
+    let mut lines = Vec::new();
+    for scope in Scope::ALL {
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::styled(
+            app.locale.text(scope.title()),
+            Style::default()
+                .fg(theme.heading)
+                .add_modifier(Modifier::BOLD),
+        ));
+        for binding in keymap::in_scope(scope) {
+            let inactive = binding.writes && app.read_only;
+            let style = if inactive {
+                Style::default().fg(theme.muted).add_modifier(Modifier::DIM)
+            } else {
+                Style::default().fg(theme.text)
+            };
+            let mut spans = vec![
+                Span::styled(
+                    format!("  {:<14}", app.keymap.label(binding)),
+                    style.add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(app.locale.text(binding.action), style),
+            ];
+            if inactive {
+                spans.push(Span::styled(
+                    format!(" ({})", app.locale.text("read-only")),
+                    style,
+                ));
+            }
+            lines.push(Line::from(spans));
+        }
+    }
+
+    frame.render_widget(Clear, area);
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(app.locale.text("Keys (any key to close)"))
+            .border_style(Style::default().fg(theme.accent)),
     );
     frame.render_widget(paragraph, area);
 }
@@ -429,34 +2812,289 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         .split(popup_layout[1])[1]
 }
 
-fn chart_bounds(data: &[(f64, f64)], area: Rect) -> (f64, f64) {
-    if data.is_empty() {
+/// Time span of the most recent samples that fit into the chart width.
+fn chart_bounds(data: &[(f64, f64)]) -> (f64, f64) {
+    let (Some((min_time, _)), Some((max_time, _))) = (data.first().copied(), data.last().copied())
+    else {
         return (0.0, 1.0);
-    }
-    let max_tick = data.last().map_or(0.0, |(x, _)| *x).max(1.0);
-    let window = area.width.saturating_sub(2).max(1) as usize;
-    let window_ticks = u32::try_from(window.min(data.len().max(1)))
-        .ok()
-        .map_or(f64::from(u32::MAX), f64::from);
-    let min_tick = if max_tick > window_ticks {
-        max_tick - window_ticks
-    } else {
-        0.0
     };
-    (min_tick, max_tick)
+    if max_time > min_time {
+        (min_time, max_time)
+    } else {
+        (max_time - 1.0, max_time)
+    }
 }
 
 type Series = Vec<(f64, f64)>;
 
-fn split_series(series: &VecDeque<(f64, f64)>) -> (Series, Series) {
-    let mut real = Vec::new();
-    let mut target = Vec::new();
-    for (index, point) in series.iter().enumerate() {
-        if index % 2 == 0 {
-            real.push(*point);
+/// Chart point marker; `--ascii` needs one cell per point so it can be swapped for `*`.
+fn chart_marker(app: &AppState) -> symbols::Marker {
+    if app.ascii {
+        symbols::Marker::Dot
+    } else {
+        app.marker.symbol()
+    }
+}
+
+/// Replaces every non-ASCII symbol in the drawn frame with a plain ASCII look-alike.
+fn asciify(buffer: &mut Buffer) {
+    for cell in &mut buffer.content {
+        let mut chars = cell.symbol().chars();
+        if let (Some(ch), None) = (chars.next(), chars.next())
+            && !ch.is_ascii()
+        {
+            cell.set_char(ascii_symbol(ch));
+        } else if !cell.symbol().is_ascii() {
+            cell.set_char('?');
+        }
+    }
+}
+
+fn ascii_symbol(ch: char) -> char {
+    match ch {
+        '─' | '━' | '═' | '┄' | '┅' | '┈' | '┉' | '╌' | '╍' => '-',
+        '│' | '┃' | '║' | '┆' | '┇' | '┊' | '┋' | '╎' | '╏' => '|',
+        '\u{2500}'..='\u{257F}' => '+',
+        '▁' | '▂' | '▃' => '_',
+        '▄' | '▅' | '▆' | '▇' => '=',
+        '\u{2580}'..='\u{259F}' | '■' => '#',
+        '\u{2800}' => ' ',
+        '\u{2801}'..='\u{28FF}' | '•' | '●' => '*',
+        '◆' => 'O',
+        '□' | '○' | '◇' => 'o',
+        '✕' => 'x',
+        '←' => '<',
+        '→' | '▸' => '>',
+        '↑' | '▲' => '^',
+        '↓' | '▼' => 'v',
+        '·' | '…' => '.',
+        _ => '?',
+    }
+}
+
+/// Plot height of a chart drawn in `area`, without the borders and the time axis rows.
+const fn plot_rows(area: Rect) -> u16 {
+    area.height.saturating_sub(4)
+}
+
+/// Rows above the bottom of a `rows` tall plot that carry a value label: 0, full scale and up to
+/// `y_ticks` evenly spaced rows between, leaving a free row between labels.
+fn tick_rows(axes: ChartAxes, rows: u16) -> Vec<u16> {
+    let top = rows.saturating_sub(1);
+    let labels = (u16::from(axes.y_ticks) + 2).min(rows.div_ceil(2)).max(2);
+    (0..labels)
+        .map(|index| index * top / (labels - 1))
+        .collect()
+}
+
+/// Value at a label row, so labels and gridlines match the row they are drawn on.
+fn tick_value(tick: u16, rows: u16, max: f64) -> f64 {
+    max * f64::from(tick) / f64::from(rows.saturating_sub(1).max(1))
+}
+
+fn value_labels(ticks: &[u16], rows: u16, max: f64) -> Vec<Span<'static>> {
+    ticks
+        .iter()
+        .map(|tick| Span::raw(format!("{:.0}", tick_value(*tick, rows, max))))
+        .collect()
+}
+
+/// Dotted points along each intermediate label row, empty when gridlines are off.
+fn gridlines(
+    app: &AppState,
+    ticks: &[u16],
+    rows: u16,
+    max: f64,
+    (min_time, max_time): (f64, f64),
+    area: Rect,
+) -> Vec<Series> {
+    if !app.chart_axes.gridlines || ticks.len() < 3 {
+        return Vec::new();
+    }
+    // Every other column, so the line reads as faint dots rather than a series.
+    let step = (max_time - min_time) * 2.0 / f64::from(area.width.max(1));
+    let points = (0..area.width.div_ceil(2))
+        .map(|column| min_time + f64::from(column) * step)
+        .collect::<Vec<_>>();
+    ticks[1..ticks.len() - 1]
+        .iter()
+        .map(|tick| {
+            let value = tick_value(*tick, rows, max);
+            points.iter().map(|time| (*time, value)).collect()
+        })
+        .collect()
+}
+
+fn gridline_dataset<'a>(app: &AppState, points: &'a [(f64, f64)]) -> Dataset<'a> {
+    Dataset::default()
+        .marker(if app.ascii {
+            symbols::Marker::Dot
         } else {
-            target.push(*point);
+            symbols::Marker::Braille
+        })
+        .style(
+            Style::default()
+                .fg(app.theme.muted)
+                .add_modifier(Modifier::DIM),
+        )
+        .graph_type(GraphType::Scatter)
+        .data(points)
+}
+
+/// Local wall-clock labels at the start, middle and end of a chart's time axis.
+fn time_labels(
+    data: &[(f64, f64)],
+    min_time: f64,
+    max_time: f64,
+    format: &str,
+) -> Vec<Span<'static>> {
+    if data.is_empty() {
+        return vec![Span::raw(""), Span::raw("")];
+    }
+    [min_time, f64::midpoint(min_time, max_time), max_time]
+        .into_iter()
+        .map(|seconds| {
+            Duration::try_from_secs_f64(seconds).map_or_else(
+                |_| Span::raw("--:--:--"),
+                |elapsed| {
+                    let time = DateTime::<Local>::from(UNIX_EPOCH + elapsed);
+                    Span::raw(time.format(format).to_string())
+                },
+            )
+        })
+        .collect()
+}
+
+/// Plots the whole retained history, decimated to two Braille dots per chart column.
+/// The charted history within the chart window, thinned to the plot width.
+fn series(app: &AppState, area: Rect, value: impl Fn(&Sample) -> f64) -> Series {
+    let points: Series = app
+        .chart_history()
+        .window(app.chart_window)
+        .map(|sample| (sample.seconds(), value(sample)))
+        .collect();
+    downsample(
+        &points,
+        usize::from(area.width.saturating_sub(2).max(1)) * 2,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::buffer::Buffer;
+    use ratatui::layout::Rect;
+
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{
+        asciify, dashboard_rows, elapsed_text, minimap_cells, register_columns, register_sparkline,
+        tick_rows,
+    };
+    use crate::app::{Panel, Panels};
+    use crate::config::ChartAxes;
+    use crate::constants::REG_SPEED_RPM;
+    use crate::data::REGISTERS;
+    use crate::history::{History, Sample};
+
+    #[test]
+    fn dashboard_rows_drop_charts_before_status_and_filters() {
+        let all = Panels::default();
+        assert_eq!(dashboard_rows(40, all), [3, 3, 18, 10, 6]);
+        assert_eq!(dashboard_rows(21, all), [3, 3, 6, 6, 3]);
+        assert_eq!(dashboard_rows(20, all), [3, 3, 11, 0, 3]);
+        assert_eq!(dashboard_rows(12, all), [3, 3, 0, 0, 6]);
+        assert_eq!(dashboard_rows(7, all), [3, 0, 0, 0, 4]);
+        assert_eq!(dashboard_rows(2, all), [2, 0, 0, 0, 0]);
+        for height in 0..60 {
+            assert!(dashboard_rows(height, all).iter().sum::<u16>() <= height);
+        }
+    }
+
+    #[test]
+    fn hidden_panels_give_their_rows_to_the_rest() {
+        let mut panels = Panels::default();
+        panels.toggle(Panel::Speed);
+        assert_eq!(dashboard_rows(40, panels), [3, 3, 28, 0, 6]);
+        panels.toggle(Panel::Status);
+        panels.toggle(Panel::Filters);
+        assert_eq!(dashboard_rows(40, panels), [0, 3, 37, 0, 0]);
+    }
+
+    #[test]
+    fn session_time_counts_hours_past_a_day() {
+        assert_eq!(elapsed_text(Duration::from_secs(59)), "0:00:59");
+        assert_eq!(elapsed_text(Duration::from_secs(3_725)), "1:02:05");
+        assert_eq!(elapsed_text(Duration::from_hours(27)), "27:00:00");
+    }
+
+    #[test]
+    fn asciify_swaps_borders_and_markers() {
+        let mut buffer = Buffer::with_lines(["┌─┐⣿•", "│█│→ä"]);
+        asciify(&mut buffer);
+        assert_eq!(buffer, Buffer::with_lines(["+-+**", "|#|>?"]));
+        assert_eq!(buffer.area, Rect::new(0, 0, 5, 2));
+    }
+
+    #[test]
+    fn minimap_compresses_history_and_marks_the_charted_span() {
+        let mut history = History::new(100);
+        for second in 0..40u32 {
+            history.push(Sample {
+                timestamp: UNIX_EPOCH + Duration::from_secs(u64::from(second)),
+                real_flow: 0.0,
+                smoothed_flow: if second < 20 { 10.0 } else { 100.0 },
+                target_flow: 0.0,
+                speed_rpm: 0.0,
+                filter_totals: [0.0; 3],
+            });
         }
+        let cells = minimap_cells(&history, 4, 100.0, (25.0, 39.0));
+        let symbols: String = cells.iter().map(|(symbol, _)| symbol).collect();
+        assert_eq!(symbols, "▁▁██");
+        let charted: Vec<bool> = cells.iter().map(|(_, charted)| *charted).collect();
+        assert_eq!(charted, [false, false, true, true]);
+        assert_eq!(
+            minimap_cells(&History::new(10), 3, 100.0, (0.0, 1.0)).len(),
+            3
+        );
+    }
+
+    #[test]
+    fn register_columns_show_raw_and_engineering_values() {
+        let speed = REGISTERS.iter().find(|info| info.address == REG_SPEED_RPM);
+        assert_eq!(
+            register_columns(speed, 0x04D2, None),
+            "0x04D2  1234 │    1234 rpm  "
+        );
+        assert_eq!(
+            register_columns(None, 7, Some(String::from("12_"))),
+            "0x0007     7 │     12_      "
+        );
+    }
+
+    #[test]
+    fn tick_rows_space_labels_evenly_and_thin_out_on_short_charts() {
+        let axes = ChartAxes::default();
+        assert_eq!(tick_rows(axes, 17), [0, 4, 8, 12, 16]);
+        assert_eq!(tick_rows(axes, 5), [0, 2, 4]);
+        assert_eq!(tick_rows(axes, 1), [0, 0]);
+        let none = ChartAxes { y_ticks: 0, ..axes };
+        assert_eq!(tick_rows(none, 17), [0, 16]);
+    }
+
+    #[test]
+    fn register_sparkline_scales_to_the_recent_range() {
+        assert_eq!(
+            register_sparkline([10, 12, 14, 17].into_iter(), false),
+            (String::from(" ▁▃▅█  "), true)
+        );
+        assert_eq!(
+            register_sparkline([0xFFFF, 0, 1].into_iter(), true),
+            (String::from(" ▁▅█   "), true)
+        );
+        assert_eq!(
+            register_sparkline([5, 5].into_iter(), false),
+            (String::from(" ▁▁    "), false)
+        );
     }
-    (real, target)
 }
@@ -0,0 +1,122 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+const CFM_PER_M3H: f64 = 0.588_577_8;
+const LPM_PER_M3H: f64 = 1000.0 / 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlowUnit {
+    #[default]
+    #[value(name = "m3h")]
+    #[serde(rename = "m3h")]
+    CubicMetersPerHour,
+    Cfm,
+    Lpm,
+}
+
+impl FlowUnit {
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::CubicMetersPerHour => "m3/h",
+            Self::Cfm => "CFM",
+            Self::Lpm => "L/min",
+        }
+    }
+
+    pub const fn next(self) -> Self {
+        match self {
+            Self::CubicMetersPerHour => Self::Cfm,
+            Self::Cfm => Self::Lpm,
+            Self::Lpm => Self::CubicMetersPerHour,
+        }
+    }
+
+    /// Converts a device value in m3/h into this unit.
+    pub fn convert(self, value: f64) -> f64 {
+        match self {
+            Self::CubicMetersPerHour => value,
+            Self::Cfm => value * CFM_PER_M3H,
+            Self::Lpm => value * LPM_PER_M3H,
+        }
+    }
+
+    /// Converts a value in this unit back into device m3/h.
+    pub fn to_m3h(self, value: f64) -> f64 {
+        match self {
+            Self::CubicMetersPerHour => value,
+            Self::Cfm => value / CFM_PER_M3H,
+            Self::Lpm => value / LPM_PER_M3H,
+        }
+    }
+
+    pub fn format(self, value_m3h: u16) -> String {
+        self.format_value(f64::from(value_m3h))
+    }
+
+    pub fn format_value(self, value_m3h: f64) -> String {
+        let value = self.convert(value_m3h);
+        match self {
+            Self::Cfm => format!("{value:.1} {}", self.label()),
+            Self::CubicMetersPerHour | Self::Lpm => format!("{value:.0} {}", self.label()),
+        }
+    }
+
+    /// Maximum number of digits needed to type a device flow value in this unit.
+    pub fn input_digits(self, max_m3h: u16) -> usize {
+        let max = self.convert(f64::from(max_m3h)).ceil();
+        format!("{max:.0}").len()
+    }
+
+    /// Whole values in this unit that fall inside a device range given in m3/h.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn input_range(self, min_m3h: u16, max_m3h: u16) -> (u32, u32) {
+        let low = self.convert(f64::from(min_m3h)).ceil();
+        let high = self.convert(f64::from(max_m3h)).floor();
+        (low as u32, high as u32)
+    }
+
+    /// A device flow in this unit, rounded to a whole value as it would be typed.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn whole(self, value_m3h: u16) -> u32 {
+        self.convert(f64::from(value_m3h)).round() as u32
+    }
+
+    /// Parses a value typed in this unit into device m3/h, rounded to the register resolution.
+    pub fn parse_m3h(self, input: &str) -> Option<u16> {
+        let value = input.parse::<f64>().ok()?;
+        Some(clamp_u16(self.to_m3h(value)))
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn clamp_u16(value: f64) -> u16 {
+    value.round().clamp(0.0, f64::from(u16::MAX)) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FlowUnit;
+
+    #[test]
+    fn conversions_roundtrip_through_m3h() {
+        for unit in [FlowUnit::CubicMetersPerHour, FlowUnit::Cfm, FlowUnit::Lpm] {
+            let converted = unit.convert(70.0);
+            assert!((unit.to_m3h(converted) - 70.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn lpm_input_is_converted_back_to_m3h() {
+        assert_eq!(FlowUnit::Lpm.parse_m3h("1000"), Some(60));
+        assert_eq!(FlowUnit::Cfm.parse_m3h("59"), Some(100));
+        assert_eq!(FlowUnit::CubicMetersPerHour.parse_m3h("abc"), None);
+    }
+
+    #[test]
+    fn input_digits_follow_unit_range() {
+        assert_eq!(FlowUnit::CubicMetersPerHour.input_digits(100), 3);
+        assert_eq!(FlowUnit::Cfm.input_digits(100), 2);
+        assert_eq!(FlowUnit::Lpm.input_digits(100), 4);
+    }
+}
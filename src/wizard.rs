@@ -0,0 +1,188 @@
+use crate::constants::BAUD_RATES;
+use crate::transport::{LinkOutcome, TransportCommand};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WizardStep {
+    Address,
+    Baud,
+    /// The new address typed a second time before anything is written.
+    RetypeAddress,
+    RetypeBaud,
+    Applying,
+    /// The device went silent with the new parameters; offer to go back.
+    Rollback,
+    Done(String),
+    Failed(String),
+}
+
+impl WizardStep {
+    /// The step takes a typed number.
+    pub const fn typed(&self) -> bool {
+        matches!(
+            self,
+            Self::Address | Self::Baud | Self::RetypeAddress | Self::RetypeBaud
+        )
+    }
+}
+
+/// Guided change of the device comm address and baud rate.
+#[derive(Debug)]
+pub struct LinkWizard {
+    pub step: WizardStep,
+    pub buffer: String,
+    pub address: u8,
+    pub baud: u32,
+    pub previous_address: u8,
+    pub previous_baud: u32,
+    pub error: Option<String>,
+}
+
+impl LinkWizard {
+    pub fn new(address: u8, baud: u32) -> Self {
+        Self {
+            step: WizardStep::Address,
+            buffer: address.to_string(),
+            address,
+            baud,
+            previous_address: address,
+            previous_baud: baud,
+            error: None,
+        }
+    }
+
+    /// Advances the wizard, returning the command to send once the change is confirmed.
+    pub fn submit(&mut self) -> Option<TransportCommand> {
+        match self.step {
+            WizardStep::Address => match self.buffer.parse::<u8>() {
+                Ok(address) if (1..=247).contains(&address) => {
+                    self.address = address;
+                    self.buffer = self.baud.to_string();
+                    self.error = None;
+                    self.step = WizardStep::Baud;
+                }
+                _ => self.error = Some("address must be within 1..=247".to_string()),
+            },
+            WizardStep::Baud => match self.buffer.parse::<u32>() {
+                Ok(baud) if BAUD_RATES.contains(&baud) => {
+                    self.baud = baud;
+                    self.buffer.clear();
+                    self.error = None;
+                    self.step = WizardStep::RetypeAddress;
+                }
+                _ => {
+                    let rates: Vec<String> = BAUD_RATES.iter().map(ToString::to_string).collect();
+                    self.error = Some(format!("baud must be one of {}", rates.join(", ")));
+                }
+            },
+            WizardStep::RetypeAddress => {
+                let matches = self.buffer.parse::<u8>() == Ok(self.address);
+                self.buffer.clear();
+                if matches {
+                    self.error = None;
+                    self.step = WizardStep::RetypeBaud;
+                } else {
+                    self.error = Some("the addresses differ, type it again".to_string());
+                }
+            }
+            WizardStep::RetypeBaud => {
+                let matches = self.buffer.parse::<u32>() == Ok(self.baud);
+                self.buffer.clear();
+                if !matches {
+                    self.error = Some("the baud rates differ, type it again".to_string());
+                    return None;
+                }
+                self.error = None;
+                self.step = WizardStep::Applying;
+                return Some(TransportCommand::Reconfigure {
+                    address: self.address,
+                    baud: self.baud,
+                });
+            }
+            WizardStep::Applying
+            | WizardStep::Rollback
+            | WizardStep::Done(_)
+            | WizardStep::Failed(_) => {}
+        }
+        None
+    }
+
+    pub fn rollback(&mut self) -> TransportCommand {
+        self.step = WizardStep::Applying;
+        TransportCommand::Reconnect {
+            address: self.previous_address,
+            baud: self.previous_baud,
+        }
+    }
+
+    pub fn handle_outcome(&mut self, outcome: &LinkOutcome) {
+        self.step = match outcome {
+            LinkOutcome::Verified { address, baud } => {
+                WizardStep::Done(format!("Device answers at address {address}, {baud} baud"))
+            }
+            LinkOutcome::Rejected(reason) => WizardStep::Failed(reason.clone()),
+            LinkOutcome::Unresponsive { .. } => WizardStep::Rollback,
+            LinkOutcome::Indeterminate(reason) => WizardStep::Failed(format!(
+                "{reason}; settings were partly written, check them on the device"
+            )),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LinkWizard, WizardStep};
+    use crate::transport::{LinkOutcome, TransportCommand};
+
+    #[test]
+    fn walks_through_address_and_baud_typed_twice() {
+        let mut wizard = LinkWizard::new(2, 19_200);
+        wizard.buffer = String::from("5");
+        assert_eq!(wizard.submit(), None);
+        assert_eq!(wizard.step, WizardStep::Baud);
+        wizard.buffer = String::from("38400");
+        assert_eq!(wizard.submit(), None);
+        assert_eq!(wizard.step, WizardStep::RetypeAddress);
+        wizard.buffer = String::from("6");
+        assert_eq!(wizard.submit(), None);
+        assert_eq!(wizard.step, WizardStep::RetypeAddress);
+        assert!(wizard.error.is_some());
+        wizard.buffer = String::from("5");
+        assert_eq!(wizard.submit(), None);
+        assert_eq!(wizard.step, WizardStep::RetypeBaud);
+        wizard.buffer = String::from("38400");
+        assert_eq!(
+            wizard.submit(),
+            Some(TransportCommand::Reconfigure {
+                address: 5,
+                baud: 38_400,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_baud() {
+        let mut wizard = LinkWizard::new(2, 19_200);
+        wizard.submit();
+        wizard.buffer = String::from("12345");
+        wizard.submit();
+        assert_eq!(wizard.step, WizardStep::Baud);
+        assert!(wizard.error.is_some());
+    }
+
+    #[test]
+    fn unresponsive_device_offers_rollback_to_previous_link() {
+        let mut wizard = LinkWizard::new(2, 19_200);
+        wizard.handle_outcome(&LinkOutcome::Unresponsive {
+            address: 5,
+            baud: 38_400,
+        });
+        assert_eq!(wizard.step, WizardStep::Rollback);
+        assert_eq!(
+            wizard.rollback(),
+            TransportCommand::Reconnect {
+                address: 2,
+                baud: 19_200,
+            }
+        );
+    }
+}